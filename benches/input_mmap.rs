@@ -0,0 +1,62 @@
+//! Compares feeding a large file to a command with `input_mmap()` and with a buffered reader.
+//!
+//! Run with `cargo bench --features mmap --bench input_mmap`. The payload size in MiB can be
+//! set with `SCRIPTY_BENCH_MIB` (default 512); use a few thousand for multi-gigabyte inputs.
+
+use scripty::*;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+const ROUNDS: usize = 3;
+
+fn best_of(rounds: usize, mut f: impl FnMut()) -> Duration {
+    (0..rounds)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() -> Result<()> {
+    let mib: usize = std::env::var("SCRIPTY_BENCH_MIB")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(512);
+
+    let file = fs::temp_file_scoped()?;
+    {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(file.path())?);
+        let chunk = vec![b'x'; 1024 * 1024];
+        for _ in 0..mib {
+            writer.write_all(&chunk)?;
+        }
+    }
+
+    let expected = (mib * 1024 * 1024).to_string();
+    let mmap = best_of(ROUNDS, || {
+        let output = cmd!("wc", "-c")
+            .no_echo()
+            .input_mmap(file.path())
+            .unwrap()
+            .output()
+            .unwrap();
+        assert_eq!(output.trim(), expected);
+    });
+    let reader = best_of(ROUNDS, || {
+        let input = std::io::BufReader::new(std::fs::File::open(file.path()).unwrap());
+        let output = input.pipe(cmd!("wc", "-c")).no_echo().output().unwrap();
+        assert_eq!(output.trim(), expected);
+    });
+
+    let throughput = |elapsed: Duration| mib as f64 / elapsed.as_secs_f64();
+    println!("payload:     {mib} MiB, best of {ROUNDS}");
+    println!("input_mmap:  {mmap:>10.2?} ({:.0} MiB/s)", throughput(mmap));
+    println!(
+        "BufReader:   {reader:>10.2?} ({:.0} MiB/s)",
+        throughput(reader)
+    );
+    Ok(())
+}
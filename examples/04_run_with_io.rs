@@ -6,6 +6,7 @@
 //! - run_with_io() - process stdin to stdout
 //! - run_with_err_io() - process stdin and capture stderr
 //! - run_with_both_io() - process stdin and capture combined output
+//! - run_with_io_full() - process stdin and capture stdout and stderr separately
 //!
 //! Estimated time: ~7 minutes
 //! Prerequisites: Understanding of Rust I/O traits (Read/Write)
@@ -313,6 +314,7 @@ fn real_world_examples() -> Result<()> {
     println!("   Command: Data validation and transformation");
     let sample_data = "apple,5\nbanana,10\ninvalid_entry\ncherry,15\ndate,abc\nfig,20";
     let input_reader = Cursor::new(sample_data.as_bytes());
+    let mut valid_entries = Vec::new();
     let error_report_file = File::create("error_report.txt")?;
 
     cmd!(
@@ -340,8 +342,13 @@ fn real_world_examples() -> Result<()> {
     "#
     )
     .no_echo()
-    .run_with_err_io(input_reader, error_report_file)?;
+    .run_with_io_full(input_reader, &mut valid_entries, error_report_file)?;
 
+    let valid_entries = String::from_utf8(valid_entries)?;
+    println!("   Transformed data:");
+    for line in valid_entries.lines() {
+        println!("     {}", line);
+    }
     let error_report = fs::read_to_string("error_report.txt")?;
     if !error_report.is_empty() {
         println!("   Validation errors detected:");
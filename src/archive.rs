@@ -0,0 +1,269 @@
+//! Creating and extracting tar and zip archives, in place of `tar` and `zip`/`unzip`.
+//!
+//! Archives are read and written in-process, so the behavior does not depend on whether the
+//! system has GNU or BSD `tar`, or has `zip` installed at all. Extraction never writes outside
+//! the destination directory, even for entries with absolute paths or `..` components. Each
+//! operation is echoed like file system operations. Requires the `archive` feature.
+
+use crate::fs::{context, echo_operation};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+
+/// Extract a tar archive into a directory, creating it if needed.
+///
+/// Gzip-compressed archives (`.tar.gz`, `.tgz`) are detected from their contents and
+/// decompressed, whatever the file name. File permissions and modification times are kept.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "archive")]
+/// # {
+/// use scripty::*;
+///
+/// archive::untar("node-v22.tar.gz", "vendor/node")?;
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn untar(archive: impl AsRef<Path>, dest: impl AsRef<Path>) -> std::io::Result<()> {
+    let archive = archive.as_ref();
+    let dest = dest.as_ref();
+    echo_operation(
+        "untar",
+        &format!("{} -> {}", archive.display(), dest.display()),
+    );
+    unpack_tar(archive, dest).map_err(context("untar", &[archive, dest]))
+}
+
+fn unpack_tar(archive: &Path, dest: &Path) -> std::io::Result<()> {
+    let mut reader = BufReader::new(File::open(archive)?);
+    let mut magic = [0; 2];
+    let gzipped = reader.read_exact(&mut magic).is_ok() && magic == [0x1f, 0x8b];
+    reader.rewind()?;
+
+    std::fs::create_dir_all(dest)?;
+    if gzipped {
+        tar::Archive::new(GzDecoder::new(reader)).unpack(dest)
+    } else {
+        tar::Archive::new(reader).unpack(dest)
+    }
+}
+
+/// Create a gzip-compressed tar archive of a directory's contents, like
+/// `tar czf archive -C dir .`.
+///
+/// Symbolic links are stored as links rather than followed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "archive")]
+/// # {
+/// use scripty::*;
+///
+/// archive::tar_gz("target/release/dist", "myapp-linux-x86_64.tar.gz")?;
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn tar_gz(dir: impl AsRef<Path>, archive: impl AsRef<Path>) -> std::io::Result<()> {
+    let dir = dir.as_ref();
+    let archive = archive.as_ref();
+    echo_operation(
+        "tar_gz",
+        &format!("{} -> {}", dir.display(), archive.display()),
+    );
+    pack_tar_gz(dir, archive).map_err(context("tar_gz", &[dir, archive]))
+}
+
+fn pack_tar_gz(dir: &Path, archive: &Path) -> std::io::Result<()> {
+    let encoder = GzEncoder::new(
+        BufWriter::new(File::create(archive)?),
+        Compression::default(),
+    );
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+    builder.append_dir_all(".", dir)?;
+    builder.into_inner()?.finish()?.into_inner()?.sync_all()
+}
+
+/// Extract a zip archive into a directory, creating it if needed.
+///
+/// Unix permissions stored in the archive are kept.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "archive")]
+/// # {
+/// use scripty::*;
+///
+/// archive::unzip("protoc-osx-universal.zip", "vendor/protoc")?;
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn unzip(archive: impl AsRef<Path>, dest: impl AsRef<Path>) -> std::io::Result<()> {
+    let archive = archive.as_ref();
+    let dest = dest.as_ref();
+    echo_operation(
+        "unzip",
+        &format!("{} -> {}", archive.display(), dest.display()),
+    );
+    unpack_zip(archive, dest).map_err(context("unzip", &[archive, dest]))
+}
+
+fn unpack_zip(archive: &Path, dest: &Path) -> std::io::Result<()> {
+    let mut zip = zip::ZipArchive::new(BufReader::new(File::open(archive)?))?;
+    std::fs::create_dir_all(dest)?;
+    Ok(zip.extract(dest)?)
+}
+
+/// Create a zip archive of a directory's contents, like `cd dir && zip -r archive .`.
+///
+/// Files are deflate-compressed and keep their Unix permissions. Symbolic links are stored as
+/// links rather than followed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "archive")]
+/// # {
+/// use scripty::*;
+///
+/// archive::zip("target/release/dist", "myapp-windows-x86_64.zip")?;
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn zip(dir: impl AsRef<Path>, archive: impl AsRef<Path>) -> std::io::Result<()> {
+    let dir = dir.as_ref();
+    let archive = archive.as_ref();
+    echo_operation(
+        "zip",
+        &format!("{} -> {}", dir.display(), archive.display()),
+    );
+    pack_zip(dir, archive).map_err(context("zip", &[dir, archive]))
+}
+
+fn pack_zip(dir: &Path, archive: &Path) -> std::io::Result<()> {
+    let mut writer = zip::ZipWriter::new(BufWriter::new(File::create(archive)?));
+    add_zip_entries(&mut writer, dir, "")?;
+    writer.finish()?.into_inner()?.sync_all()
+}
+
+/// Add the entries of `dir` to the archive under `prefix`, in name order.
+fn add_zip_entries(
+    writer: &mut zip::ZipWriter<BufWriter<File>>,
+    dir: &Path,
+    prefix: &str,
+) -> std::io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        let metadata = entry.path().symlink_metadata()?;
+        let options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(metadata.permissions().mode());
+
+        if metadata.is_symlink() {
+            let target = std::fs::read_link(entry.path())?;
+            writer.add_symlink(name, target.to_string_lossy(), options)?;
+        } else if metadata.is_dir() {
+            writer.add_directory(name.as_str(), options)?;
+            add_zip_entries(writer, &entry.path(), &format!("{name}/"))?;
+        } else {
+            writer.start_file(name, options)?;
+            std::io::copy(&mut File::open(entry.path())?, writer)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+    use crate::fs::temp_dir_scoped;
+
+    /// Create a directory with a nested file, an executable and a symbolic link.
+    fn sample_dir(root: &Path) -> std::path::PathBuf {
+        let dir = root.join("dist");
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("docs/README"), "read me").unwrap();
+        std::fs::write(dir.join("tool"), "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(dir.join("tool"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("docs/README", dir.join("link")).unwrap();
+        dir
+    }
+
+    fn assert_sample(dir: &Path) {
+        assert_eq!(
+            std::fs::read_to_string(dir.join("docs/README")).unwrap(),
+            "read me"
+        );
+        let mode = std::fs::metadata(dir.join("tool"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(
+            std::fs::read_link(dir.join("link")).unwrap(),
+            Path::new("docs/README")
+        );
+    }
+
+    #[test]
+    fn test_tar_gz_round_trip() {
+        let tmp = temp_dir_scoped().unwrap();
+        let dir = sample_dir(tmp.path());
+        let archive = tmp.path().join("dist.tar.gz");
+
+        tar_gz(&dir, &archive).unwrap();
+        untar(&archive, tmp.path().join("out")).unwrap();
+        assert_sample(&tmp.path().join("out"));
+
+        // Readable by the system tar
+        let listing = cmd!("tar", "tzf", &archive).no_echo().output().unwrap();
+        assert!(listing.lines().any(|line| line.ends_with("docs/README")));
+    }
+
+    #[test]
+    fn test_untar_uncompressed() {
+        let tmp = temp_dir_scoped().unwrap();
+        let dir = sample_dir(tmp.path());
+        let archive = tmp.path().join("dist.tar");
+        cmd!("tar", "cf", &archive, "-C", &dir, ".")
+            .no_echo()
+            .run()
+            .unwrap();
+
+        untar(&archive, tmp.path().join("out")).unwrap();
+        assert_sample(&tmp.path().join("out"));
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        let tmp = temp_dir_scoped().unwrap();
+        let dir = sample_dir(tmp.path());
+        let archive = tmp.path().join("dist.zip");
+
+        zip(&dir, &archive).unwrap();
+        unzip(&archive, tmp.path().join("out")).unwrap();
+        assert_sample(&tmp.path().join("out"));
+    }
+
+    #[test]
+    fn test_missing_archive() {
+        let tmp = temp_dir_scoped().unwrap();
+        let error = untar(tmp.path().join("missing.tar"), tmp.path()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(error.to_string().starts_with("Failed to untar "));
+        assert!(unzip(tmp.path().join("missing.zip"), tmp.path()).is_err());
+    }
+}
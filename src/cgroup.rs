@@ -0,0 +1,265 @@
+//! Resource-capped script sections using Linux control groups (cgroup v2).
+//!
+//! [`Cmd::cgroup`](crate::Cmd::cgroup) places a single command into an existing cgroup.
+//! [`scope`] creates a temporary cgroup with the given [`CgroupLimits`] and places every command
+//! spawned while a closure runs into it, so memory and CPU caps apply to a whole build step.
+//!
+//! Creating cgroups requires write access to the cgroup hierarchy, typically through a delegated
+//! subtree (for example a systemd unit with `Delegate=yes`). Only the unified (v2) hierarchy
+//! mounted at `/sys/fs/cgroup` is supported.
+
+use crate::fs::echo_operation;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+use std::sync::Mutex;
+
+/// Mount point of the unified cgroup hierarchy.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Period used for [`CgroupLimits::cpus`], in microseconds.
+const CPU_PERIOD: u64 = 100_000;
+
+/// Cgroups created by active [`scope`] calls, innermost last.
+static SCOPES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Limits applied to the cgroup created by [`scope`].
+///
+/// Unset limits are left at the kernel default (unlimited).
+///
+/// # Examples
+///
+/// ```
+/// use scripty::cgroup::CgroupLimits;
+///
+/// let limits = CgroupLimits {
+///     memory_max: Some(2 * 1024 * 1024 * 1024),
+///     cpus: Some(1.5),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CgroupLimits {
+    /// Maximum memory use in bytes (`memory.max`).
+    pub memory_max: Option<u64>,
+    /// Number of CPUs worth of time the processes may use (`cpu.max`), e.g. `0.5` or `2.0`.
+    pub cpus: Option<f64>,
+    /// Maximum number of processes and threads (`pids.max`).
+    pub pids_max: Option<u64>,
+}
+
+impl CgroupLimits {
+    /// Controller files and the values to write to them.
+    fn files(&self) -> Vec<(&'static str, String)> {
+        let mut files = Vec::new();
+        if let Some(bytes) = self.memory_max {
+            files.push(("memory.max", bytes.to_string()));
+        }
+        if let Some(cpus) = self.cpus {
+            let quota = ((cpus * CPU_PERIOD as f64) as u64).max(1000);
+            files.push(("cpu.max", format!("{quota} {CPU_PERIOD}")));
+        }
+        if let Some(n) = self.pids_max {
+            files.push(("pids.max", n.to_string()));
+        }
+        files
+    }
+}
+
+/// Run a closure with every spawned command placed into a new cgroup limited by `limits`.
+///
+/// The cgroup is created as a child of the cgroup of the current process. Commands with an
+/// explicit [`Cmd::cgroup`](crate::Cmd::cgroup) keep their own cgroup. When the closure returns
+/// (or panics), processes still running in the cgroup are killed and the cgroup is removed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+/// use scripty::cgroup::{self, CgroupLimits};
+///
+/// let limits = CgroupLimits {
+///     memory_max: Some(4 * 1024 * 1024 * 1024),
+///     cpus: Some(2.0),
+///     ..Default::default()
+/// };
+/// cgroup::scope(limits, || -> Result<()> {
+///     cmd!("cargo", "build", "--release").run()?;
+///     cmd!("cargo", "test").run()?;
+///     Ok(())
+/// })??;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn scope<T>(limits: CgroupLimits, f: impl FnOnce() -> T) -> std::io::Result<T> {
+    let _guard = ScopeGuard::create(&limits)?;
+    Ok(f())
+}
+
+/// The cgroup that commands spawned now are placed into by [`scope`], if any.
+pub(crate) fn current_scope() -> Option<PathBuf> {
+    SCOPES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .last()
+        .cloned()
+}
+
+/// Install a `pre_exec` hook that moves the child process into the cgroup at `path`.
+pub(crate) fn join(cmd: &mut StdCommand, path: &Path) -> std::io::Result<()> {
+    let procs = CString::new(path.join("cgroup.procs").as_os_str().as_bytes())?;
+    // SAFETY: the hook only calls `open`, `write`, and `close`, which are async-signal-safe, and
+    // does not allocate. Writing `0` to `cgroup.procs` moves the writing process.
+    unsafe {
+        cmd.pre_exec(move || {
+            let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+            let error = std::io::Error::last_os_error();
+            libc::close(fd);
+            if written != 1 {
+                return Err(error);
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+/// Removes the cgroup created by [`scope`] when dropped.
+struct ScopeGuard {
+    path: PathBuf,
+}
+
+impl ScopeGuard {
+    fn create(limits: &CgroupLimits) -> std::io::Result<Self> {
+        let parent = current_cgroup()?;
+        let files = limits.files();
+        enable_controllers(&parent, &files);
+
+        let path = create_unique(&parent)?;
+        echo_operation("cgroup", &path.display().to_string());
+        let guard = Self { path };
+        for (file, value) in files {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(guard.path.join(file))
+                .and_then(|mut f| std::io::Write::write_all(&mut f, value.as_bytes()))
+                .map_err(|e| std::io::Error::new(e.kind(), format!("Failed to set {file}: {e}")))?;
+        }
+
+        SCOPES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(guard.path.clone());
+        Ok(guard)
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        SCOPES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|path| path != &self.path);
+
+        if std::fs::remove_dir(&self.path).is_ok() {
+            return;
+        }
+        // Kill leftover processes; removal succeeds once the kernel has reaped them
+        let _ = std::fs::write(self.path.join("cgroup.kill"), "1");
+        for _ in 0..50 {
+            if std::fs::remove_dir(&self.path).is_ok() {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        eprintln!("Warning: Failed to remove cgroup {}", self.path.display());
+    }
+}
+
+/// Directory of the cgroup the current process belongs to.
+fn current_cgroup() -> std::io::Result<PathBuf> {
+    let root = Path::new(CGROUP_ROOT);
+    if !root.join("cgroup.controllers").exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("cgroup v2 is not mounted at {CGROUP_ROOT}"),
+        ));
+    }
+
+    let membership = std::fs::read_to_string("/proc/self/cgroup")?;
+    let relative = membership
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Process is not in a cgroup v2 hierarchy",
+            )
+        })?;
+    Ok(root.join(relative.trim_start_matches('/')))
+}
+
+/// Enable the controllers needed for `files` in the children of `parent`.
+///
+/// Failures are ignored: the controllers may already be enabled, and a missing controller is
+/// reported when writing the limit.
+fn enable_controllers(parent: &Path, files: &[(&'static str, String)]) {
+    for (file, _) in files {
+        if let Some((controller, _)) = file.split_once('.') {
+            let _ = std::fs::write(
+                parent.join("cgroup.subtree_control"),
+                format!("+{controller}"),
+            );
+        }
+    }
+}
+
+/// Create a uniquely named child cgroup of `parent`.
+fn create_unique(parent: &Path) -> std::io::Result<PathBuf> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    loop {
+        let name = format!(
+            "{}-{}-{}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = parent.join(name);
+        match std::fs::create_dir(&path) {
+            Ok(()) => return Ok(path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_files() {
+        let limits = CgroupLimits {
+            memory_max: Some(1024),
+            cpus: Some(0.5),
+            pids_max: Some(16),
+        };
+        assert_eq!(
+            limits.files(),
+            vec![
+                ("memory.max", "1024".to_string()),
+                ("cpu.max", "50000 100000".to_string()),
+                ("pids.max", "16".to_string()),
+            ]
+        );
+        assert!(CgroupLimits::default().files().is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+//! Running commands one after another depending on how the previous one went, like `a && b`
+//! and `a || b` in a shell.
+
+use crate::cmd::{Cmd, Error, Pipeline};
+
+/// How a pipeline in a [`Chain`] depends on the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    /// Run only if the previous pipeline succeeded (`&&`).
+    And,
+    /// Run only if the previous pipeline failed (`||`).
+    Or,
+}
+
+/// Pipelines run in sequence with `&&` and `||` semantics, created by
+/// [`Pipeline::and_then_cmd`], [`Pipeline::or_cmd`] or [`Pipeline::or_else`].
+///
+/// As in a shell, the operators are applied from left to right: each pipeline after `&&` runs
+/// only if the last pipeline that ran succeeded, and each one after `||` only if it failed.
+/// The result is that of the last pipeline that ran. Each pipeline is echoed with its operator
+/// in front when it runs, so the log shows which branch was taken.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// // cargo fmt --check && cargo clippy || echo 'lint failed'
+/// cmd!("cargo", "fmt", "--check")
+///     .and_then_cmd(cmd!("cargo", "clippy"))
+///     .or_cmd(cmd!("echo", "lint failed"))
+///     .run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+#[must_use = "nothing runs until `run()` or `output()` is called"]
+pub struct Chain {
+    first: Pipeline,
+    rest: Vec<(Operator, Pipeline)>,
+}
+
+impl Chain {
+    /// Run `next` if the pipelines so far succeeded, like `&&` in a shell.
+    pub fn and_then_cmd(mut self, next: impl Into<Pipeline>) -> Self {
+        self.rest.push((Operator::And, next.into()));
+        self
+    }
+
+    /// Run `fallback` if the pipelines so far failed, like `||` in a shell.
+    pub fn or_cmd(mut self, fallback: impl Into<Pipeline>) -> Self {
+        self.rest.push((Operator::Or, fallback.into()));
+        self
+    }
+
+    /// Add another fallback, tried if all the previous pipelines fail. Same as
+    /// [`or_cmd`](Self::or_cmd).
+    pub fn or_else(self, fallback: impl Into<Pipeline>) -> Self {
+        self.or_cmd(fallback)
+    }
+
+    /// Run the pipelines, returning the result of the last one that ran.
+    pub fn run(self) -> Result<(), Error> {
+        self.run_each(Pipeline::run)
+    }
+
+    /// Run the pipelines, returning the text output of the last one that ran.
+    ///
+    /// The output of the other pipelines is discarded.
+    pub fn output(self) -> Result<String, Error> {
+        self.run_each(Pipeline::output)
+    }
+
+    fn run_each<T>(self, mut f: impl FnMut(Pipeline) -> Result<T, Error>) -> Result<T, Error> {
+        let mut result = f(self.first);
+        for (operator, mut pipeline) in self.rest {
+            let run = match operator {
+                Operator::And => result.is_ok(),
+                Operator::Or => result.is_err(),
+            };
+            if run {
+                pipeline.echo_chained(match operator {
+                    Operator::And => "&&",
+                    Operator::Or => "||",
+                });
+                result = f(pipeline);
+            }
+        }
+        result
+    }
+}
+
+impl Pipeline {
+    /// Run `next` if this pipeline succeeds, like `a && b` in a shell.
+    ///
+    /// Unlike [`pipe`](Self::pipe), the commands are run one after another and are not
+    /// connected. See [`Chain`] for how further commands are run.
+    pub fn and_then_cmd(self, next: impl Into<Pipeline>) -> Chain {
+        self.chain(Operator::And, next.into())
+    }
+
+    /// Run `fallback` if this pipeline fails, like `a || b` in a shell.
+    ///
+    /// A pipeline fails if a program cannot be started or exits with a non-zero status.
+    pub fn or_cmd(self, fallback: impl Into<Pipeline>) -> Chain {
+        self.chain(Operator::Or, fallback.into())
+    }
+
+    /// Run `fallback` if this pipeline fails, like `a || b` in a shell.
+    ///
+    /// A pipeline fails if a program cannot be started or exits with a non-zero status, so
+    /// this picks between tools that may not be installed, such as `gsed` and `sed`. Chain
+    /// more fallbacks with [`Chain::or_else`]. Each pipeline is run with its own input.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// cmd!("gsed", "-i", "s/0.1.0/0.2.0/", "Cargo.toml")
+    ///     .or_else(cmd!("sed", "-i", "s/0.1.0/0.2.0/", "Cargo.toml"))
+    ///     .run()?;
+    ///
+    /// let page = cmd!("curl", "-fsSL", "https://example.com")
+    ///     .or_else(cmd!("wget", "-qO-", "https://example.com"))
+    ///     .output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn or_else(self, fallback: impl Into<Pipeline>) -> Chain {
+        self.or_cmd(fallback)
+    }
+
+    fn chain(self, operator: Operator, next: Pipeline) -> Chain {
+        Chain {
+            first: self,
+            rest: vec![(operator, next)],
+        }
+    }
+}
+
+impl Cmd {
+    /// Run `next` if this command succeeds, like `a && b` in a shell.
+    ///
+    /// See [`Pipeline::and_then_cmd`] for details.
+    pub fn and_then_cmd(self, next: impl Into<Pipeline>) -> Chain {
+        self.into_pipeline().and_then_cmd(next)
+    }
+
+    /// Run `fallback` if this command fails, like `a || b` in a shell.
+    ///
+    /// See [`Pipeline::or_cmd`] for details.
+    pub fn or_cmd(self, fallback: impl Into<Pipeline>) -> Chain {
+        self.into_pipeline().or_cmd(fallback)
+    }
+
+    /// Run `fallback` if this command fails, like `a || b` in a shell.
+    ///
+    /// See [`Pipeline::or_else`] for details.
+    pub fn or_else(self, fallback: impl Into<Pipeline>) -> Chain {
+        self.into_pipeline().or_else(fallback)
+    }
+}
@@ -1,9 +1,14 @@
 //! Command implementation and execution logic.
 
-use crate::cmd::{error::Error, types::*};
-use std::ffi::OsStr;
-use std::io::{Read, Write};
-use std::path::Path;
+use crate::cmd::{
+    error::Error, filters::LineFilter, limits::ResourceLimit, operands, progress::ProgressEvent,
+    redact, types::*,
+};
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 impl Cmd {
     /// Create a new command.
@@ -12,8 +17,29 @@ impl Cmd {
             program: program.as_ref().to_os_string(),
             args: Vec::new(),
             envs: Vec::new(),
+            env_removes: Vec::new(),
+            secret_envs: Vec::new(),
             current_dir: None,
             suppress_echo: false,
+            limits: Vec::new(),
+            isolated_home: None,
+            auto_end_of_options: false,
+            cgroup: None,
+            filter: None,
+            stdin: StdioConfig::Piped,
+            stdout_null: false,
+            stderr_null: false,
+            combine_err: false,
+            quiet: false,
+            uid: None,
+            gid: None,
+            umask: None,
+            std_hooks: Vec::new(),
+            dry_run: false,
+            shell_script: false,
+            echo_full_args: false,
+            process_group: false,
+            invalid: None,
         }
     }
 
@@ -35,6 +61,46 @@ impl Cmd {
         self
     }
 
+    /// Add `--` to mark the end of options, so that the following arguments are never
+    /// interpreted as options even if they start with `-`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let file = "-rf"; // e.g. from a directory listing
+    /// cmd!("rm", "-f").end_of_options().arg(file).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn end_of_options(mut self) -> Self {
+        self.args.push(OsString::from(operands::END_OF_OPTIONS));
+        self
+    }
+
+    /// Automatically insert `--` before the operands when the program is a well-known file
+    /// utility such as `rm`, `cp`, `mv`, `chmod`, or `cat`.
+    ///
+    /// The separator is placed after the leading options, and only when a later argument
+    /// starts with `-`. A leading argument that names an existing file (like a file called
+    /// `-rf`) counts as an operand. Arguments are never reordered, so options given after the
+    /// operands (`cp a b -v`) become operands too. Commands that already contain `--`, and
+    /// programs that are not known, are left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Runs `rm -f -- -rf` when a file named `-rf` exists
+    /// cmd!("rm", "-f", "-rf").auto_end_of_options().run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn auto_end_of_options(mut self) -> Self {
+        self.auto_end_of_options = true;
+        self
+    }
+
     /// Set an environment variable.
     pub fn env(mut self, key: impl AsRef<OsStr>, val: impl AsRef<OsStr>) -> Self {
         self.envs
@@ -42,22 +108,669 @@ impl Cmd {
         self
     }
 
+    /// Add `dir` to the front of the command's `PATH`, so that programs installed there are
+    /// found first, including the command's own program.
+    ///
+    /// The `PATH` set with [`env`](Self::env) is extended if there is one, otherwise the
+    /// inherited one. A relative `dir` is made absolute against the current directory, since
+    /// the command may run in another one.
+    ///
+    /// A `dir` containing `:`, which cannot be part of a `PATH` entry, makes the command fail
+    /// with an error when it is run.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// cmd!("npm", "install", "--prefix", "tools", "prettier").run()?;
+    /// cmd!("prettier", "--check", "src")
+    ///     .prepend_path("tools/node_modules/.bin")
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn prepend_path(self, dir: impl AsRef<Path>) -> Self {
+        let path = Self::path_with_dir(&self.envs, dir.as_ref(), true);
+        self.env_path(path)
+    }
+
+    /// Add `dir` to the end of the command's `PATH`, so that programs installed there are
+    /// found only if no other directory has them.
+    ///
+    /// See [`prepend_path`](Self::prepend_path) for details.
+    pub fn append_path(self, dir: impl AsRef<Path>) -> Self {
+        let path = Self::path_with_dir(&self.envs, dir.as_ref(), false);
+        self.env_path(path)
+    }
+
+    /// Set `PATH` as built by [`path_with_dir`](Self::path_with_dir), or keep the failure for
+    /// when the command is run.
+    fn env_path(mut self, path: Result<OsString, String>) -> Self {
+        match path {
+            Ok(path) => self.env("PATH", path),
+            Err(reason) => {
+                self.invalid.get_or_insert(reason);
+                self
+            }
+        }
+    }
+
+    /// The last `PATH` in `envs`, or the inherited one, with `dir` added at the front or the end.
+    ///
+    /// Fails with the reason to report if `dir` cannot be a `PATH` entry.
+    pub(crate) fn path_with_dir(
+        envs: &[(OsString, OsString)],
+        dir: &Path,
+        front: bool,
+    ) -> Result<OsString, String> {
+        let path = envs
+            .iter()
+            .rev()
+            .find(|(key, _)| key == "PATH")
+            .map(|(_, val)| val.clone())
+            .or_else(|| std::env::var_os("PATH"))
+            .unwrap_or_default();
+        let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let reason = format!(
+            "cannot add {} to PATH, since it contains ':'",
+            dir.display()
+        );
+        let mut dirs: Vec<PathBuf> = std::env::split_paths(&path)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .collect();
+        if front {
+            dirs.insert(0, dir);
+        } else {
+            dirs.push(dir);
+        }
+        std::env::join_paths(dirs).map_err(|_| reason)
+    }
+
+    /// Set an environment variable whose value is shown as `****` in the echo and in errors.
+    ///
+    /// Use it for credentials, so that the echo can stay on in CI logs. The value is masked
+    /// for any later [`env`](Self::env) with the same name too. See
+    /// [`echo::redact`](crate::echo::redact) to mask a value wherever it appears.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let token = std::env::var("NPM_TOKEN")?;
+    /// // Echoed as `env: NPM_TOKEN=**** npm publish`
+    /// cmd!("npm", "publish").env_secret("NPM_TOKEN", token).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn env_secret(mut self, key: impl AsRef<OsStr>, val: impl AsRef<OsStr>) -> Self {
+        let key = key.as_ref().to_os_string();
+        if !self.secret_envs.contains(&key) {
+            self.secret_envs.push(key.clone());
+        }
+        self.env(key, val)
+    }
+
+    /// Remove inherited environment variables whose name matches `predicate`.
+    ///
+    /// The predicate is checked against the environment of the current process when this
+    /// method is called. Variables set with [`env`](Self::env) are still passed on.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// cmd!("npm", "install")
+    ///     .env_remove_matching(|key| key.starts_with("AWS_"))
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn env_remove_matching(mut self, mut predicate: impl FnMut(&str) -> bool) -> Self {
+        for (key, _) in std::env::vars_os() {
+            let matches = key.to_str().is_some_and(&mut predicate);
+            if matches && !self.env_removes.contains(&key) {
+                self.env_removes.push(key);
+            }
+        }
+        self
+    }
+
+    /// Remove inherited environment variables that commonly hold credentials, so untrusted
+    /// build steps cannot read secrets from a CI environment.
+    ///
+    /// This removes cloud provider variables (`AWS_*`, `AZURE_*`, `GCP_*`, ...), variables
+    /// whose name contains `TOKEN`, `SECRET`, `PASSWORD`, `API_KEY`, `CREDENTIAL`, or `AUTH`,
+    /// and `SSH_AUTH_SOCK`, `KUBECONFIG`, and `GOOGLE_APPLICATION_CREDENTIALS`. Matching
+    /// ignores case. The list is a safety net, not a guarantee; use
+    /// [`env_remove_matching`](Self::env_remove_matching) for project-specific variables.
+    pub fn redact_sensitive(self) -> Self {
+        self.env_remove_matching(redact::is_sensitive)
+    }
+
     /// Set the working directory.
     pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
         self.current_dir = Some(dir.as_ref().to_path_buf());
         self
     }
 
+    /// Limit the number of file descriptors the command may open (`ulimit -n`).
+    ///
+    /// The limit is applied with `setrlimit(RLIMIT_NOFILE)` in the child before it starts.
+    pub fn limit_nofile(mut self, n: u64) -> Self {
+        self.limits.push(ResourceLimit::NoFile(n));
+        self
+    }
+
+    /// Prevent the command from writing core dumps (`ulimit -c 0`).
+    pub fn disable_core_dumps(mut self) -> Self {
+        self.limits.push(ResourceLimit::Core(0));
+        self
+    }
+
+    /// Limit the size of files the command may create, in bytes (`ulimit -f`).
+    ///
+    /// A process exceeding the limit receives `SIGXFSZ`.
+    pub fn limit_fsize(mut self, bytes: u64) -> Self {
+        self.limits.push(ResourceLimit::FileSize(bytes));
+        self
+    }
+
+    /// Limit the virtual memory the command may use, in bytes (`ulimit -v`).
+    ///
+    /// Allocations beyond the limit fail, which usually makes the program exit with an error.
+    pub fn limit_memory(mut self, bytes: u64) -> Self {
+        self.limits.push(ResourceLimit::Memory(bytes));
+        self
+    }
+
+    /// Limit the CPU time the command may use (`ulimit -t`), rounded up to whole seconds.
+    ///
+    /// A process exceeding the limit receives `SIGXCPU`, which terminates it by default.
+    pub fn limit_cpu_time(mut self, time: std::time::Duration) -> Self {
+        let seconds = time.as_secs() + u64::from(time.subsec_nanos() > 0);
+        self.limits.push(ResourceLimit::CpuTime(seconds));
+        self
+    }
+
+    /// Run the command with a scheduling priority, like `nice -n`.
+    ///
+    /// `niceness` ranges from -20 (highest priority) to 19 (lowest). Values below the current
+    /// niceness usually require root, and make the spawn fail otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Keep the machine responsive while the benchmarks run
+    /// cmd!("cargo", "bench").nice(10).ionice_idle().run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn nice(mut self, niceness: i32) -> Self {
+        self.limits.push(ResourceLimit::Nice(niceness));
+        self
+    }
+
+    /// Only let the command use the disk when no other process needs it, like `ionice -c 3`.
+    ///
+    /// Available on Linux, where it sets the idle I/O scheduling class.
+    #[cfg(target_os = "linux")]
+    pub fn ionice_idle(mut self) -> Self {
+        self.limits.push(ResourceLimit::IoIdle);
+        self
+    }
+
+    /// Run the command as the user with the given ID, like `setuid` before `exec`.
+    ///
+    /// Changing the user requires root. Set [`gid`](Self::gid) as well, or the command keeps
+    /// the current group. Supplementary groups are not changed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Build as an unprivileged user from a provisioning script running as root
+    /// cmd!("make").uid(1000).gid(1000).current_dir("/home/builder/src").run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn uid(mut self, uid: u32) -> Self {
+        self.uid = Some(uid);
+        self
+    }
+
+    /// Run the command with the given group ID, like `setgid` before `exec`.
+    ///
+    /// Changing the group requires root.
+    pub fn gid(mut self, gid: u32) -> Self {
+        self.gid = Some(gid);
+        self
+    }
+
+    /// Set the file mode creation mask of the command, like `umask` in a shell.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Files created by the command are only readable by the owner
+    /// cmd!("gpg", "--output", "secrets.txt", "--decrypt", "secrets.gpg").umask(0o077).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn umask(mut self, mask: u32) -> Self {
+        self.umask = Some(mask);
+        self
+    }
+
+    /// Echo the command without running it.
+    ///
+    /// The command succeeds without output, as if it were `true`, so a script can be tried out
+    /// to see what it would do. Only this command is affected: file system operations and
+    /// other commands still run.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let dry_run = std::env::args().any(|arg| arg == "--dry-run");
+    /// let mut rsync = cmd!("rsync", "-a", "--delete", "dist/", "web:/srv/www/");
+    /// if dry_run {
+    ///     rsync = rsync.dry_run();
+    /// }
+    /// rsync.run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Customize the underlying [`std::process::Command`] before it is spawned.
+    ///
+    /// This is an escape hatch for process options scripty does not model, such as
+    /// [`arg0`](std::os::unix::process::CommandExt::arg0) or
+    /// [`pre_exec`](std::os::unix::process::CommandExt::pre_exec). The closure runs after all
+    /// other settings are applied, each time the command is spawned. Stdin, stdout and stderr
+    /// are set afterwards by the pipeline, so changing them here has no effect. Calling this
+    /// more than once applies the closures in order.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    /// use std::os::unix::process::CommandExt;
+    ///
+    /// // Run busybox as the applet it should act as
+    /// cmd!("/bin/busybox", "-la").with_std(|c| {
+    ///     c.arg0("ls");
+    /// }).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_std(
+        mut self,
+        customize: impl Fn(&mut std::process::Command) + Send + Sync + 'static,
+    ) -> Self {
+        self.std_hooks.push(StdHook(Arc::new(customize)));
+        self
+    }
+
+    /// Place the command into an existing cgroup (Linux cgroup v2), so that the cgroup's memory
+    /// and CPU limits apply to it and to everything it spawns.
+    ///
+    /// `path` is the cgroup directory, such as `/sys/fs/cgroup/build.slice`. The child joins the
+    /// cgroup before it starts, by writing to `cgroup.procs`. This takes precedence over a
+    /// cgroup created by [`cgroup::scope`](crate::cgroup::scope).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// cmd!("make", "-j8").cgroup("/sys/fs/cgroup/build.slice").run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn cgroup(mut self, path: impl AsRef<Path>) -> Self {
+        self.cgroup = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// The cgroup the command is placed into: its own, or the one of the active
+    /// [`cgroup::scope`](crate::cgroup::scope).
+    pub(crate) fn effective_cgroup(&self) -> Option<PathBuf> {
+        self.cgroup.clone().or_else(crate::cgroup::current_scope)
+    }
+
+    /// Run the command with a fresh, temporary `HOME` directory.
+    ///
+    /// `HOME`, `XDG_CONFIG_HOME`, `XDG_CACHE_HOME`, `XDG_DATA_HOME`, and `XDG_STATE_HOME` point
+    /// into a new temporary directory, so tools with global configuration (git, gh, npm) cannot
+    /// see or modify the user's real configuration. The directory is created immediately so it
+    /// can be pre-seeded through [`home_dir`](Self::home_dir), and removed after the command
+    /// finishes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let git = cmd!("git", "config", "--global", "user.name").isolated_home();
+    /// if let Some(home) = git.home_dir() {
+    ///     fs::write(home.join(".gitconfig"), "[user]\n\tname = CI\n")?;
+    /// }
+    /// let name = git.output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn isolated_home(mut self) -> Self {
+        let home = crate::fs::temp_dir_scoped().and_then(|home| {
+            for (_, dir) in IsolatedHome::env_vars(home.path()).iter().skip(1) {
+                std::fs::create_dir_all(dir)?;
+            }
+            Ok(home)
+        });
+        self.isolated_home = Some(match home {
+            Ok(home) => IsolatedHome::Ready(Arc::new(home)),
+            Err(e) => IsolatedHome::Failed(e),
+        });
+        self
+    }
+
+    /// Returns the temporary home directory created by [`isolated_home`](Self::isolated_home).
+    pub fn home_dir(&self) -> Option<&Path> {
+        match &self.isolated_home {
+            Some(IsolatedHome::Ready(home)) => Some(home.path()),
+            _ => None,
+        }
+    }
+
+    /// Run the command on another host through `ssh`.
+    ///
+    /// The program, arguments, environment variables and working directory set so far become a
+    /// remote command line, quoted with [`shell_quote`](crate::shell_quote) so the remote shell
+    /// receives every argument unchanged, and the command is replaced by
+    /// `ssh -- <host> '<command line>'`. The echo shows the host and the remote command.
+    /// [`input`](Self::input) and pipelines are forwarded to the remote command's stdin, and its
+    /// exit code is returned by `ssh`.
+    ///
+    /// Methods called after `via_ssh()` configure the local `ssh` process, so set the remote
+    /// environment and directory before it. `host` may include a user (`deploy@example.com`), and
+    /// ssh options can be set in `~/.ssh/config`.
+    ///
+    /// The command fails when it is run if a variable was set with
+    /// [`env_secret`](Self::env_secret), since the remote command line is visible in the process
+    /// list of both hosts; pass secrets through the remote stdin instead. It also fails if the
+    /// program, an argument, a variable or the directory is not valid UTF-8, which the remote
+    /// command line cannot hold unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Runs: ssh -- deploy@example.com 'cd /srv/app && env RELEASE=v1.4 ./restart.sh blue'
+    /// cmd!("./restart.sh", "blue")
+    ///     .env("RELEASE", "v1.4")
+    ///     .current_dir("/srv/app")
+    ///     .via_ssh("deploy@example.com")
+    ///     .run()?;
+    ///
+    /// // Stream a local file to the remote stdin
+    /// let remote = cmd!("tee", "/srv/app/config.toml").via_ssh("deploy@example.com");
+    /// std::fs::File::open("config.toml")?.pipe(remote).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn via_ssh(mut self, host: impl AsRef<OsStr>) -> Self {
+        let mut secret = false;
+        for (key, val) in &mut self.envs {
+            if self.secret_envs.contains(key) {
+                // The command will not run; keep the value out of its echo
+                *val = OsString::from(crate::echo::MASK);
+                secret = true;
+            }
+        }
+        if secret {
+            self.invalid.get_or_insert_with(|| {
+                "env_secret() values cannot be passed through via_ssh(), which would show them \
+                 in the process list"
+                    .to_string()
+            });
+        }
+
+        let words = self.shell_words();
+        let dir = self.current_dir.take();
+        if let Some(word) = words
+            .iter()
+            .map(OsString::as_os_str)
+            .chain(dir.as_ref().map(|dir| dir.as_os_str()))
+            .find(|word| word.to_str().is_none())
+        {
+            self.invalid.get_or_insert_with(|| {
+                format!(
+                    "{} is not valid UTF-8 and cannot be passed through via_ssh()",
+                    word.to_string_lossy()
+                )
+            });
+        }
+        let mut remote = crate::shell_join(words);
+        if let Some(dir) = dir {
+            remote = format!("cd {} && {remote}", crate::shell_quote(&dir));
+        }
+
+        self.program = OsString::from("ssh");
+        self.args = vec![
+            OsString::from(operands::END_OF_OPTIONS),
+            host.as_ref().to_os_string(),
+            OsString::from(remote),
+        ];
+        self.envs.clear();
+        self.env_removes.clear();
+        self.secret_envs.clear();
+        self.auto_end_of_options = false;
+        self
+    }
+
+    /// The words of the command as run by a shell: the program and arguments, behind
+    /// `env -u <name> <name>=<value>` when environment variables are set or removed.
+    pub(crate) fn shell_words(&self) -> Vec<OsString> {
+        let mut words = Vec::new();
+        if !self.envs.is_empty() || !self.env_removes.is_empty() {
+            words.push(OsString::from("env"));
+            for key in &self.env_removes {
+                words.push(OsString::from("-u"));
+                words.push(key.clone());
+            }
+            for (key, val) in &self.envs {
+                let mut assignment = key.clone();
+                assignment.push("=");
+                assignment.push(val);
+                words.push(assignment);
+            }
+        }
+        words.push(self.program.clone());
+        words.extend(self.effective_args().iter().cloned());
+        words
+    }
+
+    /// Run the command as root through `sudo`.
+    ///
+    /// The command becomes `sudo -- <program> <args>`, so the echo shows the elevation and the
+    /// arguments are passed to the program unchanged. Environment variables set with
+    /// [`env`](Self::env) are kept with `--preserve-env=<names>`, which the sudoers policy must
+    /// allow; other variables are reset by `sudo` as usual.
+    ///
+    /// When stdin is a terminal, `sudo` can ask for a password on the terminal. Otherwise `-n`
+    /// is added, so the command fails instead of waiting for a password that cannot be entered.
+    /// When the process already runs as root, the command is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Runs: sudo --preserve-env=DEBIAN_FRONTEND -- apt-get install -y jq
+    /// cmd!("apt-get", "install", "-y", "jq")
+    ///     .env("DEBIAN_FRONTEND", "noninteractive")
+    ///     .sudo()
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn sudo(self) -> Self {
+        // SAFETY: geteuid has no preconditions and cannot fail
+        if unsafe { libc::geteuid() } == 0 {
+            return self;
+        }
+        self.with_sudo(!std::io::stdin().is_terminal())
+    }
+
+    /// Wrap the command in `sudo`, with `-n` when a password cannot be entered.
+    pub(crate) fn with_sudo(mut self, non_interactive: bool) -> Self {
+        let mut args = Vec::new();
+        if non_interactive {
+            args.push(OsString::from("-n"));
+        }
+        if !self.envs.is_empty() {
+            let mut names: Vec<&OsStr> = Vec::new();
+            for (key, _) in &self.envs {
+                if !names.contains(&key.as_os_str()) {
+                    names.push(key);
+                }
+            }
+            let mut preserve = OsString::from("--preserve-env=");
+            preserve.push(names.join(OsStr::new(",")));
+            args.push(preserve);
+        }
+        args.push(OsString::from(operands::END_OF_OPTIONS));
+        args.push(self.program.clone());
+        args.extend(self.effective_args().iter().cloned());
+
+        self.program = OsString::from("sudo");
+        self.args = args;
+        self.auto_end_of_options = false;
+        self
+    }
+
+    /// The arguments passed to the program, including an automatically inserted `--`.
+    pub(crate) fn effective_args(&self) -> Cow<'_, [OsString]> {
+        if self.auto_end_of_options && operands::is_known_program(&self.program) {
+            if let Some(args) =
+                operands::insert_end_of_options(&self.args, self.current_dir.as_deref())
+            {
+                return Cow::Owned(args);
+            }
+        }
+        Cow::Borrowed(&self.args)
+    }
+
+    /// An in-process pipeline stage, shown in the echo under its description.
+    pub(crate) fn in_process(filter: LineFilter) -> Self {
+        let mut cmd = Cmd::new(filter.describe());
+        cmd.filter = Some(filter);
+        cmd
+    }
+
     /// Convert this command into a single-command pipeline.
     pub(crate) fn into_pipeline(self) -> Pipeline {
         let suppress_echo = self.suppress_echo;
         Pipeline {
             connections: vec![(self, PipeMode::Stdout)],
             input: None,
+            input_path: None,
             suppress_echo,
+            max_output: None,
+            io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
+            ignore_broken_pipe: true,
+            stage_stderr: StderrMode::Inherit,
+            stage_timeout: None,
+            deadline: None,
+            record_input: None,
+            progress: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
         }
     }
 
+    /// Set where the command's stdin comes from.
+    ///
+    /// By default stdin is a pipe, which is used for [`input`](Self::input) and by the
+    /// `spawn_io_*` methods. In a pipeline this only affects the first command, and input set
+    /// with [`input`](Self::input) takes precedence.
+    pub fn stdin(mut self, config: StdioConfig) -> Self {
+        self.stdin = config;
+        self
+    }
+
+    /// Let the command read from the parent's stdin, typically the terminal.
+    ///
+    /// Needed for interactive programs such as `ssh`, `vim`, or password prompts.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// cmd!("ssh", "build-host", "uptime").stdin_inherit().run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stdin_inherit(self) -> Self {
+        self.stdin(StdioConfig::Inherit)
+    }
+
+    /// Connect the command's stdin to `/dev/null`.
+    ///
+    /// Shown in the echo as `< /dev/null`.
+    pub fn stdin_null(self) -> Self {
+        self.stdin(StdioConfig::Null)
+    }
+
+    /// Send the command's stdout to `/dev/null`, like `> /dev/null` in a shell.
+    ///
+    /// Unlike [`quiet`](Self::quiet), this also applies when stdout would be piped to the next
+    /// command or captured, which then see no output. Shown in the echo as `> /dev/null`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Only the errors of a noisy build are shown
+    /// cmd!("make", "-j8").stdout_null().run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stdout_null(mut self) -> Self {
+        self.stdout_null = true;
+        self
+    }
+
+    /// Send the command's stderr to `/dev/null`, like `2> /dev/null` in a shell.
+    ///
+    /// Failures then have no stderr to show in the error. Shown in the echo as `2> /dev/null`.
+    pub fn stderr_null(mut self) -> Self {
+        self.stderr_null = true;
+        self
+    }
+
+    /// Send the command's stderr to wherever its stdout goes, like `2>&1` in a shell.
+    ///
+    /// Unlike [`pipe_out_err`](Self::pipe_out_err), which merges the streams when handing them to
+    /// the next command, this applies to the command itself, so the last or only command of a
+    /// pipeline can return both streams from [`output`](Self::output). With
+    /// [`stdout_null`](Self::stdout_null), both streams are discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Keep the full log of a build, with warnings and errors in order
+    /// let log = cmd!("cargo", "build").combine_err().output()?;
+    /// fs::write("build.log", log)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn combine_err(mut self) -> Self {
+        self.combine_err = true;
+        self
+    }
+
     /// Set binary input data for the command.
     /// Accepts `Vec<u8>`, `&[u8]`, or other types that can be converted to `Vec<u8>`.
     pub fn input_bytes(self, input: impl AsRef<[u8]>) -> Pipeline {
@@ -70,18 +783,137 @@ impl Cmd {
         self.into_pipeline().input_bytes_owned(bytes)
     }
 
+    /// Stream a file to the command's stdin, memory-mapped with the `mmap` feature.
+    ///
+    /// See [`Pipeline::input_mmap`] for details.
+    pub fn input_mmap(self, path: impl AsRef<Path>) -> std::io::Result<Pipeline> {
+        self.into_pipeline().input_mmap(path)
+    }
+
+    /// Generate the command's stdin with a closure, run on the thread that feeds the input.
+    ///
+    /// See [`Pipeline::input_with`] for details.
+    pub fn input_with(
+        self,
+        write: impl FnOnce(&mut dyn Write) -> std::io::Result<()> + Send + 'static,
+    ) -> Pipeline {
+        self.into_pipeline().input_with(write)
+    }
+
     /// Set text input for the command.
     /// Optimized to convert string directly to bytes without intermediate allocation.
     pub fn input(self, input: impl AsRef<str>) -> Pipeline {
         self.into_pipeline().input(input)
     }
 
+    /// Set text input for the command, shown in the echo as a heredoc.
+    ///
+    /// See [`Pipeline::input_heredoc`] for details.
+    pub fn input_heredoc(self, text: impl AsRef<str>) -> Pipeline {
+        self.into_pipeline().input_heredoc(text)
+    }
+
+    /// Copy the input fed to the command to `writer` as well.
+    ///
+    /// See [`Pipeline::record_input_to`] for details.
+    pub fn record_input_to(self, writer: impl Write + Send + 'static) -> Pipeline {
+        self.into_pipeline().record_input_to(writer)
+    }
+
+    /// Fail if the command writes more than `bytes` bytes to stdout.
+    ///
+    /// See [`Pipeline::max_output`] for details.
+    pub fn max_output(self, bytes: usize) -> Pipeline {
+        self.into_pipeline().max_output(bytes)
+    }
+
+    /// Keep only the first `bytes` bytes the command writes to stdout.
+    ///
+    /// See [`Pipeline::max_output_truncate`] for details.
+    pub fn max_output_truncate(self, bytes: usize) -> Pipeline {
+        self.into_pipeline().max_output_truncate(bytes)
+    }
+
+    /// Set the size of the buffers used to copy the command's input and output.
+    ///
+    /// See [`Pipeline::io_buffer_size`] for details.
+    pub fn io_buffer_size(self, bytes: usize) -> Pipeline {
+        self.into_pipeline().io_buffer_size(bytes)
+    }
+
+    /// Set whether the command exiting before reading all of its input is an error.
+    ///
+    /// See [`Pipeline::ignore_broken_pipe`] for details.
+    pub fn ignore_broken_pipe(self, ignore: bool) -> Pipeline {
+        self.into_pipeline().ignore_broken_pipe(ignore)
+    }
+
+    /// Kill the command if it is still running after `timeout`.
+    ///
+    /// See [`Pipeline::stage_timeout`] for details.
+    pub fn stage_timeout(self, timeout: std::time::Duration) -> Pipeline {
+        self.into_pipeline().stage_timeout(timeout)
+    }
+
+    /// Kill the command if it has not finished by `deadline`.
+    ///
+    /// See [`Pipeline::deadline`] for details.
+    pub fn deadline(self, deadline: std::time::Instant) -> Pipeline {
+        self.into_pipeline().deadline(deadline)
+    }
+
+    /// Call `callback` with the bytes the command has read and written so far while it runs.
+    ///
+    /// See [`Pipeline::on_progress`] for details.
+    pub fn on_progress(self, callback: impl FnMut(&ProgressEvent) + Send + 'static) -> Pipeline {
+        self.into_pipeline().on_progress(callback)
+    }
+
+    /// Set how often the [`on_progress`](Self::on_progress) callback is called.
+    ///
+    /// See [`Pipeline::progress_interval`] for details.
+    pub fn progress_interval(self, interval: std::time::Duration) -> Pipeline {
+        self.into_pipeline().progress_interval(interval)
+    }
+
     /// Run without echoing the command.
     pub fn no_echo(mut self) -> Self {
         self.suppress_echo = true;
         self
     }
 
+    /// Echo the command's arguments in full, however long, instead of shortening them as set
+    /// with [`echo::set_max_arg_width`](crate::echo::set_max_arg_width).
+    pub fn echo_full_args(mut self) -> Self {
+        self.echo_full_args = true;
+        self
+    }
+
+    /// Discard the command's stdout and stderr by sending them to `/dev/null`.
+    ///
+    /// Streams that are piped to another command or captured (as by [`output`](Self::output))
+    /// are unaffected. Failures are still reported through the returned status, and `run()`
+    /// keeps the end of stderr for the error (see [`Error::stderr`]). To silence every command,
+    /// use [`set_verbosity`](crate::set_verbosity).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// cmd!("cargo", "fetch").quiet().run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    /// Whether the command's output is discarded, by [`quiet`](Self::quiet) or the verbosity.
+    pub(crate) fn is_quiet(&self) -> bool {
+        self.quiet || crate::output::verbosity() == crate::output::Verbosity::Quiet
+    }
+
     /// Pipe this command's stdout to another command's stdin.
     ///
     /// This is the standard Unix pipe behavior where stdout becomes stdin.
@@ -102,7 +934,17 @@ impl Cmd {
         Pipeline {
             connections: vec![(self, PipeMode::Stdout), (next, PipeMode::Stdout)],
             input: None,
+            input_path: None,
             suppress_echo,
+            max_output: None,
+            io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
+            ignore_broken_pipe: true,
+            stage_stderr: StderrMode::Inherit,
+            stage_timeout: None,
+            deadline: None,
+            record_input: None,
+            progress: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
         }
     }
 
@@ -135,7 +977,17 @@ impl Cmd {
         Pipeline {
             connections: vec![(self, PipeMode::Stdout), (next, PipeMode::Stderr)],
             input: None,
+            input_path: None,
             suppress_echo,
+            max_output: None,
+            io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
+            ignore_broken_pipe: true,
+            stage_stderr: StderrMode::Inherit,
+            stage_timeout: None,
+            deadline: None,
+            record_input: None,
+            progress: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
         }
     }
 
@@ -160,31 +1012,231 @@ impl Cmd {
         Pipeline {
             connections: vec![(self, PipeMode::Stdout), (next, PipeMode::Both)],
             input: None,
+            input_path: None,
+            suppress_echo,
+            max_output: None,
+            io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
+            ignore_broken_pipe: true,
+            stage_stderr: StderrMode::Inherit,
+            stage_timeout: None,
+            deadline: None,
+            record_input: None,
+            progress: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
+        }
+    }
+
+    /// Pipe this command to another command, connecting the streams given by `mode`.
+    ///
+    /// Equivalent to [`pipe_out`](Self::pipe_out), [`pipe_err`](Self::pipe_err) or
+    /// [`pipe_out_err`](Self::pipe_out_err), for code that chooses the mode at runtime.
+    pub fn pipe_with(self, next: Cmd, mode: PipeMode) -> Pipeline {
+        let suppress_echo = self.suppress_echo || next.suppress_echo;
+        Pipeline {
+            connections: vec![(self, PipeMode::Stdout), (next, mode)],
+            input: None,
+            input_path: None,
             suppress_echo,
+            max_output: None,
+            io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
+            ignore_broken_pipe: true,
+            stage_stderr: StderrMode::Inherit,
+            stage_timeout: None,
+            deadline: None,
+            record_input: None,
+            progress: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
         }
     }
 
+    /// Keep only the output lines for which `keep` returns `true`, without running `grep`.
+    ///
+    /// See [`Pipeline::filter_lines`] for details.
+    pub fn filter_lines(self, keep: impl Fn(&str) -> bool + Send + Sync + 'static) -> Pipeline {
+        self.into_pipeline().filter_lines(keep)
+    }
+
+    /// Replace each output line with the result of `map`, without running `sed`.
+    ///
+    /// See [`Pipeline::map_lines`] for details.
+    pub fn map_lines(self, map: impl Fn(&str) -> String + Send + Sync + 'static) -> Pipeline {
+        self.into_pipeline().map_lines(map)
+    }
+
+    /// Keep the first `n` output lines, without running `head`.
+    ///
+    /// See [`Pipeline::head`] for details.
+    pub fn head(self, n: usize) -> Pipeline {
+        self.into_pipeline().head(n)
+    }
+
+    /// Keep the last `n` output lines, without running `tail`.
+    ///
+    /// See [`Pipeline::tail`] for details.
+    pub fn tail(self, n: usize) -> Pipeline {
+        self.into_pipeline().tail(n)
+    }
+
     /// Run the command and return the exit status.
     pub fn run(self) -> Result<(), Error> {
         self.into_pipeline().run()
     }
 
+    /// Run the command, showing each line of its output with `prefix` in front.
+    ///
+    /// See [`Pipeline::run_prefixed`] for details.
+    pub fn run_prefixed(self, prefix: &str) -> Result<(), Error> {
+        self.into_pipeline().run_prefixed(prefix)
+    }
+
     /// Get binary output from the command.
     pub fn output_bytes(self) -> Result<Vec<u8>, Error> {
         self.into_pipeline().output_bytes()
     }
 
     /// Get text output from the command.
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`. Use [`output_utf8`](Self::output_utf8)
+    /// to fail on invalid UTF-8 instead.
     pub fn output(self) -> Result<String, Error> {
         self.into_pipeline().output()
     }
 
+    /// Get text output from the command, replacing invalid UTF-8 sequences with `U+FFFD`.
+    pub fn output_lossy(self) -> Result<String, Error> {
+        self.into_pipeline().output_lossy()
+    }
+
+    /// Get text output from the command, returning an error if it is not valid UTF-8.
+    ///
+    /// Prefer this over [`output`](Self::output) when the output must be machine-readable.
+    pub fn output_utf8(self) -> Result<String, Error> {
+        self.into_pipeline().output_utf8()
+    }
+
+    /// Get text output from the command with leading and trailing whitespace removed.
+    pub fn output_trimmed(self) -> Result<String, Error> {
+        self.into_pipeline().output_trimmed()
+    }
+
+    /// Get text output from the command as lines.
+    ///
+    /// See [`Pipeline::output_lines`] for details.
+    pub fn output_lines(self) -> Result<Vec<String>, Error> {
+        self.into_pipeline().output_lines()
+    }
+
+    /// Parse the command's trimmed text output with [`FromStr`](std::str::FromStr).
+    ///
+    /// See [`Pipeline::output_parse`] for details.
+    pub fn output_parse<T>(self) -> Result<T, Error>
+    where
+        T: std::str::FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.into_pipeline().output_parse()
+    }
+
+    /// Get output from the command as an `OsString`, keeping bytes that are not valid UTF-8.
+    ///
+    /// See [`Pipeline::output_os`] for details.
+    pub fn output_os(self) -> Result<OsString, Error> {
+        self.into_pipeline().output_os()
+    }
+
+    /// Get output from the command as lines of `OsString`, keeping bytes that are not valid
+    /// UTF-8.
+    ///
+    /// See [`Pipeline::output_os_lines`] for details.
+    pub fn output_os_lines(self) -> Result<Vec<OsString>, Error> {
+        self.into_pipeline().output_os_lines()
+    }
+
+    /// Get the text of both stdout and stderr from the command, as `(stdout, stderr)`.
+    ///
+    /// See [`Pipeline::output_with_stderr`] for details.
+    pub fn output_with_stderr(self) -> Result<(String, String), Error> {
+        self.into_pipeline().output_with_stderr()
+    }
+
+    /// Capture stdout and stderr together, as chunks tagged with the stream they came from, in
+    /// the order they arrived.
+    ///
+    /// See [`Pipeline::output_interleaved`] for details.
+    pub fn output_interleaved(self) -> Result<Vec<(StreamTag, Vec<u8>)>, Error> {
+        self.into_pipeline().output_interleaved()
+    }
+
+    /// Parse the command's stdout as JSON. Requires the `serde` feature.
+    ///
+    /// See [`Pipeline::output_json`] for details.
+    #[cfg(feature = "serde")]
+    pub fn output_json<T: serde::de::DeserializeOwned>(self) -> Result<T, Error> {
+        self.into_pipeline().output_json()
+    }
+
+    /// Get binary output from the command as a shared, reference-counted buffer.
+    ///
+    /// See [`Pipeline::output_shared`] for details.
+    pub fn output_shared(self) -> Result<std::sync::Arc<[u8]>, Error> {
+        self.into_pipeline().output_shared()
+    }
+
+    /// Capture stdout until a line matches `ready`, then return with the command still running.
+    ///
+    /// See [`Pipeline::output_until`] for details.
+    pub fn output_until(self, ready: impl FnMut(&str) -> bool) -> Result<RunningPipeline, Error> {
+        self.into_pipeline().output_until(ready)
+    }
+
     /// Stream command's stdout to a Writer.
     /// This is more memory-efficient for large outputs.
     pub fn write_to<W: Write>(self, writer: W) -> Result<(), Error> {
         self.into_pipeline().write_to(writer)
     }
 
+    /// Stream command's stdout to a writer as the final step, like a shell `>` redirection.
+    ///
+    /// See [`Pipeline::pipe_to`] for details.
+    pub fn pipe_to<W: Write + 'static>(self, writer: W) -> Result<(), Error> {
+        self.into_pipeline().pipe_to(writer)
+    }
+
+    /// Write the command's stdout to a file, like a shell `>` redirection.
+    ///
+    /// See [`Pipeline::to_file`] for details.
+    pub fn to_file(self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.into_pipeline().to_file(path)
+    }
+
+    /// Append the command's stdout to a file, like a shell `>>` redirection.
+    ///
+    /// See [`Pipeline::append_file`] for details.
+    pub fn append_file(self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.into_pipeline().append_file(path)
+    }
+
+    /// Duplicate the command's stdout into several downstream pipelines.
+    ///
+    /// See [`Pipeline::tee_pipe`] for details.
+    pub fn tee_pipe<I, P>(self, branches: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Pipeline>,
+    {
+        self.into_pipeline().tee_pipe(branches)
+    }
+
+    /// Run a command for each line of this command's stdout, like `xargs -n1 -P jobs`.
+    ///
+    /// See [`Pipeline::for_each_line_spawn`] for details.
+    pub fn for_each_line_spawn<F>(self, jobs: usize, make_cmd: F) -> Result<(), Error>
+    where
+        F: FnMut(&str) -> Cmd,
+    {
+        self.into_pipeline().for_each_line_spawn(jobs, make_cmd)
+    }
+
     /// Stream command's stderr to a Writer.
     /// This is useful for capturing error output separately.
     pub fn write_err_to<W: Write>(self, writer: W) -> Result<(), Error> {
@@ -193,10 +1245,17 @@ impl Cmd {
 
     /// Stream command's combined stdout and stderr to a Writer.
     /// This merges both output streams into the writer.
-    pub fn write_both_to<W: Write + Send + 'static>(self, writer: W) -> Result<(), Error> {
+    pub fn write_both_to<W: Write>(self, writer: W) -> Result<(), Error> {
         self.into_pipeline().write_both_to(writer)
     }
 
+    /// Stream the command's stdout and stderr to two separate writers.
+    ///
+    /// See [`Pipeline::write_split_to`] for details.
+    pub fn write_split_to<W: Write, E: Write>(self, stdout: W, stderr: E) -> Result<(), Error> {
+        self.into_pipeline().write_split_to(stdout, stderr)
+    }
+
     /// Run the command with both input Reader and output Writer.
     /// This is the most flexible method for streaming I/O.
     pub fn run_with_io<R: Read + Send + 'static, W: Write>(
@@ -219,7 +1278,7 @@ impl Cmd {
 
     /// Run the command with input Reader and combined stdout+stderr Writer.
     /// This merges both output streams for comprehensive logging.
-    pub fn run_with_both_io<R: Read + Send + 'static, W: Write + Send + 'static>(
+    pub fn run_with_both_io<R: Read + Send + 'static, W: Write>(
         self,
         reader: R,
         writer: W,
@@ -227,6 +1286,19 @@ impl Cmd {
         self.into_pipeline().run_with_both_io(reader, writer)
     }
 
+    /// Run the command with an input Reader, a stdout Writer and a stderr Writer.
+    ///
+    /// See [`Pipeline::run_with_io_full`] for details.
+    pub fn run_with_io_full<R: Read + Send + 'static, W: Write, E: Write>(
+        self,
+        reader: R,
+        stdout: W,
+        stderr: E,
+    ) -> Result<(), Error> {
+        self.into_pipeline()
+            .run_with_io_full(reader, stdout, stderr)
+    }
+
     /// Spawn the command with full I/O control.
     pub fn spawn_io_all(self) -> Result<PipelineSpawn, Error> {
         self.into_pipeline().spawn_io_all()
@@ -298,7 +1370,8 @@ impl Cmd {
     /// Quotes an argument for display purposes only if it contains characters that affect readability.
     ///
     /// **IMPORTANT**: This function is for visual display only and should NOT be used for
-    /// shell escaping or security purposes. It focuses on readability rather than shell compatibility.
+    /// shell escaping or security purposes. It focuses on readability rather than shell compatibility;
+    /// use [`shell_quote`](crate::shell_quote) to quote for a shell.
     ///
     /// Display behavior:
     /// - Arguments with spaces or control characters: wrapped in single quotes with escaping
@@ -374,4 +1447,44 @@ impl Cmd {
         // No quoting needed
         arg_str.to_string()
     }
+
+    /// Shortens a quoted argument for display, keeping its start and its last two characters.
+    ///
+    /// Returns `None` if the argument already fits within `max_width` characters. The result is
+    /// at most `max_width` characters long, with `...` marking the omitted middle part.
+    pub(crate) fn truncate_argument(quoted: &str, max_width: usize) -> Option<String> {
+        const ELLIPSIS: &str = "...";
+        const TAIL: usize = 2;
+
+        let len = quoted.chars().count();
+        if len <= max_width {
+            return None;
+        }
+
+        let head = max_width.saturating_sub(ELLIPSIS.len() + TAIL);
+        let tail: String = quoted.chars().skip(len - TAIL).collect();
+        let head: String = quoted.chars().take(head).collect();
+        Some(format!("{head}{ELLIPSIS}{tail}"))
+    }
+
+    /// Formats a byte count for display, e.g. `512 B`, `4.2 KB` or `1.5 MB`.
+    pub(crate) fn format_size(bytes: usize) -> String {
+        const KB: f64 = 1024.0;
+        const MB: f64 = KB * 1024.0;
+
+        let size = bytes as f64;
+        if size < KB {
+            format!("{bytes} B")
+        } else if size < MB {
+            format!("{:.1} KB", size / KB)
+        } else {
+            format!("{:.1} MB", size / MB)
+        }
+    }
+}
+
+impl From<Cmd> for Pipeline {
+    fn from(cmd: Cmd) -> Self {
+        cmd.into_pipeline()
+    }
 }
@@ -1,10 +1,17 @@
 //! Error handling for command execution.
 
+use std::os::unix::process::ExitStatusExt;
+
 /// Command execution error.
 #[derive(Debug)]
 pub struct Error {
     pub(crate) message: String,
+    pub(crate) command: Option<String>,
     pub(crate) source: Option<std::io::Error>,
+    /// End of the failed command's stderr, when it was captured.
+    pub(crate) stderr: Option<String>,
+    /// How the failed command exited.
+    pub(crate) status: Option<std::process::ExitStatus>,
 }
 
 impl Error {
@@ -13,24 +20,41 @@ impl Error {
     pub(crate) fn command_not_found(command: &str) -> Self {
         Error {
             message: format!("Command not found: {}", command),
+            command: None,
             source: None,
+            stderr: None,
+            status: None,
         }
     }
 
-    /// Creates an error for a command that failed with an exit code.
-    pub(crate) fn exit_code(code: Option<i32>) -> Self {
+    /// Creates an error for a command that exited unsuccessfully, with an exit code or from a
+    /// signal.
+    pub(crate) fn exit_status(status: std::process::ExitStatus) -> Self {
+        let message = match (status.code(), status.signal()) {
+            (Some(code), _) => format!("Command failed (exit code {})", code),
+            (None, Some(signal)) if status.core_dumped() => {
+                format!("Command killed by {} (core dumped)", signal_name(signal))
+            }
+            (None, Some(signal)) => format!("Command killed by {}", signal_name(signal)),
+            (None, None) => "Command failed (no exit code)".to_string(),
+        };
         Error {
-            message: format!("Command failed with exit code: {:?}", code),
+            message,
+            command: None,
             source: None,
+            stderr: None,
+            status: Some(status),
         }
     }
 
     /// Creates an error for an invalid or empty command.
-    #[allow(dead_code)]
     pub(crate) fn invalid_command(reason: &str) -> Self {
         Error {
             message: format!("Invalid command: {}", reason),
+            command: None,
             source: None,
+            stderr: None,
+            status: None,
         }
     }
 
@@ -38,7 +62,88 @@ impl Error {
     pub(crate) fn io(message: &str, source: std::io::Error) -> Self {
         Error {
             message: message.to_string(),
+            command: None,
             source: Some(source),
+            stderr: None,
+            status: None,
+        }
+    }
+
+    /// Creates an error for output that is not valid UTF-8.
+    pub(crate) fn invalid_utf8(source: std::string::FromUtf8Error) -> Self {
+        Error {
+            message: "Command output is not valid UTF-8".to_string(),
+            command: None,
+            source: Some(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                source.utf8_error(),
+            )),
+            stderr: None,
+            status: None,
+        }
+    }
+
+    /// Creates an error for output that could not be parsed as JSON, quoting the start of it.
+    #[cfg(feature = "serde")]
+    pub(crate) fn invalid_json(source: serde_json::Error, output: &[u8]) -> Self {
+        let snippet = Self::snippet(&String::from_utf8_lossy(output));
+        Error {
+            message: format!("Command output is not valid JSON (output: {snippet:?})"),
+            command: None,
+            source: Some(std::io::Error::new(std::io::ErrorKind::InvalidData, source)),
+            stderr: None,
+            status: None,
+        }
+    }
+
+    /// Creates an error for output that could not be parsed as a `type_name`, quoting the
+    /// start of it.
+    pub(crate) fn invalid_output(
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+        type_name: &str,
+        output: &str,
+    ) -> Self {
+        let snippet = Self::snippet(output);
+        Error {
+            message: format!("Command output is not a valid {type_name} (output: {snippet:?})"),
+            command: None,
+            source: Some(std::io::Error::new(std::io::ErrorKind::InvalidData, source)),
+            stderr: None,
+            status: None,
+        }
+    }
+
+    /// The start of `output` for an error message, trimmed.
+    fn snippet(output: &str) -> String {
+        const SNIPPET_CHARS: usize = 200;
+
+        let output = output.trim();
+        let mut snippet: String = output.chars().take(SNIPPET_CHARS).collect();
+        if snippet.len() < output.len() {
+            snippet.push_str("...");
+        }
+        snippet
+    }
+
+    /// Creates an error for output larger than the limit set with `max_output()`.
+    pub(crate) fn output_limit(limit: usize) -> Self {
+        Error {
+            message: format!("Command output exceeded the limit of {limit} bytes"),
+            command: None,
+            source: None,
+            stderr: None,
+            status: None,
+        }
+    }
+
+    /// Creates an error for a pipeline killed by `stage_timeout()` or `deadline()`.
+    pub(crate) fn timeout(reason: String) -> Self {
+        Error {
+            message: reason,
+            command: None,
+            source: None,
+            stderr: None,
+            status: None,
         }
     }
 
@@ -46,7 +151,10 @@ impl Error {
     pub(crate) fn no_stdout() -> Self {
         Error {
             message: "No stdout available to read from".to_string(),
+            command: None,
             source: None,
+            stderr: None,
+            status: None,
         }
     }
 
@@ -55,7 +163,10 @@ impl Error {
     pub(crate) fn no_stderr() -> Self {
         Error {
             message: "No stderr available to read from".to_string(),
+            command: None,
             source: None,
+            stderr: None,
+            status: None,
         }
     }
 
@@ -64,21 +175,176 @@ impl Error {
     pub(crate) fn mutex_poisoned() -> Self {
         Error {
             message: "Mutex was poisoned by a panicked thread".to_string(),
+            command: None,
             source: None,
+            stderr: None,
+            status: None,
+        }
+    }
+}
+
+impl Error {
+    /// Returns the rendered command line the error originated from, if known.
+    ///
+    /// This is the same (uncolored) text that the command echo shows, e.g.
+    /// `cargo clippy --all-targets -- -D warnings`.
+    pub fn command(&self) -> Option<&str> {
+        self.command.as_deref()
+    }
+
+    /// Returns the exit code of the failed command, or `None` if the error is not about a
+    /// command's exit status or the command was killed by a signal.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.status.and_then(|status| status.code())
+    }
+
+    /// Returns the number of the signal that killed the failed command, such as
+    /// `libc::SIGKILL`, or `None` if it exited by itself.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// if let Err(e) = cmd!("./fuzz-target").run() {
+    ///     if e.signal() == Some(11) {
+    ///         eprintln!("crashed with a segmentation fault");
+    ///     }
+    /// }
+    /// ```
+    pub fn signal(&self) -> Option<i32> {
+        self.status.and_then(|status| status.signal())
+    }
+
+    /// Returns the end of the failed command's stderr, if it was captured.
+    ///
+    /// `output()` and similar methods keep the last 8 KiB of the last command's stderr. `run()`
+    /// does so too when stderr is not a terminal or the command is [`quiet`](crate::Cmd::quiet),
+    /// while still passing the output on as it arrives.
+    pub fn stderr(&self) -> Option<&str> {
+        self.stderr.as_deref()
+    }
+
+    /// Attach the end of the command's stderr unless it is empty.
+    pub(crate) fn with_stderr(mut self, tail: &StderrTail) -> Self {
+        let text = tail.text();
+        if self.stderr.is_none() && !text.is_empty() {
+            self.stderr = Some(text);
+        }
+        self
+    }
+
+    /// Attach the rendered command line unless one is already present.
+    pub(crate) fn with_command(mut self, command: &str) -> Self {
+        if self.command.is_none() && !command.is_empty() {
+            self.command = Some(command.to_string());
         }
+        self
     }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.message)?;
+        if let Some(command) = &self.command {
+            write!(f, ": {}", command)?;
+        }
         if let Some(source) = &self.source {
             write!(f, ": {}", source)?;
         }
+        if let Some(stderr) = &self.stderr {
+            write!(f, "\nstderr:\n{}", stderr)?;
+        }
         Ok(())
     }
 }
 
+/// The name of `signal` with its number, such as `SIGSEGV (signal 11)`.
+fn signal_name(signal: i32) -> String {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        _ => return format!("signal {signal}"),
+    };
+    format!("{name} (signal {signal})")
+}
+
+/// Number of bytes at the end of stderr kept by [`StderrTail`].
+const STDERR_TAIL_BYTES: usize = 8 * 1024;
+
+/// The end of a command's stderr, kept to explain why the command failed.
+#[derive(Debug, Default)]
+pub(crate) struct StderrTail {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+impl StderrTail {
+    /// Read `stderr` to the end, passing everything on to `forward` and keeping the end.
+    pub(crate) fn collect(
+        mut stderr: impl std::io::Read,
+        mut forward: impl std::io::Write,
+    ) -> std::io::Result<Self> {
+        let mut tail = Self::default();
+        let mut buf = [0; 8192];
+        loop {
+            let n = match stderr.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            // Keep reading even if the output cannot be passed on, so the command never blocks
+            let _ = forward.write_all(&buf[..n]);
+            tail.push(&buf[..n]);
+        }
+        Ok(tail)
+    }
+
+    fn push(&mut self, chunk: &[u8]) {
+        self.bytes.extend_from_slice(chunk);
+        // Drop old output in batches rather than on every chunk
+        if self.bytes.len() > 2 * STDERR_TAIL_BYTES {
+            self.bytes.drain(..self.bytes.len() - STDERR_TAIL_BYTES);
+            self.truncated = true;
+        }
+    }
+
+    /// The kept text, starting at a full line with `...` when earlier output was dropped.
+    fn text(&self) -> String {
+        let start = self.bytes.len().saturating_sub(STDERR_TAIL_BYTES);
+        let mut bytes = &self.bytes[start..];
+        let truncated = self.truncated || start > 0;
+        if truncated {
+            if let Some(newline) = bytes.iter().position(|&b| b == b'\n') {
+                bytes = &bytes[newline + 1..];
+            }
+        }
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_end();
+        if truncated && !text.is_empty() {
+            format!("...\n{text}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         self.source.as_ref().map(|e| e as &dyn std::error::Error)
@@ -89,7 +355,10 @@ impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Error {
             message: "Command execution failed".to_string(),
+            command: None,
             source: Some(err),
+            stderr: None,
+            status: None,
         }
     }
 }
@@ -0,0 +1,179 @@
+//! In-process line filters that run as pipeline stages on a thread instead of a process.
+
+use crate::lines::LineStream;
+use std::collections::VecDeque;
+use std::io::{BufWriter, Read, Write};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// A pipeline stage implemented in Rust, such as `filter_lines()` or `head()`.
+#[derive(Clone)]
+pub(crate) enum LineFilter {
+    /// Keep the lines for which the predicate returns `true`.
+    Filter(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+    /// Replace every line with the result of the function.
+    Map(Arc<dyn Fn(&str) -> String + Send + Sync>),
+    /// Keep the first `n` lines.
+    Head(usize),
+    /// Keep the last `n` lines.
+    Tail(usize),
+}
+
+impl LineFilter {
+    /// How the stage is shown in the command echo.
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            LineFilter::Filter(_) => "filter_lines()".to_string(),
+            LineFilter::Map(_) => "map_lines()".to_string(),
+            LineFilter::Head(n) => format!("head({n})"),
+            LineFilter::Tail(n) => format!("tail({n})"),
+        }
+    }
+
+    /// The shell command doing the same, if there is one.
+    pub(crate) fn shell_command(&self) -> Option<String> {
+        match self {
+            LineFilter::Filter(_) | LineFilter::Map(_) => None,
+            LineFilter::Head(n) => Some(format!("head -n {n}")),
+            LineFilter::Tail(n) => Some(format!("tail -n {n}")),
+        }
+    }
+
+    /// Start the stage on a new thread, reading lines from `input` until it ends.
+    ///
+    /// The whole input is always read, even when the output is closed early or no longer
+    /// needed (as with `head`), so that upstream commands are not killed by `SIGPIPE`.
+    pub(crate) fn spawn(
+        self,
+        input: Option<impl Read + Send + 'static>,
+        output: impl Write + Send + 'static,
+    ) -> std::io::Result<JoinHandle<std::io::Result<()>>> {
+        std::thread::Builder::new()
+            .name("scripty-filter".to_string())
+            .spawn(move || match input {
+                Some(input) => self.run(input, IgnoreClosed::new(output)),
+                None => Ok(()),
+            })
+    }
+
+    fn run(&self, input: impl Read, output: impl Write) -> std::io::Result<()> {
+        let mut lines = LineStream::new(input);
+        let mut output = BufWriter::new(output);
+
+        match self {
+            LineFilter::Filter(keep) => {
+                while let Some(line) = lines.next_line()? {
+                    if keep(&String::from_utf8_lossy(line)) {
+                        output.write_all(line)?;
+                        output.write_all(b"\n")?;
+                    }
+                }
+            }
+            LineFilter::Map(map) => {
+                while let Some(line) = lines.next_line()? {
+                    output.write_all(map(&String::from_utf8_lossy(line)).as_bytes())?;
+                    output.write_all(b"\n")?;
+                }
+            }
+            LineFilter::Head(n) => {
+                let mut written = 0;
+                while let Some(line) = lines.next_line()? {
+                    if written < *n {
+                        output.write_all(line)?;
+                        output.write_all(b"\n")?;
+                        written += 1;
+                        if written == *n {
+                            output.flush()?;
+                        }
+                    }
+                }
+            }
+            LineFilter::Tail(n) => {
+                let mut last = VecDeque::with_capacity(*n);
+                while let Some(line) = lines.next_line()? {
+                    if *n == 0 {
+                        continue;
+                    }
+                    if last.len() == *n {
+                        last.pop_front();
+                    }
+                    last.push_back(line.to_vec());
+                }
+                for line in last {
+                    output.write_all(&line)?;
+                    output.write_all(b"\n")?;
+                }
+            }
+        }
+
+        output.flush()
+    }
+}
+
+impl std::fmt::Debug for LineFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.describe())
+    }
+}
+
+/// Discards everything once the reader on the other end has gone away, so the stage keeps
+/// consuming its input.
+struct IgnoreClosed<W> {
+    inner: W,
+    closed: bool,
+}
+
+impl<W: Write> IgnoreClosed<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            closed: false,
+        }
+    }
+}
+
+impl<W: Write> Write for IgnoreClosed<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if !self.closed {
+            match self.inner.write_all(buf) {
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => self.closed = true,
+                result => result?,
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.closed {
+            match self.inner.flush() {
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => self.closed = true,
+                result => result?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(filter: LineFilter, input: &str) -> String {
+        let mut output = Vec::new();
+        filter.run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_filters() {
+        let input = "one\ntwo\nthree\nfour";
+        let keep = LineFilter::Filter(Arc::new(|line| line.contains('o')));
+        assert_eq!(run(keep, input), "one\ntwo\nfour\n");
+        let upper = LineFilter::Map(Arc::new(|line| line.to_uppercase()));
+        assert_eq!(run(upper, input), "ONE\nTWO\nTHREE\nFOUR\n");
+        assert_eq!(run(LineFilter::Head(2), input), "one\ntwo\n");
+        assert_eq!(run(LineFilter::Tail(2), input), "three\nfour\n");
+        assert_eq!(run(LineFilter::Tail(0), input), "");
+        assert_eq!(run(LineFilter::Head(10), ""), "");
+    }
+}
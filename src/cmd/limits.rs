@@ -0,0 +1,119 @@
+//! Per-command resource limits and priorities applied with `setrlimit` and `setpriority`
+//! before `exec`.
+
+use std::os::unix::process::CommandExt;
+use std::process::Command as StdCommand;
+
+/// A resource limit or scheduling priority to apply to a spawned command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ResourceLimit {
+    /// Maximum number of open file descriptors (`RLIMIT_NOFILE`).
+    NoFile(u64),
+    /// Maximum size of core dump files in bytes (`RLIMIT_CORE`).
+    Core(u64),
+    /// Maximum size of files the process may create, in bytes (`RLIMIT_FSIZE`).
+    FileSize(u64),
+    /// Maximum size of the virtual address space in bytes (`RLIMIT_AS`).
+    Memory(u64),
+    /// Maximum CPU time in seconds (`RLIMIT_CPU`).
+    CpuTime(u64),
+    /// Scheduling priority, from -20 (highest) to 19 (lowest), set with `setpriority`.
+    Nice(i32),
+    /// Idle I/O scheduling class, set with `ioprio_set` (Linux only).
+    #[cfg(target_os = "linux")]
+    IoIdle,
+}
+
+impl ResourceLimit {
+    /// Name used when displaying the limit in the command echo.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ResourceLimit::NoFile(_) => "nofile",
+            ResourceLimit::Core(_) => "core",
+            ResourceLimit::FileSize(_) => "fsize",
+            ResourceLimit::Memory(_) => "as",
+            ResourceLimit::CpuTime(_) => "cpu",
+            ResourceLimit::Nice(_) => "nice",
+            #[cfg(target_os = "linux")]
+            ResourceLimit::IoIdle => "ionice",
+        }
+    }
+
+    fn apply(&self) -> std::io::Result<()> {
+        let (resource, value) = match *self {
+            ResourceLimit::NoFile(v) => (libc::RLIMIT_NOFILE, v),
+            ResourceLimit::Core(v) => (libc::RLIMIT_CORE, v),
+            ResourceLimit::FileSize(v) => (libc::RLIMIT_FSIZE, v),
+            ResourceLimit::Memory(v) => (libc::RLIMIT_AS, v),
+            ResourceLimit::CpuTime(v) => (libc::RLIMIT_CPU, v),
+            ResourceLimit::Nice(n) => {
+                // SAFETY: setpriority only changes the priority of the calling process.
+                if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, n) } != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                return Ok(());
+            }
+            #[cfg(target_os = "linux")]
+            ResourceLimit::IoIdle => {
+                const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+                const IOPRIO_CLASS_IDLE: libc::c_int = 3 << 13;
+                // SAFETY: ioprio_set only changes the I/O priority of the calling process.
+                let result = unsafe {
+                    libc::syscall(
+                        libc::SYS_ioprio_set,
+                        IOPRIO_WHO_PROCESS,
+                        0,
+                        IOPRIO_CLASS_IDLE,
+                    )
+                };
+                if result != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                return Ok(());
+            }
+        };
+        let value = value as libc::rlim_t;
+        let limit = libc::rlimit {
+            rlim_cur: value,
+            rlim_max: value,
+        };
+        // SAFETY: `limit` is a valid, fully initialized rlimit struct.
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ResourceLimit::NoFile(v)
+            | ResourceLimit::Core(v)
+            | ResourceLimit::FileSize(v)
+            | ResourceLimit::Memory(v)
+            | ResourceLimit::CpuTime(v) => write!(f, "{}={v}", self.name()),
+            ResourceLimit::Nice(n) => write!(f, "{}={n}", self.name()),
+            #[cfg(target_os = "linux")]
+            ResourceLimit::IoIdle => write!(f, "{}=idle", self.name()),
+        }
+    }
+}
+
+/// Install a `pre_exec` hook that applies the given limits in the child process.
+pub(crate) fn apply_limits(cmd: &mut StdCommand, limits: &[ResourceLimit]) {
+    if limits.is_empty() {
+        return;
+    }
+    let limits = limits.to_vec();
+    // SAFETY: the hook only makes system calls (`setrlimit`, `setpriority`, `ioprio_set`), which
+    // are async-signal-safe, and does not allocate.
+    unsafe {
+        cmd.pre_exec(move || {
+            for limit in &limits {
+                limit.apply()?;
+            }
+            Ok(())
+        });
+    }
+}
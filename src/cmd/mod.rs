@@ -1,19 +1,35 @@
 //! Simple command execution and piping functionality.
 
+mod chain;
 mod command;
 mod error;
+mod filters;
+mod limits;
 mod macros;
+mod operands;
 mod pipeline;
+mod preflight;
+mod progress;
+mod pump;
+pub(crate) mod reaper;
+mod redact;
+mod script;
+mod timeout;
 mod types;
 
 // Re-export public API
+pub use chain::Chain;
 pub use error::Error;
-pub use types::{Cmd, Pipeline, PipelineHandle, PipelineSpawn};
+pub use preflight::{CheckReport, Problem};
+pub use progress::{ProgressEvent, StageProgress};
+pub use script::shell;
+pub use types::{
+    Cmd, DropPolicy, PipeMode, Pipeline, PipelineHandle, PipelineSpawn, ProcessInfo,
+    RunningPipeline, StderrMode, StdioConfig, StreamTag,
+};
 
 // Internal items for testing and io_ext
 pub(crate) use types::CmdInput;
-#[cfg(test)]
-pub(crate) use types::PipeMode;
 
 #[cfg(test)]
 mod tests;
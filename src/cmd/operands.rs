@@ -0,0 +1,60 @@
+//! Automatic `--` separator insertion for well-known file utilities.
+
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+/// The end-of-options marker understood by getopt-style programs.
+pub(crate) const END_OF_OPTIONS: &str = "--";
+
+/// Programs that take file operands and accept `--` to end option parsing.
+const KNOWN_PROGRAMS: &[&str] = &[
+    "cat", "chgrp", "chmod", "chown", "cp", "du", "file", "head", "install", "ln", "ls", "mkdir",
+    "mv", "readlink", "realpath", "rm", "rmdir", "shred", "stat", "tail", "touch", "truncate",
+    "unlink", "wc",
+];
+
+/// Returns whether `program` (a bare name or a path) is one of the known file utilities.
+pub(crate) fn is_known_program(program: &OsStr) -> bool {
+    Path::new(program)
+        .file_name()
+        .and_then(OsStr::to_str)
+        .is_some_and(|name| KNOWN_PROGRAMS.contains(&name))
+}
+
+/// Returns the arguments with `--` inserted after the leading options, if any later argument
+/// would otherwise be mistaken for an option.
+///
+/// Leading arguments that start with `-` are treated as options unless they name an existing
+/// path (relative paths are resolved against `dir`). Nothing is inserted if `--` is already
+/// present.
+pub(crate) fn insert_end_of_options(
+    args: &[OsString],
+    dir: Option<&Path>,
+) -> Option<Vec<OsString>> {
+    if args.iter().any(|arg| arg == END_OF_OPTIONS) {
+        return None;
+    }
+
+    let looks_like_option = |arg: &OsString| {
+        let bytes = arg.as_encoded_bytes();
+        bytes.len() > 1 && bytes[0] == b'-'
+    };
+    let exists = |arg: &OsString| match dir {
+        Some(dir) => dir.join(arg).symlink_metadata().is_ok(),
+        None => Path::new(arg).symlink_metadata().is_ok(),
+    };
+
+    let position = args
+        .iter()
+        .position(|arg| !looks_like_option(arg) || exists(arg))
+        .unwrap_or(args.len());
+    if !args[position..].iter().any(looks_like_option) {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(args.len() + 1);
+    result.extend_from_slice(&args[..position]);
+    result.push(OsString::from(END_OF_OPTIONS));
+    result.extend_from_slice(&args[position..]);
+    Some(result)
+}
@@ -1,22 +1,287 @@
 //! Pipeline implementation and execution logic.
 
-use crate::cmd::{error::Error, types::*};
+use crate::cmd::{
+    error::{Error, StderrTail},
+    filters::LineFilter,
+    limits,
+    progress::ProgressEvent,
+    pump::{self, Pump},
+    reaper,
+    types::*,
+};
+use crate::echo::EchoFormat;
+use crate::fs::TempDir;
+use crate::lines::LineStream;
 use crate::style::*;
-use std::io::{BufReader, Read, Write};
+use std::ffi::{OsStr, OsString};
+use std::io::{BufReader, IsTerminal, Read, Write};
+use std::os::fd::OwnedFd;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command as StdCommand, Stdio};
+use std::sync::{Arc, Mutex};
 use std::thread;
 
+/// Characters of input set in code shown in the echo, as a `<<<` here-string.
+const INPUT_PREVIEW_WIDTH: usize = 40;
+
+/// Lines of `input_heredoc()` text shown in the echo, unless verbose echo is enabled.
+const HEREDOC_PREVIEW_LINES: usize = 10;
+
+/// Policy for handles created without an explicit [`PipelineHandle::set_drop_policy`].
+static DEFAULT_DROP_POLICY: Mutex<DropPolicy> = Mutex::new(DropPolicy::Kill);
+
 impl PipelineHandle {
+    pub(crate) fn new(children: Vec<Child>, command: String, homes: Vec<Arc<TempDir>>) -> Self {
+        Self {
+            children,
+            command,
+            homes,
+            threads: Vec::new(),
+            input: None,
+            processes: Vec::new(),
+            stage_tails: Vec::new(),
+            ignore_broken_pipe: true,
+            drop_policy: *DEFAULT_DROP_POLICY
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+            timed_out: Arc::new(Mutex::new(None)),
+            reap_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Set what happens if this handle is dropped without calling [`wait`](Self::wait).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let (mut indexer, _) = cmd!("updatedb").spawn_io_in()?;
+    /// indexer.set_drop_policy(DropPolicy::Detach);
+    /// cmd!("make", "docs").run()?;
+    /// // `updatedb` keeps running when `indexer` goes out of scope, instead of being killed
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_drop_policy(&mut self, policy: DropPolicy) {
+        self.drop_policy = policy;
+    }
+
+    /// Set the [`DropPolicy`] of all handles created from now on, for the whole process.
+    ///
+    /// [`DropPolicy::PanicInDebug`] is useful in test suites to find handles that are never
+    /// waited for.
+    pub fn set_default_drop_policy(policy: DropPolicy) {
+        *DEFAULT_DROP_POLICY
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
     /// Wait for all processes in the pipeline to complete.
-    pub fn wait(self) -> Result<(), Error> {
-        for mut child in self.children {
-            let status = child
-                .wait()
-                .map_err(|e| Error::io("Failed to wait for child process", e))?;
+    pub fn wait(mut self) -> Result<(), Error> {
+        self.wait_children()
+    }
+
+    /// Reap the children in order, leaving the rest to the drop policy if one failed.
+    fn wait_children(&mut self) -> Result<(), Error> {
+        while !self.children.is_empty() {
+            let mut child = self.children.remove(0);
+            let status = reaper::reap(&mut child, &self.reap_lock).map_err(|e| {
+                Error::io("Failed to wait for child process", e).with_command(&self.command)
+            })?;
+            crate::trace::record_exit(child.id(), status.code());
 
             if !status.success() {
-                return Err(Error::exit_code(status.code()));
+                let timed_out = self
+                    .timed_out
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .take();
+                let error = match timed_out {
+                    Some(reason) => Error::timeout(reason),
+                    None => Error::exit_status(status),
+                }
+                .with_command(&self.command);
+                return Err(self.with_stage_stderr(error, child.id()));
+            }
+        }
+        self.join_threads()
+    }
+
+    /// Attach the stderr captured from the stage with process ID `pid`, if any.
+    fn with_stage_stderr(&mut self, error: Error, pid: u32) -> Error {
+        let Some(index) = self.stage_tails.iter().position(|(stage, _)| *stage == pid) else {
+            return error;
+        };
+        match self.stage_tails.swap_remove(index).1.join() {
+            Ok(Ok(tail)) => error.with_stderr(&tail),
+            _ => error,
+        }
+    }
+
+    /// Feed `input`, if any, to the pipeline's `stdin`, returning `stdin` for the caller to
+    /// write to otherwise. Errors writing the input are returned when the handle is waited for.
+    fn feed(
+        &mut self,
+        input: Option<CmdInput>,
+        stdin: Option<std::process::ChildStdin>,
+        buffer_size: usize,
+        ignore_broken_pipe: bool,
+    ) -> Option<std::process::ChildStdin> {
+        if input.is_none() {
+            return stdin;
+        }
+        self.input = Pipeline::feed_input(input, stdin, buffer_size);
+        self.ignore_broken_pipe = ignore_broken_pipe;
+        None
+    }
+
+    /// Wait for the in-process stages and the input thread to finish, returning the first
+    /// failure.
+    fn join_threads(&mut self) -> Result<(), Error> {
+        let mut result = Pipeline::join_input(self.input.take(), self.ignore_broken_pipe)
+            .map_err(|e| e.with_command(&self.command));
+        for thread in self.threads.drain(..) {
+            let error = match thread.join() {
+                Ok(Ok(())) => continue,
+                Ok(Err(e)) => Error::io("In-process stage failed", e),
+                Err(_) => Error::io(
+                    "In-process stage failed",
+                    std::io::Error::other("the stage panicked"),
+                ),
+            };
+            if result.is_ok() {
+                result = Err(error.with_command(&self.command));
+            }
+        }
+        result
+    }
+
+    /// Wait up to `timeout` for all processes in the pipeline to complete.
+    ///
+    /// Returns `Ok(true)` if they all completed successfully and `Ok(false)` if the timeout
+    /// elapsed first, in which case the processes keep running and can be stopped with
+    /// [`kill`](Self::kill). Waiting does not need a thread per child: exits are detected by a
+    /// single background thread shared by all handles, started on first use.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    /// use std::time::Duration;
+    ///
+    /// let (mut handle, _) = cmd!("make", "test").spawn_io_in()?;
+    /// if !handle.wait_timeout(Duration::from_secs(60))? {
+    ///     handle.kill()?;
+    ///     handle.wait().ok();
+    ///     eprintln!("Tests timed out");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn wait_timeout(&mut self, timeout: std::time::Duration) -> Result<bool, Error> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.notify_on_exit(sender, ());
+        if receiver.recv_timeout(timeout).is_err() {
+            return Ok(false);
+        }
+
+        // All processes have exited, so this does not block
+        self.wait_children()?;
+        Ok(true)
+    }
+
+    /// Send `value` through `sender` once all processes in the pipeline have exited.
+    ///
+    /// This lets a supervisor wait on a single channel for any of many pipelines to finish,
+    /// then collect the exit status with [`wait`](Self::wait), which returns immediately. The
+    /// notification is sent from the shared background thread described in
+    /// [`wait_timeout`](Self::wait_timeout).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    /// use std::sync::mpsc;
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    /// let mut handles = Vec::new();
+    /// for (i, host) in ["web1", "web2", "web3"].iter().enumerate() {
+    ///     let (handle, _) = cmd!("ssh", host, "uptime").spawn_io_in()?;
+    ///     handle.notify_on_exit(sender.clone(), i);
+    ///     handles.push(Some(handle));
+    /// }
+    /// for _ in 0..handles.len() {
+    ///     let i = receiver.recv()?;
+    ///     handles[i].take().unwrap().wait()?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn notify_on_exit<T: Send + 'static>(&self, sender: std::sync::mpsc::Sender<T>, value: T) {
+        let pids = self.children.iter().map(Child::id).collect();
+        reaper::watch(pids, move || {
+            let _ = sender.send(value);
+        });
+    }
+
+    /// The IDs of the processes in the pipeline, in pipeline order.
+    ///
+    /// In-process stages such as [`filter_lines`](Pipeline::filter_lines) have no process and
+    /// are left out. The list does not change once the processes exit.
+    pub fn pids(&self) -> Vec<u32> {
+        self.processes.iter().map(ProcessInfo::pid).collect()
+    }
+
+    /// The processes in the pipeline with the commands they run, in pipeline order, for
+    /// monitoring tools that attach to them or log their resource usage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripty::*;
+    ///
+    /// let (handle, stdout) = cmd!("seq", "3").pipe(cmd!("wc", "-l")).no_echo().spawn_io_out()?;
+    /// for process in handle.processes() {
+    ///     println!("{} {:?} {:?}", process.pid(), process.program(), process.args());
+    /// }
+    /// assert_eq!(handle.processes()[1].program(), "wc");
+    /// let count = std::io::read_to_string(stdout.unwrap())?;
+    /// handle.wait()?;
+    /// # let _ = count;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn processes(&self) -> &[ProcessInfo] {
+        &self.processes
+    }
+
+    /// Record the commands `cmds` run by the children, which are in the same order.
+    fn with_processes<'a>(mut self, cmds: impl IntoIterator<Item = &'a Cmd>) -> Self {
+        self.processes = cmds
+            .into_iter()
+            .filter(|cmd| cmd.filter.is_none())
+            .zip(&self.children)
+            .map(|(cmd, child)| ProcessInfo {
+                pid: child.id(),
+                program: cmd.program.clone(),
+                args: cmd.effective_args().into_owned(),
+            })
+            .collect();
+        self
+    }
+
+    /// Kill all processes in the pipeline with `SIGKILL`.
+    ///
+    /// Processes that have already exited are skipped. Call [`wait`](Self::wait) afterwards to
+    /// reap them.
+    pub fn kill(&mut self) -> Result<(), Error> {
+        let _guard = self.reap_lock.lock().unwrap_or_else(|e| e.into_inner());
+        for child in &mut self.children {
+            if let Ok(Some(_)) = child.try_wait() {
+                continue;
             }
+            child.kill().map_err(|e| {
+                Error::io("Failed to kill child process", e).with_command(&self.command)
+            })?;
         }
         Ok(())
     }
@@ -36,15 +301,15 @@ impl PipelineHandle {
                 use std::io::Read;
                 let mut output = Vec::new();
                 let mut reader = BufReader::new(stdout);
-                reader
-                    .read_to_end(&mut output)
-                    .map_err(|e| Error::io("Failed to read stdout", e))?;
+                reader.read_to_end(&mut output).map_err(|e| {
+                    Error::io("Failed to read stdout", e).with_command(&self.command)
+                })?;
 
                 // Wait for the process to complete
-                for mut child in self.children {
-                    child
-                        .wait()
-                        .map_err(|e| Error::io("Failed to wait for child process", e))?;
+                for mut child in self.children.drain(..) {
+                    reaper::reap(&mut child, &self.reap_lock).map_err(|e| {
+                        Error::io("Failed to wait for child process", e).with_command(&self.command)
+                    })?;
                 }
 
                 return Ok(output);
@@ -53,9 +318,180 @@ impl PipelineHandle {
 
         Err(Error::no_stdout())
     }
+
+    /// Close the pipes still held by the children, discarding any remaining output, and reap
+    /// them.
+    fn drain_and_wait(&mut self) {
+        for mut child in self.children.drain(..) {
+            drop(child.stdin.take());
+            let stdout = child.stdout.take().map(|mut stdout| {
+                pump::spawn(move || std::io::copy(&mut stdout, &mut std::io::sink()))
+            });
+            if let Some(mut stderr) = child.stderr.take() {
+                let _ = std::io::copy(&mut stderr, &mut std::io::sink());
+            }
+            if let Some(stdout) = stdout {
+                let _ = stdout.join();
+            }
+            let _ = reaper::reap(&mut child, &self.reap_lock);
+        }
+        let _ = self.join_threads();
+    }
+}
+
+impl Drop for PipelineHandle {
+    fn drop(&mut self) {
+        if self.children.is_empty() && self.threads.is_empty() {
+            return;
+        }
+
+        match self.drop_policy {
+            DropPolicy::Wait => self.drain_and_wait(),
+            DropPolicy::Kill => {
+                let _ = self.kill();
+                self.drain_and_wait();
+            }
+            DropPolicy::Detach => {
+                let mut children = std::mem::take(&mut self.children);
+                for child in &mut children {
+                    drop(child.stdin.take());
+                    drop(child.stdout.take());
+                    drop(child.stderr.take());
+                }
+                let pids = children.iter().map(Child::id).collect();
+                let reap_lock = Arc::clone(&self.reap_lock);
+                reaper::watch(pids, move || {
+                    for mut child in children {
+                        let _ = reaper::reap(&mut child, &reap_lock);
+                    }
+                });
+            }
+            DropPolicy::PanicInDebug => {
+                if cfg!(debug_assertions) && !thread::panicking() {
+                    let _ = self.kill();
+                    self.drain_and_wait();
+                    panic!("PipelineHandle dropped without wait(): {}", self.command);
+                }
+                let _ = self.kill();
+                self.drain_and_wait();
+            }
+        }
+    }
 }
 
 impl Pipeline {
+    /// Create a pipeline from commands, piping the stdout of each into the next, as
+    /// `pipe()` does.
+    ///
+    /// This builds a pipeline whose commands are only known at runtime, such as a list of
+    /// filters read from a configuration file. With no commands, the pipeline does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let filters = fs::read_to_string("filters.txt")?;
+    /// let mut cmds = vec![cmd!("cat", "access.log")];
+    /// cmds.extend(filters.lines().map(|line| cmd!("sh", "-c", line)));
+    /// let output = Pipeline::from_cmds(cmds).output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn from_cmds(cmds: Vec<Cmd>) -> Self {
+        let suppress_echo = cmds.iter().any(|cmd| cmd.suppress_echo);
+        Self {
+            connections: cmds
+                .into_iter()
+                .map(|cmd| (cmd, PipeMode::Stdout))
+                .collect(),
+            input: None,
+            input_path: None,
+            suppress_echo,
+            max_output: None,
+            io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
+            ignore_broken_pipe: true,
+            stage_stderr: StderrMode::Inherit,
+            stage_timeout: None,
+            deadline: None,
+            record_input: None,
+            progress: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
+        }
+    }
+
+    /// Copy the pipeline, so it can be run more than once.
+    ///
+    /// Returns `None` if its input is streamed from a reader or a memory-mapped file, which
+    /// can only be read once, or recorded with [`record_input_to`](Self::record_input_to),
+    /// or if it has an [`on_progress`](Self::on_progress) closure. Input set from bytes or a
+    /// string is copied.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let count = cmd!("grep", "-c", "ERROR").input("ERROR one\nok\nERROR two\n");
+    /// for _ in 0..3 {
+    ///     println!("{}", count.try_clone().unwrap().output()?);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn try_clone(&self) -> Option<Self> {
+        if self.record_input.is_some() || self.progress.is_some() {
+            return None;
+        }
+        let input = match &self.input {
+            None => None,
+            Some(CmdInput::Bytes(bytes)) => Some(CmdInput::Bytes(bytes.clone())),
+            Some(CmdInput::Heredoc(text)) => Some(CmdInput::Heredoc(text.clone())),
+            Some(_) => return None,
+        };
+        Some(Self {
+            connections: self.connections.clone(),
+            input,
+            input_path: self.input_path.clone(),
+            suppress_echo: self.suppress_echo,
+            max_output: self.max_output,
+            io_buffer_size: self.io_buffer_size,
+            ignore_broken_pipe: self.ignore_broken_pipe,
+            stage_stderr: self.stage_stderr,
+            stage_timeout: self.stage_timeout,
+            deadline: self.deadline,
+            record_input: None,
+            progress: None,
+            progress_interval: self.progress_interval,
+        })
+    }
+
+    /// Add a command to the end of the pipeline, connected with `mode`.
+    ///
+    /// Like [`pipe_out`](Self::pipe_out), [`pipe_err`](Self::pipe_err) and
+    /// [`pipe_out_err`](Self::pipe_out_err), but the mode can be chosen at runtime and the
+    /// pipeline is extended in place. The mode of the first command is not used.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let mut pipeline = Pipeline::from_cmds(vec![cmd!("make", "test")]);
+    /// let mode = if std::env::var_os("ERRORS_ONLY").is_some() {
+    ///     PipeMode::Stderr
+    /// } else {
+    ///     PipeMode::Both
+    /// };
+    /// pipeline.push(cmd!("tee", "test.log"), mode);
+    /// pipeline.run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn push(&mut self, cmd: Cmd, mode: PipeMode) {
+        if self.connections.is_empty() {
+            self.suppress_echo = cmd.suppress_echo;
+        }
+        self.connections.push((cmd, mode));
+    }
+
     /// Add another command to the pipeline, piping stdout.
     pub fn pipe_out(mut self, cmd: Cmd) -> Self {
         self.connections.push((cmd, PipeMode::Stdout));
@@ -79,10 +515,90 @@ impl Pipeline {
         self
     }
 
+    /// Add another command to the pipeline, connecting the streams given by `mode`.
+    ///
+    /// Equivalent to [`pipe_out`](Self::pipe_out), [`pipe_err`](Self::pipe_err) or
+    /// [`pipe_out_err`](Self::pipe_out_err), for generic code that takes the mode as a
+    /// parameter.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// fn count_lines(pipeline: Pipeline, mode: PipeMode) -> Result<String> {
+    ///     Ok(pipeline.pipe_with(cmd!("wc", "-l"), mode).output()?)
+    /// }
+    ///
+    /// let pipeline = cmd!("cargo", "build").pipe(cmd!("grep", "warning"));
+    /// println!("{}", count_lines(pipeline, PipeMode::Both)?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn pipe_with(mut self, cmd: Cmd, mode: PipeMode) -> Self {
+        self.connections.push((cmd, mode));
+        self
+    }
+
+    /// Keep only the lines for which `keep` returns `true`, like `grep` but without starting a
+    /// process.
+    ///
+    /// In-process stages run on a thread between the surrounding commands and stream their
+    /// input line by line. Lines are passed without their line ending, with invalid UTF-8
+    /// replaced by `U+FFFD`, and kept lines are written unchanged, ending with `\n`. The stage
+    /// is shown as `filter_lines()` in the echo.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let errors = cmd!("journalctl", "-u", "myapp")
+    ///     .filter_lines(|line| line.contains("ERROR"))
+    ///     .pipe(cmd!("wc", "-l"))
+    ///     .output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn filter_lines(self, keep: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.pipe(Cmd::in_process(LineFilter::Filter(Arc::new(keep))))
+    }
+
+    /// Replace each line with the result of `map`, like `sed` but without starting a process.
+    ///
+    /// A `\n` is added after each result. See [`filter_lines`](Self::filter_lines) for how
+    /// in-process stages work.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let names = cmd!("cat", "/etc/passwd")
+    ///     .map_lines(|line| line.split(':').next().unwrap_or_default().to_string())
+    ///     .output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn map_lines(self, map: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.pipe(Cmd::in_process(LineFilter::Map(Arc::new(map))))
+    }
+
+    /// Keep the first `n` lines, like `head -n`, without starting a process.
+    ///
+    /// Unlike `head`, the rest of the input is still read (and discarded), so the commands
+    /// before it run to completion instead of being killed by `SIGPIPE`.
+    pub fn head(self, n: usize) -> Self {
+        self.pipe(Cmd::in_process(LineFilter::Head(n)))
+    }
+
+    /// Keep the last `n` lines, like `tail -n`, without starting a process.
+    pub fn tail(self, n: usize) -> Self {
+        self.pipe(Cmd::in_process(LineFilter::Tail(n)))
+    }
+
     /// Set binary input data for the pipeline.
     /// Accepts `Vec<u8>`, `&[u8]`, or other types that can be converted to `Vec<u8>`.
     pub fn input_bytes(mut self, input: impl AsRef<[u8]>) -> Self {
         self.input = Some(CmdInput::Bytes(input.as_ref().to_vec()));
+        self.input_path = None;
         self
     }
 
@@ -90,6 +606,63 @@ impl Pipeline {
     /// Takes ownership of `Vec<u8>` to avoid copying.
     pub fn input_bytes_owned(mut self, bytes: Vec<u8>) -> Self {
         self.input = Some(CmdInput::Bytes(bytes));
+        self.input_path = None;
+        self
+    }
+
+    /// Stream a file to the pipeline's stdin.
+    ///
+    /// With the `mmap` feature, the file is memory-mapped and written to stdin straight from
+    /// the mapping, without copying it through an intermediate buffer; this suits multi-gigabyte
+    /// inputs. Without the feature the file is streamed with regular reads.
+    ///
+    /// The file is opened (and mapped) immediately, so a missing file is reported here rather
+    /// than when the pipeline runs. As with any memory map, the file must not be truncated
+    /// while the pipeline is running.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let unique = cmd!("sort", "-u").input_mmap("events.log")?.output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn input_mmap(mut self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        self.input = Some(CmdInput::from_file(path.as_ref())?);
+        self.input_path = Some(path.as_ref().to_path_buf());
+        Ok(self)
+    }
+
+    /// Generate the pipeline's stdin with a closure, run on the thread that feeds the input.
+    ///
+    /// Use this for input too large to build in memory up front, or produced as the command
+    /// reads it. Stdin is closed when the closure returns. An error returned by the closure
+    /// fails the pipeline, except for a broken pipe when the command exits without reading
+    /// everything, which is ignored unless [`ignore_broken_pipe`](Self::ignore_broken_pipe) is
+    /// turned off.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let sorted = cmd!("sort", "-n")
+    ///     .input_with(|stdin| {
+    ///         for i in (0..1_000_000).rev() {
+    ///             writeln!(stdin, "{i}")?;
+    ///         }
+    ///         Ok(())
+    ///     })
+    ///     .output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn input_with(
+        mut self,
+        write: impl FnOnce(&mut dyn Write) -> std::io::Result<()> + Send + 'static,
+    ) -> Self {
+        self.input = Some(CmdInput::Writer(Box::new(write)));
+        self.input_path = None;
         self
     }
 
@@ -97,6 +670,264 @@ impl Pipeline {
     /// This is kept for backward compatibility but users should prefer the spawn_with_* methods.
     pub fn input(mut self, input: impl AsRef<str>) -> Self {
         self.input = Some(CmdInput::Bytes(input.as_ref().as_bytes().to_vec()));
+        self.input_path = None;
+        self
+    }
+
+    /// Set text input for the pipeline, shown in the echo as a heredoc.
+    ///
+    /// Works like [`input`](Self::input), but the whole text is echoed after the command line
+    /// in a `<<'EOF'` block, so that scripts fed to `sh`, `psql` or `python -` can be read and
+    /// rerun from the log. Only the first lines are shown unless verbose echo is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // scripty:cmd psql -d app <<'EOF'
+    /// // DELETE FROM sessions WHERE expires < now();
+    /// // VACUUM sessions;
+    /// // EOF
+    /// cmd!("psql", "-d", "app")
+    ///     .input_heredoc("DELETE FROM sessions WHERE expires < now();\nVACUUM sessions;\n")
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn input_heredoc(mut self, text: impl AsRef<str>) -> Self {
+        self.input = Some(CmdInput::Heredoc(text.as_ref().to_string()));
+        self.input_path = None;
+        self
+    }
+
+    /// Copy the input fed to the pipeline to `writer` as well, to see exactly what a command
+    /// was sent when it fails.
+    ///
+    /// Applies to input set on the pipeline, such as with [`input`](Self::input) or
+    /// [`input_mmap`](Self::input_mmap), whichever way the pipeline is run. Only the bytes
+    /// the first command accepted are recorded, so the record stops where the command closed
+    /// its stdin. Errors writing to `writer` fail the pipeline like errors writing the input.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// cmd!("kubectl", "apply", "-f", "-")
+    ///     .input_mmap("deploy.yaml")?
+    ///     .record_input_to(std::fs::File::create("applied.yaml")?)
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn record_input_to(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.record_input = Some(InputRecord(Box::new(writer)));
+        self
+    }
+
+    /// Fail if the pipeline writes more than `bytes` bytes to stdout, instead of collecting
+    /// all of it in memory.
+    ///
+    /// Applies to [`output`](Self::output), [`output_bytes`](Self::output_bytes) and the
+    /// methods built on them, such as [`output_utf8`](Self::output_utf8). When the limit is exceeded, the pipeline is
+    /// killed and an error is returned. Use [`max_output_truncate`](Self::max_output_truncate)
+    /// to keep the first `bytes` bytes instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // A misbehaving server cannot make the script run out of memory
+    /// let body = cmd!("curl", "-fsS", "https://example.com/status")
+    ///     .max_output(1024 * 1024)
+    ///     .output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn max_output(mut self, bytes: usize) -> Self {
+        self.max_output = Some(OutputLimit {
+            bytes,
+            truncate: false,
+        });
+        self
+    }
+
+    /// Keep only the first `bytes` bytes the pipeline writes to stdout, like `| head -c bytes`.
+    ///
+    /// Once the limit is reached, the pipeline is killed and the output collected so far is
+    /// returned. Its exit status is then ignored, since it was killed.
+    pub fn max_output_truncate(mut self, bytes: usize) -> Self {
+        self.max_output = Some(OutputLimit {
+            bytes,
+            truncate: true,
+        });
+        self
+    }
+
+    /// Set the size of the buffers used to copy the pipeline's input to its stdin and its
+    /// output to a writer, 64 KiB by default.
+    ///
+    /// Larger buffers mean fewer system calls when streaming gigabytes through a pipeline.
+    /// Input generated with [`input_with`](Self::input_with) is buffered too, so small writes
+    /// from the closure are sent in chunks of this size.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let dump = std::fs::File::create("dump.sql.gz")?;
+    /// cmd!("pg_dump", "production")
+    ///     .pipe(cmd!("gzip"))
+    ///     .io_buffer_size(1024 * 1024)
+    ///     .write_to(dump)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn io_buffer_size(mut self, bytes: usize) -> Self {
+        self.io_buffer_size = bytes.max(1);
+        self
+    }
+
+    /// Set whether the pipeline exiting before reading all of its input is an error.
+    ///
+    /// Failing to write the input, or an error returned by an [`input_with`](Self::input_with)
+    /// closure, fails the pipeline even if it exits successfully. A broken pipe is ignored by
+    /// default, since commands like `head` legitimately stop reading early; pass `false` to
+    /// make sure a command consumed all of its input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripty::*;
+    ///
+    /// let first = cmd!("head", "-n", "1").input("a\nb\n".repeat(100_000)).output()?;
+    /// assert_eq!(first, "a\n");
+    ///
+    /// let result = cmd!("true")
+    ///     .input("a\n".repeat(100_000))
+    ///     .ignore_broken_pipe(false)
+    ///     .run();
+    /// assert!(result.is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn ignore_broken_pipe(mut self, ignore: bool) -> Self {
+        self.ignore_broken_pipe = ignore;
+        self
+    }
+
+    /// Set where the stderr of the commands before the last one goes, by default the parent's
+    /// stderr. See [`StderrMode`].
+    ///
+    /// The last command's stderr is handled by the method running the pipeline, as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Keep the terminal clean, but show why `tar` failed if it does
+    /// let count = cmd!("tar", "-tzf", "backup.tar.gz")
+    ///     .pipe(cmd!("wc", "-l"))
+    ///     .stage_stderr(StderrMode::Capture)
+    ///     .output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stage_stderr(mut self, mode: StderrMode) -> Self {
+        self.stage_stderr = mode;
+        self
+    }
+
+    /// Kill the pipeline if any of its commands is still running `timeout` after the pipeline
+    /// started, failing with an error that names the commands still running.
+    ///
+    /// The commands of a pipeline all start together, so every stage gets the same limit,
+    /// measured from spawn rather than from when the stage's input ends. This is one limit for
+    /// the whole pipeline, like a [`deadline`](Self::deadline) of the spawn time plus `timeout`.
+    ///
+    /// Unlike [`PipelineHandle::wait_timeout`], which leaves stopping the pipeline to the
+    /// caller, this applies to every way of running the pipeline. With a time limit, each
+    /// command runs in a process group of its own, and the whole group is killed with
+    /// `SIGKILL`, so processes the command started do not outlive it. Being outside the
+    /// terminal's foreground process group, the commands then do not receive Ctrl-C and are
+    /// stopped if they read from the terminal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripty::*;
+    /// use std::time::Duration;
+    ///
+    /// let result = cmd!("sleep", "10")
+    ///     .pipe(cmd!("cat"))
+    ///     .stage_timeout(Duration::from_millis(100))
+    ///     .no_echo()
+    ///     .run();
+    /// let error = result.unwrap_err().to_string();
+    /// assert!(error.contains("still running: sleep (stage 1)"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stage_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.stage_timeout = Some(timeout);
+        self
+    }
+
+    /// Kill the pipeline if it has not finished by `deadline`, like
+    /// [`stage_timeout`](Self::stage_timeout) but at a fixed time, to share one time budget
+    /// between several pipelines.
+    ///
+    /// If both are set, the earlier limit applies.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// // The whole backup must be done within an hour
+    /// let deadline = Instant::now() + Duration::from_secs(60 * 60);
+    /// cmd!("pg_dump", "app").pipe(cmd!("gzip")).deadline(deadline).write_to(
+    ///     std::fs::File::create("app.sql.gz")?,
+    /// )?;
+    /// cmd!("rsync", "-a", "app.sql.gz", "backup:").deadline(deadline).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn deadline(mut self, deadline: std::time::Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Call `callback` with the bytes each command has read and written so far, every second
+    /// or as set with [`progress_interval`](Self::progress_interval), while the pipeline runs.
+    ///
+    /// The callback runs on a background thread, whichever way the pipeline is run, and once
+    /// more after all commands have exited, with [`ProgressEvent::is_done`] set. The counts
+    /// come from `/proc` and are only available on Linux; see
+    /// [`StageProgress`](crate::cmd::StageProgress).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// cmd!("pg_dump", "app")
+    ///     .pipe(cmd!("zstd"))
+    ///     .pipe(cmd!("aws", "s3", "cp", "-", "s3://backups/app.sql.zst"))
+    ///     .on_progress(|event| {
+    ///         let dumped = event.stages()[0].bytes_written();
+    ///         let rate = dumped as f64 / event.elapsed().as_secs_f64() / 1e6;
+    ///         eprint!("\r{} MB dumped ({rate:.1} MB/s)", dumped / 1_000_000);
+    ///     })
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn on_progress(mut self, callback: impl FnMut(&ProgressEvent) + Send + 'static) -> Self {
+        self.progress = Some(ProgressCallback(Box::new(callback)));
+        self
+    }
+
+    /// Set how often the [`on_progress`](Self::on_progress) callback is called, by default
+    /// every second.
+    pub fn progress_interval(mut self, interval: std::time::Duration) -> Self {
+        self.progress_interval = interval;
         self
     }
 
@@ -111,6 +942,96 @@ impl Pipeline {
         self.execute_internal(false).map(|_| ())
     }
 
+    /// Run the pipeline, showing each line of its output with `prefix` in front, like
+    /// `docker compose` does for its services.
+    ///
+    /// Lines from stdout go to stdout and lines from stderr to stderr, each written whole so
+    /// that the output of pipelines run on different threads is not mixed within a line. The
+    /// prefix is colored on a terminal, with a color picked from the prefix text, so a step
+    /// keeps its color across runs. Only the stderr of the last command is prefixed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let db = std::thread::spawn(|| cmd!("./scripts/migrate.sh").run_prefixed("db  | "));
+    /// cmd!("npm", "run", "build").run_prefixed("web | ")?;
+    /// db.join().unwrap()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn run_prefixed(mut self, prefix: &str) -> Result<(), Error> {
+        if self
+            .connections
+            .last()
+            .is_some_and(|(cmd, _)| cmd.is_quiet())
+        {
+            return self.run();
+        }
+
+        let input = self.take_input_after_echo();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let spawn = self.spawn_io_all()?;
+        let input_handle = Self::feed_input(input, spawn.stdin, buffer_size);
+
+        let mut forwarders = Vec::new();
+        if let Some(stdout) = spawn.stdout {
+            let is_terminal = std::io::stdout().is_terminal();
+            let prefix = Self::paint_prefix(prefix, is_terminal);
+            forwarders.push(pump::spawn(move || {
+                Self::forward_prefixed(stdout, &prefix, &mut std::io::stdout())
+            }));
+        }
+        if let Some(stderr) = spawn.stderr {
+            let is_terminal = std::io::stderr().is_terminal();
+            let prefix = Self::paint_prefix(prefix, is_terminal);
+            forwarders.push(pump::spawn(move || {
+                Self::forward_prefixed(stderr, &prefix, &mut std::io::stderr())
+            }));
+        }
+        for forwarder in forwarders {
+            forwarder.join()?;
+        }
+
+        let input_result = Self::join_input(input_handle, ignore_broken_pipe);
+        spawn.handle.wait()?;
+        input_result
+    }
+
+    /// Color a line prefix, if the stream it is written to should be colored.
+    pub(crate) fn paint_prefix(prefix: &str, is_terminal: bool) -> String {
+        if !crate::color::should_color(is_terminal) {
+            return prefix.to_string();
+        }
+        const PALETTE: [anstyle::Style; 6] = [
+            anstyle::Style::new().fg_color(crate::color::CYAN),
+            anstyle::Style::new().fg_color(crate::color::YELLOW),
+            anstyle::Style::new().fg_color(crate::color::GREEN),
+            anstyle::Style::new().fg_color(crate::color::MAGENTA),
+            anstyle::Style::new().fg_color(crate::color::BLUE),
+            anstyle::Style::new().fg_color(crate::color::BRIGHT_RED),
+        ];
+        let index = prefix.bytes().map(usize::from).sum::<usize>() % PALETTE.len();
+        let style = PALETTE[index];
+        format!("{style}{prefix}{style:#}")
+    }
+
+    /// Copy the lines of `stream` to `writer`, each with `prefix` in front.
+    pub(crate) fn forward_prefixed(stream: impl Read, prefix: &str, writer: &mut impl Write) {
+        let mut lines = LineStream::new(stream);
+        let mut line_buf = Vec::new();
+        while let Ok(Some(line)) = lines.next_line() {
+            line_buf.clear();
+            line_buf.extend_from_slice(prefix.as_bytes());
+            line_buf.extend_from_slice(line);
+            line_buf.push(b'\n');
+            if writer.write_all(&line_buf).is_err() {
+                break;
+            }
+        }
+    }
+
     /// Run the pipeline and return the output as a string.
     /// Get binary output from the pipeline.
     pub fn output_bytes(self) -> Result<Vec<u8>, Error> {
@@ -118,23 +1039,282 @@ impl Pipeline {
     }
 
     /// Get text output from the pipeline.
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`. Use [`output_utf8`](Self::output_utf8)
+    /// to fail on invalid UTF-8 instead.
     pub fn output(self) -> Result<String, Error> {
+        self.output_lossy()
+    }
+
+    /// Get text output from the pipeline, replacing invalid UTF-8 sequences with `U+FFFD`.
+    pub fn output_lossy(self) -> Result<String, Error> {
         let bytes = self.output_bytes()?;
         Ok(String::from_utf8_lossy(&bytes).to_string())
     }
 
+    /// Get text output from the pipeline, returning an error if it is not valid UTF-8.
+    ///
+    /// Prefer this over [`output`](Self::output) when the output must be machine-readable.
+    pub fn output_utf8(self) -> Result<String, Error> {
+        let bytes = self.output_bytes()?;
+        String::from_utf8(bytes).map_err(Error::invalid_utf8)
+    }
+
+    /// Get text output from the pipeline with leading and trailing whitespace removed, such as
+    /// the final newline.
+    pub fn output_trimmed(self) -> Result<String, Error> {
+        Ok(self.output()?.trim().to_string())
+    }
+
+    /// Get text output from the pipeline as lines.
+    ///
+    /// Lines are split on `\n` or `\r\n`, which are not included, and a final newline does
+    /// not start an empty line. Invalid UTF-8 sequences are replaced as with
+    /// [`output`](Self::output).
+    pub fn output_lines(self) -> Result<Vec<String>, Error> {
+        Ok(self.output()?.lines().map(str::to_string).collect())
+    }
+
+    /// Parse the pipeline's trimmed text output with [`FromStr`](std::str::FromStr).
+    ///
+    /// On a parse error, the error message quotes the start of the output.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let cores: usize = cmd!("nproc").output_parse()?;
+    /// let commits: u32 = cmd!("git", "rev-list", "--count", "HEAD").output_parse()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_parse<T>(self) -> Result<T, Error>
+    where
+        T: std::str::FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        let command = self.render(false);
+        let output = self.output_trimmed()?;
+        output.parse().map_err(|e| {
+            Error::invalid_output(e, std::any::type_name::<T>(), &output).with_command(&command)
+        })
+    }
+
+    /// Get output from the pipeline as an `OsString`, keeping bytes that are not valid UTF-8.
+    ///
+    /// Use this for output that names files, since a path with invalid UTF-8 would no longer
+    /// refer to the same file after the lossy conversion of [`output`](Self::output).
+    pub fn output_os(self) -> Result<OsString, Error> {
+        Ok(OsString::from_vec(self.output_bytes()?))
+    }
+
+    /// Get output from the pipeline as lines of `OsString`, keeping bytes that are not valid
+    /// UTF-8.
+    ///
+    /// Lines are split on `\n`, which is not included, and a final newline does not start an
+    /// empty line.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// for path in cmd!("git", "ls-files", "--", "*.orig").output_os_lines()? {
+    ///     fs::remove_file(&path)?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_os_lines(self) -> Result<Vec<OsString>, Error> {
+        let mut bytes = self.output_bytes()?;
+        if bytes.last() == Some(&b'\n') {
+            bytes.pop();
+        }
+        if bytes.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(bytes
+            .split(|&b| b == b'\n')
+            .map(|line| OsString::from_vec(line.to_vec()))
+            .collect())
+    }
+
+    /// Get the text of both stdout and stderr from the pipeline, as `(stdout, stderr)`.
+    ///
+    /// Stderr is that of the last command; earlier commands write theirs to the terminal.
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`, as with [`output`](Self::output).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let (stdout, stderr) = cmd!("cargo", "metadata", "--format-version", "1")
+    ///     .output_with_stderr()?;
+    /// if !stderr.is_empty() {
+    ///     eprintln!("cargo warned:\n{stderr}");
+    /// }
+    /// # let _ = stdout;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_with_stderr(mut self) -> Result<(String, String), Error> {
+        let input = self.take_spawn_input();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let spawn = self.spawn_io_all()?;
+        let input_handle = Self::feed_input(input, spawn.stdin, buffer_size);
+
+        // Read stderr on a thread so neither pipe can fill up while the other is read
+        let stderr_handle = spawn.stderr.map(|stderr| {
+            pump::spawn(move || {
+                let mut bytes = Vec::new();
+                StderrTail::collect(stderr, &mut bytes).map(|tail| (bytes, tail))
+            })
+        });
+
+        let mut stdout = Vec::new();
+        if let Some(mut reader) = spawn.stdout {
+            reader
+                .read_to_end(&mut stdout)
+                .map_err(|e| Error::io("Failed to read stdout", e))?;
+        }
+        let (stderr, tail) = match stderr_handle.map(Pump::join).transpose()? {
+            Some(result) => result.map_err(|e| Error::io("Failed to read stderr", e))?,
+            None => Default::default(),
+        };
+
+        let input_result = Self::join_input(input_handle, ignore_broken_pipe);
+
+        spawn.handle.wait().map_err(|e| e.with_stderr(&tail))?;
+        input_result?;
+        Ok((
+            String::from_utf8_lossy(&stdout).into_owned(),
+            String::from_utf8_lossy(&stderr).into_owned(),
+        ))
+    }
+
+    /// Parse the pipeline's stdout as JSON.
+    ///
+    /// On a parse error, the error message quotes the start of the output and the source error
+    /// gives the line and column. Requires the `serde` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// use scripty::*;
+    ///
+    /// let metadata: serde_json::Value =
+    ///     cmd!("cargo", "metadata", "--format-version", "1", "--no-deps").output_json()?;
+    /// println!("{}", metadata["workspace_root"]);
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn output_json<T: serde::de::DeserializeOwned>(self) -> Result<T, Error> {
+        let command = self.render(false);
+        let bytes = self.output_bytes()?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| Error::invalid_json(e, &bytes).with_command(&command))
+    }
+
+    /// Get binary output from the pipeline as a shared, reference-counted buffer.
+    ///
+    /// Clones of the returned [`Arc`](std::sync::Arc) share one buffer, so the same output can be
+    /// handed to several consumers (line parsing, JSON decoding, reports) without copying it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let log = cmd!("git", "log", "--oneline").output_shared()?;
+    /// let commits = LineStream::new(&log[..]).count();
+    /// let text = String::from_utf8_lossy(&log);
+    /// let fixes = text.lines().filter(|line| line.contains("fix")).count();
+    /// println!("{fixes} of {commits} commits are fixes");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_shared(self) -> Result<std::sync::Arc<[u8]>, Error> {
+        self.output_bytes().map(std::sync::Arc::from)
+    }
+
+    /// Capture stdout until a line matches `ready`, then return with the pipeline still running.
+    ///
+    /// This is meant for waiting until a service reports that it is ready. The returned
+    /// [`RunningPipeline`] holds the captured lines, the rest of stdout, and the handle to wait
+    /// for or kill the processes. Stderr is inherited, as with
+    /// [`spawn_io_out`](Self::spawn_io_out).
+    ///
+    /// Returns an error if stdout ends without a matching line, after waiting for the pipeline.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let mut server = cmd!("python3", "-u", "-m", "http.server", "8000")
+    ///     .output_until(|line| line.starts_with("Serving HTTP"))?;
+    /// println!("{}", server.captured);
+    /// cmd!("curl", "-sf", "http://localhost:8000/").run()?;
+    /// server.handle.kill()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_until(
+        self,
+        mut ready: impl FnMut(&str) -> bool,
+    ) -> Result<RunningPipeline, Error> {
+        let (mut handle, stdout) = self.spawn_io_out()?;
+        let Some(stdout) = stdout else {
+            return Err(Error::no_stdout().with_command(&handle.command));
+        };
+
+        let mut stdout = LineStream::new(stdout);
+        let mut captured = String::new();
+        loop {
+            let line = match stdout.next_line() {
+                Ok(Some(line)) => String::from_utf8_lossy(line).into_owned(),
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = handle.kill();
+                    let e = Error::io("Failed to read stdout", e);
+                    return Err(e.with_command(&handle.command));
+                }
+            };
+            captured.push_str(&line);
+            captured.push('\n');
+            if ready(&line) {
+                return Ok(RunningPipeline {
+                    captured,
+                    stdout,
+                    handle,
+                });
+            }
+        }
+
+        let command = handle.command.clone();
+        drop(stdout);
+        handle.wait()?;
+        let eof = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
+        Err(Error::io("Output ended before the expected line", eof).with_command(&command))
+    }
+
     /// Spawn pipeline with full I/O access.
     /// User is responsible for managing stdin, stdout, and stderr in separate threads.
-    pub fn spawn_io_all(self) -> Result<PipelineSpawn, Error> {
-        if !self.suppress_echo {
-            self.echo_pipeline();
-        }
+    ///
+    /// Input set on the pipeline, such as with [`input`](Self::input), is fed to stdin on a
+    /// background thread and `stdin` is `None`; errors writing it are returned by
+    /// [`PipelineHandle::wait`]. The other `spawn_io_*` methods feed it the same way.
+    pub fn spawn_io_all(mut self) -> Result<PipelineSpawn, Error> {
+        let input = self.take_spawn_input();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let command = self.render(false);
+        let homes = self.isolated_homes();
 
         if self.connections.is_empty() {
             return Ok(PipelineSpawn {
-                handle: PipelineHandle {
-                    children: Vec::new(),
-                },
+                handle: PipelineHandle::new(Vec::new(), command, homes),
                 stdin: None,
                 stdout: None,
                 stderr: None,
@@ -143,31 +1323,29 @@ impl Pipeline {
 
         // For single command, handle it specially
         if self.connections.len() == 1 {
+            let time_limit = self.take_time_limit();
+            let progress = self.take_progress();
             let cmd = self.connections.into_iter().next().unwrap().0;
-            let mut std_cmd = Self::build_std_command_static(&cmd);
+            let mut std_cmd = Self::build_std_command_static(&cmd)?;
 
             // Set up I/O - always enable stdin for compatibility
-            std_cmd.stdin(Stdio::piped());
+            Self::pipe_stdin(&mut std_cmd, &cmd);
             std_cmd.stdout(Stdio::piped());
             std_cmd.stderr(Stdio::piped());
 
-            let mut child = std_cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!("Failed to spawn command: {}", cmd.program.to_string_lossy()),
-                    e,
-                )
-            })?;
+            let mut child = Self::spawn_child(&mut std_cmd, &cmd, &command)?;
 
             let stdin = child.stdin.take();
             let stdout = child.stdout.take();
             let stderr = child.stderr.take();
 
-            // For single commands, input handling is done by existing methods for backward compatibility
-
+            let mut handle = PipelineHandle::new(vec![child], command, homes)
+                .with_processes([&cmd])
+                .with_time_limit(time_limit)
+                .with_progress(progress);
+            let stdin = handle.feed(input, stdin, buffer_size, ignore_broken_pipe);
             return Ok(PipelineSpawn {
-                handle: PipelineHandle {
-                    children: vec![child],
-                },
+                handle,
                 stdin,
                 stdout,
                 stderr,
@@ -175,20 +1353,40 @@ impl Pipeline {
         }
 
         // Multi-command pipeline
+        let time_limit = self.take_time_limit();
+        let progress = self.take_progress();
         let mut children: Vec<Child> = Vec::new();
+        let mut threads = Vec::new();
         let mut prev_reader: Option<std::io::PipeReader> = None;
         let mut first_stdin = None;
         let mut last_stdout = None;
         let mut last_stderr = None;
+        let mut stage_tails = Vec::new();
 
         // Spawn all commands in the pipeline
         for (i, (cmd_def, _pipe_mode)) in self.connections.iter().enumerate() {
-            let mut cmd = Self::build_std_command_static(cmd_def);
+            if let Some(filter) = &cmd_def.filter {
+                let input = prev_reader.take();
+                let output: Box<dyn Write + Send> = if i == self.connections.len() - 1 {
+                    let (reader, writer) = std::io::pipe()
+                        .map_err(|e| Error::io("Failed to create stdout pipe", e))?;
+                    last_stdout = Some(OwnedFd::from(reader).into());
+                    Box::new(writer)
+                } else {
+                    let (output, reader) = Self::in_process_output(self.connections[i + 1].1)?;
+                    prev_reader = Some(reader);
+                    output
+                };
+                threads.push(Self::spawn_in_process(filter, input, output)?);
+                continue;
+            }
+
+            let mut cmd = Self::build_std_command_static(cmd_def)?;
 
             // Set up stdin
             if i == 0 {
                 // First command: set up for potential input
-                cmd.stdin(Stdio::piped());
+                Self::pipe_stdin(&mut cmd, cmd_def);
             } else {
                 // Subsequent commands: use previous command's output
                 if let Some(reader) = prev_reader.take() {
@@ -205,41 +1403,14 @@ impl Pipeline {
             } else {
                 // Intermediate commands: pipe to next command
                 let next_pipe_mode = self.connections[i + 1].1;
-                match next_pipe_mode {
-                    PipeMode::Stdout => {
-                        let (reader, writer) = std::io::pipe()
-                            .map_err(|e| Error::io("Failed to create stdout pipe", e))?;
-                        cmd.stdout(Stdio::from(writer));
-                        prev_reader = Some(reader);
-                    }
-                    PipeMode::Stderr => {
-                        let (reader, writer) = std::io::pipe()
-                            .map_err(|e| Error::io("Failed to create stderr pipe", e))?;
-                        cmd.stderr(Stdio::from(writer));
-                        prev_reader = Some(reader);
-                    }
-                    PipeMode::Both => {
-                        let (reader, writer) = std::io::pipe()
-                            .map_err(|e| Error::io("Failed to create combined pipe", e))?;
-                        let writer_clone = writer
-                            .try_clone()
-                            .map_err(|e| Error::io("Failed to clone pipe writer", e))?;
-                        cmd.stdout(Stdio::from(writer));
-                        cmd.stderr(Stdio::from(writer_clone));
-                        prev_reader = Some(reader);
-                    }
-                }
+                prev_reader = Some(Self::connect_stage(
+                    &mut cmd,
+                    next_pipe_mode,
+                    self.stage_stderr,
+                )?);
             }
 
-            let mut child = cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!(
-                        "Failed to spawn command: {}",
-                        cmd_def.program.to_string_lossy()
-                    ),
-                    e,
-                )
-            })?;
+            let mut child = Self::spawn_child(&mut cmd, cmd_def, &command)?;
 
             // Capture I/O handles
             if i == 0 {
@@ -248,15 +1419,22 @@ impl Pipeline {
             if is_last {
                 last_stdout = child.stdout.take();
                 last_stderr = child.stderr.take();
+            } else {
+                Self::capture_stage_stderr(&mut child, &mut stage_tails);
             }
 
             children.push(child);
         }
 
-        // For pipelines, input handling is now user's responsibility via spawn API
-
+        let mut handle = PipelineHandle::new(children, command, homes)
+            .with_processes(self.connections.iter().map(|(cmd, _)| cmd))
+            .with_time_limit(time_limit)
+            .with_progress(progress);
+        handle.threads = threads;
+        handle.stage_tails = stage_tails;
+        let first_stdin = handle.feed(input, first_stdin, buffer_size, ignore_broken_pipe);
         Ok(PipelineSpawn {
-            handle: PipelineHandle { children },
+            handle,
             stdin: first_stdin,
             stdout: last_stdout,
             stderr: last_stderr,
@@ -264,54 +1442,53 @@ impl Pipeline {
     }
 
     /// Spawn pipeline with stdin access only.
-    pub fn spawn_io_in(self) -> Result<(PipelineHandle, Option<std::process::ChildStdin>), Error> {
-        if !self.suppress_echo {
-            self.echo_pipeline();
-        }
+    pub fn spawn_io_in(
+        mut self,
+    ) -> Result<(PipelineHandle, Option<std::process::ChildStdin>), Error> {
+        let input = self.take_spawn_input();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let command = self.render(false);
+        let homes = self.isolated_homes();
 
         if self.connections.is_empty() {
-            return Ok((
-                PipelineHandle {
-                    children: Vec::new(),
-                },
-                None,
-            ));
+            return Ok((PipelineHandle::new(Vec::new(), command, homes), None));
         }
 
         // For single command, handle specially to avoid stdin hanging
         if self.connections.len() == 1 {
+            let time_limit = self.take_time_limit();
+            let progress = self.take_progress();
             let cmd = self.connections.into_iter().next().unwrap().0;
-            let mut std_cmd = Self::build_std_command_static(&cmd);
+            let mut std_cmd = Self::build_std_command_static(&cmd)?;
 
             // Only set up stdin as piped - let stdout/stderr inherit
-            std_cmd.stdin(Stdio::piped());
+            Self::pipe_stdin(&mut std_cmd, &cmd);
 
-            let mut child = std_cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!("Failed to spawn command: {}", cmd.program.to_string_lossy()),
-                    e,
-                )
-            })?;
+            let mut child = Self::spawn_child(&mut std_cmd, &cmd, &command)?;
 
             let stdin = child.stdin.take();
 
-            return Ok((
-                PipelineHandle {
-                    children: vec![child],
-                },
-                stdin,
-            ));
+            let mut handle = PipelineHandle::new(vec![child], command, homes)
+                .with_processes([&cmd])
+                .with_time_limit(time_limit)
+                .with_progress(progress);
+            let stdin = handle.feed(input, stdin, buffer_size, ignore_broken_pipe);
+            return Ok((handle, stdin));
         }
 
         // For multi-command pipelines, use full spawn_io_all
-        let spawn = self.spawn_io_all()?;
-        Ok((spawn.handle, spawn.stdin))
+        let mut spawn = self.spawn_io_all()?;
+        let stdin = spawn
+            .handle
+            .feed(input, spawn.stdin, buffer_size, ignore_broken_pipe);
+        Ok((spawn.handle, stdin))
     }
 
     /// Spawn pipeline with stdin and stdout access.
     /// This is the most common interactive pattern for data transformation and interactive tools.
     pub fn spawn_io_in_out(
-        self,
+        mut self,
     ) -> Result<
         (
             PipelineHandle,
@@ -320,57 +1497,52 @@ impl Pipeline {
         ),
         Error,
     > {
-        if !self.suppress_echo {
-            self.echo_pipeline();
-        }
+        let input = self.take_spawn_input();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let command = self.render(false);
+        let homes = self.isolated_homes();
 
         if self.connections.is_empty() {
-            return Ok((
-                PipelineHandle {
-                    children: Vec::new(),
-                },
-                None,
-                None,
-            ));
+            return Ok((PipelineHandle::new(Vec::new(), command, homes), None, None));
         }
 
         // For single command, handle specially to avoid stderr hanging
         if self.connections.len() == 1 {
+            let time_limit = self.take_time_limit();
+            let progress = self.take_progress();
             let cmd = self.connections.into_iter().next().unwrap().0;
-            let mut std_cmd = Self::build_std_command_static(&cmd);
+            let mut std_cmd = Self::build_std_command_static(&cmd)?;
 
             // Only set up stdin and stdout as piped - let stderr inherit
-            std_cmd.stdin(Stdio::piped());
+            Self::pipe_stdin(&mut std_cmd, &cmd);
             std_cmd.stdout(Stdio::piped());
 
-            let mut child = std_cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!("Failed to spawn command: {}", cmd.program.to_string_lossy()),
-                    e,
-                )
-            })?;
+            let mut child = Self::spawn_child(&mut std_cmd, &cmd, &command)?;
 
             let stdin = child.stdin.take();
             let stdout = child.stdout.take();
 
-            return Ok((
-                PipelineHandle {
-                    children: vec![child],
-                },
-                stdin,
-                stdout,
-            ));
+            let mut handle = PipelineHandle::new(vec![child], command, homes)
+                .with_processes([&cmd])
+                .with_time_limit(time_limit)
+                .with_progress(progress);
+            let stdin = handle.feed(input, stdin, buffer_size, ignore_broken_pipe);
+            return Ok((handle, stdin, stdout));
         }
 
         // For multi-command pipelines, use full spawn_io_all
-        let spawn = self.spawn_io_all()?;
-        Ok((spawn.handle, spawn.stdin, spawn.stdout))
+        let mut spawn = self.spawn_io_all()?;
+        let stdin = spawn
+            .handle
+            .feed(input, spawn.stdin, buffer_size, ignore_broken_pipe);
+        Ok((spawn.handle, stdin, spawn.stdout))
     }
 
     /// Spawn pipeline with stdin and stderr access.
     /// Useful for debugging scenarios where you need to send data and monitor errors.
     pub fn spawn_io_in_err(
-        self,
+        mut self,
     ) -> Result<
         (
             PipelineHandle,
@@ -379,150 +1551,149 @@ impl Pipeline {
         ),
         Error,
     > {
-        if !self.suppress_echo {
-            self.echo_pipeline();
-        }
+        let input = self.take_spawn_input();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let command = self.render(false);
+        let homes = self.isolated_homes();
 
         if self.connections.is_empty() {
-            return Ok((
-                PipelineHandle {
-                    children: Vec::new(),
-                },
-                None,
-                None,
-            ));
+            return Ok((PipelineHandle::new(Vec::new(), command, homes), None, None));
         }
 
         // For single command, handle specially to avoid stdout hanging
         if self.connections.len() == 1 {
+            let time_limit = self.take_time_limit();
+            let progress = self.take_progress();
             let cmd = self.connections.into_iter().next().unwrap().0;
-            let mut std_cmd = Self::build_std_command_static(&cmd);
+            let mut std_cmd = Self::build_std_command_static(&cmd)?;
 
             // Only set up stdin and stderr as piped - let stdout inherit
-            std_cmd.stdin(Stdio::piped());
+            Self::pipe_stdin(&mut std_cmd, &cmd);
             std_cmd.stderr(Stdio::piped());
 
-            let mut child = std_cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!("Failed to spawn command: {}", cmd.program.to_string_lossy()),
-                    e,
-                )
-            })?;
+            let mut child = Self::spawn_child(&mut std_cmd, &cmd, &command)?;
 
             let stdin = child.stdin.take();
             let stderr = child.stderr.take();
 
-            return Ok((
-                PipelineHandle {
-                    children: vec![child],
-                },
-                stdin,
-                stderr,
-            ));
+            let mut handle = PipelineHandle::new(vec![child], command, homes)
+                .with_processes([&cmd])
+                .with_time_limit(time_limit)
+                .with_progress(progress);
+            let stdin = handle.feed(input, stdin, buffer_size, ignore_broken_pipe);
+            return Ok((handle, stdin, stderr));
         }
 
         // For multi-command pipelines, use full spawn_io_all
-        let spawn = self.spawn_io_all()?;
-        Ok((spawn.handle, spawn.stdin, spawn.stderr))
+        let mut spawn = self.spawn_io_all()?;
+        let stdin = spawn
+            .handle
+            .feed(input, spawn.stdin, buffer_size, ignore_broken_pipe);
+        Ok((spawn.handle, stdin, spawn.stderr))
     }
 
     /// Spawn pipeline with stdout access only.
     pub fn spawn_io_out(
-        self,
+        mut self,
     ) -> Result<(PipelineHandle, Option<std::process::ChildStdout>), Error> {
-        if !self.suppress_echo {
-            self.echo_pipeline();
-        }
+        let input = self.take_spawn_input();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let command = self.render(false);
+        let homes = self.isolated_homes();
 
         if self.connections.is_empty() {
-            return Ok((
-                PipelineHandle {
-                    children: Vec::new(),
-                },
-                None,
-            ));
+            return Ok((PipelineHandle::new(Vec::new(), command, homes), None));
         }
 
         // For single command, handle specially to avoid stdin hanging
         if self.connections.len() == 1 {
+            let time_limit = self.take_time_limit();
+            let progress = self.take_progress();
             let cmd = self.connections.into_iter().next().unwrap().0;
-            let mut std_cmd = Self::build_std_command_static(&cmd);
+            let mut std_cmd = Self::build_std_command_static(&cmd)?;
 
             // Only set up stdout as piped - let stdin/stderr inherit
             std_cmd.stdout(Stdio::piped());
 
-            let mut child = std_cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!("Failed to spawn command: {}", cmd.program.to_string_lossy()),
-                    e,
-                )
-            })?;
+            if input.is_some() {
+                Self::pipe_stdin(&mut std_cmd, &cmd);
+            }
 
+            let mut child = Self::spawn_child(&mut std_cmd, &cmd, &command)?;
+
+            let stdin = child.stdin.take();
             let stdout = child.stdout.take();
 
-            return Ok((
-                PipelineHandle {
-                    children: vec![child],
-                },
-                stdout,
-            ));
+            let mut handle = PipelineHandle::new(vec![child], command, homes)
+                .with_processes([&cmd])
+                .with_time_limit(time_limit)
+                .with_progress(progress);
+            handle.feed(input, stdin, buffer_size, ignore_broken_pipe);
+            return Ok((handle, stdout));
         }
 
         // For multi-command pipelines, use full spawn_io_all
-        let spawn = self.spawn_io_all()?;
+        let mut spawn = self.spawn_io_all()?;
+        spawn
+            .handle
+            .feed(input, spawn.stdin, buffer_size, ignore_broken_pipe);
         Ok((spawn.handle, spawn.stdout))
     }
 
     /// Spawn pipeline with stderr access only.
     pub fn spawn_io_err(
-        self,
+        mut self,
     ) -> Result<(PipelineHandle, Option<std::process::ChildStderr>), Error> {
-        if !self.suppress_echo {
-            self.echo_pipeline();
-        }
+        let input = self.take_spawn_input();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let command = self.render(false);
+        let homes = self.isolated_homes();
 
         if self.connections.is_empty() {
-            return Ok((
-                PipelineHandle {
-                    children: Vec::new(),
-                },
-                None,
-            ));
+            return Ok((PipelineHandle::new(Vec::new(), command, homes), None));
         }
 
         // For single command, handle specially to avoid stdin hanging
         if self.connections.len() == 1 {
+            let time_limit = self.take_time_limit();
+            let progress = self.take_progress();
             let cmd = self.connections.into_iter().next().unwrap().0;
-            let mut std_cmd = Self::build_std_command_static(&cmd);
+            let mut std_cmd = Self::build_std_command_static(&cmd)?;
 
             // Only set up stderr as piped - let stdin/stdout inherit
             std_cmd.stderr(Stdio::piped());
 
-            let mut child = std_cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!("Failed to spawn command: {}", cmd.program.to_string_lossy()),
-                    e,
-                )
-            })?;
+            if input.is_some() {
+                Self::pipe_stdin(&mut std_cmd, &cmd);
+            }
+
+            let mut child = Self::spawn_child(&mut std_cmd, &cmd, &command)?;
 
+            let stdin = child.stdin.take();
             let stderr = child.stderr.take();
 
-            return Ok((
-                PipelineHandle {
-                    children: vec![child],
-                },
-                stderr,
-            ));
+            let mut handle = PipelineHandle::new(vec![child], command, homes)
+                .with_processes([&cmd])
+                .with_time_limit(time_limit)
+                .with_progress(progress);
+            handle.feed(input, stdin, buffer_size, ignore_broken_pipe);
+            return Ok((handle, stderr));
         }
 
         // For multi-command pipelines, use full spawn_io_all
-        let spawn = self.spawn_io_all()?;
+        let mut spawn = self.spawn_io_all()?;
+        spawn
+            .handle
+            .feed(input, spawn.stdin, buffer_size, ignore_broken_pipe);
         Ok((spawn.handle, spawn.stderr))
     }
 
     /// Spawn pipeline with both stdout and stderr access.
     pub fn spawn_io_out_err(
-        self,
+        mut self,
     ) -> Result<
         (
             PipelineHandle,
@@ -531,500 +1702,1032 @@ impl Pipeline {
         ),
         Error,
     > {
-        if !self.suppress_echo {
-            self.echo_pipeline();
-        }
+        let input = self.take_spawn_input();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let command = self.render(false);
+        let homes = self.isolated_homes();
 
         if self.connections.is_empty() {
-            return Ok((
-                PipelineHandle {
-                    children: Vec::new(),
-                },
-                None,
-                None,
-            ));
+            return Ok((PipelineHandle::new(Vec::new(), command, homes), None, None));
         }
 
         // For single command, handle specially to avoid stdin hanging
         if self.connections.len() == 1 {
+            let time_limit = self.take_time_limit();
+            let progress = self.take_progress();
             let cmd = self.connections.into_iter().next().unwrap().0;
-            let mut std_cmd = Self::build_std_command_static(&cmd);
+            let mut std_cmd = Self::build_std_command_static(&cmd)?;
 
             // Only set up stdout and stderr as piped - let stdin inherit
             std_cmd.stdout(Stdio::piped());
             std_cmd.stderr(Stdio::piped());
 
-            let mut child = std_cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!("Failed to spawn command: {}", cmd.program.to_string_lossy()),
-                    e,
-                )
-            })?;
+            if input.is_some() {
+                Self::pipe_stdin(&mut std_cmd, &cmd);
+            }
+
+            let mut child = Self::spawn_child(&mut std_cmd, &cmd, &command)?;
 
+            let stdin = child.stdin.take();
             let stdout = child.stdout.take();
             let stderr = child.stderr.take();
 
-            return Ok((
-                PipelineHandle {
-                    children: vec![child],
-                },
-                stdout,
-                stderr,
-            ));
+            let mut handle = PipelineHandle::new(vec![child], command, homes)
+                .with_processes([&cmd])
+                .with_time_limit(time_limit)
+                .with_progress(progress);
+            handle.feed(input, stdin, buffer_size, ignore_broken_pipe);
+            return Ok((handle, stdout, stderr));
         }
 
         // For multi-command pipelines, use full spawn_io_all
-        let spawn = self.spawn_io_all()?;
+        let mut spawn = self.spawn_io_all()?;
+        spawn
+            .handle
+            .feed(input, spawn.stdin, buffer_size, ignore_broken_pipe);
         Ok((spawn.handle, spawn.stdout, spawn.stderr))
     }
 
     /// Stream pipeline's stdout to a Writer.
     /// This is more memory-efficient for large outputs.
     pub fn write_to<W: Write>(mut self, mut writer: W) -> Result<(), Error> {
-        // Extract input before spawning
-        let input = self.input.take();
+        let input = self.take_input_after_echo();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
         let spawn = self.spawn_io_all()?;
+        let command = spawn.handle.command.clone();
 
         // Handle input in separate thread if provided
-        let input_handle = match input {
-            Some(CmdInput::Bytes(bytes)) => spawn.stdin.map(|mut stdin| {
-                thread::spawn(move || {
-                    use std::io::Write;
-                    let _ = stdin.write_all(&bytes);
-                    drop(stdin);
-                })
-            }),
-            Some(CmdInput::Reader(mut reader)) => spawn.stdin.map(|stdin| {
-                thread::spawn(move || {
-                    use std::io::copy;
-                    let mut stdin = stdin;
-                    let _ = copy(&mut reader, &mut stdin);
-                    drop(stdin);
-                })
-            }),
-            None => None,
-        };
+        let input_handle = Self::feed_input(input, spawn.stdin, buffer_size);
+        let stderr_handle = Self::drain_stderr(spawn.stderr);
 
         // Handle stdout in current thread
         if let Some(stdout) = spawn.stdout {
             use std::io::copy;
-            copy(&mut BufReader::new(stdout), &mut writer)
-                .map_err(|e| Error::io("Failed to copy pipeline stdout to writer", e))?;
+            copy(
+                &mut BufReader::with_capacity(buffer_size, stdout),
+                &mut writer,
+            )
+            .map_err(|e| {
+                Error::io("Failed to copy pipeline stdout to writer", e).with_command(&command)
+            })?;
         }
 
+        let tail = Self::join_stderr(stderr_handle);
+
         // Wait for input thread to complete if exists
-        if let Some(handle) = input_handle {
-            if handle.join().is_err() {
-                eprintln!("Warning: Input thread panicked");
+        let input_result = Self::join_input(input_handle, ignore_broken_pipe)
+            .map_err(|e| e.with_command(&command));
+
+        spawn.handle.wait().map_err(|e| e.with_stderr(&tail))?;
+        input_result
+    }
+
+    /// Stream pipeline's stdout to a writer as the final step, like a shell `>` redirection.
+    ///
+    /// This is [`write_to`](Self::write_to) for owned writers, with the destination shown in
+    /// the echo: the path for a `File`, `/dev/stdout` or `/dev/stderr` for the standard
+    /// streams, and the type name otherwise. Use `write_to` for borrowed writers.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    /// use std::fs::File;
+    ///
+    /// // Echoed as: ls | sort > listing.txt
+    /// cmd!("ls").pipe(cmd!("sort")).pipe_to(File::create("listing.txt")?)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn pipe_to<W: Write + 'static>(self, writer: W) -> Result<(), Error> {
+        let target = crate::io_ext::describe_writer(&writer);
+        self.redirect_to(">", &target, writer)
+    }
+
+    /// Write the pipeline's stdout to a file, like a shell `>` redirection.
+    ///
+    /// The file is created, or truncated if it exists, and shown in the echo as `> path`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Echoed as: git log --oneline | head -n 20 > CHANGES.txt
+    /// cmd!("git", "log", "--oneline")
+    ///     .pipe(cmd!("head", "-n", "20"))
+    ///     .to_file("CHANGES.txt")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_file(self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let file = std::fs::File::create(path)
+            .map_err(|e| Error::io(&format!("Failed to create {}", path.display()), e))?;
+        let target = Cmd::quote_argument(path.as_os_str());
+        self.redirect_to(">", &target, file)
+    }
+
+    /// Append the pipeline's stdout to a file, like a shell `>>` redirection.
+    ///
+    /// The file is created if it does not exist, and shown in the echo as `>> path`.
+    pub fn append_file(self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| Error::io(&format!("Failed to open {}", path.display()), e))?;
+        let target = Cmd::quote_argument(path.as_os_str());
+        self.redirect_to(">>", &target, file)
+    }
+
+    /// Echo the pipeline followed by the redirection `op target`, then write its stdout to
+    /// `writer`.
+    fn redirect_to(mut self, op: &str, target: &str, writer: impl Write) -> Result<(), Error> {
+        if !self.suppress_echo {
+            self.echo_pipeline_with(|styled| {
+                if styled {
+                    format!(
+                        " {MAGENTA}{op}{MAGENTA:#} {UNDERLINE_BRIGHT_BLUE}{target}{UNDERLINE_BRIGHT_BLUE:#}"
+                    )
+                } else {
+                    format!(" {op} {target}")
+                }
+            });
+            self.suppress_echo = true;
+        }
+        self.write_to(writer)
+    }
+
+    /// Duplicate the pipeline's stdout into several downstream pipelines, like
+    /// `producer | tee >(consumer1) >(consumer2)` in bash.
+    ///
+    /// Each branch receives the complete output on its stdin, while its own stdout and stderr
+    /// are inherited. Data is streamed in chunks, so the output is never held in memory as a
+    /// whole. A branch that stops reading early (like `head`) is dropped without affecting the
+    /// others. Input set on a branch is ignored.
+    ///
+    /// Returns an error if the producer or any branch fails, after all of them have finished.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Archive the raw log and show the errors at the same time
+    /// cmd!("journalctl", "-u", "myapp", "--since", "today").tee_pipe([
+    ///     cmd!("gzip").pipe(cmd!("dd", "of=myapp.log.gz", "status=none")),
+    ///     cmd!("grep", "ERROR").into(),
+    /// ])?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn tee_pipe<I, P>(mut self, branches: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Pipeline>,
+    {
+        let branches: Vec<Pipeline> = branches.into_iter().map(Into::into).collect();
+
+        if !self.suppress_echo {
+            let max_arg_width = Self::echo_arg_width();
+            self.echo_pipeline_with(|styled| {
+                let paint = |style: anstyle::Style, text: &str| {
+                    if styled {
+                        format!("{style}{text}{style:#}")
+                    } else {
+                        text.to_string()
+                    }
+                };
+                let mut suffix = format!(" {} {}", paint(MAGENTA, "|"), paint(BOLD_CYAN, "tee"));
+                for branch in &branches {
+                    suffix.push_str(&format!(
+                        " {}{}{}",
+                        paint(MAGENTA, ">("),
+                        branch.render_with(styled, max_arg_width),
+                        paint(MAGENTA, ")")
+                    ));
+                }
+                suffix
+            });
+            self.suppress_echo = true;
+        }
+
+        let mut handles = Vec::with_capacity(branches.len());
+        let mut outputs = Vec::with_capacity(branches.len());
+        for mut branch in branches {
+            branch.input = None;
+            branch.suppress_echo = true;
+            let spawn = branch.spawn_inherit_stdio(false)?;
+            handles.push(spawn.handle);
+            outputs.extend(spawn.stdin);
+        }
+
+        let result = self.write_to(TeeWriter { outputs });
+
+        // Wait for every branch, even if the producer failed
+        let mut branch_result = Ok(());
+        for handle in handles {
+            if let Err(e) = handle.wait() {
+                if branch_result.is_ok() {
+                    branch_result = Err(e);
+                }
             }
         }
+        result.and(branch_result)
+    }
+
+    /// Run a command for each line of the pipeline's stdout, like `xargs -n1 -P jobs`.
+    ///
+    /// `make_cmd` is called with every non-empty line (without its line ending, invalid UTF-8
+    /// replaced with `U+FFFD`) as soon as it is read, and up to `jobs` of the resulting
+    /// commands run at the same time. Their stdout and stderr are inherited, and each one is
+    /// echoed as it starts.
+    ///
+    /// After the first failing command no new commands are started; the running ones are
+    /// waited for and the failure is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// // Compress every log file, four at a time
+    /// cmd!("find", "logs", "-name", "*.log")
+    ///     .for_each_line_spawn(4, |path| cmd!("gzip", path))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn for_each_line_spawn<F>(self, jobs: usize, make_cmd: F) -> Result<(), Error>
+    where
+        F: FnMut(&str) -> Cmd,
+    {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut spawner = LineSpawner {
+            make_cmd,
+            line: Vec::new(),
+            running: (0..jobs.max(1)).map(|_| None).collect(),
+            sender,
+            receiver,
+            error: None,
+        };
 
-        spawn.handle.wait()
+        let result = self.write_to(&mut spawner);
+        match spawner.finish() {
+            Some(e) => Err(e),
+            None => result,
+        }
     }
 
     /// Stream pipeline's stderr to a Writer.
     /// This is useful for capturing error output separately.
     pub fn write_err_to<W: Write>(mut self, mut writer: W) -> Result<(), Error> {
-        // Extract input before spawning
-        let input = self.input.take();
+        let input = self.take_input_after_echo();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
         let spawn = self.spawn_io_all()?;
 
         // Handle input in separate thread if provided
-        let input_handle = match input {
-            Some(CmdInput::Bytes(bytes)) => spawn.stdin.map(|mut stdin| {
-                thread::spawn(move || {
-                    use std::io::Write;
-                    let _ = stdin.write_all(&bytes);
-                    drop(stdin);
-                })
-            }),
-            Some(CmdInput::Reader(mut reader)) => spawn.stdin.map(|stdin| {
-                thread::spawn(move || {
-                    use std::io::copy;
-                    let mut stdin = stdin;
-                    let _ = copy(&mut reader, &mut stdin);
-                    drop(stdin);
-                })
-            }),
-            None => None,
-        };
+        let input_handle = Self::feed_input(input, spawn.stdin, buffer_size);
 
         // Handle stderr in current thread
         if let Some(stderr) = spawn.stderr {
             use std::io::copy;
-            copy(&mut BufReader::new(stderr), &mut writer)
-                .map_err(|e| Error::io("Failed to copy pipeline stderr to writer", e))?;
+            copy(
+                &mut BufReader::with_capacity(buffer_size, stderr),
+                &mut writer,
+            )
+            .map_err(|e| Error::io("Failed to copy pipeline stderr to writer", e))?;
         }
 
         // Wait for input thread to complete if exists
-        if let Some(handle) = input_handle {
-            if handle.join().is_err() {
-                eprintln!("Warning: Input thread panicked");
-            }
-        }
+        let input_result = Self::join_input(input_handle, ignore_broken_pipe);
 
-        spawn.handle.wait()
+        spawn.handle.wait()?;
+        input_result
     }
 
     /// Stream pipeline's combined stdout and stderr to a Writer.
     /// This merges both output streams into the writer.
-    pub fn write_both_to<W: Write + Send + 'static>(mut self, writer: W) -> Result<(), Error> {
-        use std::sync::{Arc, Mutex};
-
-        // Extract input before spawning
-        let input = self.input.take();
+    ///
+    /// Output is written in the order it arrives on either stream, so lines of stdout and stderr
+    /// may be interleaved. Errors from writing to the writer are returned.
+    pub fn write_both_to<W: Write>(mut self, mut writer: W) -> Result<(), Error> {
+        let input = self.take_input_after_echo();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
         let spawn = self.spawn_io_all()?;
 
-        // Wrap writer in Arc<Mutex<>> for safe sharing between threads
-        let writer = Arc::new(Mutex::new(writer));
-
         // Handle input in separate thread if provided
-        let input_handle = match input {
-            Some(CmdInput::Bytes(bytes)) => spawn.stdin.map(|mut stdin| {
-                thread::spawn(move || {
-                    use std::io::Write;
-                    let _ = stdin.write_all(&bytes);
-                    drop(stdin);
-                })
-            }),
-            Some(CmdInput::Reader(mut reader)) => spawn.stdin.map(|stdin| {
-                thread::spawn(move || {
-                    use std::io::copy;
-                    let mut stdin = stdin;
-                    let _ = copy(&mut reader, &mut stdin);
-                    drop(stdin);
-                })
-            }),
-            None => None,
-        };
+        let input_handle = Self::feed_input(input, spawn.stdin, buffer_size);
 
-        // Handle both stdout and stderr in separate threads
-        let stdout_handle = spawn.stdout.map(|stdout| {
-            let writer_clone = Arc::clone(&writer);
-            thread::spawn(move || {
-                use std::io::copy;
-                match writer_clone.lock() {
-                    Ok(mut writer_guard) => {
-                        if let Err(e) = copy(&mut BufReader::new(stdout), &mut *writer_guard) {
-                            eprintln!("Warning: Failed to copy stdout to writer: {}", e);
-                        }
-                    }
-                    Err(_) => {
-                        eprintln!(
-                            "Warning: Failed to acquire writer lock for stdout (mutex poisoned)"
-                        );
-                    }
-                }
-            })
-        });
-
-        let stderr_handle = spawn.stderr.map(|stderr| {
-            let writer_clone = Arc::clone(&writer);
-            thread::spawn(move || {
-                use std::io::copy;
-                match writer_clone.lock() {
-                    Ok(mut writer_guard) => {
-                        if let Err(e) = copy(&mut BufReader::new(stderr), &mut *writer_guard) {
-                            eprintln!("Warning: Failed to copy stderr to writer: {}", e);
-                        }
-                    }
-                    Err(_) => {
-                        eprintln!(
-                            "Warning: Failed to acquire writer lock for stderr (mutex poisoned)"
-                        );
-                    }
-                }
-            })
-        });
+        // Copy both streams into the writer in the current thread
+        Self::copy_both_to(spawn.stdout, spawn.stderr, buffer_size, &mut writer)?;
 
         // Wait for input thread to complete if exists
-        if let Some(handle) = input_handle {
-            if handle.join().is_err() {
-                eprintln!("Warning: Input thread panicked");
-            }
-        }
+        let input_result = Self::join_input(input_handle, ignore_broken_pipe);
+
+        spawn.handle.wait()?;
+        input_result
+    }
+
+    /// Stream the pipeline's stdout to `stdout` and its stderr to `stderr`, each to its own
+    /// writer.
+    ///
+    /// Both pipes are read at the same time, so the command cannot block on one stream while
+    /// the other is being copied. The writers are called from the current thread, so they need
+    /// not be `Send`. Stderr is that of the last command, as with
+    /// [`write_err_to`](Self::write_err_to).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let log = std::fs::File::create("build.log")?;
+    /// let errors = std::fs::File::create("build.err")?;
+    /// cmd!("make", "-k").write_split_to(log, errors)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn write_split_to<W: Write, E: Write>(
+        mut self,
+        mut stdout: W,
+        mut stderr: E,
+    ) -> Result<(), Error> {
+        let input = self.take_input_after_echo();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let spawn = self.spawn_io_all()?;
+
+        let input_handle = Self::feed_input(input, spawn.stdin, buffer_size);
 
-        // Wait for output threads to complete
-        if let Some(handle) = stdout_handle {
-            if handle.join().is_err() {
-                eprintln!("Warning: Stdout thread panicked");
+        Self::read_both(spawn.stdout, spawn.stderr, buffer_size, |tag, chunk| {
+            match tag {
+                StreamTag::Stdout => stdout.write_all(chunk),
+                StreamTag::Stderr => stderr.write_all(chunk),
             }
-        }
-        if let Some(handle) = stderr_handle {
-            if handle.join().is_err() {
-                eprintln!("Warning: Stderr thread panicked");
+            .map_err(|e| Error::io("Failed to copy pipeline output to writer", e))
+        })?;
+        stdout
+            .flush()
+            .and_then(|()| stderr.flush())
+            .map_err(|e| Error::io("Failed to flush writer", e))?;
+
+        let input_result = Self::join_input(input_handle, ignore_broken_pipe);
+
+        spawn.handle.wait()?;
+        input_result
+    }
+
+    /// Capture stdout and stderr together, as chunks tagged with the stream they came from, in
+    /// the order they arrived.
+    ///
+    /// Both pipes are read at the same time, so a command writing a lot to one stream cannot
+    /// block on it while the other is being read. Consecutive chunks from the same stream are
+    /// merged. The order between the streams is the order in which the data was read, which
+    /// matches the order it was written unless the command buffers one of them; many programs
+    /// buffer stdout when it is not a terminal.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// for (stream, chunk) in cmd!("make", "-k").output_interleaved()? {
+    ///     let text = String::from_utf8_lossy(&chunk);
+    ///     match stream {
+    ///         StreamTag::Stdout => print!("{text}"),
+    ///         StreamTag::Stderr => print!("[err] {text}"),
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_interleaved(mut self) -> Result<Vec<(StreamTag, Vec<u8>)>, Error> {
+        let input = self.take_input_after_echo();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let spawn = self.spawn_io_all()?;
+        let input_handle = Self::feed_input(input, spawn.stdin, buffer_size);
+
+        let mut chunks: Vec<(StreamTag, Vec<u8>)> = Vec::new();
+        Self::read_both(spawn.stdout, spawn.stderr, buffer_size, |tag, chunk| {
+            match chunks.last_mut() {
+                Some((last_tag, last)) if *last_tag == tag => last.extend_from_slice(chunk),
+                _ => chunks.push((tag, chunk.to_vec())),
             }
-        }
+            Ok(())
+        })?;
+
+        let input_result = Self::join_input(input_handle, ignore_broken_pipe);
 
-        spawn.handle.wait()
+        spawn.handle.wait()?;
+        input_result?;
+        Ok(chunks)
     }
 
     /// Run the pipeline with both input Reader and output Writer.
     /// This is the most flexible method for streaming I/O.
+    ///
+    /// Errors reading `reader` or writing stdin are returned, except for a broken pipe unless
+    /// [`ignore_broken_pipe`](Self::ignore_broken_pipe) is turned off. The same goes for
+    /// [`run_with_err_io`](Self::run_with_err_io) and
+    /// [`run_with_both_io`](Self::run_with_both_io).
     pub fn run_with_io<R: Read + Send + 'static, W: Write>(
         self,
-        mut reader: R,
+        reader: R,
         mut writer: W,
     ) -> Result<(), Error> {
-        let spawn = self.spawn_io_all()?;
-
-        // Handle input in separate thread
-        if let Some(mut stdin) = spawn.stdin {
-            thread::spawn(move || {
-                use std::io::copy;
-                let _ = copy(&mut reader, &mut stdin);
-            });
-        }
-
-        // Handle output in current thread
-        if let Some(stdout) = spawn.stdout {
-            use std::io::copy;
-            copy(&mut BufReader::new(stdout), &mut writer)
+        self.run_with_reader(reader, |spawn, buffer_size| {
+            let stderr_handle = Self::drain_stderr(spawn.stderr.take());
+            if let Some(stdout) = spawn.stdout.take() {
+                std::io::copy(
+                    &mut BufReader::with_capacity(buffer_size, stdout),
+                    &mut writer,
+                )
                 .map_err(|e| Error::io("Failed to copy pipeline output to writer", e))?;
-        }
-
-        spawn.handle.wait()
+            }
+            Ok(Self::join_stderr(stderr_handle))
+        })
     }
 
     /// Run the pipeline with input Reader and stderr Writer.
     /// This is useful for processing data while capturing error output.
     pub fn run_with_err_io<R: Read + Send + 'static, W: Write>(
         self,
-        mut reader: R,
+        reader: R,
         mut writer: W,
     ) -> Result<(), Error> {
-        let spawn = self.spawn_io_all()?;
-
-        // Handle input in separate thread
-        if let Some(mut stdin) = spawn.stdin {
-            thread::spawn(move || {
-                use std::io::copy;
-                let _ = copy(&mut reader, &mut stdin);
+        self.run_with_reader(reader, |spawn, buffer_size| {
+            // Stdout is discarded, but read so that the pipeline never blocks on it
+            let stdout_handle = spawn.stdout.take().map(|mut stdout| {
+                pump::spawn(move || std::io::copy(&mut stdout, &mut std::io::sink()))
             });
-        }
-
-        // Handle stderr output in current thread
-        if let Some(stderr) = spawn.stderr {
-            use std::io::copy;
-            copy(&mut BufReader::new(stderr), &mut writer)
+            if let Some(stderr) = spawn.stderr.take() {
+                std::io::copy(
+                    &mut BufReader::with_capacity(buffer_size, stderr),
+                    &mut writer,
+                )
                 .map_err(|e| Error::io("Failed to copy pipeline stderr to writer", e))?;
-        }
-
-        spawn.handle.wait()
+            }
+            if let Some(handle) = stdout_handle {
+                let _ = handle.join();
+            }
+            Ok(StderrTail::default())
+        })
     }
 
     /// Run the pipeline with input Reader and combined stdout+stderr Writer.
     /// This merges both output streams for comprehensive logging.
-    pub fn run_with_both_io<R: Read + Send + 'static, W: Write + Send + 'static>(
+    pub fn run_with_both_io<R: Read + Send + 'static, W: Write>(
         self,
-        mut reader: R,
-        writer: W,
+        reader: R,
+        mut writer: W,
     ) -> Result<(), Error> {
-        use std::sync::{Arc, Mutex};
+        self.run_with_reader(reader, |spawn, buffer_size| {
+            Self::copy_both_to(
+                spawn.stdout.take(),
+                spawn.stderr.take(),
+                buffer_size,
+                &mut writer,
+            )?;
+            Ok(StderrTail::default())
+        })
+    }
 
-        let spawn = self.spawn_io_all()?;
+    /// Spawn the pipeline with `reader` fed to its stdin, run `copy_output` on its output
+    /// streams, then wait for the input thread and the pipeline.
+    ///
+    /// `copy_output` must read both streams to the end, and returns the end of stderr it kept
+    /// for errors.
+    fn run_with_reader<R: Read + Send + 'static>(
+        mut self,
+        reader: R,
+        copy_output: impl FnOnce(&mut PipelineSpawn, usize) -> Result<StderrTail, Error>,
+    ) -> Result<(), Error> {
+        self.input = Some(CmdInput::Reader(Box::new(reader)));
+        self.input_path = None;
+        let input = self.take_input_after_echo();
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let mut spawn = self.spawn_io_all()?;
+        let input_handle = Self::feed_input(input, spawn.stdin.take(), buffer_size);
+
+        let tail = copy_output(&mut spawn, buffer_size)
+            .map_err(|e| e.with_command(&spawn.handle.command))?;
+
+        let input_result = Self::join_input(input_handle, ignore_broken_pipe)
+            .map_err(|e| e.with_command(&spawn.handle.command));
+
+        spawn.handle.wait().map_err(|e| e.with_stderr(&tail))?;
+        input_result
+    }
 
-        // Handle input in separate thread
-        if let Some(mut stdin) = spawn.stdin {
-            thread::spawn(move || {
-                use std::io::copy;
-                let _ = copy(&mut reader, &mut stdin);
-            });
-        }
+    /// Read the last command's stderr on a pooled thread, keeping the end for errors, so that
+    /// the pipeline never blocks on a full stderr pipe while its stdout is read.
+    fn drain_stderr(
+        stderr: Option<std::process::ChildStderr>,
+    ) -> Option<Pump<std::io::Result<StderrTail>>> {
+        stderr.map(|stderr| pump::spawn(move || StderrTail::collect(stderr, std::io::sink())))
+    }
 
-        // Wrap writer in Arc<Mutex<>> for safe sharing between threads
-        let writer = Arc::new(Mutex::new(writer));
+    /// Wait for a stderr reader, returning what it kept. Reading stderr is best effort; it
+    /// only adds context to errors.
+    fn join_stderr(handle: Option<Pump<std::io::Result<StderrTail>>>) -> StderrTail {
+        handle
+            .and_then(|handle| handle.join().ok())
+            .and_then(Result::ok)
+            .unwrap_or_default()
+    }
 
-        // Handle stdout in separate thread
-        let stdout_handle = spawn.stdout.map(|stdout| {
-            let writer = Arc::clone(&writer);
-            thread::spawn(move || {
-                use std::io::copy;
-                let mut reader = BufReader::new(stdout);
-                match writer.lock() {
-                    Ok(mut writer) => {
-                        if let Err(e) = copy(&mut reader, &mut *writer) {
-                            eprintln!("Warning: Failed to copy stdout to writer: {}", e);
+    /// Run the pipeline with an input Reader, a stdout Writer and a stderr Writer.
+    ///
+    /// The output streams are copied as with [`write_split_to`](Self::write_split_to). Errors
+    /// reading `reader` or writing stdin are returned, except for a broken pipe unless
+    /// [`ignore_broken_pipe`](Self::ignore_broken_pipe) is turned off.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let mut report = Vec::new();
+    /// cmd!("awk", "-F,", "NF != 2 { print \"bad line \" NR > \"/dev/stderr\"; next } { print $1 }")
+    ///     .run_with_io_full(std::fs::File::open("data.csv")?, &mut report, std::io::stderr())?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn run_with_io_full<R: Read + Send + 'static, W: Write, E: Write>(
+        mut self,
+        reader: R,
+        stdout: W,
+        stderr: E,
+    ) -> Result<(), Error> {
+        self.input = Some(CmdInput::Reader(Box::new(reader)));
+        self.input_path = None;
+        self.write_split_to(stdout, stderr)
+    }
+
+    /// Copy stdout and stderr into `writer` in the order chunks arrive on either stream.
+    fn copy_both_to<W: Write>(
+        stdout: Option<std::process::ChildStdout>,
+        stderr: Option<std::process::ChildStderr>,
+        buffer_size: usize,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        Self::read_both(stdout, stderr, buffer_size, |_, chunk| {
+            writer
+                .write_all(chunk)
+                .map_err(|e| Error::io("Failed to copy pipeline output to writer", e))
+        })?;
+        writer
+            .flush()
+            .map_err(|e| Error::io("Failed to flush writer", e))
+    }
+
+    /// Pass chunks of stdout and stderr to `on_chunk` in the order they arrive on either stream.
+    ///
+    /// Each stream is read on its own thread and sent over a bounded channel to the calling
+    /// thread, which alone calls `on_chunk`. Neither stream can hold the consumer while the
+    /// other one fills its pipe, and a slow consumer slows down both streams instead of blocking
+    /// one. Reading stops at the first error returned by `on_chunk`.
+    fn read_both(
+        stdout: Option<std::process::ChildStdout>,
+        stderr: Option<std::process::ChildStderr>,
+        buffer_size: usize,
+        mut on_chunk: impl FnMut(StreamTag, &[u8]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        const CHANNEL_CAPACITY: usize = 16;
+
+        let (sender, receiver) = std::sync::mpsc::sync_channel(CHANNEL_CAPACITY);
+        let streams: [Option<(StreamTag, Box<dyn Read + Send>)>; 2] = [
+            stdout.map(|s| (StreamTag::Stdout, Box::new(s) as Box<dyn Read + Send>)),
+            stderr.map(|s| (StreamTag::Stderr, Box::new(s) as Box<dyn Read + Send>)),
+        ];
+        let readers: Vec<_> = streams
+            .into_iter()
+            .flatten()
+            .map(|(tag, mut stream)| {
+                let sender = sender.clone();
+                pump::spawn(move || {
+                    let mut buf = vec![0; buffer_size];
+                    loop {
+                        let chunk = match stream.read(&mut buf) {
+                            Ok(0) => break,
+                            Ok(n) => Ok(buf[..n].to_vec()),
+                            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                            Err(e) => Err(e),
+                        };
+                        let failed = chunk.is_err();
+                        // Sending fails once the consumer has failed; stop reading then
+                        if sender.send((tag, chunk)).is_err() || failed {
+                            break;
                         }
                     }
-                    Err(_) => {
-                        eprintln!(
-                            "Warning: Failed to acquire writer lock for stdout (mutex poisoned)"
-                        );
-                    }
-                }
+                })
             })
-        });
+            .collect();
+        drop(sender);
+
+        let mut result = Ok(());
+        for (tag, chunk) in receiver.iter() {
+            let consumed = match chunk {
+                Ok(chunk) => on_chunk(tag, &chunk),
+                Err(e) => Err(Error::io("Failed to read pipeline output", e)),
+            };
+            if consumed.is_err() {
+                result = consumed;
+                break;
+            }
+        }
+        // Dropping the receiver makes the readers stop, closing the pipes
+        drop(receiver);
 
-        // Handle stderr in separate thread
+        for reader in readers {
+            let joined = reader.join();
+            if result.is_ok() {
+                result = joined;
+            }
+        }
+        result
+    }
+
+    fn execute_internal(mut self, capture_output: bool) -> Result<Vec<u8>, Error> {
+        // Extract input before moving self; input needs the first command's stdin pipe
+        let input = self.take_spawn_input();
+
+        let quiet = self
+            .connections
+            .last()
+            .is_some_and(|(cmd, _)| cmd.is_quiet());
+        let limit = self.max_output;
+        let buffer_size = self.io_buffer_size;
+        let ignore_broken_pipe = self.ignore_broken_pipe;
+        let mut spawn = if capture_output {
+            self.spawn_io_all()?
+        } else {
+            // For run() method, don't capture output - let it go to terminal
+            self.spawn_inherit_stdio(true)?
+        };
+        let command = spawn.handle.command.clone();
+        let input_handle = Self::feed_input(input, spawn.stdin, buffer_size);
+
+        // Keep the end of stderr for the error; run() passes it on to our stderr as it arrives
         let stderr_handle = spawn.stderr.map(|stderr| {
-            let writer = Arc::clone(&writer);
-            thread::spawn(move || {
-                use std::io::copy;
-                let mut reader = BufReader::new(stderr);
-                match writer.lock() {
-                    Ok(mut writer) => {
-                        if let Err(e) = copy(&mut reader, &mut *writer) {
-                            eprintln!("Warning: Failed to copy stderr to writer: {}", e);
-                        }
-                    }
-                    Err(_) => {
-                        eprintln!(
-                            "Warning: Failed to acquire writer lock for stderr (mutex poisoned)"
-                        );
-                    }
+            pump::spawn(move || {
+                if capture_output || quiet {
+                    StderrTail::collect(stderr, std::io::sink())
+                } else {
+                    StderrTail::collect(stderr, std::io::stderr())
                 }
             })
         });
 
-        // Wait for all threads to complete
-        if let Some(handle) = stdout_handle {
-            if handle.join().is_err() {
-                eprintln!("Warning: Stdout thread panicked");
+        let mut output = Vec::new();
+        let mut exceeded = None;
+        if let Some(stdout) = spawn.stdout.take() {
+            // Read one byte past the limit to tell whether it was exceeded
+            let max = limit.map_or(u64::MAX, |limit| limit.bytes as u64 + 1);
+            BufReader::with_capacity(buffer_size, stdout)
+                .take(max)
+                .read_to_end(&mut output)
+                .map_err(|e| Error::io("Failed to read stdout", e).with_command(&command))?;
+            if let Some(limit) = limit.filter(|limit| output.len() > limit.bytes) {
+                output.truncate(limit.bytes);
+                exceeded = Some(limit);
+                // Kill before waiting for stderr, which stays open while the pipeline runs
+                spawn.handle.kill()?;
             }
         }
-        if let Some(handle) = stderr_handle {
-            if handle.join().is_err() {
-                eprintln!("Warning: Stderr thread panicked");
+
+        let tail = Self::join_stderr(stderr_handle);
+
+        // Wait for input thread to complete if exists
+        let input_result = Self::join_input(input_handle, ignore_broken_pipe)
+            .map_err(|e| e.with_command(&command));
+
+        match exceeded {
+            Some(limit) => {
+                let _ = spawn.handle.wait();
+                input_result?;
+                if limit.truncate {
+                    Ok(output)
+                } else {
+                    Err(Error::output_limit(limit.bytes).with_command(&command))
+                }
+            }
+            None => {
+                spawn.handle.wait().map_err(|e| e.with_stderr(&tail))?;
+                input_result?;
+                Ok(output)
             }
         }
-
-        spawn.handle.wait()
     }
 
-    fn execute_internal(mut self, capture_output: bool) -> Result<Vec<u8>, Error> {
-        // Echo pipeline first if not suppressed
-        let original_suppress = self.suppress_echo;
-        if !original_suppress {
-            self.echo_pipeline();
+    fn build_std_command_static(cmd_def: &Cmd) -> Result<StdCommand, Error> {
+        if let Some(reason) = &cmd_def.invalid {
+            return Err(Error::invalid_command(reason));
         }
+        let args = cmd_def.effective_args();
+        crate::taint::check_command(&cmd_def.program, &args)?;
 
-        // Extract input before moving self
-        let input = self.input.take();
+        if cmd_def.dry_run {
+            // `true` stands in for the command: it exits successfully without output
+            return Ok(StdCommand::new("true"));
+        }
 
-        if capture_output {
-            // Call spawn_io_all with echo suppressed to avoid double echo
-            self.suppress_echo = true;
-            let spawn = self.spawn_io_all()?;
-
-            // Handle input if provided (for backward compatibility)
-            let input_handle = match input {
-                Some(CmdInput::Bytes(bytes)) => {
-                    spawn.stdin.map(|mut stdin| {
-                        thread::spawn(move || {
-                            use std::io::Write;
-                            let _ = stdin.write_all(&bytes);
-                            drop(stdin); // Close stdin to signal EOF
-                        })
-                    })
-                }
-                Some(CmdInput::Reader(mut reader)) => {
-                    spawn.stdin.map(|stdin| {
-                        thread::spawn(move || {
-                            use std::io::copy;
-                            let mut stdin = stdin;
-                            let _ = copy(&mut reader, &mut stdin);
-                            drop(stdin); // Close stdin to signal EOF
-                        })
-                    })
-                }
-                None => None,
-            };
+        let mut cmd = StdCommand::new(&cmd_def.program);
+        cmd.args(args.iter());
 
-            if let Some(stdout) = spawn.stdout {
-                let mut output = Vec::new();
-                let mut reader = BufReader::new(stdout);
-                reader
-                    .read_to_end(&mut output)
-                    .map_err(|e| Error::io("Failed to read stdout", e))?;
-
-                // Wait for input thread to complete if exists
-                if let Some(handle) = input_handle {
-                    if handle.join().is_err() {
-                        eprintln!("Warning: Input thread panicked");
-                    }
+        match &cmd_def.isolated_home {
+            Some(IsolatedHome::Ready(home)) => {
+                for (key, val) in IsolatedHome::env_vars(home.path()) {
+                    cmd.env(key, val);
                 }
+            }
+            Some(IsolatedHome::Failed(e)) => {
+                return Err(Error::io(
+                    "Failed to create isolated home directory",
+                    std::io::Error::new(e.kind(), e.to_string()),
+                ));
+            }
+            None => {}
+        }
 
-                spawn.handle.wait()?;
-                Ok(output)
-            } else {
-                // Wait for input thread to complete if exists
-                if let Some(handle) = input_handle {
-                    if handle.join().is_err() {
-                        eprintln!("Warning: Input thread panicked");
+        for key in &cmd_def.env_removes {
+            cmd.env_remove(key);
+        }
+        for (key, val) in &cmd_def.envs {
+            cmd.env(key, val);
+        }
+
+        if let Some(current_dir) = &cmd_def.current_dir {
+            cmd.current_dir(current_dir);
+        }
+
+        if cmd_def.is_quiet() {
+            cmd.stdout(Stdio::null());
+            cmd.stderr(Stdio::null());
+        }
+
+        match cmd_def.stdin {
+            StdioConfig::Piped => {}
+            StdioConfig::Inherit => {
+                cmd.stdin(Stdio::inherit());
+            }
+            StdioConfig::Null => {
+                cmd.stdin(Stdio::null());
+            }
+        }
+
+        limits::apply_limits(&mut cmd, &cmd_def.limits);
+
+        if cmd_def.process_group {
+            cmd.process_group(0);
+        }
+
+        if let Some(uid) = cmd_def.uid {
+            cmd.uid(uid);
+        }
+        if let Some(gid) = cmd_def.gid {
+            cmd.gid(gid);
+        }
+        if let Some(mask) = cmd_def.umask {
+            // SAFETY: umask is async-signal-safe and cannot fail.
+            unsafe {
+                cmd.pre_exec(move || {
+                    libc::umask(mask as libc::mode_t);
+                    Ok(())
+                });
+            }
+        }
+
+        if cmd_def.combine_err {
+            // SAFETY: dup2 is async-signal-safe. Pre-exec closures run after the stdio setup,
+            // so stderr ends up wherever stdout was connected.
+            unsafe {
+                cmd.pre_exec(|| {
+                    if libc::dup2(1, 2) == -1 {
+                        return Err(std::io::Error::last_os_error());
                     }
-                }
+                    Ok(())
+                });
+            }
+        }
 
-                spawn.handle.wait()?;
-                Ok(Vec::new())
+        if let Some(cgroup) = cmd_def.effective_cgroup() {
+            crate::cgroup::join(&mut cmd, &cgroup)
+                .map_err(|e| Error::io("Invalid cgroup path", e))?;
+        }
+
+        for StdHook(customize) in &cmd_def.std_hooks {
+            customize(&mut cmd);
+        }
+
+        Ok(crate::mock::apply(cmd))
+    }
+
+    /// Write the pipeline's input to its stdin on a pooled thread, closing stdin when done.
+    fn feed_input(
+        input: Option<CmdInput>,
+        stdin: Option<std::process::ChildStdin>,
+        buffer_size: usize,
+    ) -> Option<Pump<std::io::Result<()>>> {
+        let (input, mut stdin) = (input?, stdin?);
+        Some(pump::spawn(move || {
+            Self::write_input(input, &mut stdin, buffer_size)
+        }))
+    }
+
+    /// Write `input` to `stdin`, reading or buffering with `buffer_size` bytes.
+    fn write_input(
+        input: CmdInput,
+        stdin: &mut dyn Write,
+        buffer_size: usize,
+    ) -> std::io::Result<()> {
+        match input {
+            CmdInput::Bytes(bytes) => stdin.write_all(&bytes),
+            CmdInput::Heredoc(text) => stdin.write_all(text.as_bytes()),
+            #[cfg(feature = "mmap")]
+            CmdInput::Mapped(map) => stdin.write_all(&map),
+            CmdInput::Reader(reader) => {
+                std::io::copy(&mut BufReader::with_capacity(buffer_size, reader), stdin).map(drop)
             }
-        } else {
-            // For run() method, don't capture output - let it go to terminal
-            self.suppress_echo = true;
-            let spawn = self.spawn_inherit_stdio()?;
-
-            // Handle input if provided (for backward compatibility)
-            let input_handle = match input {
-                Some(CmdInput::Bytes(bytes)) => {
-                    spawn.stdin.map(|mut stdin| {
-                        thread::spawn(move || {
-                            use std::io::Write;
-                            let _ = stdin.write_all(&bytes);
-                            drop(stdin); // Close stdin to signal EOF
-                        })
-                    })
-                }
-                Some(CmdInput::Reader(mut reader)) => {
-                    spawn.stdin.map(|stdin| {
-                        thread::spawn(move || {
-                            use std::io::copy;
-                            let mut stdin = stdin;
-                            let _ = copy(&mut reader, &mut stdin);
-                            drop(stdin); // Close stdin to signal EOF
-                        })
-                    })
-                }
-                None => None,
-            };
+            CmdInput::File(mut file) => std::io::copy(&mut file, stdin).map(drop),
+            CmdInput::Writer(write) => {
+                let mut stdin = std::io::BufWriter::with_capacity(buffer_size, stdin);
+                write(&mut stdin).and_then(|()| stdin.flush())
+            }
+        }
+    }
 
-            // Wait for input thread to complete if exists
-            if let Some(handle) = input_handle {
-                if handle.join().is_err() {
-                    eprintln!("Warning: Input thread panicked");
-                }
+    /// Wait for the thread started by [`feed_input`](Self::feed_input), if any, returning the
+    /// error it failed with.
+    fn join_input(
+        handle: Option<Pump<std::io::Result<()>>>,
+        ignore_broken_pipe: bool,
+    ) -> Result<(), Error> {
+        match handle.map(Pump::join).transpose()? {
+            Some(Err(e)) if !(ignore_broken_pipe && e.kind() == std::io::ErrorKind::BrokenPipe) => {
+                Err(Error::io("Failed to write input", e))
             }
+            _ => Ok(()),
+        }
+    }
+
+    /// Give the first command a stdin pipe, unless it asked for another stdin.
+    fn pipe_stdin(std_cmd: &mut StdCommand, cmd_def: &Cmd) {
+        if cmd_def.stdin == StdioConfig::Piped {
+            std_cmd.stdin(Stdio::piped());
+        }
+    }
 
-            spawn.handle.wait()?;
-            Ok(Vec::new())
+    /// Apply the command's `/dev/null` redirections, which override piping and capture, and
+    /// spawn it, recording it for [`trace`](crate::trace). A failure is reported with `command`,
+    /// the rendered pipeline.
+    fn spawn_child(std_cmd: &mut StdCommand, cmd_def: &Cmd, command: &str) -> Result<Child, Error> {
+        if cmd_def.stdout_null {
+            std_cmd.stdout(Stdio::null());
+        }
+        if cmd_def.stderr_null {
+            std_cmd.stderr(Stdio::null());
         }
+        let child = std_cmd.spawn().map_err(|e| {
+            Error::io(
+                &format!(
+                    "Failed to spawn command: {}",
+                    cmd_def.program.to_string_lossy()
+                ),
+                e,
+            )
+            .with_command(command)
+        })?;
+        crate::trace::record_spawn(cmd_def, child.id());
+        crate::interrupt::track(child.id());
+        Ok(child)
     }
 
-    fn build_std_command_static(cmd_def: &Cmd) -> StdCommand {
-        let mut cmd = StdCommand::new(&cmd_def.program);
-        cmd.args(&cmd_def.args);
+    /// Whether the command's inherited stderr may be replaced by a pipe to keep its end for errors.
+    ///
+    /// A terminal is left alone, since programs often behave differently when writing to one
+    /// (colors, progress bars); the user sees the output there anyway.
+    fn can_capture_stderr(cmd_def: &Cmd) -> bool {
+        cmd_def.is_quiet() || !std::io::stderr().is_terminal()
+    }
 
-        for (key, val) in &cmd_def.envs {
-            cmd.env(key, val);
+    /// Show the command's stdout and stderr in the terminal, unless it is quiet.
+    fn inherit_output(std_cmd: &mut StdCommand, cmd_def: &Cmd) {
+        if !cmd_def.is_quiet() {
+            std_cmd.stdout(Stdio::inherit());
+            std_cmd.stderr(Stdio::inherit());
         }
+    }
 
-        if let Some(current_dir) = &cmd_def.current_dir {
-            cmd.current_dir(current_dir);
+    /// Create the output of an in-process stage and the read end for the next stage.
+    ///
+    /// In-process stages only have stdout, so a following `pipe_err()` stage gets no input.
+    fn in_process_output(
+        next_pipe_mode: PipeMode,
+    ) -> Result<(Box<dyn Write + Send>, std::io::PipeReader), Error> {
+        let (reader, writer) =
+            std::io::pipe().map_err(|e| Error::io("Failed to create stdout pipe", e))?;
+        match next_pipe_mode {
+            PipeMode::Stdout | PipeMode::Both => Ok((Box::new(writer), reader)),
+            PipeMode::Stderr => Ok((Box::new(std::io::sink()), reader)),
         }
+    }
 
-        cmd
+    /// Connect the output of a command before the last one to the next stage as `mode` asks,
+    /// returning the next stage's input. Its stderr goes where `stage_stderr` says unless it is
+    /// piped on.
+    fn connect_stage(
+        cmd: &mut StdCommand,
+        mode: PipeMode,
+        stage_stderr: StderrMode,
+    ) -> Result<std::io::PipeReader, Error> {
+        let mode = match (mode, stage_stderr) {
+            (PipeMode::Stdout, StderrMode::MergeIntoPipe) => PipeMode::Both,
+            (PipeMode::Stdout, StderrMode::Null) => {
+                cmd.stderr(Stdio::null());
+                mode
+            }
+            (PipeMode::Stdout, StderrMode::Capture) => {
+                cmd.stderr(Stdio::piped());
+                mode
+            }
+            _ => mode,
+        };
+        match mode {
+            PipeMode::Stdout => {
+                let (reader, writer) =
+                    std::io::pipe().map_err(|e| Error::io("Failed to create stdout pipe", e))?;
+                cmd.stdout(Stdio::from(writer));
+                Ok(reader)
+            }
+            PipeMode::Stderr => {
+                let (reader, writer) =
+                    std::io::pipe().map_err(|e| Error::io("Failed to create stderr pipe", e))?;
+                cmd.stderr(Stdio::from(writer));
+                Ok(reader)
+            }
+            PipeMode::Both => {
+                let (reader, writer) =
+                    std::io::pipe().map_err(|e| Error::io("Failed to create combined pipe", e))?;
+                let writer_clone = writer
+                    .try_clone()
+                    .map_err(|e| Error::io("Failed to clone pipe writer", e))?;
+                cmd.stdout(Stdio::from(writer));
+                cmd.stderr(Stdio::from(writer_clone));
+                Ok(reader)
+            }
+        }
+    }
+
+    /// Collect the stderr of a stage spawned with [`StderrMode::Capture`], if it was piped.
+    fn capture_stage_stderr(
+        child: &mut Child,
+        stage_tails: &mut Vec<(u32, Pump<std::io::Result<StderrTail>>)>,
+    ) {
+        if let Some(stderr) = child.stderr.take() {
+            let tail = pump::spawn(move || StderrTail::collect(stderr, std::io::sink()));
+            stage_tails.push((child.id(), tail));
+        }
+    }
+
+    /// Start an in-process stage on its own thread.
+    fn spawn_in_process(
+        filter: &LineFilter,
+        input: Option<std::io::PipeReader>,
+        output: Box<dyn Write + Send>,
+    ) -> Result<thread::JoinHandle<std::io::Result<()>>, Error> {
+        filter
+            .clone()
+            .spawn(input, output)
+            .map_err(|e| Error::io("Failed to start in-process stage", e))
+    }
+
+    /// Temporary home directories that must outlive the spawned processes.
+    fn isolated_homes(&self) -> Vec<Arc<TempDir>> {
+        self.connections
+            .iter()
+            .filter_map(|(cmd, _)| match &cmd.isolated_home {
+                Some(IsolatedHome::Ready(home)) => Some(Arc::clone(home)),
+                _ => None,
+            })
+            .collect()
     }
 
     /// Spawn pipeline with stdio inherited from parent (for run() method)
-    fn spawn_inherit_stdio(self) -> Result<PipelineSpawn, Error> {
+    ///
+    /// With `capture_stderr`, the last command's stderr is returned as a pipe instead when
+    /// [`can_capture_stderr`](Self::can_capture_stderr) allows it.
+    fn spawn_inherit_stdio(mut self, capture_stderr: bool) -> Result<PipelineSpawn, Error> {
         if !self.suppress_echo {
             self.echo_pipeline();
         }
+        let command = self.render(false);
+        let homes = self.isolated_homes();
 
         if self.connections.is_empty() {
             return Ok(PipelineSpawn {
-                handle: PipelineHandle {
-                    children: Vec::new(),
-                },
+                handle: PipelineHandle::new(Vec::new(), command, homes),
                 stdin: None,
                 stdout: None,
                 stderr: None,
@@ -1033,46 +2736,69 @@ impl Pipeline {
 
         // For single command, inherit stdio from parent
         if self.connections.len() == 1 {
+            let time_limit = self.take_time_limit();
+            let progress = self.take_progress();
             let cmd = self.connections.into_iter().next().unwrap().0;
-            let mut std_cmd = Self::build_std_command_static(&cmd);
+            let mut std_cmd = Self::build_std_command_static(&cmd)?;
 
             // Set up I/O - inherit stdout/stderr from parent, but allow stdin input
-            std_cmd.stdin(Stdio::piped());
-            std_cmd.stdout(Stdio::inherit());
-            std_cmd.stderr(Stdio::inherit());
+            Self::pipe_stdin(&mut std_cmd, &cmd);
+            Self::inherit_output(&mut std_cmd, &cmd);
+            if capture_stderr && Self::can_capture_stderr(&cmd) {
+                std_cmd.stderr(Stdio::piped());
+            }
 
-            let mut child = std_cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!("Failed to spawn command: {}", cmd.program.to_string_lossy()),
-                    e,
-                )
-            })?;
+            let mut child = Self::spawn_child(&mut std_cmd, &cmd, &command)?;
 
             let stdin = child.stdin.take();
+            let stderr = child.stderr.take();
 
             return Ok(PipelineSpawn {
-                handle: PipelineHandle {
-                    children: vec![child],
-                },
+                handle: PipelineHandle::new(vec![child], command, homes)
+                    .with_processes([&cmd])
+                    .with_time_limit(time_limit)
+                    .with_progress(progress),
                 stdin,
                 stdout: None,
-                stderr: None,
+                stderr,
             });
         }
 
         // Multi-command pipeline - inherit stdio for the last command
+        let time_limit = self.take_time_limit();
+        let progress = self.take_progress();
         let mut children: Vec<Child> = Vec::new();
+        let mut threads = Vec::new();
         let mut prev_reader: Option<std::io::PipeReader> = None;
         let mut first_stdin = None;
+        let mut last_stderr = None;
+        let mut stage_tails = Vec::new();
 
         // Spawn all commands in the pipeline
         for (i, (cmd_def, _pipe_mode)) in self.connections.iter().enumerate() {
-            let mut cmd = Self::build_std_command_static(cmd_def);
+            if let Some(filter) = &cmd_def.filter {
+                let input = prev_reader.take();
+                let output: Box<dyn Write + Send> = if i == self.connections.len() - 1 {
+                    if cmd_def.is_quiet() {
+                        Box::new(std::io::sink())
+                    } else {
+                        Box::new(std::io::stdout())
+                    }
+                } else {
+                    let (output, reader) = Self::in_process_output(self.connections[i + 1].1)?;
+                    prev_reader = Some(reader);
+                    output
+                };
+                threads.push(Self::spawn_in_process(filter, input, output)?);
+                continue;
+            }
+
+            let mut cmd = Self::build_std_command_static(cmd_def)?;
 
             // Set up stdin
             if i == 0 {
                 // First command: set up for potential input
-                cmd.stdin(Stdio::piped());
+                Self::pipe_stdin(&mut cmd, cmd_def);
             } else {
                 // Subsequent commands: use previous command's output
                 if let Some(reader) = prev_reader.take() {
@@ -1084,75 +2810,152 @@ impl Pipeline {
             let is_last = i == self.connections.len() - 1;
             if is_last {
                 // Last command: inherit stdio to display output to terminal
-                cmd.stdout(Stdio::inherit());
-                cmd.stderr(Stdio::inherit());
+                Self::inherit_output(&mut cmd, cmd_def);
+                if capture_stderr && Self::can_capture_stderr(cmd_def) {
+                    cmd.stderr(Stdio::piped());
+                }
             } else {
                 // Intermediate commands: pipe to next command
                 let next_pipe_mode = self.connections[i + 1].1;
-                match next_pipe_mode {
-                    PipeMode::Stdout => {
-                        let (reader, writer) = std::io::pipe()
-                            .map_err(|e| Error::io("Failed to create stdout pipe", e))?;
-                        cmd.stdout(Stdio::from(writer));
-                        prev_reader = Some(reader);
-                    }
-                    PipeMode::Stderr => {
-                        let (reader, writer) = std::io::pipe()
-                            .map_err(|e| Error::io("Failed to create stderr pipe", e))?;
-                        cmd.stderr(Stdio::from(writer));
-                        prev_reader = Some(reader);
-                    }
-                    PipeMode::Both => {
-                        let (reader, writer) = std::io::pipe()
-                            .map_err(|e| Error::io("Failed to create combined pipe", e))?;
-                        let writer_clone = writer
-                            .try_clone()
-                            .map_err(|e| Error::io("Failed to clone pipe writer", e))?;
-                        cmd.stdout(Stdio::from(writer));
-                        cmd.stderr(Stdio::from(writer_clone));
-                        prev_reader = Some(reader);
-                    }
-                }
+                prev_reader = Some(Self::connect_stage(
+                    &mut cmd,
+                    next_pipe_mode,
+                    self.stage_stderr,
+                )?);
             }
 
-            let mut child = cmd.spawn().map_err(|e| {
-                Error::io(
-                    &format!(
-                        "Failed to spawn command: {}",
-                        cmd_def.program.to_string_lossy()
-                    ),
-                    e,
-                )
-            })?;
+            let mut child = Self::spawn_child(&mut cmd, cmd_def, &command)?;
 
             // Store stdin of first command for potential input
             if i == 0 {
                 first_stdin = child.stdin.take();
             }
+            if is_last {
+                last_stderr = child.stderr.take();
+            } else {
+                Self::capture_stage_stderr(&mut child, &mut stage_tails);
+            }
 
             children.push(child);
         }
 
+        let mut handle = PipelineHandle::new(children, command, homes)
+            .with_processes(self.connections.iter().map(|(cmd, _)| cmd))
+            .with_time_limit(time_limit)
+            .with_progress(progress);
+        handle.threads = threads;
+        handle.stage_tails = stage_tails;
         Ok(PipelineSpawn {
-            handle: PipelineHandle { children },
+            handle,
             stdin: first_stdin,
             stdout: None,
-            stderr: None,
+            stderr: last_stderr,
         })
     }
 
+    /// Take the input to feed to the spawned pipeline, as [`take_input_after_echo`] does, and
+    /// give the first command a stdin pipe for it.
+    ///
+    /// [`take_input_after_echo`]: Self::take_input_after_echo
+    fn take_spawn_input(&mut self) -> Option<CmdInput> {
+        let input = self.take_input_after_echo();
+        if input.is_some() {
+            if let Some((first, _)) = self.connections.first_mut() {
+                first.stdin = StdioConfig::Piped;
+            }
+        }
+        input
+    }
+
+    /// Echo the pipeline unless suppressed, then take its input to feed it, so that the echo
+    /// still shows where the input comes from.
+    fn take_input_after_echo(&mut self) -> Option<CmdInput> {
+        if !self.suppress_echo {
+            self.echo_pipeline();
+        }
+        self.suppress_echo = true;
+        let input = self.input.take()?;
+        match self.record_input.take() {
+            Some(InputRecord(mut record)) => {
+                let buffer_size = self.io_buffer_size;
+                Some(CmdInput::Writer(Box::new(move |stdin| {
+                    let mut tee = InputTee {
+                        stdin,
+                        record: &mut record,
+                    };
+                    let result = Self::write_input(input, &mut tee, buffer_size);
+                    result.and(record.flush())
+                })))
+            }
+            None => Some(input),
+        }
+    }
+
     fn echo_pipeline(&self) {
-        if !crate::output::should_echo() {
-            return;
+        self.echo_pipeline_with(|_| String::new());
+    }
+
+    /// Echo the pipeline as run after another one, such as `|| <command line>`, unless
+    /// suppressed.
+    pub(crate) fn echo_chained(&mut self, operator: &str) {
+        if !self.suppress_echo {
+            let max_arg_width = Self::echo_arg_width();
+            crate::echo::emit(" ", "cmd", |styled| {
+                let operator = if styled {
+                    format!("{MAGENTA}{operator}{MAGENTA:#}")
+                } else {
+                    operator.to_string()
+                };
+                format!("{operator} {}", self.render_with(styled, max_arg_width))
+            });
         }
+        self.suppress_echo = true;
+    }
 
-        let mut parts = Vec::new();
+    /// Echo the pipeline followed by a suffix (such as a redirection), rendered with or
+    /// without colors.
+    fn echo_pipeline_with(&self, suffix: impl FnOnce(bool) -> String) {
+        let max_arg_width = Self::echo_arg_width();
+        crate::echo::emit(" ", "cmd", |styled| {
+            self.render_with(styled, max_arg_width) + &suffix(styled)
+        });
+    }
+
+    /// Maximum width of echoed arguments: the one set with `echo::set_max_arg_width()` or the
+    /// terminal width, unless verbose echo is enabled.
+    pub(crate) fn echo_arg_width() -> Option<usize> {
+        if crate::output::verbose_echo() {
+            None
+        } else {
+            Some(crate::echo::max_arg_width().unwrap_or_else(crate::output::terminal_width))
+        }
+    }
+
+    /// Render the full pipeline the way it is shown in the command echo, optionally with colors.
+    pub(crate) fn render(&self, styled: bool) -> String {
+        self.render_with(styled, None)
+    }
+
+    /// Render the pipeline, shortening arguments longer than `max_arg_width` characters.
+    pub(crate) fn render_with(&self, styled: bool, max_arg_width: Option<usize>) -> String {
+        let format = crate::echo::format();
+        let normalize = |path: &Path| -> PathBuf {
+            if format >= EchoFormat::V1 {
+                path.components().collect()
+            } else {
+                path.to_path_buf()
+            }
+        };
+        let paint = |style: anstyle::Style, text: &str| {
+            if styled {
+                format!("{style}{text}{style:#}")
+            } else {
+                text.to_string()
+            }
+        };
 
-        // Add cmd prefix
-        parts.push(format!(
-            " {BRIGHT_BLACK}{}:cmd{BRIGHT_BLACK:#}",
-            env!("CARGO_PKG_NAME")
-        ));
+        let mut parts = Vec::new();
+        let mut heredoc = None;
 
         for (i, (cmd, pipe_mode)) in self.connections.iter().enumerate() {
             if i > 0 {
@@ -1161,39 +2964,315 @@ impl Pipeline {
                     PipeMode::Stderr => "|&",
                     PipeMode::Both => "|&&",
                 };
-                parts.push(format!("{MAGENTA}{pipe_symbol}{MAGENTA:#}"));
+                parts.push(paint(MAGENTA, pipe_symbol));
+            }
+
+            // In-process stages only show their description
+            if let Some(filter) = &cmd.filter {
+                parts.push(paint(BOLD_CYAN, &filter.describe()));
+                continue;
+            }
+
+            if cmd.dry_run {
+                parts.push(paint(BRIGHT_BLUE, "dry-run:"));
             }
 
             // Add current directory if set
             if let Some(current_dir) = &cmd.current_dir {
-                let quoted_dir = Cmd::quote_argument(current_dir.as_os_str());
-                parts.push(format!("{BRIGHT_BLUE}cd:{BRIGHT_BLUE:#}"));
-                parts.push(format!(
-                    "{UNDERLINE_BRIGHT_BLUE}{quoted_dir}{UNDERLINE_BRIGHT_BLUE:#}"
-                ));
+                let quoted_dir = Cmd::quote_argument(normalize(current_dir).as_os_str());
+                parts.push(paint(BRIGHT_BLUE, "cd:"));
+                parts.push(paint(UNDERLINE_BRIGHT_BLUE, &quoted_dir));
+            }
+
+            // Add isolated home directory
+            if let Some(IsolatedHome::Ready(home)) = &cmd.isolated_home {
+                let quoted_home = Cmd::quote_argument(normalize(home.path()).as_os_str());
+                parts.push(paint(BRIGHT_BLUE, "home:"));
+                parts.push(paint(UNDERLINE_BRIGHT_BLUE, &quoted_home));
+            }
+
+            // Add cgroup
+            if let Some(cgroup) = cmd.effective_cgroup() {
+                let quoted_cgroup = Cmd::quote_argument(normalize(&cgroup).as_os_str());
+                parts.push(paint(BRIGHT_BLUE, "cgroup:"));
+                parts.push(paint(UNDERLINE_BRIGHT_BLUE, &quoted_cgroup));
+            }
+
+            // Add user and group
+            if let Some(uid) = cmd.uid {
+                parts.push(paint(BRIGHT_BLUE, "uid:"));
+                parts.push(paint(UNDERLINE_BRIGHT_BLUE, &uid.to_string()));
+            }
+            if let Some(gid) = cmd.gid {
+                parts.push(paint(BRIGHT_BLUE, "gid:"));
+                parts.push(paint(UNDERLINE_BRIGHT_BLUE, &gid.to_string()));
             }
 
             // Add environment variables
-            for (key, val) in &cmd.envs {
+            let mut envs: Vec<_> = cmd.envs.iter().collect();
+            if format >= EchoFormat::V1 {
+                envs.sort_by(|(a, _), (b, _)| a.cmp(b));
+            }
+            for (key, val) in envs {
                 let quoted_key = Cmd::quote_argument(key);
-                let quoted_val = Cmd::quote_argument(val);
-                parts.push(format!("{BRIGHT_BLUE}env:{BRIGHT_BLUE:#}"));
-                parts.push(format!(
-                    "{UNDERLINE_BRIGHT_BLUE}{quoted_key}={quoted_val}{UNDERLINE_BRIGHT_BLUE:#}"
+                let quoted_val = if cmd.secret_envs.contains(key) {
+                    crate::echo::MASK.to_string()
+                } else {
+                    Cmd::quote_argument(val)
+                };
+                parts.push(paint(BRIGHT_BLUE, "env:"));
+                parts.push(paint(
+                    UNDERLINE_BRIGHT_BLUE,
+                    &format!("{quoted_key}={quoted_val}"),
                 ));
             }
 
+            // Add removed environment variables and resource limits in verbose mode
+            if crate::output::verbose_echo() {
+                for key in &cmd.env_removes {
+                    parts.push(paint(BRIGHT_BLUE, "unset:"));
+                    parts.push(paint(UNDERLINE_BRIGHT_BLUE, &Cmd::quote_argument(key)));
+                }
+                for limit in &cmd.limits {
+                    parts.push(paint(BRIGHT_BLUE, "ulimit:"));
+                    parts.push(paint(UNDERLINE_BRIGHT_BLUE, &limit.to_string()));
+                }
+                if let Some(mask) = cmd.umask {
+                    parts.push(paint(BRIGHT_BLUE, "umask:"));
+                    parts.push(paint(UNDERLINE_BRIGHT_BLUE, &format!("{mask:03o}")));
+                }
+            }
+
             // Add program
             let quoted_program = Cmd::quote_argument(&cmd.program);
-            parts.push(format!("{BOLD_CYAN}{quoted_program}{BOLD_CYAN:#}"));
+            parts.push(paint(BOLD_CYAN, &quoted_program));
 
             // Add arguments
-            for arg in &cmd.args {
+            for (j, arg) in cmd.effective_args().iter().enumerate() {
+                // A `shell()` script is shown as written
+                if cmd.shell_script && j == 1 {
+                    parts.push(paint(BOLD_YELLOW, &arg.to_string_lossy()));
+                    continue;
+                }
                 let quoted_arg = Cmd::quote_argument(arg);
-                parts.push(format!("{BOLD_UNDERLINE}{quoted_arg}{BOLD_UNDERLINE:#}"));
+                let max_arg_width = max_arg_width.filter(|_| !cmd.echo_full_args);
+                match max_arg_width.and_then(|width| Cmd::truncate_argument(&quoted_arg, width)) {
+                    Some(shortened) => {
+                        let note = format!("({} truncated)", Cmd::format_size(arg.len()));
+                        parts.push(paint(BOLD_UNDERLINE, &shortened));
+                        parts.push(paint(BRIGHT_BLACK, &note));
+                    }
+                    None => parts.push(paint(BOLD_UNDERLINE, &quoted_arg)),
+                }
+            }
+
+            // Add redirections
+            if i == 0 {
+                let source = match (&self.input_path, &self.input) {
+                    (Some(path), _) => {
+                        Some(("<", Cmd::quote_argument(normalize(path).as_os_str())))
+                    }
+                    (None, Some(CmdInput::Heredoc(text))) => {
+                        let delimiter = Self::heredoc_delimiter(text);
+                        parts.push(paint(MAGENTA, &format!("<<'{delimiter}'")));
+                        heredoc = Some((text, delimiter));
+                        None
+                    }
+                    // Input set in code is only shown from V2 on
+                    (None, Some(_)) if format < EchoFormat::V2 => None,
+                    (None, Some(CmdInput::Bytes(bytes))) => {
+                        let quoted = Cmd::quote_argument(OsStr::from_bytes(bytes));
+                        let width = max_arg_width
+                            .map_or(INPUT_PREVIEW_WIDTH, |width| width.min(INPUT_PREVIEW_WIDTH));
+                        match Cmd::truncate_argument(&quoted, width) {
+                            Some(shortened) => Some((
+                                "<<<",
+                                format!("{shortened} ({})", Cmd::format_size(bytes.len())),
+                            )),
+                            None => Some(("<<<", quoted)),
+                        }
+                    }
+                    (None, Some(CmdInput::Reader(_))) => Some(("<", "<reader>".to_string())),
+                    (None, Some(CmdInput::Writer(_))) => Some(("<", "<closure>".to_string())),
+                    #[cfg(feature = "mmap")]
+                    (None, Some(CmdInput::Mapped(_))) => Some(("<", "<file>".to_string())),
+                    (None, Some(CmdInput::File(_))) => Some(("<", "<file>".to_string())),
+                    (None, None) if cmd.stdin == StdioConfig::Null => {
+                        Some(("<", "/dev/null".to_string()))
+                    }
+                    (None, None) => None,
+                };
+                if let Some((operator, source)) = source {
+                    parts.push(paint(MAGENTA, operator));
+                    parts.push(paint(UNDERLINE_BRIGHT_BLUE, &source));
+                }
+            }
+            if cmd.stdout_null {
+                parts.push(paint(MAGENTA, ">"));
+                parts.push(paint(UNDERLINE_BRIGHT_BLUE, "/dev/null"));
+            }
+            if cmd.stderr_null {
+                parts.push(paint(MAGENTA, "2>"));
+                parts.push(paint(UNDERLINE_BRIGHT_BLUE, "/dev/null"));
+            }
+            if cmd.combine_err {
+                parts.push(paint(MAGENTA, "2>&1"));
             }
         }
 
-        eprintln!("{}", parts.join(" "));
+        let mut line = parts.join(" ");
+        if let Some((text, delimiter)) = heredoc {
+            let limit = max_arg_width.map_or(usize::MAX, |_| HEREDOC_PREVIEW_LINES);
+            let lines: Vec<&str> = text.lines().collect();
+            for text_line in lines.iter().take(limit) {
+                line.push('\n');
+                line.push_str(text_line);
+            }
+            if lines.len() > limit {
+                let note = format!("... ({} more lines)", lines.len() - limit);
+                line.push('\n');
+                line.push_str(&paint(BRIGHT_BLACK, &note));
+            }
+            line.push('\n');
+            line.push_str(&paint(MAGENTA, &delimiter));
+        }
+        crate::echo::redact_line(line)
+    }
+
+    /// A heredoc delimiter, `EOF` unless `text` has a line that would end the heredoc early.
+    fn heredoc_delimiter(text: &str) -> String {
+        let mut delimiter = "EOF".to_string();
+        while text.lines().any(|line| line == delimiter) {
+            delimiter.push('_');
+        }
+        delimiter
+    }
+}
+
+/// Writes to a command's stdin, copying what it accepts to the writer set with
+/// [`Pipeline::record_input_to`].
+struct InputTee<'a> {
+    stdin: &'a mut dyn Write,
+    record: &'a mut dyn Write,
+}
+
+impl Write for InputTee<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.stdin.write(buf)?;
+        self.record.write_all(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stdin.flush()
+    }
+}
+
+/// Writes everything to each of the branch stdins of [`Pipeline::tee_pipe`].
+struct TeeWriter {
+    outputs: Vec<std::process::ChildStdin>,
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // A branch that stopped reading is dropped; the others keep receiving data
+        self.outputs
+            .retain_mut(|output| output.write_all(buf).is_ok());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.outputs.retain_mut(|output| output.flush().is_ok());
+        Ok(())
+    }
+}
+
+/// Spawns a command for each line written to it, for [`Pipeline::for_each_line_spawn`].
+struct LineSpawner<F> {
+    make_cmd: F,
+    /// The incomplete line read so far.
+    line: Vec<u8>,
+    /// One slot per job; a running command reports its slot through `sender` when it exits.
+    running: Vec<Option<PipelineHandle>>,
+    sender: std::sync::mpsc::Sender<usize>,
+    receiver: std::sync::mpsc::Receiver<usize>,
+    /// The first failure, after which no more commands are started.
+    error: Option<Error>,
+}
+
+impl<F: FnMut(&str) -> Cmd> LineSpawner<F> {
+    /// Start the command for the buffered line, once a slot is free.
+    fn spawn_line(&mut self) -> Result<(), Error> {
+        let mut line = String::from_utf8_lossy(&self.line).into_owned();
+        self.line.clear();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+        if line.is_empty() {
+            return Ok(());
+        }
+        let slot = match self.running.iter().position(Option::is_none) {
+            Some(slot) => slot,
+            None => {
+                let slot = self.receiver.recv().expect("sender is kept alive");
+                self.reap(slot)?;
+                slot
+            }
+        };
+
+        let cmd = (self.make_cmd)(&line);
+        let handle = cmd.into_pipeline().spawn_inherit_stdio(false)?.handle;
+        handle.notify_on_exit(self.sender.clone(), slot);
+        self.running[slot] = Some(handle);
+        Ok(())
+    }
+
+    fn reap(&mut self, slot: usize) -> Result<(), Error> {
+        match self.running[slot].take() {
+            Some(handle) => handle.wait(),
+            None => Ok(()),
+        }
+    }
+
+    /// Run the command for a last line without line ending, then wait for all commands.
+    ///
+    /// Returns the first failure.
+    fn finish(mut self) -> Option<Error> {
+        if self.error.is_none() && !self.line.is_empty() {
+            if let Err(e) = self.spawn_line() {
+                self.error = Some(e);
+            }
+        }
+        for slot in 0..self.running.len() {
+            if let Err(e) = self.reap(slot) {
+                self.error.get_or_insert(e);
+            }
+        }
+        self.error
+    }
+}
+
+impl<F: FnMut(&str) -> Cmd> Write for LineSpawner<F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.error.is_some() {
+            return Err(std::io::Error::other("a per-line command failed"));
+        }
+        for chunk in buf.split_inclusive(|&b| b == b'\n') {
+            match chunk.strip_suffix(b"\n") {
+                Some(rest) => {
+                    self.line.extend_from_slice(rest);
+                    if let Err(e) = self.spawn_line() {
+                        self.error = Some(e);
+                        return Err(std::io::Error::other("a per-line command failed"));
+                    }
+                }
+                None => self.line.extend_from_slice(chunk),
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
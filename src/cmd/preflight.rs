@@ -0,0 +1,158 @@
+//! Checks run before spawning a pipeline, so that a missing program in a later stage is found
+//! before earlier stages have done anything.
+
+use crate::cmd::{Cmd, Pipeline};
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// A problem found by [`Pipeline::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The program is not an executable file, or is not found in `PATH`.
+    ProgramNotFound {
+        /// Index of the command in the pipeline.
+        stage: usize,
+        /// The program as given.
+        program: OsString,
+    },
+    /// The working directory set with [`Cmd::current_dir`] is not a directory.
+    MissingDir {
+        /// Index of the command in the pipeline.
+        stage: usize,
+        /// The directory as given.
+        dir: PathBuf,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::ProgramNotFound { program, .. } => {
+                write!(f, "Command not found: {}", program.to_string_lossy())
+            }
+            Problem::MissingDir { dir, .. } => {
+                write!(f, "Directory not found: {}", dir.display())
+            }
+        }
+    }
+}
+
+/// The problems found by [`Pipeline::check`], in pipeline order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    problems: Vec<Problem>,
+}
+
+impl CheckReport {
+    /// The problems found, at least one.
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pipeline check failed")?;
+        for problem in &self.problems {
+            write!(f, "\n  {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CheckReport {}
+
+impl Pipeline {
+    /// Check that every program can be found and every working directory exists, without
+    /// running anything.
+    ///
+    /// Programs given as a path must be executable files, and others are looked up in the
+    /// `PATH` the command will run with. In-process stages have nothing to check. Use this
+    /// before a pipeline with destructive stages, which would otherwise run before a later
+    /// stage fails to start.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let cleanup = cmd!("find", "/var/cache/app", "-mtime", "+30", "-print0")
+    ///     .pipe(cmd!("xargs", "-0", "rm", "-f"));
+    /// cleanup.check()?;
+    /// cleanup.run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn check(&self) -> Result<(), CheckReport> {
+        let problems: Vec<Problem> = self
+            .connections
+            .iter()
+            .enumerate()
+            .flat_map(|(stage, (cmd, _))| check_cmd(stage, cmd))
+            .collect();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(CheckReport { problems })
+        }
+    }
+}
+
+impl Cmd {
+    /// Check that the program can be found and the working directory exists, without running
+    /// anything.
+    ///
+    /// See [`Pipeline::check`] for details.
+    pub fn check(&self) -> Result<(), CheckReport> {
+        let problems = check_cmd(0, self);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(CheckReport { problems })
+        }
+    }
+}
+
+fn check_cmd(stage: usize, cmd: &Cmd) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if cmd.filter.is_some() {
+        return problems;
+    }
+    if !program_exists(cmd) {
+        problems.push(Problem::ProgramNotFound {
+            stage,
+            program: cmd.program.clone(),
+        });
+    }
+    if let Some(dir) = &cmd.current_dir {
+        if !dir.is_dir() {
+            problems.push(Problem::MissingDir {
+                stage,
+                dir: dir.clone(),
+            });
+        }
+    }
+    problems
+}
+
+fn program_exists(cmd: &Cmd) -> bool {
+    let program = Path::new(&cmd.program);
+    if cmd.program.as_encoded_bytes().contains(&b'/') {
+        return is_executable(program);
+    }
+    // The last PATH set on the command wins, as when it is spawned
+    let path = match cmd.envs.iter().rev().find(|(key, _)| key == "PATH") {
+        Some((_, path)) => Some(path.clone()),
+        None if cmd.env_removes.iter().any(|key| key == OsStr::new("PATH")) => None,
+        None => std::env::var_os("PATH"),
+    };
+    path.is_some_and(|path| {
+        std::env::split_paths(&path).any(|dir| is_executable(&dir.join(program)))
+    })
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
@@ -0,0 +1,158 @@
+//! Progress events for long pipelines, sampled by a thread per pipeline.
+
+use crate::cmd::{Pipeline, PipelineHandle, reaper, types::ProgressCallback};
+use std::ffi::{OsStr, OsString};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// The bytes moved by the commands of a pipeline so far, passed to the closure set with
+/// [`Pipeline::on_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressEvent {
+    elapsed: Duration,
+    stages: Vec<StageProgress>,
+    done: bool,
+}
+
+impl ProgressEvent {
+    /// The time since the pipeline started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The processes of the pipeline, in pipeline order. In-process stages such as
+    /// [`filter_lines`](Pipeline::filter_lines) are left out.
+    pub fn stages(&self) -> &[StageProgress] {
+        &self.stages
+    }
+
+    /// Whether all processes have exited. This is the last event for the pipeline.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+/// The bytes moved by one process of a pipeline, part of a [`ProgressEvent`].
+///
+/// The counts are the bytes the process read and wrote with `read` and `write` system calls,
+/// from `/proc/<pid>/io`, so they include files and sockets as well as its stdin and stdout.
+/// They are 0 on other systems than Linux and for processes that cannot be inspected, such as
+/// those run with [`sudo`](crate::Cmd::sudo). Once the process exits, they keep the values of
+/// the last sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageProgress {
+    pid: u32,
+    program: OsString,
+    bytes_read: u64,
+    bytes_written: u64,
+    running: bool,
+}
+
+impl StageProgress {
+    /// The process ID.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// The program, as given to [`Cmd::new`](crate::Cmd::new).
+    pub fn program(&self) -> &OsStr {
+        &self.program
+    }
+
+    /// The bytes the process has read.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// The bytes the process has written.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Whether the process was still running when the event was sampled.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Update the counts from `/proc`, unless the process has exited.
+    fn sample(&mut self) {
+        if reaper::has_exited(self.pid as libc::pid_t) {
+            self.running = false;
+            return;
+        }
+        if let Some((read, written)) = read_io(self.pid) {
+            self.bytes_read = read;
+            self.bytes_written = written;
+        }
+    }
+}
+
+/// The `rchar` and `wchar` counters of process `pid`.
+#[cfg(target_os = "linux")]
+fn read_io(pid: u32) -> Option<(u64, u64)> {
+    let text = std::fs::read_to_string(format!("/proc/{pid}/io")).ok()?;
+    let counter = |name: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.trim().parse().ok())
+    };
+    Some((counter("rchar:")?, counter("wchar:")?))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_io(_pid: u32) -> Option<(u64, u64)> {
+    None
+}
+
+impl Pipeline {
+    /// Take the progress callback of the pipeline about to be spawned, with its interval.
+    ///
+    /// It is taken so that spawn methods delegating to each other only start one sampler.
+    pub(crate) fn take_progress(&mut self) -> Option<(ProgressCallback, Duration)> {
+        let callback = self.progress.take()?;
+        Some((callback, self.progress_interval))
+    }
+}
+
+impl PipelineHandle {
+    /// Call the progress callback every interval until all processes have exited, then once
+    /// more with [`ProgressEvent::is_done`] set.
+    pub(crate) fn with_progress(self, progress: Option<(ProgressCallback, Duration)>) -> Self {
+        let Some((ProgressCallback(mut callback), interval)) = progress else {
+            return self;
+        };
+        let mut stages: Vec<StageProgress> = self
+            .processes
+            .iter()
+            .map(|process| StageProgress {
+                pid: process.pid,
+                program: process.program.clone(),
+                bytes_read: 0,
+                bytes_written: 0,
+                running: true,
+            })
+            .collect();
+        let (sender, receiver) = mpsc::channel();
+        reaper::watch(self.pids(), move || {
+            let _ = sender.send(());
+        });
+
+        let start = Instant::now();
+        std::thread::spawn(move || {
+            loop {
+                let done = receiver.recv_timeout(interval) != Err(RecvTimeoutError::Timeout);
+                for stage in &mut stages {
+                    stage.sample();
+                }
+                callback(&ProgressEvent {
+                    elapsed: start.elapsed(),
+                    stages: stages.clone(),
+                    done,
+                });
+                if done {
+                    return;
+                }
+            }
+        });
+        self
+    }
+}
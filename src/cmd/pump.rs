@@ -0,0 +1,91 @@
+//! Shared pool of threads that copy data to and from child processes.
+//!
+//! Feeding stdin and reading output on a background thread used to spawn a new thread per
+//! stream and per command, which adds up in scripts running hundreds of short commands. Jobs
+//! are instead handed to an idle pooled thread, and a new thread is started only when none is
+//! idle. A pump may block for as long as its process runs, so the pool is not bounded; threads
+//! that stay idle for a while exit.
+
+use crate::cmd::Error;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// How long an idle thread waits for a job before exiting.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Channels to the idle threads, each waiting for one job.
+static IDLE: Mutex<Vec<Sender<Job>>> = Mutex::new(Vec::new());
+
+/// A job running on the pool. Dropping it detaches the job.
+pub(crate) struct Pump<T> {
+    result: Receiver<std::thread::Result<T>>,
+}
+
+impl<T> Pump<T> {
+    /// Wait for the job to finish, returning an error if it panicked.
+    pub(crate) fn join(self) -> Result<T, Error> {
+        let panic = match self.result.recv() {
+            Ok(Ok(value)) => return Ok(value),
+            Ok(Err(panic)) => panic,
+            Err(_) => Box::new("the job was dropped"),
+        };
+        let message = panic
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("the thread panicked");
+        Err(Error::io(
+            "I/O thread panicked",
+            std::io::Error::other(message.to_string()),
+        ))
+    }
+}
+
+/// Run `job` on a pooled thread.
+pub(crate) fn spawn<T: Send + 'static>(job: impl FnOnce() -> T + Send + 'static) -> Pump<T> {
+    let (sender, result) = mpsc::sync_channel(1);
+    execute(Box::new(move || {
+        let _ = sender.send(std::panic::catch_unwind(std::panic::AssertUnwindSafe(job)));
+    }));
+    Pump { result }
+}
+
+fn execute(mut job: Job) {
+    {
+        let mut idle = IDLE.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some(thread) = idle.pop() {
+            // Fails if the thread timed out in the meantime; try the next one
+            match thread.send(job) {
+                Ok(()) => return,
+                Err(mpsc::SendError(returned)) => job = returned,
+            }
+        }
+    }
+    std::thread::Builder::new()
+        .name("scripty-pump".to_string())
+        .spawn(move || run(job))
+        .expect("Failed to start I/O thread");
+}
+
+fn run(mut job: Job) {
+    loop {
+        job();
+        let (sender, receiver) = mpsc::channel();
+        IDLE.lock().unwrap_or_else(|e| e.into_inner()).push(sender);
+        job = match receiver.recv_timeout(IDLE_TIMEOUT) {
+            Ok(next) => next,
+            Err(RecvTimeoutError::Timeout) => {
+                // Jobs are sent with the lock held, so none can arrive once it is taken here
+                let _idle = IDLE.lock().unwrap_or_else(|e| e.into_inner());
+                match receiver.try_recv() {
+                    Ok(next) => next,
+                    Err(_) => return,
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+    }
+}
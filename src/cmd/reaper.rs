@@ -0,0 +1,235 @@
+//! Shared background thread that watches spawned children for exit.
+//!
+//! Waiting with a timeout or reacting to the exit of many children would otherwise need one
+//! blocked thread per child. Instead, a single thread is started on first use. It is woken by
+//! `SIGCHLD` (through a self-pipe written from a signal handler that chains to any previously
+//! installed handler) and checks the watched processes with `waitid(WNOWAIT)`, which detects the
+//! exit without reaping, so the `std::process::Child` values stay usable for `wait()`.
+//!
+//! The thread also re-checks periodically, so a missed signal only delays the notification.
+
+use std::io;
+use std::os::fd::RawFd;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// How often the watched processes are checked when no `SIGCHLD` arrives.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Processes to watch and the callback to run once all of them have exited.
+struct Watch {
+    pids: Vec<libc::pid_t>,
+    notify: Box<dyn FnOnce() + Send>,
+}
+
+struct Reaper {
+    watches: Mutex<Vec<Watch>>,
+    wake_fd: RawFd,
+}
+
+static REAPER: OnceLock<Reaper> = OnceLock::new();
+
+/// Write end of the self-pipe, for the signal handler.
+static WAKE_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// The `SIGCHLD` disposition that was in place before ours, called from our handler.
+static PREVIOUS_ACTION: OnceLock<libc::sigaction> = OnceLock::new();
+
+/// Run `notify` on the reaper thread once all processes in `pids` have exited.
+pub(crate) fn watch(pids: Vec<u32>, notify: impl FnOnce() + Send + 'static) {
+    let reaper = REAPER.get_or_init(start);
+    reaper
+        .watches
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Watch {
+            pids: pids.into_iter().map(|pid| pid as libc::pid_t).collect(),
+            notify: Box::new(notify),
+        });
+    // The processes may already have exited, so check right away
+    wake(reaper.wake_fd);
+}
+
+fn start() -> Reaper {
+    let mut fds = [-1; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } == 0 {
+        for fd in fds {
+            unsafe {
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK);
+            }
+        }
+        WAKE_FD.store(fds[1], Ordering::Relaxed);
+        install_handler();
+    }
+    let [read_fd, wake_fd] = fds;
+
+    let spawned = std::thread::Builder::new()
+        .name("scripty-reaper".to_string())
+        .spawn(move || run(read_fd));
+    if let Err(e) = spawned {
+        eprintln!("Warning: Failed to start reaper thread: {}", e);
+    }
+
+    Reaper {
+        watches: Mutex::new(Vec::new()),
+        wake_fd,
+    }
+}
+
+fn run(read_fd: RawFd) {
+    let reaper = loop {
+        // `start` runs inside `get_or_init`, so wait until the reaper is published
+        match REAPER.get() {
+            Some(reaper) => break reaper,
+            None => std::thread::yield_now(),
+        }
+    };
+
+    loop {
+        wait_for_wake(read_fd);
+
+        let finished: Vec<Watch> = {
+            let mut watches = reaper.watches.lock().unwrap_or_else(|e| e.into_inner());
+            let (finished, pending) = std::mem::take(&mut *watches)
+                .into_iter()
+                .partition(|watch| watch.pids.iter().all(|&pid| has_exited(pid)));
+            *watches = pending;
+            finished
+        };
+
+        for watch in finished {
+            (watch.notify)();
+        }
+    }
+}
+
+/// Block until the self-pipe becomes readable or the poll interval elapses, then drain it.
+fn wait_for_wake(read_fd: RawFd) {
+    if read_fd < 0 {
+        std::thread::sleep(POLL_INTERVAL);
+        return;
+    }
+
+    let mut pollfd = libc::pollfd {
+        fd: read_fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    unsafe { libc::poll(&mut pollfd, 1, POLL_INTERVAL.as_millis() as libc::c_int) };
+
+    let mut buf = [0u8; 64];
+    while unsafe { libc::read(read_fd, buf.as_mut_ptr().cast(), buf.len()) } > 0 {}
+}
+
+/// Returns whether the process has exited, without reaping it.
+///
+/// A process that was already reaped (for example by `Child::wait`) also counts as exited.
+pub(crate) fn has_exited(pid: libc::pid_t) -> bool {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::waitid(
+            libc::P_PID,
+            pid as libc::id_t,
+            &mut info,
+            libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+        )
+    };
+    if result != 0 {
+        return true;
+    }
+
+    #[cfg(target_os = "linux")]
+    let exited_pid = unsafe { info.si_pid() };
+    #[cfg(not(target_os = "linux"))]
+    let exited_pid = info.si_pid;
+    exited_pid != 0
+}
+
+/// Wait for `child` to exit and reap it, holding `lock` only while reaping.
+///
+/// Code that checks [`has_exited`] and then signals the process under the same lock can
+/// never signal a process ID that was reaped, and possibly reused, in between.
+pub(crate) fn reap(child: &mut Child, lock: &Mutex<()>) -> io::Result<ExitStatus> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    // Fails once the child was reaped, which `wait()` below then reports from its cache
+    while unsafe {
+        libc::waitid(
+            libc::P_PID,
+            child.id() as libc::id_t,
+            &mut info,
+            libc::WEXITED | libc::WNOWAIT,
+        )
+    } != 0
+        && errno() == libc::EINTR
+    {}
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    child.wait()
+}
+
+fn wake(fd: RawFd) {
+    if fd >= 0 {
+        unsafe { libc::write(fd, [1u8].as_ptr().cast(), 1) };
+    }
+}
+
+fn install_handler() {
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    action.sa_sigaction = on_sigchld as *const () as usize;
+    action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART | libc::SA_NOCLDSTOP;
+    unsafe { libc::sigemptyset(&mut action.sa_mask) };
+
+    let mut previous: libc::sigaction = unsafe { std::mem::zeroed() };
+    if unsafe { libc::sigaction(libc::SIGCHLD, &action, &mut previous) } != 0 {
+        return;
+    }
+    if previous.sa_sigaction == libc::SIG_IGN {
+        // Ignoring SIGCHLD makes the kernel reap children itself; keep that behavior
+        unsafe { libc::sigaction(libc::SIGCHLD, &previous, std::ptr::null_mut()) };
+        return;
+    }
+    let _ = PREVIOUS_ACTION.set(previous);
+}
+
+extern "C" fn on_sigchld(
+    signal: libc::c_int,
+    info: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    let saved_errno = errno();
+    wake(WAKE_FD.load(Ordering::Relaxed));
+    unsafe { *errno_location() = saved_errno };
+
+    if let Some(previous) = PREVIOUS_ACTION.get() {
+        let handler = previous.sa_sigaction;
+        if handler == libc::SIG_DFL || handler == libc::SIG_IGN {
+            return;
+        }
+        unsafe {
+            if previous.sa_flags & libc::SA_SIGINFO != 0 {
+                let handler: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                    std::mem::transmute(handler);
+                handler(signal, info, context);
+            } else {
+                let handler: extern "C" fn(libc::c_int) = std::mem::transmute(handler);
+                handler(signal);
+            }
+        }
+    }
+}
+
+pub(crate) fn errno() -> libc::c_int {
+    unsafe { *errno_location() }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) unsafe fn errno_location() -> *mut libc::c_int {
+    unsafe { libc::__errno_location() }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) unsafe fn errno_location() -> *mut libc::c_int {
+    unsafe { libc::__error() }
+}
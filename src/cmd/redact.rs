@@ -0,0 +1,40 @@
+//! Detection of environment variables that commonly hold credentials.
+
+/// Variables removed by `Cmd::redact_sensitive()` regardless of their name pattern.
+const SENSITIVE_NAMES: &[&str] = &[
+    "GOOGLE_APPLICATION_CREDENTIALS",
+    "KUBECONFIG",
+    "NETRC",
+    "SSH_AUTH_SOCK",
+];
+
+/// Name prefixes of cloud provider credential variables.
+const SENSITIVE_PREFIXES: &[&str] = &["AWS_", "AZURE_", "GCP_", "DIGITALOCEAN_", "VAULT_"];
+
+/// Name fragments that mark a variable as a secret, such as `GITHUB_TOKEN` or `DB_PASSWORD`.
+const SENSITIVE_FRAGMENTS: &[&str] = &[
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "API_KEY",
+    "APIKEY",
+    "ACCESS_KEY",
+    "PRIVATE_KEY",
+    "CREDENTIAL",
+    "AUTH",
+];
+
+/// Returns whether an environment variable name looks like it holds a credential.
+///
+/// The check ignores ASCII case.
+pub(crate) fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    SENSITIVE_NAMES.contains(&key.as_str())
+        || SENSITIVE_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+        || SENSITIVE_FRAGMENTS
+            .iter()
+            .any(|fragment| key.contains(fragment))
+}
@@ -0,0 +1,44 @@
+//! Running shell scripts with `sh -c`.
+
+use crate::cmd::Cmd;
+use std::ffi::OsStr;
+
+/// Create a command running `script` with `sh -c`.
+///
+/// Use this for the shell features that have no equivalent in [`Cmd`], such as loops and
+/// globbing. The script is shown as written in the echo instead of quoted. Pass values to the
+/// script with [`shell_arg`](Cmd::shell_arg) rather than formatting them into it, so that they
+/// are never parsed by the shell.
+///
+/// `sh` is used rather than `$SHELL`, which may be a shell with a different syntax such as
+/// `fish`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// shell("for f in *.log; do gzip \"$f\"; done").run()?;
+///
+/// let name = "O'Brien";
+/// shell("echo \"hello, $1\"").shell_arg(name).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn shell(script: impl AsRef<OsStr>) -> Cmd {
+    let mut cmd = Cmd::new("sh").arg("-c").arg(script);
+    cmd.shell_script = true;
+    cmd
+}
+
+impl Cmd {
+    /// Add a positional parameter for a [`shell`] script, available as `$1`, `$2` and so on.
+    ///
+    /// `sh -c` assigns the first argument after the script to `$0`, so `sh` is added in front
+    /// of the first parameter. On other commands this is the same as [`arg`](Self::arg).
+    pub fn shell_arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        if self.shell_script && self.args.len() == 2 {
+            self = self.arg("sh");
+        }
+        self.arg(arg)
+    }
+}
@@ -0,0 +1,42 @@
+//! Cgroup placement tests.
+//!
+//! Tests for `Cmd::cgroup()`. Real cgroups need privileges, so these tests point the command at
+//! an ordinary directory containing a `cgroup.procs` file: the child still writes to it before
+//! `exec`, which is what the placement relies on.
+
+use crate::cmd;
+
+/// Tests that the child writes to `cgroup.procs` before it starts
+#[test]
+fn test_cgroup_joins_before_exec() {
+    let dir = crate::fs::temp_dir_scoped().unwrap();
+    std::fs::write(dir.path().join("cgroup.procs"), "").unwrap();
+
+    let output = cmd!("echo", "hello")
+        .cgroup(dir.path())
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "hello\n");
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("cgroup.procs")).unwrap(),
+        "0"
+    );
+}
+
+/// Tests that a missing cgroup fails the spawn instead of running unconfined
+#[test]
+fn test_cgroup_missing() {
+    let result = cmd!("echo", "hello")
+        .cgroup("/nonexistent/scripty/cgroup")
+        .no_echo()
+        .run();
+    assert!(result.is_err());
+}
+
+/// Tests that the cgroup is shown in the command echo
+#[test]
+fn test_cgroup_echo() {
+    let pipeline = cmd!("make").cgroup("/sys/fs/cgroup/build").into_pipeline();
+    assert_eq!(pipeline.render(false), "cgroup: /sys/fs/cgroup/build make");
+}
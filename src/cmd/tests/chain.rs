@@ -0,0 +1,78 @@
+//! Command chaining tests.
+//!
+//! Tests for `and_then_cmd()`, `or_cmd()` and `or_else()`, which run commands one after another
+//! like `&&` and `||` in a shell.
+
+use crate::cmd;
+
+/// Tests that the fallback runs when the program is not installed or exits with an error
+#[test]
+fn test_fallback_used() {
+    let output = cmd!("no-such-program-scripty", "hello")
+        .no_echo()
+        .or_else(cmd!("echo", "fallback").no_echo())
+        .output()
+        .unwrap();
+    assert_eq!(output, "fallback\n");
+
+    let output = cmd!("sh", "-c", "echo partial; exit 1")
+        .no_echo()
+        .or_else(cmd!("false").no_echo())
+        .or_else(cmd!("echo", "third").no_echo())
+        .output()
+        .unwrap();
+    assert_eq!(output, "third\n");
+}
+
+/// Tests that the fallback does not run when the first command succeeds
+#[test]
+fn test_fallback_skipped() {
+    let tmp = crate::fs::temp_dir_scoped().unwrap();
+    let marker = tmp.path().join("marker");
+    cmd!("true")
+        .no_echo()
+        .or_else(cmd!("touch", &marker).no_echo())
+        .run()
+        .unwrap();
+    assert!(!marker.exists());
+}
+
+/// Tests that the error of the last command is returned when all of them fail
+#[test]
+fn test_all_fail() {
+    let error = cmd!("false")
+        .no_echo()
+        .or_else(cmd!("sh", "-c", "exit 3").no_echo())
+        .run()
+        .unwrap_err();
+    assert_eq!(error.command(), Some("sh -c 'exit 3'"));
+}
+
+/// Tests that `a && b || c` runs like in a shell
+#[test]
+fn test_and_or() {
+    let output = cmd!("true")
+        .no_echo()
+        .and_then_cmd(cmd!("echo", "b").no_echo())
+        .or_cmd(cmd!("echo", "c").no_echo())
+        .output()
+        .unwrap();
+    assert_eq!(output, "b\n");
+
+    let output = cmd!("false")
+        .no_echo()
+        .and_then_cmd(cmd!("echo", "b").no_echo())
+        .or_cmd(cmd!("echo", "c").no_echo())
+        .output()
+        .unwrap();
+    assert_eq!(output, "c\n");
+
+    // The result is that of the last command run
+    let error = cmd!("true")
+        .no_echo()
+        .and_then_cmd(cmd!("false").no_echo())
+        .and_then_cmd(cmd!("echo", "skipped").no_echo())
+        .output()
+        .unwrap_err();
+    assert_eq!(error.command(), Some("false"));
+}
@@ -0,0 +1,44 @@
+//! Clone tests.
+//!
+//! Tests for reusing commands as templates with `Clone`, and for `Pipeline::try_clone()`.
+
+use crate::cmd;
+
+/// Tests that a cloned command keeps the template's settings and can be extended independently
+#[test]
+fn test_cmd_template() {
+    let template = cmd!("sh", "-c").env("GREETING", "hello").no_echo();
+    let first = template
+        .clone()
+        .arg("echo $GREETING $0")
+        .arg("first")
+        .output()
+        .unwrap();
+    let second = template
+        .clone()
+        .arg("echo $GREETING $0")
+        .arg("second")
+        .output()
+        .unwrap();
+    assert_eq!(first.trim(), "hello first");
+    assert_eq!(second.trim(), "hello second");
+    assert_eq!(template.args, ["-c"]);
+}
+
+/// Tests that pipelines with byte input can be cloned and run again
+#[test]
+fn test_pipeline_try_clone() {
+    let pipeline = cmd!("tr", "a-z", "A-Z").input("hello").no_echo();
+    let copy = pipeline.try_clone().unwrap();
+    assert_eq!(copy.output().unwrap(), "HELLO");
+    assert_eq!(pipeline.output().unwrap(), "HELLO");
+}
+
+/// Tests that pipelines reading from a stream cannot be cloned
+#[test]
+fn test_pipeline_try_clone_reader() {
+    use crate::ReadExt;
+
+    let pipeline = std::io::Cursor::new(b"hello".to_vec()).pipe(cmd!("cat"));
+    assert!(pipeline.try_clone().is_none());
+}
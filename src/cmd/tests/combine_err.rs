@@ -0,0 +1,46 @@
+//! Stream merging tests.
+//!
+//! Tests for `combine_err()`, which sends a command's stderr to its stdout like `2>&1`.
+
+use crate::cmd;
+
+/// Tests that stderr is captured with stdout, in the order it was written
+#[test]
+fn test_combine_err_output() {
+    let output = cmd!("sh", "-c", "echo one; echo two >&2; echo three")
+        .combine_err()
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "one\ntwo\nthree\n");
+}
+
+/// Tests that the merged streams of the last command of a pipeline are captured
+#[test]
+fn test_combine_err_last_in_pipeline() {
+    let output = cmd!("echo", "input")
+        .pipe(cmd!("sh", "-c", "cat; echo warning >&2").combine_err())
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "input\nwarning\n");
+}
+
+/// Tests that `stdout_null()` with `combine_err()` discards both streams
+#[test]
+fn test_combine_err_with_stdout_null() {
+    let (stdout, stderr) = cmd!("sh", "-c", "echo out; echo err >&2")
+        .stdout_null()
+        .combine_err()
+        .no_echo()
+        .output_with_stderr()
+        .unwrap();
+    assert_eq!((stdout.as_str(), stderr.as_str()), ("", ""));
+}
+
+/// Tests that the merge is shown after the command's other redirections
+#[test]
+fn test_combine_err_echo() {
+    let pipeline = cmd!("make").stdout_null().combine_err().into_pipeline();
+    assert_eq!(pipeline.render(false), "make > /dev/null 2>&1");
+}
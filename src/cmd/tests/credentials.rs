@@ -0,0 +1,49 @@
+//! Credential tests.
+//!
+//! Tests for `uid()`, `gid()` and `umask()`. Changing the user needs root, so those tests only
+//! check the IDs when running as root.
+
+use crate::cmd;
+
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Tests that `umask()` is visible to the child shell
+#[test]
+fn test_umask() {
+    let output = cmd!("sh", "-c", "umask")
+        .umask(0o027)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "0027");
+}
+
+/// Tests that `uid()` and `gid()` change the user and group of the child
+#[test]
+fn test_uid_gid() {
+    if !is_root() {
+        let error = cmd!("true").uid(0).no_echo().run().unwrap_err();
+        assert!(
+            error.to_string().contains("Operation not permitted"),
+            "{error}"
+        );
+        return;
+    }
+    let output = cmd!("sh", "-c", "echo $(id -u) $(id -g)")
+        .uid(65534)
+        .gid(65534)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "65534 65534");
+}
+
+/// Tests that the user and group are shown in the command echo
+#[test]
+fn test_credentials_echo() {
+    let pipeline = cmd!("make").uid(1000).gid(100).umask(0o022).into_pipeline();
+    assert_eq!(pipeline.render(false), "uid: 1000 gid: 100 make");
+}
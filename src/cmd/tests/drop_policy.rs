@@ -0,0 +1,136 @@
+//! Drop policy tests.
+//!
+//! Tests for what happens when a `PipelineHandle` is dropped without `wait()`. A zombie still
+//! answers `kill(pid, 0)`, so a process counts as reaped only once that call fails.
+
+use crate::cmd;
+use crate::cmd::DropPolicy;
+use std::time::{Duration, Instant};
+
+fn is_reaped(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, 0) != 0 }
+}
+
+/// Polls until the process has been reaped or the timeout elapses.
+fn wait_reaped(pid: u32, timeout: Duration) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if is_reaped(pid) {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    false
+}
+
+/// Tests that the wait policy waits for the process to exit
+#[test]
+fn test_drop_wait() {
+    let (mut handle, _) = cmd!("sleep", "0.2").no_echo().spawn_io_in().unwrap();
+    handle.set_drop_policy(DropPolicy::Wait);
+    let pid = handle.children[0].id();
+    let start = Instant::now();
+    drop(handle);
+    assert!(start.elapsed() >= Duration::from_millis(150));
+    assert!(is_reaped(pid));
+}
+
+/// Tests that waiting discards unread output instead of blocking on a full pipe
+#[test]
+fn test_drop_wait_drains_output() {
+    let spawn = cmd!("head", "-c", "1000000", "/dev/zero")
+        .no_echo()
+        .spawn_io_all()
+        .unwrap();
+    let mut handle = spawn.handle;
+    handle.set_drop_policy(DropPolicy::Wait);
+    let pid = handle.children[0].id();
+    // Give the output back to the handle, as if it had never been taken
+    handle.children[0].stdout = spawn.stdout;
+    drop(handle);
+    assert!(is_reaped(pid));
+}
+
+/// Tests that the default kill policy stops a process that never exits on its own
+#[test]
+#[serial_test::serial]
+fn test_drop_kill() {
+    let (handle, _) = cmd!("tail", "-f", "/dev/null")
+        .no_echo()
+        .spawn_io_in()
+        .unwrap();
+    assert_eq!(handle.drop_policy, DropPolicy::Kill);
+    let pid = handle.children[0].id();
+    let start = Instant::now();
+    drop(handle);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(is_reaped(pid));
+}
+
+/// Tests that detached processes keep running and are reaped in the background
+#[test]
+fn test_drop_detach() {
+    let (mut handle, _) = cmd!("sleep", "0.2")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .spawn_io_in()
+        .unwrap();
+    handle.set_drop_policy(DropPolicy::Detach);
+    let pids: Vec<u32> = handle.children.iter().map(|c| c.id()).collect();
+    let start = Instant::now();
+    drop(handle);
+    assert!(start.elapsed() < Duration::from_millis(150));
+    assert!(!is_reaped(pids[0]));
+    for pid in pids {
+        assert!(wait_reaped(pid, Duration::from_secs(5)));
+    }
+}
+
+/// Tests that the panic policy panics in debug builds after reaping the processes
+#[test]
+fn test_drop_panic_in_debug() {
+    let (mut handle, _) = cmd!("sleep", "30").no_echo().spawn_io_in().unwrap();
+    handle.set_drop_policy(DropPolicy::PanicInDebug);
+    let pid = handle.children[0].id();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(handle)));
+    assert_eq!(result.is_err(), cfg!(debug_assertions));
+    assert!(is_reaped(pid));
+}
+
+/// Tests that handles that were waited for do not trigger the drop policy
+#[test]
+fn test_drop_after_wait() {
+    let (mut handle, _) = cmd!("true").no_echo().spawn_io_in().unwrap();
+    handle.set_drop_policy(DropPolicy::PanicInDebug);
+    handle.wait().unwrap();
+
+    let (mut handle, _) = cmd!("true").no_echo().spawn_io_in().unwrap();
+    handle.set_drop_policy(DropPolicy::PanicInDebug);
+    assert!(handle.wait_timeout(Duration::from_secs(10)).unwrap());
+}
+
+/// Tests that remaining stages are reaped when an earlier stage fails in wait()
+#[test]
+fn test_wait_failure_reaps_remaining() {
+    let (handle, _) = cmd!("sh", "-c", "exit 1")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .spawn_io_in()
+        .unwrap();
+    let pids: Vec<u32> = handle.children.iter().map(|c| c.id()).collect();
+    assert!(handle.wait().is_err());
+    for pid in pids {
+        assert!(is_reaped(pid));
+    }
+}
+
+/// Tests that the default policy applies to handles created afterwards
+#[test]
+#[serial_test::serial]
+fn test_default_drop_policy() {
+    crate::PipelineHandle::set_default_drop_policy(DropPolicy::Detach);
+    let (handle, _) = cmd!("true").no_echo().spawn_io_in().unwrap();
+    crate::PipelineHandle::set_default_drop_policy(DropPolicy::default());
+    assert_eq!(handle.drop_policy, DropPolicy::Detach);
+    handle.wait().unwrap();
+}
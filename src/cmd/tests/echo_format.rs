@@ -0,0 +1,84 @@
+//! Echo format stability tests.
+//!
+//! Tests that the rendered command line is deterministic and follows the selected `EchoFormat`.
+
+use crate::ReadExt;
+use crate::cmd;
+use crate::echo::{self, EchoFormat};
+use serial_test::serial;
+
+/// Tests that environment variables are sorted by name regardless of insertion order
+#[test]
+#[serial]
+fn test_env_sorted() {
+    let a = cmd!("make")
+        .env("ZED", "1")
+        .env("ALPHA", "2")
+        .into_pipeline();
+    let b = cmd!("make")
+        .env("ALPHA", "2")
+        .env("ZED", "1")
+        .into_pipeline();
+    assert_eq!(a.render(false), "env: ALPHA=2 env: ZED=1 make");
+    assert_eq!(a.render(false), b.render(false));
+}
+
+/// Tests that working directory paths are normalized
+#[test]
+#[serial]
+fn test_current_dir_normalized() {
+    let pipeline = cmd!("ls").current_dir("/tmp//build/./out/").into_pipeline();
+    assert_eq!(pipeline.render(false), "cd: /tmp/build/out ls");
+}
+
+/// Tests that `EchoFormat::V0` keeps insertion order and paths as given
+#[test]
+#[serial]
+fn test_format_v0_compatibility() {
+    echo::set_format(EchoFormat::V0);
+    let pipeline = cmd!("ls")
+        .current_dir("/tmp//build/")
+        .env("ZED", "1")
+        .env("ALPHA", "2")
+        .into_pipeline();
+    let rendered = pipeline.render(false);
+    echo::set_format(EchoFormat::default());
+
+    assert_eq!(rendered, "cd: /tmp//build/ env: ZED=1 env: ALPHA=2 ls");
+}
+
+/// Tests that `EchoFormat::V2` shows input set in code as a redirection, with long text
+/// shortened
+#[test]
+#[serial]
+fn test_format_v2_input_redirection() {
+    echo::set_format(EchoFormat::V2);
+    let text = cmd!("grep", "x").input("hello world").render(false);
+    let long = cmd!("wc", "-c")
+        .input("a".repeat(100))
+        .pipe(cmd!("cat"))
+        .render(false);
+    let reader = std::io::Cursor::new("b\na\n")
+        .pipe(cmd!("sort"))
+        .render(false);
+    let replaced = cmd!("cat").stdin_null().input("text").render(false);
+    echo::set_format(EchoFormat::default());
+
+    assert_eq!(text, "grep x <<< 'hello world'");
+    assert!(long.starts_with("wc -c <<< aaa"), "{long}");
+    assert!(long.ends_with(" (100 B) | cat"), "{long}");
+    assert_eq!(reader, "sort < <reader>");
+    assert_eq!(replaced, "cat <<< text");
+}
+
+/// Tests that `EchoFormat::V1`, the default, hides input set in code but keeps file
+/// redirections
+#[test]
+#[serial]
+fn test_format_v1_hides_input() {
+    let text = cmd!("grep", "x").input("hello").render(false);
+    let null = cmd!("cat").stdin_null().into_pipeline().render(false);
+
+    assert_eq!(text, "grep x");
+    assert_eq!(null, "cat < /dev/null");
+}
@@ -0,0 +1,89 @@
+//! End-of-options separator tests.
+//!
+//! Tests for `end_of_options()` and the automatic `--` insertion for known file utilities.
+
+use super::*;
+use crate::cmd;
+use std::ffi::OsString;
+
+fn args_of(cmd: &Cmd) -> Vec<String> {
+    cmd.effective_args()
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Tests that `end_of_options()` adds a literal `--`
+#[test]
+fn test_end_of_options() {
+    let cmd = cmd!("rm", "-f").end_of_options().arg("-rf");
+    assert_eq!(args_of(&cmd), ["-f", "--", "-rf"]);
+}
+
+/// Tests that an existing file named like an option is protected
+#[test]
+fn test_auto_end_of_options_existing_file() {
+    let tmp = crate::fs::temp_dir_scoped().unwrap();
+    std::fs::write(tmp.path().join("-rf"), "").unwrap();
+
+    let cmd = cmd!("rm", "-f", "-rf", "other")
+        .current_dir(tmp.path())
+        .auto_end_of_options();
+    assert_eq!(args_of(&cmd), ["-f", "--", "-rf", "other"]);
+}
+
+/// Tests that a dash-prefixed operand after a regular operand is protected
+#[test]
+fn test_auto_end_of_options_after_operand() {
+    let cmd = cmd!("/bin/cat", "-n", "notes.txt", "-weird-name").auto_end_of_options();
+    assert_eq!(args_of(&cmd), ["-n", "--", "notes.txt", "-weird-name"]);
+}
+
+/// Tests that nothing is inserted when it isn't needed or not applicable
+#[test]
+fn test_auto_end_of_options_unchanged() {
+    // No dash-prefixed operands
+    let cmd = cmd!("rm", "-rf", "build").auto_end_of_options();
+    assert_eq!(args_of(&cmd), ["-rf", "build"]);
+
+    // Already separated
+    let cmd = cmd!("rm", "-f", "--", "-x", "-y").auto_end_of_options();
+    assert_eq!(args_of(&cmd), ["-f", "--", "-x", "-y"]);
+
+    // Unknown program
+    let cmd = cmd!("git", "log", "-n", "1").auto_end_of_options();
+    assert_eq!(args_of(&cmd), ["log", "-n", "1"]);
+
+    // Not opted in
+    let cmd = cmd!("cat", "a.txt", "-b");
+    assert_eq!(args_of(&cmd), ["a.txt", "-b"]);
+}
+
+/// Tests that a lone `-` (stdin) is treated as an operand, not an option
+#[test]
+fn test_auto_end_of_options_stdin_dash() {
+    let cmd = cmd!("cat", "-", "b.txt").auto_end_of_options();
+    assert_eq!(args_of(&cmd), ["-", "b.txt"]);
+}
+
+/// Tests that the separator reaches the child process
+#[test]
+fn test_auto_end_of_options_runs() {
+    let tmp = crate::fs::temp_dir_scoped().unwrap();
+    std::fs::write(tmp.path().join("-n"), "content\n").unwrap();
+
+    let output = cmd!("cat", "-n")
+        .current_dir(tmp.path())
+        .auto_end_of_options()
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "content\n");
+
+    let args: Vec<OsString> = cmd!("cat", "-n")
+        .current_dir(tmp.path())
+        .auto_end_of_options()
+        .effective_args()
+        .into_owned();
+    assert_eq!(args, [OsString::from("--"), OsString::from("-n")]);
+}
@@ -4,6 +4,7 @@
 //! for command execution, including error handling for invalid paths.
 
 use crate::cmd;
+use serial_test::serial;
 use std::env;
 
 /// Tests setting environment variables for command execution
@@ -132,3 +133,122 @@ fn test_empty_environment_variables() {
     // printenv should fail for unset variables
     assert!(result.is_err());
 }
+
+/// Tests removing inherited variables by name pattern
+#[test]
+fn test_env_remove_matching() {
+    // SAFETY: Unique variable names, set only for this test
+    unsafe {
+        env::set_var("SCRIPTY_STRIP_A", "a");
+        env::set_var("SCRIPTY_STRIP_B", "b");
+        env::set_var("SCRIPTY_KEEP_C", "c");
+    }
+
+    let output = cmd!(
+        "sh",
+        "-c",
+        "echo ${SCRIPTY_STRIP_A:-none} ${SCRIPTY_STRIP_B:-none} ${SCRIPTY_KEEP_C:-none}"
+    )
+    .env_remove_matching(|key| key.starts_with("SCRIPTY_STRIP_"))
+    .env("SCRIPTY_STRIP_B", "explicit")
+    .no_echo()
+    .output()
+    .unwrap();
+    assert_eq!(output.trim(), "none explicit c");
+
+    // SAFETY: Cleaning up the variables set above
+    unsafe {
+        env::remove_var("SCRIPTY_STRIP_A");
+        env::remove_var("SCRIPTY_STRIP_B");
+        env::remove_var("SCRIPTY_KEEP_C");
+    }
+}
+
+/// Tests that redact_sensitive() removes credential-like variables only
+#[test]
+fn test_redact_sensitive() {
+    // SAFETY: Unique variable names, set only for this test
+    unsafe {
+        env::set_var("SCRIPTY_TEST_API_TOKEN", "secret");
+        env::set_var("aws_scripty_test_key", "secret");
+        env::set_var("SCRIPTY_TEST_PLAIN", "visible");
+    }
+
+    let output = cmd!(
+        "sh",
+        "-c",
+        "echo ${SCRIPTY_TEST_API_TOKEN:-none} ${aws_scripty_test_key:-none} $SCRIPTY_TEST_PLAIN"
+    )
+    .redact_sensitive()
+    .no_echo()
+    .output()
+    .unwrap();
+    assert_eq!(output.trim(), "none none visible");
+
+    // SAFETY: Cleaning up the variables set above
+    unsafe {
+        env::remove_var("SCRIPTY_TEST_API_TOKEN");
+        env::remove_var("aws_scripty_test_key");
+        env::remove_var("SCRIPTY_TEST_PLAIN");
+    }
+}
+
+/// Tests that env_secret() passes the value but masks it in the echo
+#[test]
+fn test_env_secret() {
+    let pipeline = cmd!("printenv", "DEPLOY_TOKEN")
+        .env_secret("DEPLOY_TOKEN", "hunter2")
+        .env("REGION", "eu")
+        .into_pipeline();
+    assert_eq!(
+        pipeline.render(false),
+        "env: DEPLOY_TOKEN=**** env: REGION=eu printenv DEPLOY_TOKEN"
+    );
+
+    let output = pipeline.no_echo().output().unwrap();
+    assert_eq!(output.trim(), "hunter2");
+}
+
+/// Tests that prepend_path() and append_path() extend PATH, relative to the current directory
+#[test]
+#[serial]
+fn test_prepend_and_append_path() {
+    let inherited = env::var("PATH").unwrap();
+    let cwd = env::current_dir().unwrap();
+
+    let output = cmd!("sh", "-c", "echo $PATH")
+        .prepend_path("/opt/first")
+        .prepend_path("tools/bin")
+        .append_path("/opt/last")
+        .no_echo()
+        .output()
+        .unwrap();
+    let expected = format!(
+        "{}:/opt/first:{inherited}:/opt/last",
+        cwd.join("tools/bin").display()
+    );
+    assert_eq!(output.trim(), expected);
+
+    // A PATH set with env() is extended instead of the inherited one
+    let output = cmd!("printenv", "PATH")
+        .env("PATH", "/usr/bin:/bin")
+        .prepend_path("/opt/bin")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "/opt/bin:/usr/bin:/bin");
+}
+
+/// Tests that a directory containing ':' fails the command when it runs instead of panicking
+#[test]
+fn test_prepend_path_with_colon() {
+    let error = cmd!("true")
+        .prepend_path("/opt/a:b")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid command: cannot add /opt/a:b to PATH, since it contains ':'"
+    );
+}
@@ -37,6 +37,29 @@ fn test_command_not_found_error() {
     assert!(result.is_err());
 }
 
+/// Tests that a spawn failure reports the whole command line, arguments included
+#[test]
+fn test_spawn_error_includes_command() {
+    let error = cmd!("nonexistent_command_12345", "--flag", "two words")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(
+        error.command(),
+        Some("nonexistent_command_12345 --flag 'two words'")
+    );
+
+    let error = cmd!("echo", "hi")
+        .pipe(cmd!("nonexistent_command_12345", "-x"))
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert_eq!(
+        error.command(),
+        Some("echo hi | nonexistent_command_12345 -x")
+    );
+}
+
 /// Tests command that exits with non-zero status
 #[test]
 fn test_exit_code_handling() {
@@ -95,3 +118,106 @@ fn test_pipeline_error_propagation() {
     let result = cmd!("sh", "-c", "exit 1").pipe(cmd!("cat")).no_echo().run();
     assert!(result.is_err());
 }
+
+/// Tests that errors include the rendered command line
+#[test]
+fn test_error_includes_command() {
+    let error = cmd!("sh", "-c", "exit 2").no_echo().run().unwrap_err();
+    assert_eq!(error.command(), Some("sh -c 'exit 2'"));
+    assert_eq!(
+        error.to_string(),
+        "Command failed (exit code 2): sh -c 'exit 2'"
+    );
+
+    // The full pipeline is rendered, including cd/env context
+    let error = cmd!("echo", "hello")
+        .pipe(cmd!("sh", "-c", "cat; exit 3").env("LANG", "C"))
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert_eq!(
+        error.command(),
+        Some("echo hello | env: LANG=C sh -c 'cat; exit 3'")
+    );
+    assert!(
+        error
+            .to_string()
+            .starts_with("Command failed (exit code 3): ")
+    );
+
+    // Echo suppression does not affect the error context
+    let error = cmd!("false").no_echo().run().unwrap_err();
+    assert_eq!(error.command(), Some("false"));
+}
+
+/// Tests that a failed command's stderr is attached to the error and shown by `Display`
+#[test]
+fn test_error_includes_stderr() {
+    let error = cmd!("sh", "-c", "echo missing >&2; exit 1")
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert_eq!(error.stderr(), Some("missing"));
+    assert_eq!(
+        error.to_string(),
+        "Command failed (exit code 1): sh -c 'echo missing >&2; exit 1'\nstderr:\nmissing"
+    );
+
+    // Test output is not a terminal, so run() captures stderr as well
+    let error = cmd!("sh", "-c", "echo 'bad flag' >&2; exit 2")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(error.stderr(), Some("bad flag"));
+
+    let error = cmd!("sh", "-c", "echo quiet failure >&2; exit 1")
+        .quiet()
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(error.stderr(), Some("quiet failure"));
+
+    let error = cmd!("false").no_echo().output().unwrap_err();
+    assert_eq!(error.stderr(), None);
+}
+
+/// Tests that only the end of long stderr output is kept, starting at a full line
+#[test]
+fn test_error_stderr_is_bounded() {
+    let error = cmd!("sh", "-c", "seq 1 10000 >&2; exit 1")
+        .no_echo()
+        .output()
+        .unwrap_err();
+    let stderr = error.stderr().unwrap();
+    assert!(stderr.len() <= 8 * 1024 + 4);
+    assert!(stderr.starts_with("...\n"));
+    assert!(stderr.ends_with("\n9999\n10000"));
+    let first = stderr.lines().nth(1).unwrap();
+    assert!(first.parse::<u32>().is_ok(), "{first}");
+}
+
+/// Tests that a command killed by a signal is reported with the signal's name
+#[test]
+fn test_error_killed_by_signal() {
+    let error = cmd!("sh", "-c", "kill -TERM $$")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(error.signal(), Some(libc::SIGTERM));
+    assert_eq!(error.exit_code(), None);
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Command killed by SIGTERM (signal {}): sh -c 'kill -TERM $$'",
+            libc::SIGTERM
+        )
+    );
+
+    let error = cmd!("sh", "-c", "exit 4").no_echo().run().unwrap_err();
+    assert_eq!(error.exit_code(), Some(4));
+    assert_eq!(error.signal(), None);
+
+    // Errors not about an exit status have neither
+    let error = cmd!("scripty-no-such-program").no_echo().run().unwrap_err();
+    assert_eq!((error.exit_code(), error.signal()), (None, None));
+}
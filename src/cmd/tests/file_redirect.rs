@@ -0,0 +1,81 @@
+//! File redirection tests.
+//!
+//! Tests for `cat()` as a pipeline source and `to_file()` / `append_file()` as its end.
+
+use crate::{cat, cmd};
+
+/// Tests that a file read with `cat()` is piped through and written with `to_file()`
+#[test]
+fn test_cat_to_file() {
+    let dir = crate::fs::temp_dir_scoped().unwrap();
+    let input = dir.path().join("words.txt");
+    let output = dir.path().join("sorted.txt");
+    std::fs::write(&input, "banana\napple\ncherry\n").unwrap();
+    std::fs::write(&output, "old content that is longer\n").unwrap();
+
+    cat(&input)
+        .unwrap()
+        .pipe(cmd!("sort"))
+        .no_echo()
+        .to_file(&output)
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "apple\nbanana\ncherry\n"
+    );
+}
+
+/// Tests that `append_file()` keeps the existing content
+#[test]
+fn test_append_file() {
+    let file = crate::fs::temp_file_scoped().unwrap();
+    std::fs::write(file.path(), "first\n").unwrap();
+
+    cmd!("echo", "second")
+        .no_echo()
+        .append_file(file.path())
+        .unwrap();
+    cmd!("echo", "third")
+        .no_echo()
+        .append_file(file.path())
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(file.path()).unwrap(),
+        "first\nsecond\nthird\n"
+    );
+}
+
+/// Tests that a missing input file is reported by `cat()` with its path
+#[test]
+fn test_cat_missing_file() {
+    let error = cat("/nonexistent/input.txt").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    assert!(error.to_string().contains("/nonexistent/input.txt"));
+}
+
+/// Tests that an output file that cannot be created fails before the command runs
+#[test]
+fn test_to_file_error() {
+    let error = cmd!("echo", "hello")
+        .no_echo()
+        .to_file("/nonexistent/output.txt")
+        .unwrap_err();
+    assert!(error.to_string().contains("/nonexistent/output.txt"));
+}
+
+/// Tests that the input file is shown after the first command
+#[test]
+fn test_cat_echo() {
+    let pipeline = cat("Cargo.toml")
+        .unwrap()
+        .pipe(cmd!("grep", "name"))
+        .pipe(cmd!("head", "-n", "1"));
+    assert_eq!(pipeline.render(false), "grep name < Cargo.toml | head -n 1");
+
+    // Replacing the input drops the redirection
+    let pipeline = cat("Cargo.toml")
+        .unwrap()
+        .pipe(cmd!("wc", "-l"))
+        .input("text");
+    assert_eq!(pipeline.render(false), "wc -l");
+}
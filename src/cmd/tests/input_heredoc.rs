@@ -0,0 +1,47 @@
+//! Heredoc input tests.
+//!
+//! Tests for `input_heredoc()`, its input and its echo as a `<<'EOF'` block.
+
+use crate::cmd;
+
+/// Tests that the text is fed to stdin like `input()`
+#[test]
+fn test_input_heredoc_feeds_stdin() {
+    let output = cmd!("sh")
+        .input_heredoc("echo one\necho two\n")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "one\ntwo\n");
+}
+
+/// Tests that the echo shows the text in a heredoc after the whole pipeline
+#[test]
+fn test_input_heredoc_echo() {
+    let pipeline = cmd!("python3", "-")
+        .input_heredoc("import sys\nprint(sys.version)\n")
+        .pipe(cmd!("head", "-n1"));
+    assert_eq!(
+        pipeline.render(false),
+        "python3 - <<'EOF' | head -n1\nimport sys\nprint(sys.version)\nEOF"
+    );
+}
+
+/// Tests that the delimiter changes when the text contains a line that would end the heredoc
+#[test]
+fn test_input_heredoc_delimiter_in_text() {
+    let pipeline = cmd!("cat").input_heredoc("a\nEOF\nb\n");
+    assert_eq!(pipeline.render(false), "cat <<'EOF_'\na\nEOF\nb\nEOF_");
+}
+
+/// Tests that heredoc input is kept by `to_shell_string()` and `try_clone()`
+#[test]
+fn test_input_heredoc_shell_string_and_clone() {
+    let pipeline = cmd!("sh").input_heredoc("echo hi\n");
+    assert_eq!(
+        pipeline.to_shell_string().unwrap(),
+        "printf '%s' 'echo hi\n' | sh"
+    );
+    let output = pipeline.try_clone().unwrap().no_echo().output().unwrap();
+    assert_eq!(output, "hi\n");
+}
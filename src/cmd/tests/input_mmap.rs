@@ -0,0 +1,87 @@
+//! File input tests.
+//!
+//! Tests for `input_mmap()`, which maps the file with the `mmap` feature and streams it otherwise,
+//! and for files piped with `ReadExt::pipe`.
+
+use crate::cmd;
+use std::io::Write;
+
+/// Tests that the file content reaches the command's stdin
+#[test]
+fn test_input_mmap() {
+    let file = crate::fs::temp_file_scoped().unwrap();
+    std::fs::write(file.path(), "banana\napple\ncherry\n").unwrap();
+
+    let output = cmd!("sort")
+        .no_echo()
+        .input_mmap(file.path())
+        .unwrap()
+        .output()
+        .unwrap();
+    assert_eq!(output, "apple\nbanana\ncherry\n");
+}
+
+/// Tests a file larger than the pipe buffer, through a pipeline
+#[test]
+fn test_input_mmap_large() {
+    let file = crate::fs::temp_file_scoped().unwrap();
+    {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(file.path()).unwrap());
+        for i in 0..100_000 {
+            writeln!(writer, "line {i}").unwrap();
+        }
+    }
+
+    let output = cmd!("cat")
+        .pipe(cmd!("wc", "-l"))
+        .no_echo()
+        .input_mmap(file.path())
+        .unwrap()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "100000");
+}
+
+/// Tests that an empty file gives empty input
+#[test]
+fn test_input_mmap_empty() {
+    let file = crate::fs::temp_file_scoped().unwrap();
+    std::fs::write(file.path(), "").unwrap();
+
+    let output = cmd!("wc", "-c")
+        .no_echo()
+        .input_mmap(file.path())
+        .unwrap()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "0");
+}
+
+/// Tests that a missing file is reported immediately
+#[test]
+fn test_input_mmap_missing_file() {
+    let err = cmd!("cat")
+        .input_mmap("/nonexistent/scripty/input.bin")
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+/// Tests that a piped `File` is fed from its current position and echoed as a file
+#[test]
+#[serial_test::serial]
+fn test_pipe_file() {
+    use crate::ReadExt;
+    use std::io::{Seek, SeekFrom};
+
+    let path = crate::fs::temp_file_scoped().unwrap();
+    std::fs::write(path.path(), "header\nb\na\n").unwrap();
+    let mut file = std::fs::File::open(path.path()).unwrap();
+    file.seek(SeekFrom::Start("header\n".len() as u64)).unwrap();
+
+    let pipeline = file.pipe(cmd!("sort"));
+    crate::echo::set_format(crate::echo::EchoFormat::V2);
+    let rendered = pipeline.render(false);
+    crate::echo::set_format(crate::echo::EchoFormat::default());
+    assert_eq!(rendered, "sort < <file>");
+    assert_eq!(pipeline.no_echo().output().unwrap(), "a\nb\n");
+}
@@ -0,0 +1,77 @@
+//! Generated input tests.
+//!
+//! Tests for `input_with()`, which writes stdin from a closure on the input thread.
+
+use crate::cmd;
+
+/// Tests that the closure's output reaches the command, and stdin is closed after it
+#[test]
+fn test_input_with() {
+    let output = cmd!("sort")
+        .no_echo()
+        .input_with(|stdin| stdin.write_all(b"banana\napple\ncherry\n"))
+        .output()
+        .unwrap();
+    assert_eq!(output, "apple\nbanana\ncherry\n");
+}
+
+/// Tests input larger than the pipe buffer, through a pipeline and `write_to()`
+#[test]
+fn test_input_with_large() {
+    let mut output = Vec::new();
+    cmd!("cat")
+        .pipe(cmd!("wc", "-l"))
+        .no_echo()
+        .input_with(|stdin| {
+            for i in 0..100_000 {
+                writeln!(stdin, "line {i}")?;
+            }
+            Ok(())
+        })
+        .write_to(&mut output)
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap().trim(), "100000");
+}
+
+/// Tests that a command exiting before reading its input does not hang or fail the pipeline
+#[test]
+fn test_input_with_early_exit() {
+    let output = cmd!("head", "-n", "1")
+        .no_echo()
+        .input_with(|stdin| {
+            loop {
+                stdin.write_all(b"y\n")?;
+            }
+        })
+        .output()
+        .unwrap();
+    assert_eq!(output, "y\n");
+}
+
+/// Tests that an error returned by the closure fails the command
+#[test]
+fn test_input_with_error() {
+    let error = cmd!("cat")
+        .no_echo()
+        .input_with(|_| Err(std::io::Error::other("source went away")))
+        .output()
+        .unwrap_err();
+    assert_eq!(error.message, "Failed to write input");
+    assert_eq!(error.source.unwrap().to_string(), "source went away");
+}
+
+/// Tests that a broken pipe fails the command once `ignore_broken_pipe(false)` is set
+#[test]
+fn test_input_with_broken_pipe_not_ignored() {
+    let error = cmd!("head", "-n", "1")
+        .no_echo()
+        .input_with(|stdin| {
+            loop {
+                stdin.write_all(b"y\n")?;
+            }
+        })
+        .ignore_broken_pipe(false)
+        .output()
+        .unwrap_err();
+    assert_eq!(error.source.unwrap().kind(), std::io::ErrorKind::BrokenPipe);
+}
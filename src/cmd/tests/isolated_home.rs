@@ -0,0 +1,72 @@
+//! Isolated home directory tests.
+//!
+//! Tests for `Cmd::isolated_home()`, which points HOME and the XDG base directories
+//! at a temporary directory for the lifetime of the command.
+
+use crate::cmd;
+
+/// Tests that HOME and XDG variables point into the temporary directory
+#[test]
+fn test_isolated_home_env() {
+    let cmd = cmd!(
+        "sh",
+        "-c",
+        "echo $HOME; echo $XDG_CONFIG_HOME; echo $XDG_CACHE_HOME"
+    )
+    .isolated_home()
+    .no_echo();
+    let home = cmd.home_dir().unwrap().to_path_buf();
+    assert!(home.is_dir());
+    assert!(home.join(".config").is_dir());
+
+    let output = cmd.output().unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], home.display().to_string());
+    assert_eq!(lines[1], home.join(".config").display().to_string());
+    assert_eq!(lines[2], home.join(".cache").display().to_string());
+
+    // Cleaned up once the command has finished
+    assert!(!home.exists());
+}
+
+/// Tests that the home directory can be pre-seeded before running
+#[test]
+fn test_isolated_home_preseed() {
+    let cmd = cmd!("cat", ".tool.conf").isolated_home().no_echo();
+    let home = cmd.home_dir().unwrap().to_path_buf();
+    std::fs::write(home.join(".tool.conf"), "seeded").unwrap();
+
+    let output = cmd.current_dir(&home).output().unwrap();
+    assert_eq!(output, "seeded");
+}
+
+/// Tests that the home directory outlives the command when spawned
+#[test]
+fn test_isolated_home_spawn_keeps_dir() {
+    let cmd = cmd!("sh", "-c", "cat >/dev/null; ls -a \"$HOME\"")
+        .isolated_home()
+        .no_echo();
+    let home = cmd.home_dir().unwrap().to_path_buf();
+
+    let (handle, stdin, stdout) = cmd.spawn_io_in_out().unwrap();
+    assert!(home.is_dir());
+    drop(stdin);
+
+    let mut output = String::new();
+    std::io::Read::read_to_string(&mut stdout.unwrap(), &mut output).unwrap();
+    handle.wait().unwrap();
+    assert!(output.contains(".config"));
+    assert!(!home.exists());
+}
+
+/// Tests that explicit env settings override the isolated defaults
+#[test]
+fn test_isolated_home_env_override() {
+    let output = cmd!("sh", "-c", "echo $XDG_CONFIG_HOME")
+        .isolated_home()
+        .env("XDG_CONFIG_HOME", "/custom")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "/custom");
+}
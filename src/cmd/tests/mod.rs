@@ -8,12 +8,44 @@ use super::*;
 
 // Test modules
 mod basic;
+mod cgroup;
+mod chain;
+mod clone;
+mod combine_err;
+mod credentials;
+mod drop_policy;
+mod echo_format;
+mod end_of_options;
 mod environment;
 mod error_handling;
+mod file_redirect;
+mod input_heredoc;
+mod input_mmap;
+mod input_with;
 mod io_patterns;
+mod isolated_home;
 
 mod no_echo;
+mod null_redirect;
+mod output_methods;
 mod pipeline;
+mod preflight;
+mod processes;
+mod progress;
+mod pump;
+mod quiet;
 mod quoting;
+mod reaper;
+mod record_input;
+mod resource_limits;
 mod run_output_verification;
+mod run_prefixed;
+mod shell_script;
+mod spawn_input;
+mod stage_stderr;
+mod stdin_config;
+mod sudo;
+mod timeout;
+mod via_ssh;
+mod with_std;
 mod write_methods;
@@ -0,0 +1,72 @@
+//! `/dev/null` redirection tests.
+//!
+//! Tests for `stdout_null()` and `stderr_null()`, and how they and `stdin_null()` are echoed.
+
+use crate::cmd;
+
+/// Tests that `stdout_null()` discards stdout, also when it is captured
+#[test]
+fn test_stdout_null() {
+    let output = cmd!("echo", "hello")
+        .stdout_null()
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "");
+
+    // Stderr is kept
+    let (stdout, stderr) = cmd!("sh", "-c", "echo out; echo err >&2")
+        .stdout_null()
+        .no_echo()
+        .output_with_stderr()
+        .unwrap();
+    assert_eq!((stdout.as_str(), stderr.as_str()), ("", "err\n"));
+}
+
+/// Tests that `stderr_null()` discards stderr and keeps stdout
+#[test]
+fn test_stderr_null() {
+    let (stdout, stderr) = cmd!("sh", "-c", "echo out; echo err >&2")
+        .stderr_null()
+        .no_echo()
+        .output_with_stderr()
+        .unwrap();
+    assert_eq!((stdout.as_str(), stderr.as_str()), ("out\n", ""));
+}
+
+/// Tests that a stage with `stdout_null()` gives the next stage no input
+#[test]
+fn test_stdout_null_in_pipeline() {
+    let output = cmd!("echo", "hello")
+        .stdout_null()
+        .pipe(cmd!("wc", "-c"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "0");
+
+    let output = cmd!("sh", "-c", "echo out; echo err >&2")
+        .stdout_null()
+        .pipe_err(cmd!("cat"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "err\n");
+}
+
+/// Tests that the redirections are shown after each command's arguments
+#[test]
+fn test_null_redirect_echo() {
+    let pipeline = cmd!("find", "/", "-name", "core")
+        .stdin_null()
+        .stderr_null()
+        .pipe(cmd!("xargs", "rm").stdout_null());
+    assert_eq!(
+        pipeline.render(false),
+        "find / -name core < /dev/null 2> /dev/null | xargs rm > /dev/null"
+    );
+
+    // Input takes precedence over stdin_null()
+    let pipeline = cmd!("cat").stdin_null().input("text");
+    assert_eq!(pipeline.render(false), "cat");
+}
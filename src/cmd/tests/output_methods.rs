@@ -0,0 +1,363 @@
+//! Output method tests.
+//!
+//! Tests for the text and binary output variants on `Cmd` and `Pipeline`,
+//! including strict and lossy UTF-8 handling.
+
+use crate::cmd;
+use crate::cmd::StreamTag;
+
+/// Tests that `output_utf8()` returns valid UTF-8 output unchanged
+#[test]
+fn test_output_utf8_valid() {
+    let output = cmd!("echo", "héllo").no_echo().output_utf8().unwrap();
+    assert_eq!(output.trim(), "héllo");
+
+    let output = cmd!("echo", "hello")
+        .pipe(cmd!("tr", "[:lower:]", "[:upper:]"))
+        .no_echo()
+        .output_utf8()
+        .unwrap();
+    assert_eq!(output.trim(), "HELLO");
+}
+
+/// Tests that `output_utf8()` fails on invalid UTF-8 while `output_lossy()` replaces it
+#[test]
+fn test_output_utf8_invalid() {
+    let invalid = vec![b'a', 0xff, 0xfe, b'b'];
+
+    let result = cmd!("cat").input_bytes(&invalid).no_echo().output_utf8();
+    let error = result.unwrap_err();
+    assert!(error.message.contains("not valid UTF-8"));
+    assert_eq!(
+        error.source.as_ref().map(|e| e.kind()),
+        Some(std::io::ErrorKind::InvalidData)
+    );
+
+    let output = cmd!("cat")
+        .input_bytes(&invalid)
+        .no_echo()
+        .output_lossy()
+        .unwrap();
+    assert_eq!(output, "a\u{FFFD}\u{FFFD}b");
+
+    // output() keeps the lossy behavior
+    let output = cmd!("cat")
+        .input_bytes(&invalid)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "a\u{FFFD}\u{FFFD}b");
+}
+
+/// Tests that `output_os()` and `output_os_lines()` keep bytes that are not valid UTF-8
+#[test]
+fn test_output_os() {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+
+    let input = b"caf\xe9.txt\nplain.txt\n";
+    let output = cmd!("cat")
+        .input_bytes(input)
+        .no_echo()
+        .output_os()
+        .unwrap();
+    assert_eq!(output.into_vec(), input);
+
+    let lines = cmd!("cat")
+        .input_bytes(input)
+        .no_echo()
+        .output_os_lines()
+        .unwrap();
+    assert_eq!(
+        lines,
+        [
+            OsString::from_vec(b"caf\xe9.txt".to_vec()),
+            OsString::from("plain.txt")
+        ]
+    );
+
+    // Only the final newline is dropped
+    let lines = cmd!("printf", "a\\n\\nb")
+        .no_echo()
+        .output_os_lines()
+        .unwrap();
+    assert_eq!(lines, ["a", "", "b"]);
+    assert!(cmd!("true").no_echo().output_os_lines().unwrap().is_empty());
+}
+
+/// Tests that `output_shared()` returns the output in a buffer shared by its clones
+#[test]
+fn test_output_shared() {
+    let output = cmd!("printf", "a\\nb\\n")
+        .no_echo()
+        .output_shared()
+        .unwrap();
+    assert_eq!(&output[..], b"a\nb\n");
+
+    let copy = std::sync::Arc::clone(&output);
+    assert!(std::ptr::eq(output.as_ptr(), copy.as_ptr()));
+    assert_eq!(crate::LineStream::new(&copy[..]).count(), 2);
+
+    let output = cmd!("echo", "x")
+        .pipe(cmd!("tr", "x", "y"))
+        .no_echo()
+        .output_shared()
+        .unwrap();
+    assert_eq!(&output[..], b"y\n");
+}
+
+/// Tests that `output_until()` returns at the matching line with the command still running
+#[test]
+fn test_output_until_ready_line() {
+    let mut running = cmd!(
+        "sh",
+        "-c",
+        "echo starting; echo ready; sleep 0.2; echo after"
+    )
+    .no_echo()
+    .output_until(|line| line == "ready")
+    .unwrap();
+    assert_eq!(running.captured, "starting\nready\n");
+    assert!(
+        !running
+            .handle
+            .wait_timeout(std::time::Duration::ZERO)
+            .unwrap()
+    );
+
+    assert_eq!(running.stdout.next_str().unwrap(), Some("after"));
+    assert_eq!(running.stdout.next_str().unwrap(), None);
+    running.handle.wait().unwrap();
+}
+
+/// Tests that `output_until()` fails when the output ends without a matching line
+#[test]
+fn test_output_until_no_match() {
+    let Err(error) = cmd!("echo", "done")
+        .no_echo()
+        .output_until(|line| line == "ready")
+    else {
+        panic!("expected an error");
+    };
+    assert!(error.message.contains("ended before"));
+    assert_eq!(
+        error.source.as_ref().map(|e| e.kind()),
+        Some(std::io::ErrorKind::UnexpectedEof)
+    );
+
+    let Err(error) = cmd!("sh", "-c", "echo starting; exit 3")
+        .no_echo()
+        .output_until(|line| line == "ready")
+    else {
+        panic!("expected an error");
+    };
+    assert!(error.message.contains("exit code 3"));
+}
+
+/// Tests that `output_json()` parses stdout into a deserializable type
+#[cfg(feature = "serde")]
+#[test]
+fn test_output_json() {
+    let value: serde_json::Value = cmd!("echo", r#"{"name": "scripty", "tags": [1, 2]}"#)
+        .no_echo()
+        .output_json()
+        .unwrap();
+    assert_eq!(value["name"], "scripty");
+    assert_eq!(value["tags"][1], 2);
+
+    let tags: Vec<u32> = cmd!("echo", "[1, 2, 3]")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .output_json()
+        .unwrap();
+    assert_eq!(tags, vec![1, 2, 3]);
+}
+
+/// Tests that `output_json()` errors quote the output and name the command
+#[cfg(feature = "serde")]
+#[test]
+fn test_output_json_invalid() {
+    let error = cmd!("echo", "error: not logged in")
+        .no_echo()
+        .output_json::<serde_json::Value>()
+        .unwrap_err();
+    assert!(error.message.contains("not valid JSON"));
+    assert!(error.message.contains("\"error: not logged in\""));
+    assert_eq!(error.command(), Some("echo 'error: not logged in'"));
+    assert_eq!(
+        error.source.as_ref().map(|e| e.kind()),
+        Some(std::io::ErrorKind::InvalidData)
+    );
+
+    let long = "x".repeat(1000);
+    let error = cmd!("echo", &long)
+        .no_echo()
+        .output_json::<serde_json::Value>()
+        .unwrap_err();
+    assert!(error.message.contains(&format!("{}...", "x".repeat(200))));
+    assert!(!error.message.contains(&long));
+}
+
+/// Tests that `output_trimmed()` and `output_lines()` strip whitespace and split lines
+#[test]
+fn test_output_trimmed_and_lines() {
+    let output = cmd!("printf", "  main\n\n")
+        .no_echo()
+        .output_trimmed()
+        .unwrap();
+    assert_eq!(output, "main");
+
+    let lines = cmd!("printf", "a\r\nb\n").no_echo().output_lines().unwrap();
+    assert_eq!(lines, ["a", "b"]);
+    let lines = cmd!("true").no_echo().output_lines().unwrap();
+    assert!(lines.is_empty());
+}
+
+/// Tests that `output_parse()` parses the trimmed output and quotes it on errors
+#[test]
+fn test_output_parse() {
+    let count: u32 = cmd!("echo", "42").no_echo().output_parse().unwrap();
+    assert_eq!(count, 42);
+
+    let error = cmd!("echo", "n/a")
+        .no_echo()
+        .output_parse::<u32>()
+        .unwrap_err();
+    assert!(error.message.contains("not a valid u32"));
+    assert!(error.message.contains("\"n/a\""));
+    assert_eq!(error.command(), Some("echo n/a"));
+    assert_eq!(
+        error.source.as_ref().map(|e| e.kind()),
+        Some(std::io::ErrorKind::InvalidData)
+    );
+}
+
+/// Tests that `output_with_stderr()` returns both streams separately
+#[test]
+fn test_output_with_stderr() {
+    let (stdout, stderr) = cmd!("sh", "-c", "echo out; echo err >&2")
+        .no_echo()
+        .output_with_stderr()
+        .unwrap();
+    assert_eq!(stdout, "out\n");
+    assert_eq!(stderr, "err\n");
+
+    // Only the last command's stderr is captured
+    let (stdout, stderr) = cmd!("sh", "-c", "cat; echo first >&2")
+        .input("in\n")
+        .pipe(cmd!("sh", "-c", "tr a-z A-Z; echo last >&2"))
+        .no_echo()
+        .output_with_stderr()
+        .unwrap();
+    assert_eq!(stdout, "IN\n");
+    assert_eq!(stderr, "last\n");
+}
+
+/// Tests that `output_with_stderr()` reads large output on both streams without blocking
+#[test]
+fn test_output_with_stderr_large() {
+    let (stdout, stderr) = cmd!(
+        "sh",
+        "-c",
+        "head -c 200000 /dev/zero | tr '\\0' e >&2; head -c 200000 /dev/zero | tr '\\0' o"
+    )
+    .no_echo()
+    .output_with_stderr()
+    .unwrap();
+    assert_eq!(stdout.len(), 200_000);
+    assert_eq!(stderr.len(), 200_000);
+}
+
+/// Tests that `max_output()` kills a command producing too much output and fails
+#[test]
+fn test_max_output_exceeded() {
+    let start = std::time::Instant::now();
+    let error = cmd!("yes").no_echo().max_output(1000).output().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Command output exceeded the limit of 1000 bytes: yes"
+    );
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+
+    // Output up to the limit is returned as usual
+    let output = cmd!("printf", "12345")
+        .no_echo()
+        .max_output(5)
+        .output()
+        .unwrap();
+    assert_eq!(output, "12345");
+}
+
+/// Tests that `max_output_truncate()` returns the start of the output
+#[test]
+fn test_max_output_truncate() {
+    let output = cmd!("yes")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .max_output_truncate(6)
+        .output()
+        .unwrap();
+    assert_eq!(output, "y\ny\ny\n");
+}
+
+/// Tests that `output_interleaved()` keeps the order of writes to stdout and stderr
+#[test]
+fn test_output_interleaved() {
+    let chunks = cmd!(
+        "sh",
+        "-c",
+        "echo one; sleep 0.1; echo two >&2; sleep 0.1; echo three; echo four"
+    )
+    .no_echo()
+    .output_interleaved()
+    .unwrap();
+    assert_eq!(
+        chunks,
+        [
+            (StreamTag::Stdout, b"one\n".to_vec()),
+            (StreamTag::Stderr, b"two\n".to_vec()),
+            (StreamTag::Stdout, b"three\nfour\n".to_vec()),
+        ]
+    );
+}
+
+/// Tests that `output_interleaved()` reads large output on both streams without blocking
+#[test]
+fn test_output_interleaved_large() {
+    let chunks = cmd!("sh", "-c", "seq 100000 >&2; seq 100000; exit 3")
+        .no_echo()
+        .output_interleaved();
+    assert!(chunks.is_err());
+
+    let chunks = cmd!("sh", "-c", "seq 100000 >&2; seq 100000")
+        .no_echo()
+        .output_interleaved()
+        .unwrap();
+    let total = |tag| -> usize {
+        chunks
+            .iter()
+            .filter(|(t, _)| *t == tag)
+            .map(|(_, chunk)| chunk.len())
+            .sum()
+    };
+    assert_eq!(total(StreamTag::Stdout), total(StreamTag::Stderr));
+    assert!(total(StreamTag::Stdout) > 500_000);
+}
+
+/// Tests that output() does not deadlock when the last command writes more than a pipe buffer
+/// to stderr
+#[test]
+fn test_output_large_stderr() {
+    let output = cmd!("sh", "-c", "seq 100000 >&2; echo done")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "done\n");
+
+    let output = cmd!("echo", "first")
+        .pipe(cmd!("sh", "-c", "cat; seq 100000 >&2"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "first\n");
+}
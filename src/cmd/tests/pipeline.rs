@@ -3,7 +3,8 @@
 //! Tests for command pipelining with different modes (stdout, stderr, both),
 //! including complex multi-stage pipelines and pipe mode combinations.
 
-use super::Pipeline;
+use super::types::{DEFAULT_IO_BUFFER_SIZE, DEFAULT_PROGRESS_INTERVAL};
+use super::{Pipeline, StderrMode};
 use crate::cmd;
 use crate::cmd::PipeMode;
 
@@ -293,7 +294,17 @@ fn test_empty_pipeline() {
     let pipeline = Pipeline {
         connections: vec![],
         input: None,
+        input_path: None,
         suppress_echo: true,
+        max_output: None,
+        io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
+        ignore_broken_pipe: true,
+        stage_stderr: StderrMode::Inherit,
+        stage_timeout: None,
+        deadline: None,
+        record_input: None,
+        progress: None,
+        progress_interval: DEFAULT_PROGRESS_INTERVAL,
     };
     let result = pipeline.output().unwrap();
     assert!(result.is_empty());
@@ -392,3 +403,192 @@ fn test_pipe_out_err_mixed_output() {
     assert_eq!(lines[0], "ERR:message2");
     assert_eq!(lines[1], "OUT:message1");
 }
+
+/// Tests that tee_pipe() feeds the complete output to every branch
+#[test]
+fn test_tee_pipe_multiple_branches() {
+    let tmp = crate::fs::temp_dir_scoped().unwrap();
+    let all = tmp.path().join("all.txt");
+    let errors = tmp.path().join("errors.txt");
+    let dd_to =
+        |path: &std::path::Path| cmd!("dd", format!("of={}", path.display()), "status=none");
+
+    cmd!("printf", "INFO start\nERROR one\nINFO middle\nERROR two\n")
+        .no_echo()
+        .tee_pipe([
+            Pipeline::from(dd_to(&all)),
+            cmd!("grep", "ERROR").pipe(dd_to(&errors)),
+        ])
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&all).unwrap(),
+        "INFO start\nERROR one\nINFO middle\nERROR two\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&errors).unwrap(),
+        "ERROR one\nERROR two\n"
+    );
+}
+
+/// Tests that a branch exiting early does not starve the other branches
+#[test]
+fn test_tee_pipe_branch_exits_early() {
+    let tmp = crate::fs::temp_dir_scoped().unwrap();
+    let count = tmp.path().join("count.txt");
+
+    cmd!("seq", "1", "200000")
+        .no_echo()
+        .tee_pipe([
+            cmd!("head", "-1").pipe(cmd!("dd", "of=/dev/null", "status=none")),
+            cmd!("wc", "-l").pipe(cmd!("dd", format!("of={}", count.display()), "status=none")),
+        ])
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(&count).unwrap().trim(), "200000");
+}
+
+/// Tests that a failing branch is reported
+#[test]
+fn test_tee_pipe_branch_failure() {
+    let result =
+        cmd!("echo", "data")
+            .no_echo()
+            .tee_pipe([cmd!("sh", "-c", "cat >/dev/null; exit 4")]);
+    assert!(result.unwrap_err().to_string().contains("exit code 4"));
+}
+
+/// Tests that a command runs for every line, skipping empty ones
+#[test]
+fn test_for_each_line_spawn() {
+    let dir = crate::fs::temp_dir_scoped().unwrap();
+    let path = dir.path().to_path_buf();
+    cmd!("printf", "a\nb\n\nc")
+        .no_echo()
+        .for_each_line_spawn(2, |line| cmd!("touch").arg(path.join(line)).no_echo())
+        .unwrap();
+
+    let mut names: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["a", "b", "c"]);
+}
+
+/// Tests that up to `jobs` commands run at the same time
+#[test]
+fn test_for_each_line_spawn_parallel() {
+    let start = std::time::Instant::now();
+    cmd!("printf", "0.3\n0.3\n0.3\n0.3\n")
+        .no_echo()
+        .for_each_line_spawn(4, |secs| cmd!("sleep", secs).no_echo())
+        .unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_millis(1000));
+}
+
+/// Tests that no commands are started after one fails
+#[test]
+fn test_for_each_line_spawn_failure() {
+    let mut started = Vec::new();
+    let err = cmd!("printf", "true\nfalse\ntrue\ntrue\n")
+        .no_echo()
+        .for_each_line_spawn(1, |program| {
+            started.push(program.to_string());
+            cmd!(program).no_echo()
+        })
+        .unwrap_err();
+    assert!(err.to_string().contains("exit code 1"));
+    assert_eq!(started, ["true", "false"]);
+}
+
+/// Tests in-process stages between and after external commands
+#[test]
+fn test_in_process_stages() {
+    let output = cmd!("printf", "apple\nbanana\ncherry\navocado\n")
+        .filter_lines(|line| line.starts_with('a'))
+        .map_lines(|line| line.to_uppercase())
+        .pipe(cmd!("sort", "-r"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "AVOCADO\nAPPLE\n");
+
+    let output = cmd!("seq", "1", "10").tail(3).no_echo().output().unwrap();
+    assert_eq!(output, "8\n9\n10\n");
+}
+
+/// Tests that head() reads the whole input, so the producer does not fail with SIGPIPE
+#[test]
+fn test_in_process_head_drains_input() {
+    let output = cmd!("seq", "1", "100000")
+        .head(2)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "1\n2\n");
+}
+
+/// Tests that in-process stages are shown in the echo
+#[test]
+fn test_in_process_stages_render() {
+    let pipeline = cmd!("dmesg")
+        .filter_lines(|line| line.contains("usb"))
+        .head(5);
+    assert_eq!(pipeline.render(false), "dmesg | filter_lines() | head(5)");
+}
+
+/// Tests that a panicking in-process stage is reported as an error
+#[test]
+fn test_in_process_stage_panic() {
+    let result = cmd!("echo", "hello")
+        .map_lines(|_| panic!("boom"))
+        .no_echo()
+        .output();
+    assert!(result.is_err());
+}
+
+/// Tests that from_cmds() pipes stdout between commands built at runtime
+#[test]
+fn test_from_cmds() {
+    let filters = ["tr a-z A-Z", "rev"];
+    let mut cmds = vec![cmd!("echo", "hello").no_echo()];
+    cmds.extend(filters.iter().map(|filter| cmd!("sh", "-c", filter)));
+    let output = Pipeline::from_cmds(cmds).output().unwrap();
+    assert_eq!(output.trim(), "OLLEH");
+
+    assert_eq!(Pipeline::from_cmds(Vec::new()).render(false), "");
+}
+
+/// Tests that push() connects each command with the given mode
+#[test]
+fn test_push() {
+    let mut pipeline =
+        Pipeline::from_cmds(vec![cmd!("sh", "-c", "echo out; echo err >&2").no_echo()]);
+    pipeline.push(cmd!("tr", "a-z", "A-Z"), PipeMode::Stderr);
+    assert_eq!(pipeline.output().unwrap().trim(), "ERR");
+
+    let mut pipeline = Pipeline::from_cmds(Vec::new());
+    pipeline.push(cmd!("echo", "first").no_echo(), PipeMode::Stdout);
+    pipeline.push(cmd!("wc", "-l"), PipeMode::Both);
+    assert_eq!(pipeline.output().unwrap().trim(), "1");
+}
+
+/// Tests that pipe_with() connects stages like the dedicated pipe methods
+#[test]
+fn test_pipe_with() {
+    let script = "echo out; echo err >&2";
+    for (mode, expected) in [
+        (PipeMode::Stdout, "OUT"),
+        (PipeMode::Stderr, "ERR"),
+        (PipeMode::Both, "ERR\nOUT"),
+    ] {
+        let output = cmd!("sh", "-c", script)
+            .pipe_with(cmd!("tr", "a-z", "A-Z"), mode)
+            .pipe_with(cmd!("sort"), PipeMode::Stdout)
+            .no_echo()
+            .output()
+            .unwrap();
+        assert_eq!(output.trim(), expected, "{mode:?}");
+    }
+}
@@ -0,0 +1,79 @@
+//! Preflight check tests.
+//!
+//! Tests for `check()` on `Cmd` and `Pipeline`, which look for missing programs and working
+//! directories before anything is spawned.
+
+use crate::cmd;
+use crate::cmd::Problem;
+use std::ffi::OsString;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+/// Tests that a pipeline of existing programs passes
+#[test]
+fn test_check_ok() {
+    let pipeline = cmd!("echo", "hello")
+        .pipe(cmd!("/bin/cat"))
+        .filter_lines(|line| !line.is_empty())
+        .pipe(cmd!("wc", "-l").current_dir("/"));
+    assert!(pipeline.check().is_ok());
+}
+
+/// Tests that every problem is reported, with the stage it was found in
+#[test]
+fn test_check_problems() {
+    let tmp = crate::fs::temp_dir_scoped().unwrap();
+    let marker = tmp.path().join("marker");
+    let missing_dir = tmp.path().join("missing");
+    let pipeline = cmd!("touch", &marker)
+        .pipe(cmd!("no-such-program-scripty"))
+        .pipe(cmd!("cat").current_dir(&missing_dir));
+
+    let report = pipeline.check().unwrap_err();
+    assert_eq!(
+        report.problems(),
+        [
+            Problem::ProgramNotFound {
+                stage: 1,
+                program: OsString::from("no-such-program-scripty"),
+            },
+            Problem::MissingDir {
+                stage: 2,
+                dir: missing_dir.clone(),
+            },
+        ]
+    );
+    assert_eq!(
+        report.to_string(),
+        format!(
+            "Pipeline check failed\n  Command not found: no-such-program-scripty\n  \
+             Directory not found: {}",
+            missing_dir.display()
+        )
+    );
+    // Nothing ran
+    assert!(!marker.exists());
+}
+
+/// Tests that programs are looked up in the command's own PATH, and that paths must be
+/// executable files
+#[test]
+fn test_check_program_lookup() {
+    let tmp = crate::fs::temp_dir_scoped().unwrap();
+    let script = tmp.path().join("deploy.sh");
+    std::fs::write(&script, "#!/bin/sh\n").unwrap();
+
+    // Not executable yet
+    assert!(cmd!(&script).check().is_err());
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(cmd!(&script).check().is_ok());
+
+    assert!(cmd!("deploy.sh").check().is_err());
+    assert!(cmd!("deploy.sh").env("PATH", tmp.path()).check().is_ok());
+    assert!(cmd!("ls").env("PATH", tmp.path()).check().is_err());
+    assert!(
+        cmd!(PathBuf::from("/"))
+            .check()
+            .is_err_and(|report| matches!(report.problems()[0], Problem::ProgramNotFound { .. }))
+    );
+}
@@ -0,0 +1,46 @@
+//! Spawned process tests.
+//!
+//! Tests for `pids()` and `processes()` on `PipelineHandle`.
+
+use crate::cmd;
+use std::io::Read;
+
+/// Tests that the reported process ID is the one of the spawned process
+#[test]
+fn test_pids() {
+    let (handle, stdout) = cmd!("sh", "-c", "echo $$")
+        .no_echo()
+        .spawn_io_out()
+        .unwrap();
+    let mut output = String::new();
+    stdout.unwrap().read_to_string(&mut output).unwrap();
+    assert_eq!(handle.pids(), [output.trim().parse::<u32>().unwrap()]);
+    handle.wait().unwrap();
+}
+
+/// Tests that processes map back to their commands, without in-process stages
+#[test]
+fn test_processes() {
+    let spawn = cmd!("seq", "3")
+        .filter_lines(|_| true)
+        .pipe(cmd!("grep", "-c", ""))
+        .no_echo()
+        .spawn_io_all()
+        .unwrap();
+    let processes = spawn.handle.processes().to_vec();
+    assert_eq!(processes.len(), 2);
+    assert_eq!(processes[0].program(), "seq");
+    assert_eq!(processes[0].args(), ["3"]);
+    assert_eq!(processes[1].program(), "grep");
+    assert_eq!(processes[1].args(), ["-c", ""]);
+    assert_eq!(
+        spawn.handle.pids(),
+        [processes[0].pid(), processes[1].pid()]
+    );
+
+    drop(spawn.stdin);
+    let mut output = String::new();
+    spawn.stdout.unwrap().read_to_string(&mut output).unwrap();
+    assert_eq!(output, "3\n");
+    spawn.handle.wait().unwrap();
+}
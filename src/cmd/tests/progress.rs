@@ -0,0 +1,75 @@
+//! Progress event tests.
+//!
+//! Tests for `on_progress()` and `progress_interval()`, which report the bytes moved by each
+//! command while a pipeline runs.
+
+use crate::cmd;
+use crate::cmd::ProgressEvent;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Run `pipeline`, returning its output and the progress events it reported.
+fn run_with_progress(pipeline: crate::cmd::Pipeline) -> (String, Vec<ProgressEvent>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&events);
+    let output = pipeline
+        .on_progress(move |event| recorded.lock().unwrap().push(event.clone()))
+        .progress_interval(Duration::from_millis(20))
+        .no_echo()
+        .output()
+        .unwrap();
+    // The last event is sent from the sampler thread once the processes have exited
+    for _ in 0..100 {
+        if events
+            .lock()
+            .unwrap()
+            .last()
+            .is_some_and(ProgressEvent::is_done)
+        {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let events = events.lock().unwrap().clone();
+    (output, events)
+}
+
+/// Tests that events name the stages and end with a single event marked as done
+#[test]
+fn test_on_progress_events() {
+    let (output, events) =
+        run_with_progress(cmd!("sh", "-c", "sleep 0.2; echo hi").pipe(cmd!("cat")));
+    assert_eq!(output, "hi\n");
+    assert!(events.len() >= 2, "{events:?}");
+
+    let last = events.last().unwrap();
+    assert!(last.is_done());
+    assert_eq!(events.iter().filter(|event| event.is_done()).count(), 1);
+    let programs: Vec<_> = last.stages().iter().map(|stage| stage.program()).collect();
+    assert_eq!(programs, ["sh", "cat"]);
+    assert!(last.stages().iter().all(|stage| !stage.is_running()));
+    assert!(events[0].stages()[1].is_running());
+    assert!(
+        events
+            .windows(2)
+            .all(|pair| pair[0].elapsed() <= pair[1].elapsed())
+    );
+}
+
+/// Tests that the bytes read by a stage are counted while it runs
+#[cfg(target_os = "linux")]
+#[test]
+fn test_on_progress_counts_bytes() {
+    let (output, events) = run_with_progress(
+        cmd!("sh", "-c", "head -c 100000 /dev/zero; sleep 0.3")
+            .pipe(cmd!("cat"))
+            .pipe(cmd!("wc", "-c")),
+    );
+    assert_eq!(output.trim(), "100000");
+    let cat_read = events
+        .iter()
+        .map(|event| event.stages()[1].bytes_read())
+        .max()
+        .unwrap();
+    assert!(cat_read >= 100_000, "{cat_read}");
+}
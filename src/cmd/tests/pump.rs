@@ -0,0 +1,73 @@
+//! Pump thread pool tests.
+//!
+//! Tests for the shared threads feeding input to and reading output from commands.
+
+use crate::cmd;
+use crate::cmd::pump;
+use std::time::Duration;
+
+/// Tests that consecutive jobs reuse idle threads
+#[test]
+fn test_reuses_threads() {
+    let mut ids = Vec::new();
+    for _ in 0..10 {
+        ids.push(pump::spawn(|| std::thread::current().id()).join().unwrap());
+        // Give the thread time to become idle again
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    ids.sort_by_key(|id| format!("{id:?}"));
+    ids.dedup();
+    assert!(ids.len() < 10);
+}
+
+/// Tests that a panicking job is reported as an error and does not take the thread down
+#[test]
+fn test_panic_is_an_error() {
+    let error = pump::spawn(|| panic!("broken input")).join().unwrap_err();
+    assert_eq!(error.message, "I/O thread panicked");
+    assert_eq!(error.source.unwrap().to_string(), "broken input");
+    assert_eq!(pump::spawn(|| 1 + 1).join().unwrap(), 2);
+}
+
+/// Tests that a panic while generating input fails the command
+#[test]
+fn test_input_panic_fails_command() {
+    let error = cmd!("cat")
+        .no_echo()
+        .input_with(|_| panic!("no input"))
+        .output()
+        .unwrap_err();
+    assert_eq!(error.message, "I/O thread panicked");
+}
+
+/// A reader that panics, standing in for a broken input source.
+struct PanickingReader;
+
+impl std::io::Read for PanickingReader {
+    fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+        panic!("reader failed")
+    }
+}
+
+/// Tests that a panic while reading the input of the `run_with_*io()` methods fails the command
+#[test]
+fn test_run_with_io_reader_panic() {
+    let error = cmd!("cat")
+        .no_echo()
+        .run_with_io(PanickingReader, Vec::new())
+        .unwrap_err();
+    assert_eq!(error.message, "I/O thread panicked");
+    assert_eq!(error.command(), Some("cat"));
+
+    let error = cmd!("cat")
+        .no_echo()
+        .run_with_err_io(PanickingReader, Vec::new())
+        .unwrap_err();
+    assert_eq!(error.message, "I/O thread panicked");
+
+    let error = cmd!("cat")
+        .no_echo()
+        .run_with_both_io(PanickingReader, Vec::new())
+        .unwrap_err();
+    assert_eq!(error.message, "I/O thread panicked");
+}
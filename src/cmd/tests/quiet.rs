@@ -0,0 +1,62 @@
+//! Quiet mode tests.
+//!
+//! Tests for `quiet()`, which sends output that is not piped or captured to `/dev/null`. The
+//! shell reports where its own stdout and stderr point through `/proc/$$/fd`, from a pipeline so
+//! that the report's redirection does not apply to the shell itself.
+
+use crate::cmd;
+use crate::fs::temp_dir_scoped;
+
+/// Tests that `quiet()` discards stdout and keeps stderr only for the error with `run()`
+#[test]
+fn test_quiet_run() {
+    let tmp = temp_dir_scoped().unwrap();
+    let report = tmp.path().join("fds");
+    cmd!(
+        "sh",
+        "-c",
+        "readlink /proc/$$/fd/1 /proc/$$/fd/2 | cat > \"$0\"",
+        &report
+    )
+    .quiet()
+    .no_echo()
+    .run()
+    .unwrap();
+    let fds = std::fs::read_to_string(&report).unwrap();
+    let fds: Vec<&str> = fds.lines().collect();
+    assert_eq!(fds[0], "/dev/null");
+    assert!(fds[1].starts_with("pipe:"), "{}", fds[1]);
+}
+
+/// Tests that captured stdout is still returned
+#[test]
+fn test_quiet_output_still_captured() {
+    let output = cmd!("sh", "-c", "echo out; echo err >&2")
+        .quiet()
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "out\n");
+}
+
+/// Tests that a quiet command still reports failure
+#[test]
+fn test_quiet_failure() {
+    let result = cmd!("sh", "-c", "echo failing >&2; exit 2")
+        .quiet()
+        .no_echo()
+        .run();
+    assert!(result.unwrap_err().message.contains("exit code 2"));
+}
+
+/// Tests that piping a quiet command still passes its stdout along
+#[test]
+fn test_quiet_in_pipeline() {
+    let output = cmd!("echo", "hello")
+        .quiet()
+        .pipe(cmd!("tr", "a-z", "A-Z"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "HELLO\n");
+}
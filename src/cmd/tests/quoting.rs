@@ -168,3 +168,83 @@ fn test_quote_argument_mixed_everything() {
         "\"can't handle\\tthis\\ncomplex 'string' with\\0null\""
     );
 }
+
+/// Tests that short arguments are not truncated
+#[test]
+fn test_truncate_argument_fits() {
+    assert_eq!(Cmd::truncate_argument("'{\"a\": 1}'", 20), None);
+    assert_eq!(Cmd::truncate_argument("exactly10!", 10), None);
+}
+
+/// Tests truncation of long arguments keeping the start and the closing characters
+#[test]
+fn test_truncate_argument_long() {
+    let quoted = Cmd::quote_argument(&OsString::from(format!(
+        "{{\"data\": \"{}\"}}",
+        "x".repeat(100)
+    )));
+    let shortened = Cmd::truncate_argument(&quoted, 20).unwrap();
+    assert_eq!(shortened, "'{\"data\": \"xxxx...}'");
+    assert_eq!(shortened.chars().count(), 20);
+}
+
+/// Tests that truncation respects multi-byte characters
+#[test]
+fn test_truncate_argument_multibyte() {
+    let shortened = Cmd::truncate_argument(&"日本語".repeat(10), 10).unwrap();
+    assert_eq!(shortened, "日本語日本...本語");
+}
+
+/// Tests human-readable byte sizes for truncation notes
+#[test]
+fn test_format_size() {
+    assert_eq!(Cmd::format_size(512), "512 B");
+    assert_eq!(Cmd::format_size(4300), "4.2 KB");
+    assert_eq!(Cmd::format_size(3 * 1024 * 1024 / 2), "1.5 MB");
+}
+
+/// Tests that long arguments are shortened in the echo unless `echo_full_args()` is set
+#[test]
+fn test_echo_full_args() {
+    let program = format!(".[] | {}", "select(.a) | ".repeat(20));
+    let shortened = crate::cmd!("jq", &program).into_pipeline();
+    let rendered = shortened.render_with(false, Some(20));
+    assert_eq!(rendered, "jq '.[] | select(.... ' (266 B truncated)");
+
+    let full = crate::cmd!("jq", &program).echo_full_args().into_pipeline();
+    assert_eq!(full.render_with(false, Some(20)), format!("jq '{program}'"));
+}
+
+/// Tests that `echo::set_max_arg_width()` replaces the terminal width unless verbose echo is on
+#[test]
+#[serial_test::serial]
+fn test_set_max_arg_width() {
+    let saved = std::env::var_os("SCRIPTY_VERBOSE");
+    unsafe {
+        std::env::remove_var("SCRIPTY_VERBOSE");
+    }
+    crate::echo::set_max_arg_width(Some(30));
+
+    let width = Pipeline::echo_arg_width();
+    assert_eq!(width, Some(30));
+    let rendered = crate::cmd!("echo", "x".repeat(100))
+        .into_pipeline()
+        .render_with(false, width);
+    assert_eq!(
+        rendered,
+        "echo xxxxxxxxxxxxxxxxxxxxxxxxx...xx (100 B truncated)"
+    );
+    let shortened = rendered.split(' ').nth(1).unwrap();
+    assert_eq!(shortened.chars().count(), 30);
+
+    crate::set_verbosity(crate::Verbosity::Verbose);
+    assert_eq!(Pipeline::echo_arg_width(), None);
+
+    crate::set_verbosity(crate::Verbosity::Normal);
+    crate::echo::set_max_arg_width(None);
+    if let Some(val) = saved {
+        unsafe {
+            std::env::set_var("SCRIPTY_VERBOSE", val);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Background reaper tests.
+//!
+//! Tests for `wait_timeout()`, `notify_on_exit()`, and `kill()` on `PipelineHandle`.
+
+use crate::cmd;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Tests that a quick command completes within the timeout
+#[test]
+fn test_wait_timeout_completes() {
+    let (mut handle, _) = cmd!("true").no_echo().spawn_io_in().unwrap();
+    assert!(handle.wait_timeout(Duration::from_secs(10)).unwrap());
+    // The status is already collected, so wait() returns immediately
+    handle.wait().unwrap();
+}
+
+/// Tests that a failing command is reported after it exits
+#[test]
+fn test_wait_timeout_failure() {
+    let (mut handle, _) = cmd!("sh", "-c", "exit 3").no_echo().spawn_io_in().unwrap();
+    let err = handle.wait_timeout(Duration::from_secs(10)).unwrap_err();
+    assert!(err.to_string().contains("exit code 3"));
+}
+
+/// Tests that the timeout elapses for a long-running command, which can then be killed
+#[test]
+fn test_wait_timeout_expires_and_kill() {
+    let (mut handle, _) = cmd!("sleep", "30").no_echo().spawn_io_in().unwrap();
+    let start = Instant::now();
+    assert!(!handle.wait_timeout(Duration::from_millis(200)).unwrap());
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    handle.kill().unwrap();
+    assert!(handle.wait().is_err());
+}
+
+/// Tests that many pipelines can report their completion through one channel
+#[test]
+fn test_notify_on_exit_many() {
+    let (sender, receiver) = mpsc::channel();
+    let mut handles = Vec::new();
+    for i in 0..20 {
+        let (handle, _) = cmd!("sh", "-c", format!("sleep 0.0{}", i % 5))
+            .pipe(cmd!("cat"))
+            .no_echo()
+            .spawn_io_in()
+            .unwrap();
+        handle.notify_on_exit(sender.clone(), i);
+        handles.push(Some(handle));
+    }
+
+    for _ in 0..handles.len() {
+        let i = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        handles[i].take().unwrap().wait().unwrap();
+    }
+    assert!(handles.iter().all(Option::is_none));
+}
@@ -0,0 +1,74 @@
+//! Input recording tests.
+//!
+//! Tests for `record_input_to()`, which copies the input fed to a pipeline to a writer.
+
+use crate::cmd;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// A writer that can be inspected after the pipeline has run.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Tests that the input is fed to the command and recorded
+#[test]
+fn test_record_input_to() {
+    let record = SharedBuffer::default();
+    let output = cmd!("tr", "a-z", "A-Z")
+        .input("hello\nworld\n")
+        .record_input_to(record.clone())
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "HELLO\nWORLD\n");
+    assert_eq!(*record.0.lock().unwrap(), b"hello\nworld\n");
+}
+
+/// Tests that input generated with `input_with()` is recorded when running with `run()`
+#[test]
+fn test_record_input_with_writer() {
+    let record = SharedBuffer::default();
+    cmd!("cat")
+        .quiet()
+        .input_with(|stdin| {
+            for i in 0..3 {
+                writeln!(stdin, "{i}")?;
+            }
+            Ok(())
+        })
+        .record_input_to(record.clone())
+        .no_echo()
+        .run()
+        .unwrap();
+    assert_eq!(*record.0.lock().unwrap(), b"0\n1\n2\n");
+}
+
+/// Tests that only the input the command accepted is recorded
+#[test]
+fn test_record_input_stops_when_stdin_closed() {
+    let record = SharedBuffer::default();
+    cmd!("true")
+        .input("a\n".repeat(1_000_000))
+        .record_input_to(record.clone())
+        .no_echo()
+        .run()
+        .unwrap();
+    assert!(record.0.lock().unwrap().len() < 2_000_000);
+}
+
+/// Tests that a pipeline recording its input cannot be cloned, since the writer cannot be
+#[test]
+fn test_record_input_try_clone() {
+    let pipeline = cmd!("cat").input("x").record_input_to(std::io::sink());
+    assert!(pipeline.try_clone().is_none());
+}
@@ -0,0 +1,117 @@
+//! Resource limit tests.
+//!
+//! Tests for the `ulimit`-style builder methods that apply `setrlimit` in the child.
+
+use crate::cmd;
+
+/// Tests that `limit_nofile()` is visible to the child shell
+#[test]
+fn test_limit_nofile() {
+    let output = cmd!("sh", "-c", "ulimit -n")
+        .limit_nofile(64)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "64");
+}
+
+/// Tests that `disable_core_dumps()` sets the core size limit to zero
+#[test]
+fn test_disable_core_dumps() {
+    let output = cmd!("sh", "-c", "ulimit -c")
+        .disable_core_dumps()
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "0");
+}
+
+/// Tests that `limit_fsize()` prevents writing files beyond the limit
+#[test]
+fn test_limit_fsize() {
+    let path = std::env::temp_dir().join(format!("scripty_fsize_{}", std::process::id()));
+    let script = format!("head -c 65536 /dev/zero > '{}'", path.display());
+
+    let result = cmd!("sh", "-c", &script).limit_fsize(4096).no_echo().run();
+    assert!(result.is_err());
+    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    assert!(size <= 4096);
+
+    // Within the limit the command succeeds
+    let script = format!("head -c 1024 /dev/zero > '{}'", path.display());
+    cmd!("sh", "-c", &script)
+        .limit_fsize(4096)
+        .no_echo()
+        .run()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Tests that limits apply per stage in a pipeline
+#[test]
+fn test_limits_in_pipeline() {
+    let output = cmd!("echo", "hello")
+        .pipe(cmd!("sh", "-c", "cat >/dev/null; ulimit -n").limit_nofile(32))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "32");
+}
+
+/// Tests that `limit_memory()` and `limit_cpu_time()` are visible to the child shell
+#[test]
+fn test_limit_memory_and_cpu_time() {
+    let output = cmd!("sh", "-c", "ulimit -v; ulimit -t")
+        .limit_memory(512 * 1024 * 1024)
+        .limit_cpu_time(std::time::Duration::from_millis(1500))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "524288\n2\n");
+}
+
+/// Tests that `nice()` and `ionice_idle()` lower the child's priorities
+#[test]
+fn test_nice_and_ionice() {
+    let output = cmd!("nice").nice(7).no_echo().output().unwrap();
+    assert_eq!(output.trim(), "7");
+
+    let output = cmd!("ionice").ionice_idle().no_echo().output().unwrap();
+    assert_eq!(output.trim(), "idle");
+}
+
+/// Tests that the applied limits are echoed with `SCRIPTY_VERBOSE`, and hidden otherwise
+#[test]
+#[serial_test::serial]
+fn test_limits_verbose_echo() {
+    let saved = std::env::var_os("SCRIPTY_VERBOSE");
+    let limited = || {
+        cmd!("make", "test")
+            .limit_nofile(64)
+            .disable_core_dumps()
+            .limit_cpu_time(std::time::Duration::from_secs(30))
+            .nice(10)
+            .into_pipeline()
+    };
+
+    unsafe {
+        std::env::remove_var("SCRIPTY_VERBOSE");
+    }
+    assert_eq!(limited().render(false), "make test");
+
+    unsafe {
+        std::env::set_var("SCRIPTY_VERBOSE", "1");
+    }
+    let rendered = limited().render(false);
+    unsafe {
+        match saved {
+            Some(val) => std::env::set_var("SCRIPTY_VERBOSE", val),
+            None => std::env::remove_var("SCRIPTY_VERBOSE"),
+        }
+    }
+    assert_eq!(
+        rendered,
+        "ulimit: nofile=64 ulimit: core=0 ulimit: cpu=30 ulimit: nice=10 make test"
+    );
+}
@@ -0,0 +1,50 @@
+//! Prefixed output tests.
+//!
+//! Tests for `run_prefixed()`, which shows each line of a command's output with a prefix.
+
+use crate::cmd;
+use crate::cmd::Pipeline;
+use crate::color::{self, ColorChoice};
+use serial_test::serial;
+use std::io::Cursor;
+
+/// Tests that every line gets the prefix, including a last line without a newline
+#[test]
+fn test_forward_prefixed() {
+    let mut output = Vec::new();
+    Pipeline::forward_prefixed(Cursor::new("one\n\ntwo\r\nthree"), "web | ", &mut output);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "web | one\nweb | \nweb | two\nweb | three\n"
+    );
+}
+
+/// Tests that the prefix is colored only when colors are enabled, and keeps its color
+#[test]
+#[serial]
+fn test_paint_prefix() {
+    color::set_choice(ColorChoice::Never);
+    assert_eq!(Pipeline::paint_prefix("db | ", true), "db | ");
+
+    color::set_choice(ColorChoice::Always);
+    let painted = Pipeline::paint_prefix("db | ", false);
+    assert!(painted.starts_with("\u{1b}[") && painted.contains("db | "));
+    assert_eq!(Pipeline::paint_prefix("db | ", false), painted);
+    color::set_choice(ColorChoice::Auto);
+}
+
+/// Tests that the command's result is returned, with input fed to it
+#[test]
+fn test_run_prefixed_result() {
+    cmd!("cat")
+        .input("hello\n")
+        .no_echo()
+        .run_prefixed("test | ")
+        .unwrap();
+    assert!(
+        cmd!("sh", "-c", "echo failing >&2; exit 3")
+            .no_echo()
+            .run_prefixed("test | ")
+            .is_err()
+    );
+}
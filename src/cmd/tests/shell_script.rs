@@ -0,0 +1,34 @@
+//! Shell script tests.
+//!
+//! Tests for `shell()` scripts, their positional parameters and how they are echoed.
+
+use crate::cmd::shell;
+
+/// Tests that the script is run by `sh` with shell features such as loops
+#[test]
+fn test_shell_runs_script() {
+    let output = shell("for i in 1 2 3; do printf $i; done")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "123");
+}
+
+/// Tests that parameters reach the script as `$1`, `$2`, without being parsed by the shell
+#[test]
+fn test_shell_arg_positional_parameters() {
+    let output = shell("printf '%s|' \"$0\" \"$1\" \"$2\"")
+        .shell_arg("it's")
+        .shell_arg("$(echo no)")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "sh|it's|$(echo no)|");
+}
+
+/// Tests that the script is echoed as written and the parameters are quoted
+#[test]
+fn test_shell_echo_shows_script_verbatim() {
+    let pipeline = shell("gzip \"$1\"").shell_arg("a b.log").into_pipeline();
+    assert_eq!(pipeline.render(false), "sh -c gzip \"$1\" sh 'a b.log'");
+}
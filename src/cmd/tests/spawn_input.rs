@@ -0,0 +1,80 @@
+//! Spawned input tests.
+//!
+//! Tests that input set on a command or pipeline is fed to the processes started by the
+//! `spawn_io_*` methods.
+
+use crate::cmd;
+use std::io::Read;
+
+fn read_all(mut reader: impl Read) -> String {
+    let mut output = String::new();
+    reader.read_to_string(&mut output).unwrap();
+    output
+}
+
+/// Tests that input reaches a single command whose stdout is captured
+#[test]
+fn test_spawn_io_out_with_input() {
+    let (handle, stdout) = cmd!("sort")
+        .no_echo()
+        .input("b\na\n")
+        .spawn_io_out()
+        .unwrap();
+    assert_eq!(read_all(stdout.unwrap()), "a\nb\n");
+    handle.wait().unwrap();
+}
+
+/// Tests that input reaches the first command of a pipeline
+#[test]
+fn test_spawn_io_out_err_pipeline_with_input() {
+    let (handle, stdout, stderr) = cmd!("cat")
+        .pipe(cmd!("tr", "a-z", "A-Z"))
+        .no_echo()
+        .input("hello\n")
+        .spawn_io_out_err()
+        .unwrap();
+    assert_eq!(read_all(stdout.unwrap()), "HELLO\n");
+    assert_eq!(read_all(stderr.unwrap()), "");
+    handle.wait().unwrap();
+}
+
+/// Tests that stdin is not handed out when input is fed, for single commands and pipelines
+#[test]
+fn test_spawn_io_all_with_input() {
+    let spawn = cmd!("cat").no_echo().input("one\n").spawn_io_all().unwrap();
+    assert!(spawn.stdin.is_none());
+    assert_eq!(read_all(spawn.stdout.unwrap()), "one\n");
+    spawn.handle.wait().unwrap();
+
+    let (handle, stdin, stdout) = cmd!("cat")
+        .pipe(cmd!("wc", "-l"))
+        .no_echo()
+        .input("one\ntwo\n")
+        .spawn_io_in_out()
+        .unwrap();
+    assert!(stdin.is_none());
+    assert_eq!(read_all(stdout.unwrap()).trim(), "2");
+    handle.wait().unwrap();
+}
+
+/// Tests that stdin is still handed out when no input is set
+#[test]
+fn test_spawn_io_in_without_input() {
+    let (handle, stdin) = cmd!("true").no_echo().spawn_io_in().unwrap();
+    assert!(stdin.is_some());
+    drop(stdin);
+    handle.wait().unwrap();
+}
+
+/// Tests that an error writing the input is returned by wait()
+#[test]
+fn test_spawn_input_error_on_wait() {
+    let (handle, stdout) = cmd!("cat")
+        .no_echo()
+        .input_with(|_| Err(std::io::Error::other("source went away")))
+        .spawn_io_out()
+        .unwrap();
+    assert_eq!(read_all(stdout.unwrap()), "");
+    let error = handle.wait().unwrap_err();
+    assert_eq!(error.message, "Failed to write input");
+}
@@ -0,0 +1,63 @@
+//! Stage stderr tests.
+//!
+//! Tests for `stage_stderr()`, which sets where the stderr of the commands before the last one
+//! goes.
+
+use crate::cmd;
+use crate::cmd::StderrMode;
+
+/// Tests that merged stderr is sent down the pipe with stdout
+#[test]
+fn test_stage_stderr_merge_into_pipe() {
+    let output = cmd!("sh", "-c", "echo out; echo err >&2")
+        .pipe(cmd!("sort"))
+        .stage_stderr(StderrMode::MergeIntoPipe)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "err\nout\n");
+}
+
+/// Tests that captured stderr is shown in the error of the failing stage
+#[test]
+fn test_stage_stderr_capture() {
+    let error = cmd!("sh", "-c", "echo 'tar: bad archive' >&2; exit 2")
+        .pipe(cmd!("sh", "-c", "cat; echo last >&2"))
+        .stage_stderr(StderrMode::Capture)
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert_eq!(error.stderr(), Some("tar: bad archive"));
+
+    let output = cmd!("sh", "-c", "seq 100000 >&2; echo ok")
+        .pipe(cmd!("cat"))
+        .stage_stderr(StderrMode::Capture)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "ok\n");
+}
+
+/// Tests that discarded stderr is neither piped on nor kept for the error
+#[test]
+fn test_stage_stderr_null() {
+    let error = cmd!("sh", "-c", "echo hidden >&2; echo out; exit 1")
+        .pipe(cmd!("cat"))
+        .stage_stderr(StderrMode::Null)
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert_eq!(error.stderr(), None);
+}
+
+/// Tests that stages piping stderr to the next command are not affected
+#[test]
+fn test_stage_stderr_pipe_err_unaffected() {
+    let output = cmd!("sh", "-c", "echo err >&2")
+        .pipe_err(cmd!("tr", "a-z", "A-Z"))
+        .stage_stderr(StderrMode::Null)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "ERR\n");
+}
@@ -0,0 +1,62 @@
+//! Stdin configuration tests.
+//!
+//! Tests for `stdin()`, `stdin_inherit()`, and `stdin_null()`. The child reports what its stdin
+//! is connected to through `/proc/self/fd/0`.
+
+use crate::cmd;
+use crate::cmd::StdioConfig;
+
+fn stdin_target(cmd: crate::Cmd) -> String {
+    cmd.no_echo().output().unwrap().trim().to_string()
+}
+
+/// Tests that stdin is a pipe by default
+#[test]
+fn test_stdin_piped_by_default() {
+    let target = stdin_target(cmd!("readlink", "/proc/self/fd/0"));
+    assert!(target.starts_with("pipe:"), "{target}");
+}
+
+/// Tests that `stdin_inherit()` passes the parent's stdin to the command
+#[test]
+fn test_stdin_inherit() {
+    let parent = std::fs::read_link("/proc/self/fd/0").unwrap();
+    let target = stdin_target(cmd!("readlink", "/proc/self/fd/0").stdin_inherit());
+    assert_eq!(target, parent.to_string_lossy());
+}
+
+/// Tests that `stdin_null()` connects stdin to `/dev/null`, also when run through `run()`
+#[test]
+fn test_stdin_null() {
+    let target = stdin_target(cmd!("readlink", "/proc/self/fd/0").stdin_null());
+    assert_eq!(target, "/dev/null");
+    cmd!("cat")
+        .stdin(StdioConfig::Null)
+        .no_echo()
+        .run()
+        .unwrap();
+}
+
+/// Tests that the configuration applies to the first command of a pipeline only
+#[test]
+fn test_stdin_config_in_pipeline() {
+    let output = cmd!("readlink", "/proc/self/fd/0")
+        .stdin_null()
+        .pipe(cmd!("cat").stdin_null())
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "/dev/null");
+}
+
+/// Tests that input takes precedence over the stdin configuration
+#[test]
+fn test_input_overrides_stdin_config() {
+    let output = cmd!("cat")
+        .stdin_null()
+        .input("hello")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "hello");
+}
@@ -0,0 +1,47 @@
+//! Elevation tests.
+//!
+//! Tests for `sudo()`. The arguments are checked through `with_sudo()`, which does not depend on
+//! whether the tests run as root or from a terminal.
+
+use crate::cmd;
+
+/// Tests that `with_sudo()` puts the command after `sudo --`, with `-n` when non-interactive
+#[test]
+fn test_sudo_arguments() {
+    let sudo = cmd!("rm", "-rf", "/var/cache/app").with_sudo(false);
+    assert_eq!(sudo.program, "sudo");
+    assert_eq!(sudo.args, ["--", "rm", "-rf", "/var/cache/app"]);
+
+    let sudo = cmd!("systemctl", "restart", "app").with_sudo(true);
+    assert_eq!(sudo.args, ["-n", "--", "systemctl", "restart", "app"]);
+}
+
+/// Tests that variables set with `env()` are kept with `--preserve-env`
+#[test]
+fn test_sudo_preserves_env() {
+    let sudo = cmd!("apt-get", "install", "-y", "jq")
+        .env("DEBIAN_FRONTEND", "noninteractive")
+        .env("LANG", "C")
+        .env("LANG", "C.UTF-8")
+        .with_sudo(true);
+    assert_eq!(
+        sudo.args,
+        [
+            "-n",
+            "--preserve-env=DEBIAN_FRONTEND,LANG",
+            "--",
+            "apt-get",
+            "install",
+            "-y",
+            "jq"
+        ]
+    );
+    assert_eq!(sudo.envs.len(), 3);
+}
+
+/// Tests that the echo shows the elevation
+#[test]
+fn test_sudo_echo() {
+    let pipeline = cmd!("ls", "/root").with_sudo(false).into_pipeline();
+    assert_eq!(pipeline.render(false), "sudo -- ls /root");
+}
@@ -0,0 +1,85 @@
+//! Time limit tests.
+//!
+//! Tests for `stage_timeout()` and `deadline()`, which kill a pipeline that runs too long.
+
+use crate::cmd;
+use crate::cmd::shell;
+use std::time::{Duration, Instant};
+
+/// Tests that a pipeline finishing in time is not affected
+#[test]
+fn test_stage_timeout_not_reached() {
+    let output = cmd!("echo", "hello")
+        .pipe(cmd!("tr", "a-z", "A-Z"))
+        .stage_timeout(Duration::from_secs(10))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "HELLO\n");
+}
+
+/// Tests that the error names the limit and the stage that was still running
+#[test]
+fn test_stage_timeout_reports_running_stage() {
+    let start = Instant::now();
+    let error = cmd!("echo", "hello")
+        .pipe(cmd!("sleep", "10"))
+        .stage_timeout(Duration::from_millis(200))
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(
+        error.to_string().contains(
+            "Pipeline exceeded its stage timeout of 200ms; still running: sleep (stage 2)"
+        ),
+        "{error}"
+    );
+    assert_eq!(error.command(), Some("echo hello | sleep 10"));
+}
+
+/// Tests that a deadline in the past kills a single command right away
+#[test]
+fn test_deadline() {
+    let error = cmd!("sleep", "10")
+        .deadline(Instant::now())
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Pipeline exceeded its deadline; still running: sleep"),
+        "{error}"
+    );
+}
+
+/// Tests that processes started by the command are killed with it, so its output ends
+#[test]
+fn test_stage_timeout_kills_process_group() {
+    let start = Instant::now();
+    let result = shell("sleep 10; echo done")
+        .stage_timeout(Duration::from_millis(200))
+        .no_echo()
+        .output();
+    assert!(result.is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+/// Tests that the stage timeout is measured from spawn for every stage, not from when the
+/// stage before it finished
+#[test]
+fn test_stage_timeout_measured_from_spawn() {
+    let error = shell("sleep 0.3")
+        .pipe(shell("cat; sleep 0.5"))
+        .stage_timeout(Duration::from_millis(600))
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("stage timeout of 600ms; still running: sh (stage 2):"),
+        "{error}"
+    );
+}
@@ -0,0 +1,101 @@
+//! Remote execution tests.
+//!
+//! Tests for `via_ssh()`. No ssh server is available in tests, so the generated remote command
+//! line is run with a local `sh -c`, the way the remote shell would run it.
+
+use crate::cmd;
+use crate::fs::temp_dir_scoped;
+
+/// Returns the remote command line of a command wrapped with `via_ssh()`.
+fn remote_command(cmd: crate::Cmd) -> String {
+    assert_eq!(cmd.program, "ssh");
+    assert_eq!(cmd.args[..2], ["--", "deploy@example.com"]);
+    cmd.args[2].to_str().unwrap().to_string()
+}
+
+/// Tests that `via_ssh()` wraps the command in `ssh -- <host> <command line>`
+#[test]
+fn test_via_ssh_command_line() {
+    let ssh = cmd!("./restart.sh", "blue").via_ssh("deploy@example.com");
+    assert_eq!(ssh.args.len(), 3);
+    assert_eq!(remote_command(ssh), "./restart.sh blue");
+
+    let ssh = cmd!("ls", "-l")
+        .env("LANG", "C")
+        .current_dir("/srv/my app")
+        .via_ssh("deploy@example.com");
+    assert!(ssh.envs.is_empty());
+    assert!(ssh.current_dir.is_none());
+    assert_eq!(remote_command(ssh), "cd '/srv/my app' && env LANG=C ls -l");
+}
+
+/// Tests that arguments reach the remote command unchanged, whatever they contain
+#[test]
+fn test_via_ssh_quoting_round_trip() {
+    let tmp = temp_dir_scoped().unwrap();
+    let args = ["it's", "$HOME", "`id`", "a b", "*", "new\nline", ""];
+    let remote = remote_command(
+        cmd!("printf", "%s|")
+            .args(args)
+            .current_dir(tmp.path())
+            .via_ssh("deploy@example.com"),
+    );
+    let output = cmd!("sh", "-c", format!("{remote} && pwd"))
+        .no_echo()
+        .output()
+        .unwrap();
+    let expected: String = args.iter().map(|arg| format!("{arg}|")).collect();
+    assert_eq!(output, format!("{expected}{}\n", tmp.path().display()));
+
+    let remote = remote_command(
+        cmd!("printenv", "GREETING")
+            .env("GREETING", "hello world")
+            .via_ssh("deploy@example.com"),
+    );
+    let output = cmd!("sh", "-c", remote).no_echo().output().unwrap();
+    assert_eq!(output, "hello world\n");
+}
+
+/// Tests that `env_secret()` values are refused rather than put on the ssh command line, and
+/// kept out of the echo
+#[test]
+fn test_via_ssh_env_secret() {
+    let ssh = cmd!("deploy")
+        .env_secret("TOKEN", "via-ssh-secret")
+        .via_ssh("deploy@example.com");
+    assert_eq!(
+        ssh.clone().into_pipeline().render(false),
+        "ssh -- deploy@example.com \"env 'TOKEN=****' deploy\""
+    );
+    let error = ssh.no_echo().run().unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .starts_with("Invalid command: env_secret() values cannot be passed through via_ssh()")
+    );
+}
+
+/// Tests that words that are not valid UTF-8 fail the command instead of being changed
+#[test]
+fn test_via_ssh_non_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let name = std::ffi::OsStr::from_bytes(b"caf\xe9");
+    let error = cmd!("ls", name)
+        .via_ssh("deploy@example.com")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid command: caf\u{FFFD} is not valid UTF-8 and cannot be passed through via_ssh()"
+    );
+
+    let error = cmd!("ls")
+        .current_dir(name)
+        .via_ssh("deploy@example.com")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert!(error.to_string().contains("is not valid UTF-8"));
+}
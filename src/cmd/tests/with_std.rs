@@ -0,0 +1,37 @@
+//! Customization tests.
+//!
+//! Tests for `with_std()`, which gives access to the underlying `std::process::Command`.
+
+use crate::cmd;
+use std::os::unix::process::CommandExt;
+
+/// Tests that `with_std()` can set options scripty does not model, such as `arg0`
+#[test]
+fn test_with_std_arg0() {
+    let output = cmd!("sh", "-c", "echo $0")
+        .with_std(|c| {
+            c.arg0("custom-name");
+        })
+        .no_echo()
+        .output()
+        .unwrap();
+    // Without extra arguments, `sh -c` sets $0 to its own argv[0]
+    assert_eq!(output.trim(), "custom-name");
+}
+
+/// Tests that closures run after the builder settings, in the order they were added
+#[test]
+fn test_with_std_order() {
+    let output = cmd!("sh", "-c", "echo $GREETING")
+        .env("GREETING", "builder")
+        .with_std(|c| {
+            c.env("GREETING", "first");
+        })
+        .with_std(|c| {
+            c.env("GREETING", "second");
+        })
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "second");
+}
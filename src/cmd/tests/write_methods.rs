@@ -180,3 +180,257 @@ fn test_write_with_cursor() {
     // Note: This test verifies that write_to executes with a cursor without error
     // For actual output verification, we use the buffer-based tests above
 }
+
+#[test]
+#[serial]
+fn test_write_both_to_large_simultaneous_output() {
+    // Both streams write far more than a pipe buffer at the same time
+    let mut buffer = Vec::new();
+    cmd!(
+        "sh",
+        "-c",
+        "head -c 1000000 /dev/zero & head -c 1000000 /dev/zero >&2; wait"
+    )
+    .no_echo()
+    .write_both_to(&mut buffer)
+    .unwrap();
+
+    assert_eq!(buffer.len(), 2_000_000);
+}
+
+#[test]
+#[serial]
+fn test_write_both_to_stderr_before_stdout_ends() {
+    // Stdout stays open while stderr fills its pipe; copying one stream to the end before the
+    // other would never finish
+    let mut buffer = Vec::new();
+    cmd!(
+        "sh",
+        "-c",
+        "echo start; head -c 500000 /dev/zero >&2; echo end"
+    )
+    .no_echo()
+    .write_both_to(&mut buffer)
+    .unwrap();
+
+    let text: Vec<u8> = buffer.iter().copied().filter(|&b| b != 0).collect();
+    assert_eq!(text, b"start\nend\n");
+    assert_eq!(buffer.len(), 500_000 + text.len());
+}
+
+#[test]
+#[serial]
+fn test_write_both_to_alternating_output() {
+    // Many small writes alternating between the streams, well past a pipe buffer in total
+    let mut buffer = Vec::new();
+    cmd!(
+        "sh",
+        "-c",
+        "i=0; while [ $i -lt 20000 ]; do echo out$i; echo err$i >&2; i=$((i+1)); done"
+    )
+    .no_echo()
+    .write_both_to(&mut buffer)
+    .unwrap();
+
+    let text = String::from_utf8(buffer).unwrap();
+    let out: Vec<&str> = text
+        .lines()
+        .filter(|line| line.starts_with("out"))
+        .collect();
+    let err: Vec<&str> = text
+        .lines()
+        .filter(|line| line.starts_with("err"))
+        .collect();
+    // Each stream keeps its own order, and no line is lost
+    assert_eq!(out.len(), 20000);
+    assert_eq!(err.len(), 20000);
+    assert_eq!(out[19999], "out19999");
+    assert_eq!(err[19999], "err19999");
+}
+
+#[test]
+#[serial]
+fn test_write_both_to_writer_error() {
+    struct FailingWriter;
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let error = cmd!("sh", "-c", "echo out; echo err >&2")
+        .no_echo()
+        .write_both_to(FailingWriter)
+        .unwrap_err();
+    assert!(error.message.contains("Failed to copy pipeline output"));
+}
+
+#[test]
+#[serial]
+fn test_run_with_both_io_large_output() {
+    let mut buffer = Vec::new();
+    cmd!("sh", "-c", "cat; head -c 300000 /dev/zero >&2")
+        .no_echo()
+        .run_with_both_io(Cursor::new(b"input\n".to_vec()), &mut buffer)
+        .unwrap();
+
+    assert_eq!(buffer.len(), "input\n".len() + 300_000);
+    assert!(buffer.starts_with(b"input\n"));
+}
+
+/// Tests that large input and output are copied intact with small and large buffers.
+#[test]
+#[serial]
+fn test_write_to_io_buffer_size() {
+    let input: Vec<u8> = (0..500_000u32).map(|i| (i % 251) as u8).collect();
+    for size in [1, 7, 1024 * 1024] {
+        let data = input.clone();
+        let mut buffer = Vec::new();
+        cmd!("cat")
+            .no_echo()
+            .input_with(move |stdin| {
+                for chunk in data.chunks(3) {
+                    stdin.write_all(chunk)?;
+                }
+                Ok(())
+            })
+            .io_buffer_size(size)
+            .write_to(&mut buffer)
+            .unwrap();
+        assert_eq!(buffer, input, "buffer size {size}");
+    }
+}
+
+/// Tests that `write_split_to()` keeps the streams apart, even when both exceed a pipe buffer.
+#[test]
+#[serial]
+fn test_write_split_to() {
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    cmd!(
+        "sh",
+        "-c",
+        "echo out; head -c 500000 /dev/zero >&2; echo err >&2; head -c 300000 /dev/zero"
+    )
+    .no_echo()
+    .write_split_to(&mut out, &mut err)
+    .unwrap();
+
+    assert_eq!(out.len(), "out\n".len() + 300_000);
+    assert!(out.starts_with(b"out\n"));
+    assert_eq!(err.len(), 500_000 + "err\n".len());
+    assert!(err.ends_with(b"err\n"));
+}
+
+/// Tests that `run_with_io_full()` feeds the reader and splits the output streams.
+#[test]
+#[serial]
+fn test_run_with_io_full() {
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    cmd!("sh", "-c", "tr a-z A-Z; echo done >&2")
+        .no_echo()
+        .run_with_io_full(Cursor::new(b"input\n".to_vec()), &mut out, &mut err)
+        .unwrap();
+
+    assert_eq!(out, b"INPUT\n");
+    assert_eq!(err, b"done\n");
+}
+
+/// A reader that fails, standing in for a broken input source.
+struct FailingReader;
+
+impl std::io::Read for FailingReader {
+    fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("source went away"))
+    }
+}
+
+/// Tests that an error reading the input of `run_with_io()` and its variants fails the command.
+#[test]
+fn test_run_with_io_reader_error() {
+    let error = cmd!("cat")
+        .no_echo()
+        .run_with_io(FailingReader, Vec::new())
+        .unwrap_err();
+    assert_eq!(error.message, "Failed to write input");
+    assert_eq!(error.source.unwrap().to_string(), "source went away");
+
+    assert!(
+        cmd!("cat")
+            .no_echo()
+            .run_with_err_io(FailingReader, Vec::new())
+            .is_err()
+    );
+    assert!(
+        cmd!("cat")
+            .no_echo()
+            .run_with_both_io(FailingReader, Vec::new())
+            .is_err()
+    );
+}
+
+/// Tests that a command closing stdin early is only an error with `ignore_broken_pipe(false)`.
+#[test]
+fn test_run_with_io_broken_pipe() {
+    let mut out = Vec::new();
+    cmd!("head", "-c", "1")
+        .no_echo()
+        .run_with_io(std::io::repeat(b'y'), &mut out)
+        .unwrap();
+    assert_eq!(out, b"y");
+
+    let error = cmd!("head", "-c", "1")
+        .no_echo()
+        .ignore_broken_pipe(false)
+        .run_with_io(std::io::repeat(b'y'), Vec::new())
+        .unwrap_err();
+    assert_eq!(error.source.unwrap().kind(), std::io::ErrorKind::BrokenPipe);
+}
+
+/// Tests that `write_to()` and `run_with_io()` do not deadlock when the last command writes
+/// more than a pipe buffer to stderr, and that `run_with_err_io()` does not on stdout.
+#[test]
+fn test_write_methods_large_unread_stream() {
+    let mut out = Vec::new();
+    cmd!("sh", "-c", "seq 100000 >&2; echo done")
+        .no_echo()
+        .write_to(&mut out)
+        .unwrap();
+    assert_eq!(out, b"done\n");
+
+    let mut out = Vec::new();
+    cmd!("sh", "-c", "cat; seq 100000 >&2")
+        .no_echo()
+        .run_with_io(Cursor::new("first\n"), &mut out)
+        .unwrap();
+    assert_eq!(out, b"first\n");
+
+    let mut err = Vec::new();
+    cmd!("sh", "-c", "seq 100000; echo done >&2")
+        .no_echo()
+        .run_with_err_io(Cursor::new(""), &mut err)
+        .unwrap();
+    assert_eq!(err, b"done\n");
+}
+
+/// Tests that the end of stderr is attached to the error when `write_to()` fails.
+#[test]
+fn test_write_to_error_stderr() {
+    let error = cmd!("sh", "-c", "seq 100000 >&2; echo failed >&2; exit 3")
+        .no_echo()
+        .write_to(Vec::new())
+        .unwrap_err();
+    assert!(error.stderr.unwrap().ends_with("failed"));
+
+    let error = cmd!("sh", "-c", "echo failed >&2; exit 3")
+        .no_echo()
+        .run_with_io(Cursor::new(""), Vec::new())
+        .unwrap_err();
+    assert_eq!(error.stderr.as_deref(), Some("failed"));
+}
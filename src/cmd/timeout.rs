@@ -0,0 +1,96 @@
+//! Enforcing `stage_timeout()` and `deadline()` with a watchdog job per pipeline.
+
+use crate::cmd::{Pipeline, PipelineHandle, pump, reaper};
+use std::sync::Arc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Instant;
+
+/// When a spawned pipeline must be killed, and what to report.
+pub(crate) struct TimeLimit {
+    at: Instant,
+    /// The limit that applies, such as "stage timeout of 5s".
+    reason: String,
+    /// How each command started for the pipeline is named in the error, in pipeline order.
+    stages: Vec<String>,
+}
+
+impl Pipeline {
+    /// Take the time limit to enforce on the pipeline about to be spawned, starting its
+    /// commands in process groups of their own so that the processes they start are killed too.
+    ///
+    /// The limit is taken so that spawn methods delegating to each other only start one
+    /// watchdog.
+    pub(crate) fn take_time_limit(&mut self) -> Option<TimeLimit> {
+        let stage_limit = self.stage_timeout.take().map(|timeout| {
+            (
+                Instant::now() + timeout,
+                format!("stage timeout of {timeout:?}"),
+            )
+        });
+        let deadline = self.deadline.take().map(|at| (at, "deadline".to_string()));
+        let (at, reason) = match (stage_limit, deadline) {
+            (Some(stage), Some(deadline)) if deadline.0 < stage.0 => deadline,
+            (Some(stage), _) => stage,
+            (None, deadline) => deadline?,
+        };
+
+        let single = self.connections.len() == 1;
+        let mut stages = Vec::new();
+        for (i, (cmd, _)) in self.connections.iter_mut().enumerate() {
+            if cmd.filter.is_some() {
+                continue;
+            }
+            cmd.process_group = true;
+            let program = cmd.program.to_string_lossy();
+            stages.push(if single {
+                program.into_owned()
+            } else {
+                format!("{program} (stage {})", i + 1)
+            });
+        }
+        Some(TimeLimit { at, reason, stages })
+    }
+}
+
+impl PipelineHandle {
+    /// Kill the process groups of the commands still running once `limit` is reached,
+    /// recording the reason for [`wait`](Self::wait) to report.
+    pub(crate) fn with_time_limit(self, limit: Option<TimeLimit>) -> Self {
+        let Some(limit) = limit else {
+            return self;
+        };
+        let pids: Vec<u32> = self.children.iter().map(|child| child.id()).collect();
+        let (sender, receiver) = mpsc::channel();
+        reaper::watch(pids.clone(), move || {
+            let _ = sender.send(());
+        });
+
+        let timed_out = Arc::clone(&self.timed_out);
+        let reap_lock = Arc::clone(&self.reap_lock);
+        drop(pump::spawn(move || {
+            let timeout = limit.at.saturating_duration_since(Instant::now());
+            if receiver.recv_timeout(timeout) != Err(RecvTimeoutError::Timeout) {
+                return;
+            }
+            // The handle reaps under this lock, so the commands found running below cannot be
+            // reaped before they are killed
+            let _guard = reap_lock.lock().unwrap_or_else(|e| e.into_inner());
+            let running: Vec<(u32, String)> = pids
+                .into_iter()
+                .zip(limit.stages)
+                .filter(|(pid, _)| !reaper::has_exited(*pid as libc::pid_t))
+                .collect();
+            let names: Vec<&str> = running.iter().map(|(_, name)| name.as_str()).collect();
+            *timed_out.lock().unwrap_or_else(|e| e.into_inner()) = Some(format!(
+                "Pipeline exceeded its {}; still running: {}",
+                limit.reason,
+                names.join(", ")
+            ));
+            // Each command leads its own process group, whose ID stays its own until it is reaped
+            for (pid, _) in running {
+                unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) };
+            }
+        }));
+        self
+    }
+}
@@ -1,9 +1,18 @@
 //! Type definitions for command execution and piping.
 
-use std::ffi::OsString;
-use std::io::Read;
-use std::path::PathBuf;
+use crate::cmd::error::StderrTail;
+use crate::cmd::filters::LineFilter;
+use crate::cmd::limits::ResourceLimit;
+use crate::cmd::progress::ProgressEvent;
+use crate::cmd::pump::Pump;
+use crate::fs::TempDir;
+use crate::lines::LineStream;
+use std::ffi::{OsStr, OsString};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::Child;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 
 /// Input source for commands - either bytes in memory or a streaming reader.
 pub(crate) enum CmdInput {
@@ -11,6 +20,58 @@ pub(crate) enum CmdInput {
     Bytes(Vec<u8>),
     /// Streaming reader (boxed for object safety)
     Reader(Box<dyn Read + Send>),
+    /// Open file, which `std::io::copy` feeds to the stdin pipe in the kernel on Linux
+    /// (`sendfile`/`splice`) instead of copying it through userspace
+    File(std::fs::File),
+    /// Memory-mapped file
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+    /// Closure generating the input on the input thread
+    Writer(InputWriter),
+    /// Text shown as a `<<EOF` block in the echo
+    Heredoc(String),
+}
+
+/// Closure set with `Pipeline::input_with()`.
+pub(crate) type InputWriter = Box<dyn FnOnce(&mut dyn Write) -> std::io::Result<()> + Send>;
+
+/// Writer set with `Pipeline::record_input_to()`.
+pub(crate) struct InputRecord(pub(crate) Box<dyn Write + Send>);
+
+impl std::fmt::Debug for InputRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("record_input_to()")
+    }
+}
+
+/// Closure set with `Pipeline::on_progress()`.
+pub(crate) struct ProgressCallback(pub(crate) Box<dyn FnMut(&ProgressEvent) + Send>);
+
+impl std::fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("on_progress()")
+    }
+}
+
+impl CmdInput {
+    /// Input read from a file, memory-mapped if the `mmap` feature is enabled.
+    pub(crate) fn from_file(path: &Path) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+
+        #[cfg(feature = "mmap")]
+        {
+            // Empty files cannot be mapped on every platform
+            if file.metadata()?.len() == 0 {
+                return Ok(CmdInput::Bytes(Vec::new()));
+            }
+            // SAFETY: the mapping is only read; the caller must not truncate the file meanwhile
+            let map = unsafe { memmap2::Mmap::map(&file)? };
+            Ok(CmdInput::Mapped(map))
+        }
+
+        #[cfg(not(feature = "mmap"))]
+        Ok(CmdInput::File(file))
+    }
 }
 
 impl std::fmt::Debug for CmdInput {
@@ -21,29 +82,163 @@ impl std::fmt::Debug for CmdInput {
                 .field(&format!("{} bytes", bytes.len()))
                 .finish(),
             CmdInput::Reader(_) => f.debug_tuple("Reader").field(&"<reader>").finish(),
+            CmdInput::File(file) => f.debug_tuple("File").field(file).finish(),
+            CmdInput::Writer(_) => f.debug_tuple("Writer").field(&"<closure>").finish(),
+            CmdInput::Heredoc(text) => f
+                .debug_tuple("Heredoc")
+                .field(&format!("{} bytes", text.len()))
+                .finish(),
+            #[cfg(feature = "mmap")]
+            CmdInput::Mapped(map) => f
+                .debug_tuple("Mapped")
+                .field(&format!("{} bytes", map.len()))
+                .finish(),
         }
     }
 }
 
-/// A simple command builder.
+/// Temporary HOME/XDG directory created by `Cmd::isolated_home()`.
 #[derive(Debug)]
+pub(crate) enum IsolatedHome {
+    /// The directory was created and is removed once the last reference is dropped.
+    Ready(Arc<TempDir>),
+    /// Creating the directory failed; the error is reported when the command is spawned.
+    Failed(std::io::Error),
+}
+
+impl Clone for IsolatedHome {
+    /// Clones share the directory. A creation error is copied with its kind and message.
+    fn clone(&self) -> Self {
+        match self {
+            IsolatedHome::Ready(home) => IsolatedHome::Ready(Arc::clone(home)),
+            IsolatedHome::Failed(e) => {
+                IsolatedHome::Failed(std::io::Error::new(e.kind(), e.to_string()))
+            }
+        }
+    }
+}
+
+impl IsolatedHome {
+    /// Environment variables pointing the child at the isolated home directory.
+    pub(crate) fn env_vars(home: &Path) -> [(&'static str, PathBuf); 5] {
+        [
+            ("HOME", home.to_path_buf()),
+            ("XDG_CONFIG_HOME", home.join(".config")),
+            ("XDG_CACHE_HOME", home.join(".cache")),
+            ("XDG_DATA_HOME", home.join(".local/share")),
+            ("XDG_STATE_HOME", home.join(".local/state")),
+        ]
+    }
+}
+
+/// A simple command builder.
+///
+/// A `Cmd` can be cloned, so a base command with common arguments, environment and working
+/// directory can serve as a template for many invocations. Clones made after
+/// [`isolated_home`](Cmd::isolated_home) share the same home directory.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let cargo = cmd!("cargo")
+///     .env("RUSTFLAGS", "-D warnings")
+///     .current_dir("crates/core");
+/// cargo.clone().arg("build").run()?;
+/// cargo.clone().args(["test", "--workspace"]).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
 pub struct Cmd {
     pub(crate) program: OsString,
     pub(crate) args: Vec<OsString>,
     pub(crate) envs: Vec<(OsString, OsString)>,
+    /// Inherited environment variables removed before spawning.
+    pub(crate) env_removes: Vec<OsString>,
+    /// Environment variables set with `env_secret()`, whose values are masked in the echo.
+    pub(crate) secret_envs: Vec<OsString>,
     pub(crate) current_dir: Option<PathBuf>,
     pub(crate) suppress_echo: bool,
+    pub(crate) limits: Vec<ResourceLimit>,
+    pub(crate) isolated_home: Option<IsolatedHome>,
+    pub(crate) auto_end_of_options: bool,
+    pub(crate) cgroup: Option<PathBuf>,
+    /// Set for in-process stages such as `filter_lines()`, which run on a thread.
+    pub(crate) filter: Option<LineFilter>,
+    pub(crate) stdin: StdioConfig,
+    /// Send stdout to `/dev/null`, even when it would be piped or captured.
+    pub(crate) stdout_null: bool,
+    /// Send stderr to `/dev/null`, even when it would be piped or captured.
+    pub(crate) stderr_null: bool,
+    /// Redirect stderr into stdout in the child, after the other redirections.
+    pub(crate) combine_err: bool,
+    /// Send stdout and stderr to `/dev/null` unless they are piped or captured.
+    pub(crate) quiet: bool,
+    pub(crate) uid: Option<u32>,
+    pub(crate) gid: Option<u32>,
+    /// File mode creation mask set in the child before it starts.
+    pub(crate) umask: Option<u32>,
+    /// Closures registered with `Cmd::with_std()`, applied in order.
+    pub(crate) std_hooks: Vec<StdHook>,
+    /// Echo the command but run `true` in its place.
+    pub(crate) dry_run: bool,
+    /// Created by `shell()`: the argument after `-c` is a script, echoed as written.
+    pub(crate) shell_script: bool,
+    /// Echo long arguments in full instead of shortening them.
+    pub(crate) echo_full_args: bool,
+    /// Start the command in a process group of its own, so that it can be killed together with
+    /// the processes it starts.
+    pub(crate) process_group: bool,
+    /// Why the command cannot run, found while it was built and reported when it is spawned.
+    pub(crate) invalid: Option<String>,
+}
+
+/// A closure that customizes the `std::process::Command` built for a `Cmd`.
+#[derive(Clone)]
+pub(crate) struct StdHook(pub(crate) Arc<dyn Fn(&mut std::process::Command) + Send + Sync>);
+
+impl std::fmt::Debug for StdHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("with_std()")
+    }
+}
+
+/// Where a command's stdin comes from when it is not connected to a previous pipeline stage.
+///
+/// Set it with [`Cmd::stdin`], or the shortcuts [`Cmd::stdin_inherit`] and [`Cmd::stdin_null`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StdioConfig {
+    /// A pipe used for [`input`](Cmd::input) or returned by the `spawn_io_*` methods (the
+    /// default). Without input, the pipe is closed once the command is spawned.
+    #[default]
+    Piped,
+    /// The parent's stdin, so interactive programs can read from the terminal.
+    Inherit,
+    /// `/dev/null`, so the command sees end of input immediately.
+    Null,
+}
+
+/// The output stream a chunk of [`Pipeline::output_interleaved`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamTag {
+    /// Standard output.
+    Stdout,
+    /// Standard error.
+    Stderr,
 }
 
 /// Specifies which output streams should be piped between commands.
 ///
-/// This enum is used internally to track pipe modes, but you typically don't need
-/// to use it directly. Instead, use the convenient builder methods on `Cmd`:
+/// Pipelines written out in code usually use the builder methods on `Cmd` and `Pipeline`
+/// instead:
 ///
 /// - `pipe(cmd)` - pipes stdout (default)
 /// - `pipe_err(cmd)` - pipes stderr only
 /// - `pipe_out_err(cmd)` - pipes both stdout and stderr combined
 ///
+/// Use it with [`Pipeline::push`] to choose the mode at runtime.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -71,8 +266,8 @@ pub struct Cmd {
 ///     .output()?;
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub(crate) enum PipeMode {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeMode {
     /// Pipe only stdout between commands (default behavior).
     ///
     /// This is the standard Unix pipe behavior where each command's stdout
@@ -92,17 +287,129 @@ pub(crate) enum PipeMode {
     Both,
 }
 
+/// Where the stderr of the commands before the last one in a pipeline goes, set with
+/// [`Pipeline::stage_stderr`].
+///
+/// Stages whose stderr is already piped to the next command, with
+/// [`pipe_err`](Pipeline::pipe_err) or [`pipe_out_err`](Pipeline::pipe_out_err), are not
+/// affected, and neither are the commands' own [`stderr_null`](Cmd::stderr_null) and
+/// [`combine_err`](Cmd::combine_err).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StderrMode {
+    /// The parent's stderr, as in a shell (the default).
+    #[default]
+    Inherit,
+    /// `/dev/null`, keeping the terminal clean.
+    Null,
+    /// Merged into the pipe to the next command, like `|&` in a shell.
+    MergeIntoPipe,
+    /// Kept out of the terminal, and shown in the error if the stage fails (see
+    /// [`Error::stderr`](crate::Error::stderr)).
+    Capture,
+}
+
+/// What happens to the processes of a [`PipelineHandle`] that is dropped without calling
+/// [`wait`](PipelineHandle::wait).
+///
+/// Set it per handle with [`PipelineHandle::set_drop_policy`], or for all handles created
+/// afterwards with [`PipelineHandle::set_default_drop_policy`]. Every policy reaps the
+/// processes eventually, so none are left behind as zombies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// Discard any remaining output and wait for the processes to exit.
+    ///
+    /// This blocks the dropping thread for as long as the processes run, forever for one that
+    /// never exits on its own, such as `tail -f` or a process waiting for more input.
+    Wait,
+    /// Kill the processes with `SIGKILL` and wait for them (the default).
+    #[default]
+    Kill,
+    /// Let the processes keep running; they are reaped in the background once they exit.
+    /// Their pipes held by the handle are closed.
+    Detach,
+    /// Panic in debug builds, after killing and reaping the processes, to catch forgotten
+    /// `wait()` calls. Release builds behave like [`Kill`](Self::Kill).
+    PanicInDebug,
+}
+
 /// Handle to a spawned pipeline for waiting and collecting results.
+///
+/// Dropping the handle without calling [`wait`](Self::wait) applies its [`DropPolicy`], which
+/// kills the processes by default. Call `wait` to let them finish, or use
+/// [`DropPolicy::Detach`] to leave them running.
 pub struct PipelineHandle {
     pub(crate) children: Vec<Child>,
+    /// Rendered command line, attached to errors for context.
+    pub(crate) command: String,
+    /// Isolated home directories kept alive until the handle is dropped.
+    #[allow(dead_code)]
+    pub(crate) homes: Vec<Arc<TempDir>>,
+    /// Threads running the in-process stages.
+    pub(crate) threads: Vec<JoinHandle<std::io::Result<()>>>,
+    /// Thread feeding the input set on the pipeline to its stdin.
+    pub(crate) input: Option<Pump<std::io::Result<()>>>,
+    /// The processes started, in pipeline order, kept after they are reaped.
+    pub(crate) processes: Vec<ProcessInfo>,
+    /// Threads collecting the stderr of stages, by process ID, for [`StderrMode::Capture`].
+    pub(crate) stage_tails: Vec<(u32, Pump<std::io::Result<StderrTail>>)>,
+    pub(crate) ignore_broken_pipe: bool,
+    pub(crate) drop_policy: DropPolicy,
+    /// Set by the watchdog of [`Pipeline::stage_timeout`] and [`Pipeline::deadline`] when it
+    /// kills the pipeline, with the reason to report.
+    pub(crate) timed_out: Arc<Mutex<Option<String>>>,
+    /// Held while reaping children, and by the time limit watchdog while it checks and kills
+    /// them, so it never kills the process group of a reaped child whose process ID was reused.
+    pub(crate) reap_lock: Arc<Mutex<()>>,
+}
+
+/// A process started for one command of a pipeline, returned by
+/// [`PipelineHandle::processes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub(crate) pid: u32,
+    pub(crate) program: OsString,
+    pub(crate) args: Vec<OsString>,
+}
+
+impl ProcessInfo {
+    /// The process ID.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// The program, as given to [`Cmd::new`].
+    pub fn program(&self) -> &OsStr {
+        &self.program
+    }
+
+    /// The arguments, including the `--` added by
+    /// [`auto_end_of_options`](Cmd::auto_end_of_options).
+    pub fn args(&self) -> &[OsString] {
+        &self.args
+    }
 }
 
 /// Complete I/O access to a spawned pipeline.
+///
+/// The pipes are declared before the handle, so dropping the whole value closes them before the
+/// handle's [`DropPolicy`] applies to the processes.
 pub struct PipelineSpawn {
-    pub handle: PipelineHandle,
     pub stdin: Option<std::process::ChildStdin>,
     pub stdout: Option<std::process::ChildStdout>,
     pub stderr: Option<std::process::ChildStderr>,
+    pub handle: PipelineHandle,
+}
+
+/// A pipeline left running by [`Pipeline::output_until`] once its marker line appeared.
+///
+/// Like [`PipelineSpawn`], the rest of stdout is declared before the handle, so dropping the
+/// whole value closes it before the handle's [`DropPolicy`] applies to the processes.
+pub struct RunningPipeline {
+    /// Stdout up to and including the line that matched.
+    pub captured: String,
+    /// The rest of stdout. Keep reading or drop it, so the processes do not block on a full pipe.
+    pub stdout: LineStream<std::process::ChildStdout>,
+    pub handle: PipelineHandle,
 }
 
 /// A pipeline of commands.
@@ -110,5 +417,39 @@ pub struct PipelineSpawn {
 pub struct Pipeline {
     pub(crate) connections: Vec<(Cmd, PipeMode)>,
     pub(crate) input: Option<CmdInput>,
+    /// File the input is read from, shown in the echo as a `<` redirection.
+    pub(crate) input_path: Option<PathBuf>,
     pub(crate) suppress_echo: bool,
+    /// Most stdout bytes captured by `output()` and similar methods.
+    pub(crate) max_output: Option<OutputLimit>,
+    /// Size of the buffers used to copy input and output.
+    pub(crate) io_buffer_size: usize,
+    /// Whether the command closing stdin before reading all the input is not an error.
+    pub(crate) ignore_broken_pipe: bool,
+    /// Where the stderr of the stages before the last one goes.
+    pub(crate) stage_stderr: StderrMode,
+    /// Longest time a command may run, from the start of the pipeline.
+    pub(crate) stage_timeout: Option<std::time::Duration>,
+    /// Time by which the whole pipeline must have finished.
+    pub(crate) deadline: Option<std::time::Instant>,
+    /// Writer receiving a copy of the input fed to the pipeline.
+    pub(crate) record_input: Option<InputRecord>,
+    /// Closure receiving progress events while the pipeline runs.
+    pub(crate) progress: Option<ProgressCallback>,
+    /// How often `progress` is called.
+    pub(crate) progress_interval: std::time::Duration,
+}
+
+/// Default size of the buffers used to copy a pipeline's input and output.
+pub(crate) const DEFAULT_IO_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Default interval between progress events.
+pub(crate) const DEFAULT_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Limit set with `Pipeline::max_output()` or `Pipeline::max_output_truncate()`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OutputLimit {
+    pub(crate) bytes: usize,
+    /// Keep the first `bytes` bytes instead of failing.
+    pub(crate) truncate: bool,
 }
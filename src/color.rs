@@ -19,3 +19,90 @@ pub const BRIGHT_BLUE: Option<Color> = Some(Color::Ansi(AnsiColor::BrightBlue));
 pub const BRIGHT_MAGENTA: Option<Color> = Some(Color::Ansi(AnsiColor::BrightMagenta));
 pub const BRIGHT_CYAN: Option<Color> = Some(Color::Ansi(AnsiColor::BrightCyan));
 pub const BRIGHT_WHITE: Option<Color> = Some(Color::Ansi(AnsiColor::BrightWhite));
+
+/// When to color the command and file system echo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color only if `NO_COLOR` is not set and the echo goes to a terminal (the default).
+    #[default]
+    Auto,
+    /// Always color, even when the output is redirected.
+    Always,
+    /// Never color.
+    Never,
+}
+
+static CHOICE: std::sync::RwLock<ColorChoice> = std::sync::RwLock::new(ColorChoice::Auto);
+
+/// Set when the echo is colored, overriding the `NO_COLOR` and terminal detection.
+///
+/// # Examples
+///
+/// ```
+/// use scripty::color::{self, ColorChoice};
+///
+/// // Keep colors when stderr is piped into a pager that understands them
+/// color::set_choice(ColorChoice::Always);
+/// ```
+pub fn set_choice(choice: ColorChoice) {
+    *CHOICE.write().unwrap_or_else(|e| e.into_inner()) = choice;
+}
+
+/// Get the current color choice.
+pub fn choice() -> ColorChoice {
+    *CHOICE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Decide whether to color output written to a stream, given whether it is a terminal.
+pub(crate) fn should_color(is_terminal: bool) -> bool {
+    match choice() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            is_terminal && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_color_choice_override() {
+        set_choice(ColorChoice::Always);
+        assert!(should_color(false));
+
+        set_choice(ColorChoice::Never);
+        assert!(!should_color(true));
+
+        set_choice(ColorChoice::Auto);
+        assert!(!should_color(false));
+    }
+
+    #[test]
+    #[serial]
+    fn test_no_color_env() {
+        let original = std::env::var_os("NO_COLOR");
+
+        unsafe {
+            std::env::set_var("NO_COLOR", "1");
+        }
+        assert!(!should_color(true));
+
+        // An empty NO_COLOR does not disable colors
+        unsafe {
+            std::env::set_var("NO_COLOR", "");
+        }
+        assert!(should_color(true));
+
+        unsafe {
+            match original {
+                Some(val) => std::env::set_var("NO_COLOR", val),
+                None => std::env::remove_var("NO_COLOR"),
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Scoped changes of the process working directory.
+
+use crate::fs::echo_operation;
+use std::path::{Path, PathBuf};
+
+/// Restores the previous working directory when dropped.
+///
+/// Created by [`cd`].
+#[derive(Debug)]
+#[must_use = "the previous directory is restored as soon as the guard is dropped"]
+pub struct DirGuard {
+    previous: PathBuf,
+}
+
+impl DirGuard {
+    /// The working directory that will be restored on drop.
+    pub fn previous(&self) -> &Path {
+        &self.previous
+    }
+}
+
+impl Drop for DirGuard {
+    fn drop(&mut self) {
+        echo_operation("cd", &self.previous.display().to_string());
+        if let Err(e) = std::env::set_current_dir(&self.previous) {
+            eprintln!(
+                "Warning: Failed to restore working directory {}: {}",
+                self.previous.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Change the working directory of the current process until the returned guard is dropped.
+///
+/// The change is echoed like `cd path`, and so is the restore. Note that the working directory
+/// is shared by all threads of the process; prefer [`Cmd::current_dir`](crate::Cmd::current_dir)
+/// in multi-threaded code.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// {
+///     let _dir = cd("frontend")?;
+///     cmd!("npm", "ci").run()?;
+///     cmd!("npm", "run", "build").run()?;
+/// } // back to the original directory
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn cd(path: impl AsRef<Path>) -> std::io::Result<DirGuard> {
+    let path = path.as_ref();
+    let previous = std::env::current_dir()?;
+    echo_operation("cd", &path.display().to_string());
+    std::env::set_current_dir(path)?;
+    Ok(DirGuard { previous })
+}
+
+/// Run a closure with the working directory changed to `path`, restoring it afterwards.
+///
+/// The previous directory is restored even if the closure panics.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// with_dir("backend", || -> Result<()> {
+///     cmd!("cargo", "build", "--release").run()?;
+///     Ok(())
+/// })??;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn with_dir<T>(path: impl AsRef<Path>, f: impl FnOnce() -> T) -> std::io::Result<T> {
+    let _guard = cd(path)?;
+    Ok(f())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_cd_restores_on_drop() {
+        let original = std::env::current_dir().unwrap();
+        let tmp = crate::fs::temp_dir_scoped().unwrap();
+        let target = std::fs::canonicalize(tmp.path()).unwrap();
+
+        {
+            let guard = cd(&target).unwrap();
+            assert_eq!(guard.previous(), original);
+            assert_eq!(std::env::current_dir().unwrap(), target);
+        }
+        assert_eq!(std::env::current_dir().unwrap(), original);
+    }
+
+    #[test]
+    #[serial]
+    fn test_with_dir() {
+        let original = std::env::current_dir().unwrap();
+        let tmp = crate::fs::temp_dir_scoped().unwrap();
+        std::fs::write(tmp.path().join("marker.txt"), "here").unwrap();
+
+        let content = with_dir(tmp.path(), || std::fs::read_to_string("marker.txt")).unwrap();
+        assert_eq!(content.unwrap(), "here");
+        assert_eq!(std::env::current_dir().unwrap(), original);
+    }
+
+    #[test]
+    #[serial]
+    fn test_cd_missing_dir() {
+        let original = std::env::current_dir().unwrap();
+        assert!(cd("/nonexistent/scripty/cd/target").is_err());
+        assert_eq!(std::env::current_dir().unwrap(), original);
+    }
+}
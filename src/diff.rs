@@ -0,0 +1,149 @@
+//! Line-oriented diffing used by the comparison helpers.
+
+/// A single line in a [`LineDiff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    /// A line present in both inputs.
+    Same(String),
+    /// A line only present in the left (expected) input.
+    Removed(String),
+    /// A line only present in the right (actual) input.
+    Added(String),
+}
+
+/// The line-by-line difference between two texts.
+///
+/// The `Display` implementation renders the changed lines prefixed with `-`/`+`, with up to
+/// three lines of unchanged context around each change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineDiff {
+    lines: Vec<DiffLine>,
+}
+
+/// Number of unchanged lines shown around each change when displaying a diff.
+const CONTEXT: usize = 3;
+
+impl LineDiff {
+    /// Compute the difference between two texts, line by line.
+    pub fn new(left: &str, right: &str) -> Self {
+        let left: Vec<&str> = left.lines().collect();
+        let right: Vec<&str> = right.lines().collect();
+
+        // Longest common subsequence table, computed from the end of both inputs
+        let mut lcs = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+        for i in (0..left.len()).rev() {
+            for j in (0..right.len()).rev() {
+                lcs[i][j] = if left[i] == right[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let mut lines = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < left.len() && j < right.len() {
+            if left[i] == right[j] {
+                lines.push(DiffLine::Same(left[i].to_string()));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                lines.push(DiffLine::Removed(left[i].to_string()));
+                i += 1;
+            } else {
+                lines.push(DiffLine::Added(right[j].to_string()));
+                j += 1;
+            }
+        }
+        lines.extend(left[i..].iter().map(|l| DiffLine::Removed(l.to_string())));
+        lines.extend(right[j..].iter().map(|l| DiffLine::Added(l.to_string())));
+
+        Self { lines }
+    }
+
+    /// Returns `true` if both texts have identical lines.
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(|l| matches!(l, DiffLine::Same(_)))
+    }
+
+    /// All lines of the diff, including unchanged ones.
+    pub fn lines(&self) -> &[DiffLine] {
+        &self.lines
+    }
+}
+
+impl std::fmt::Display for LineDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let changed: Vec<usize> = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| !matches!(l, DiffLine::Same(_)))
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut last_shown: Option<usize> = None;
+        for (i, line) in self.lines.iter().enumerate() {
+            let near_change = changed
+                .iter()
+                .any(|&c| i + CONTEXT >= c && i <= c + CONTEXT);
+            if !near_change {
+                continue;
+            }
+            if last_shown.is_some_and(|last| i > last + 1) || (last_shown.is_none() && i > 0) {
+                writeln!(f, "...")?;
+            }
+            match line {
+                DiffLine::Same(l) => writeln!(f, "  {}", l)?,
+                DiffLine::Removed(l) => writeln!(f, "- {}", l)?,
+                DiffLine::Added(l) => writeln!(f, "+ {}", l)?,
+            }
+            last_shown = Some(i);
+        }
+        if last_shown.is_some_and(|last| last + 1 < self.lines.len()) {
+            writeln!(f, "...")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical() {
+        let diff = LineDiff::new("a\nb\n", "a\nb\n");
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "");
+    }
+
+    #[test]
+    fn test_changes() {
+        let diff = LineDiff::new("a\nb\nc\n", "a\nx\nc\nd\n");
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.lines(),
+            &[
+                DiffLine::Same("a".into()),
+                DiffLine::Removed("b".into()),
+                DiffLine::Added("x".into()),
+                DiffLine::Same("c".into()),
+                DiffLine::Added("d".into()),
+            ]
+        );
+        assert_eq!(diff.to_string(), "  a\n- b\n+ x\n  c\n+ d\n");
+    }
+
+    #[test]
+    fn test_context_is_collapsed() {
+        let left: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let right = left.replace("10\n", "ten\n");
+        let rendered = LineDiff::new(&left, &right).to_string();
+        assert_eq!(
+            rendered,
+            "...\n  7\n  8\n  9\n- 10\n+ ten\n  11\n  12\n  13\n...\n"
+        );
+    }
+}
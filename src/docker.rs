@@ -0,0 +1,429 @@
+//! Building `docker` and `docker compose` commands.
+//!
+//! The builders turn into ordinary [`Cmd`]s with [`into_cmd`](Run::into_cmd), so they are
+//! echoed, mocked and piped like any other command, and long-running output such as
+//! [`logs`] can be streamed with [`output_until`](Cmd::output_until),
+//! [`run_prefixed`](Cmd::run_prefixed) or [`spawn_io_out`](Cmd::spawn_io_out). Requires the
+//! `docker` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #[cfg(feature = "docker")]
+//! # {
+//! use scripty::*;
+//!
+//! docker::build(".").tag("app:dev").run()?;
+//! docker::run("app:dev")
+//!     .publish(8080, 80)
+//!     .volume("./data", "/data")
+//!     .env("RUST_LOG", "debug")
+//!     .run()?;
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{Cmd, Error, cmd};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Build an image from the Dockerfile in `context`, like `docker build <context>`.
+pub fn build(context: impl AsRef<Path>) -> Build {
+    Build {
+        context: context.as_ref().to_path_buf(),
+        tags: Vec::new(),
+        file: None,
+        build_args: Vec::new(),
+    }
+}
+
+/// A `docker build` configured by [`build`].
+#[derive(Debug, Clone)]
+#[must_use = "nothing is built until `run()` is called"]
+pub struct Build {
+    context: PathBuf,
+    tags: Vec<String>,
+    file: Option<PathBuf>,
+    build_args: Vec<(OsString, OsString)>,
+}
+
+impl Build {
+    /// Tag the image, such as `app:1.2.0`. Can be called more than once.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Use `file` instead of the `Dockerfile` in the context.
+    pub fn file(mut self, file: impl AsRef<Path>) -> Self {
+        self.file = Some(file.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set a build argument declared with `ARG` in the Dockerfile.
+    pub fn build_arg(mut self, key: impl AsRef<OsStr>, val: impl AsRef<OsStr>) -> Self {
+        self.build_args
+            .push((key.as_ref().to_os_string(), val.as_ref().to_os_string()));
+        self
+    }
+
+    /// The `docker build` command.
+    pub fn into_cmd(self) -> Cmd {
+        let mut docker = cmd!("docker", "build");
+        for tag in self.tags {
+            docker = docker.arg(format!("--tag={tag}"));
+        }
+        if let Some(file) = self.file {
+            docker = docker.arg(flag("--file=", file.as_os_str()));
+        }
+        for (key, val) in self.build_args {
+            let mut arg = key;
+            arg.push("=");
+            arg.push(val);
+            docker = docker.arg(flag("--build-arg=", &arg));
+        }
+        docker.arg(self.context)
+    }
+
+    /// Run `docker build`.
+    pub fn run(self) -> Result<(), Error> {
+        self.into_cmd().run()
+    }
+}
+
+/// Run a container from `image`, like `docker run --rm <image>`.
+///
+/// The container is removed when it exits unless [`keep`](Run::keep) is called.
+pub fn run(image: impl Into<String>) -> Run {
+    Run {
+        image: image.into(),
+        name: None,
+        remove: true,
+        detach: false,
+        ports: Vec::new(),
+        volumes: Vec::new(),
+        envs: Vec::new(),
+        workdir: None,
+        args: Vec::new(),
+    }
+}
+
+/// A `docker run` configured by [`run`].
+#[derive(Debug, Clone)]
+#[must_use = "nothing runs until `run()` or `output()` is called"]
+pub struct Run {
+    image: String,
+    name: Option<String>,
+    remove: bool,
+    detach: bool,
+    ports: Vec<(u16, u16)>,
+    volumes: Vec<(PathBuf, String)>,
+    envs: Vec<(OsString, OsString)>,
+    workdir: Option<String>,
+    args: Vec<OsString>,
+}
+
+impl Run {
+    /// Name the container.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Keep the container after it exits, instead of passing `--rm`.
+    pub fn keep(mut self) -> Self {
+        self.remove = false;
+        self
+    }
+
+    /// Start the container in the background. `run()` returns once it has started, and
+    /// `output()` returns its ID.
+    pub fn detach(mut self) -> Self {
+        self.detach = true;
+        self
+    }
+
+    /// Publish `container_port` on `host_port` of the host.
+    pub fn publish(mut self, host_port: u16, container_port: u16) -> Self {
+        self.ports.push((host_port, container_port));
+        self
+    }
+
+    /// Mount `host_path` at `container_path`. Relative host paths are made absolute against
+    /// the current directory, as docker requires.
+    pub fn volume(
+        mut self,
+        host_path: impl AsRef<Path>,
+        container_path: impl Into<String>,
+    ) -> Self {
+        self.volumes
+            .push((host_path.as_ref().to_path_buf(), container_path.into()));
+        self
+    }
+
+    /// Set an environment variable in the container.
+    ///
+    /// The value is passed through the environment of the `docker` process rather than on its
+    /// command line, so [`Cmd::env_secret`] can mask it: see [`into_cmd`](Self::into_cmd).
+    pub fn env(mut self, key: impl AsRef<OsStr>, val: impl AsRef<OsStr>) -> Self {
+        self.envs
+            .push((key.as_ref().to_os_string(), val.as_ref().to_os_string()));
+        self
+    }
+
+    /// Set the working directory inside the container.
+    pub fn workdir(mut self, dir: impl Into<String>) -> Self {
+        self.workdir = Some(dir.into());
+        self
+    }
+
+    /// Add an argument to the command run in the container.
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Add arguments to the command run in the container.
+    pub fn args(mut self, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> Self {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
+    /// The `docker run` command.
+    ///
+    /// Environment variables are passed as `--env=<name>` and set on the returned command, so
+    /// more can be added, or masked in the echo, with [`Cmd::env`] and [`Cmd::env_secret`].
+    pub fn into_cmd(self) -> Cmd {
+        let mut docker = cmd!("docker", "run");
+        if self.remove {
+            docker = docker.arg("--rm");
+        }
+        if self.detach {
+            docker = docker.arg("--detach");
+        }
+        if let Some(name) = self.name {
+            docker = docker.arg(format!("--name={name}"));
+        }
+        for (host_port, container_port) in self.ports {
+            docker = docker.arg(format!("--publish={host_port}:{container_port}"));
+        }
+        for (host_path, container_path) in self.volumes {
+            let host_path = std::path::absolute(&host_path).unwrap_or(host_path);
+            let mut volume = host_path.into_os_string();
+            volume.push(":");
+            volume.push(container_path);
+            docker = docker.arg(flag("--volume=", &volume));
+        }
+        if let Some(workdir) = self.workdir {
+            docker = docker.arg(format!("--workdir={workdir}"));
+        }
+        for (key, val) in self.envs {
+            docker = docker.arg(flag("--env=", &key)).env(key, val);
+        }
+        docker.arg(self.image).args(self.args)
+    }
+
+    /// Run the container, with its output shown as it arrives.
+    pub fn run(self) -> Result<(), Error> {
+        self.into_cmd().run()
+    }
+
+    /// Run the container and return its output.
+    pub fn output(self) -> Result<String, Error> {
+        self.into_cmd().output()
+    }
+}
+
+/// Push `image` to its registry, like `docker push <image>`.
+pub fn push(image: impl AsRef<OsStr>) -> Cmd {
+    cmd!("docker", "push").arg(image)
+}
+
+/// Show the logs of `container`, like `docker logs <container>`.
+///
+/// Add `--follow` to keep streaming new output, and read it as it arrives with
+/// [`output_until`](Cmd::output_until) or [`run_prefixed`](Cmd::run_prefixed).
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "docker")]
+/// # {
+/// use scripty::*;
+///
+/// docker::run("postgres:16").name("db").detach().env("POSTGRES_PASSWORD", "dev").run()?;
+/// let mut db = docker::logs("db")
+///     .arg("--follow")
+///     .combine_err()
+///     .output_until(|line| line.contains("ready to accept connections"))?;
+/// db.handle.kill()?;
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn logs(container: impl AsRef<OsStr>) -> Cmd {
+    cmd!("docker", "logs").arg(container)
+}
+
+/// Manage a `docker compose` project, using `compose.yaml` in the current directory unless
+/// [`file`](Compose::file) is set.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "docker")]
+/// # {
+/// use scripty::*;
+///
+/// let stack = docker::compose().file("deploy/compose.yaml").project("it");
+/// stack.up().run()?;
+/// let result = cmd!("cargo", "test", "--test", "integration").run();
+/// stack.down().arg("--volumes").run()?;
+/// result?;
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn compose() -> Compose {
+    Compose::default()
+}
+
+/// A `docker compose` project, configured by [`compose`].
+#[derive(Debug, Clone, Default)]
+pub struct Compose {
+    files: Vec<PathBuf>,
+    project: Option<String>,
+}
+
+impl Compose {
+    /// Use `file` as the compose file. Can be called more than once to merge files.
+    pub fn file(mut self, file: impl AsRef<Path>) -> Self {
+        self.files.push(file.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the project name, which prefixes the container names.
+    pub fn project(mut self, name: impl Into<String>) -> Self {
+        self.project = Some(name.into());
+        self
+    }
+
+    /// `docker compose up --detach --wait`, which returns once the services are running and
+    /// healthy.
+    pub fn up(&self) -> Cmd {
+        self.cmd("up").args(["--detach", "--wait"])
+    }
+
+    /// `docker compose down`, which stops and removes the containers.
+    pub fn down(&self) -> Cmd {
+        self.cmd("down")
+    }
+
+    /// `docker compose logs --follow`, streaming the output of all services.
+    pub fn logs(&self) -> Cmd {
+        self.cmd("logs").arg("--follow")
+    }
+
+    /// `docker compose <subcommand>` with the project's files and name.
+    pub fn cmd(&self, subcommand: impl AsRef<OsStr>) -> Cmd {
+        let mut docker = cmd!("docker", "compose");
+        for file in &self.files {
+            docker = docker.arg(flag("--file=", file.as_os_str()));
+        }
+        if let Some(project) = &self.project {
+            docker = docker.arg(format!("--project-name={project}"));
+        }
+        docker.arg(subcommand)
+    }
+}
+
+/// An option with its value in one argument, such as `--file=<path>`.
+fn flag(name: &str, value: &OsStr) -> OsString {
+    let mut flag = OsString::from(name);
+    flag.push(value);
+    flag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(cmd: &Cmd) -> Vec<String> {
+        std::iter::once(&cmd.program)
+            .chain(&cmd.args)
+            .map(|word| word.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_build() {
+        let docker = build("app")
+            .tag("app:1.0")
+            .tag("app:latest")
+            .file("app/Dockerfile.release")
+            .build_arg("VERSION", "1.0")
+            .into_cmd();
+        assert_eq!(
+            words(&docker),
+            [
+                "docker",
+                "build",
+                "--tag=app:1.0",
+                "--tag=app:latest",
+                "--file=app/Dockerfile.release",
+                "--build-arg=VERSION=1.0",
+                "app"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let docker = run("alpine")
+            .name("probe")
+            .publish(8080, 80)
+            .volume("/srv/data", "/data")
+            .env("TOKEN", "secret")
+            .workdir("/data")
+            .args(["ls", "-l"])
+            .into_cmd();
+        assert_eq!(
+            words(&docker),
+            [
+                "docker",
+                "run",
+                "--rm",
+                "--name=probe",
+                "--publish=8080:80",
+                "--volume=/srv/data:/data",
+                "--workdir=/data",
+                "--env=TOKEN",
+                "alpine",
+                "ls",
+                "-l"
+            ]
+        );
+        assert_eq!(docker.envs, [("TOKEN".into(), "secret".into())]);
+
+        let docker = run("nginx").keep().detach().into_cmd();
+        assert_eq!(words(&docker), ["docker", "run", "--detach", "nginx"]);
+    }
+
+    #[test]
+    fn test_compose() {
+        let stack = compose().file("compose.yaml").project("it");
+        assert_eq!(
+            words(&stack.up()),
+            [
+                "docker",
+                "compose",
+                "--file=compose.yaml",
+                "--project-name=it",
+                "up",
+                "--detach",
+                "--wait"
+            ]
+        );
+        assert_eq!(words(&compose().down()), ["docker", "compose", "down"]);
+        assert_eq!(words(&logs("db")), ["docker", "logs", "db"]);
+    }
+}
@@ -0,0 +1,128 @@
+//! Comparing file contents.
+
+use super::echo_operation;
+use super::error::context;
+use crate::diff::LineDiff;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Returns whether two files have the same contents.
+///
+/// Files of different sizes are not read, and otherwise the comparison stops at the first
+/// difference.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// if !fs::files_equal("src/generated.rs", "target/generated.rs")? {
+///     fs::copy("target/generated.rs", "src/generated.rs")?;
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn files_equal(a: impl AsRef<Path>, b: impl AsRef<Path>) -> std::io::Result<bool> {
+    let a = a.as_ref();
+    let b = b.as_ref();
+    echo_operation("files_equal", &format!("{} {}", a.display(), b.display()));
+    contents_equal(a, b).map_err(context("files_equal", &[a, b]))
+}
+
+fn contents_equal(a: &Path, b: &Path) -> std::io::Result<bool> {
+    let (file_a, file_b) = (std::fs::File::open(a)?, std::fs::File::open(b)?);
+    if file_a.metadata()?.len() != file_b.metadata()?.len() {
+        return Ok(false);
+    }
+
+    let mut reader_a = BufReader::new(file_a);
+    let mut reader_b = BufReader::new(file_b);
+    let mut buf_a = [0; 8192];
+    let mut buf_b = [0; 8192];
+    loop {
+        let n = read_full(&mut reader_a, &mut buf_a)?;
+        if n != read_full(&mut reader_b, &mut buf_b)? || buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+        if n == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Read until `buf` is full or the end of the input, returning the number of bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Compare two files line by line, like `diff`.
+///
+/// Invalid UTF-8 is replaced with `U+FFFD`. See [`LineDiff`] for displaying the result.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let changes = fs::diff("expected.txt", "actual.txt")?;
+/// if !changes.is_empty() {
+///     print!("{changes}");
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn diff(a: impl AsRef<Path>, b: impl AsRef<Path>) -> std::io::Result<LineDiff> {
+    let a = a.as_ref();
+    let b = b.as_ref();
+    echo_operation("diff", &format!("{} {}", a.display(), b.display()));
+    let left = std::fs::read(a).map_err(context("diff", &[a, b]))?;
+    let right = std::fs::read(b).map_err(context("diff", &[a, b]))?;
+    Ok(LineDiff::new(
+        &String::from_utf8_lossy(&left),
+        &String::from_utf8_lossy(&right),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp_dir_scoped;
+
+    #[test]
+    fn test_files_equal() {
+        let tmp = temp_dir_scoped().unwrap();
+        let a = tmp.path().join("a");
+        let b = tmp.path().join("b");
+        std::fs::write(&a, "same contents").unwrap();
+        std::fs::write(&b, "same contents").unwrap();
+        assert!(files_equal(&a, &b).unwrap());
+
+        std::fs::write(&b, "same_contents").unwrap();
+        assert!(!files_equal(&a, &b).unwrap());
+        std::fs::write(&b, "longer contents").unwrap();
+        assert!(!files_equal(&a, &b).unwrap());
+
+        let error = files_equal(&a, tmp.path().join("missing")).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_diff() {
+        let tmp = temp_dir_scoped().unwrap();
+        let a = tmp.path().join("a");
+        let b = tmp.path().join("b");
+        std::fs::write(&a, "one\ntwo\nthree\n").unwrap();
+        std::fs::write(&b, "one\n2\nthree\nfour\n").unwrap();
+
+        let changes = diff(&a, &b).unwrap();
+        assert_eq!(changes.to_string(), "  one\n- two\n+ 2\n  three\n+ four\n");
+
+        std::fs::write(&b, "one\ntwo\nthree").unwrap();
+        assert!(diff(&a, &b).unwrap().is_empty());
+    }
+}
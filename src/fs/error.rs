@@ -0,0 +1,80 @@
+//! Errors from file system operations, with the paths they failed on.
+
+use std::path::{Path, PathBuf};
+
+/// Details of a failed file system operation: what was done, on which paths, and why it failed.
+///
+/// The functions in this module return their errors as [`std::io::Error`] with the same
+/// [`kind`](std::io::Error::kind) as the underlying error, so they keep working with `?` and
+/// `std::io::Result`. Their message includes the operation and the paths, for example
+/// `Failed to read_to_string config.toml: No such file or directory (os error 2)`.
+/// Use [`FsError::from_io`] to get the details back.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let error = fs::read_to_string("config.toml").unwrap_err();
+/// let details = fs::FsError::from_io(&error).unwrap();
+/// assert_eq!(details.operation(), "read_to_string");
+/// assert_eq!(details.paths(), [std::path::Path::new("config.toml")]);
+/// ```
+#[derive(Debug)]
+pub struct FsError {
+    operation: &'static str,
+    paths: Vec<PathBuf>,
+    source: std::io::Error,
+}
+
+impl FsError {
+    /// Returns the name of the operation that failed, such as `"copy"`.
+    pub fn operation(&self) -> &str {
+        self.operation
+    }
+
+    /// Returns the paths the operation was given, in argument order.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Returns the details of an error returned by a function in this module, or `None` for
+    /// other errors.
+    pub fn from_io(error: &std::io::Error) -> Option<&FsError> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+impl std::fmt::Display for FsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to {}", self.operation)?;
+        for (i, path) in self.paths.iter().enumerate() {
+            let separator = if i == 0 { " " } else { " -> " };
+            write!(f, "{separator}{}", path.display())?;
+        }
+        write!(f, ": {}", self.source)
+    }
+}
+
+impl std::error::Error for FsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Returns a function for `map_err` that adds the operation and paths to an error, keeping
+/// its kind.
+pub(crate) fn context<'a>(
+    operation: &'static str,
+    paths: &'a [&'a Path],
+) -> impl FnOnce(std::io::Error) -> std::io::Error + 'a {
+    move |source| {
+        let kind = source.kind();
+        let error = FsError {
+            operation,
+            paths: paths.iter().map(|path| path.to_path_buf()).collect(),
+            source,
+        };
+        std::io::Error::new(kind, error)
+    }
+}
@@ -0,0 +1,180 @@
+//! Following a growing file, like `tail -F`.
+
+use super::echo_operation;
+use super::error::context;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A reader that returns data appended to a file, created by [`follow`].
+///
+/// Reads block until new data is written, so the reader never reaches end of file. When the
+/// file is truncated it is read again from the start, and when it is replaced (for example by
+/// log rotation) the new file is opened, like `tail -F`.
+#[derive(Debug)]
+pub struct Follow {
+    path: PathBuf,
+    poll_interval: Duration,
+    from_start: bool,
+    /// The open file and its `(device, inode)`, once the first read has opened it.
+    file: Option<(File, (u64, u64))>,
+}
+
+impl Follow {
+    /// Set how often the file is checked for new data (default: 100 ms).
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Read the existing contents of the file first, instead of only data written afterwards.
+    pub fn from_start(mut self) -> Self {
+        self.from_start = true;
+        self
+    }
+
+    /// Open the file on the first read, positioned at its end unless reading from the start.
+    fn open(&mut self) -> std::io::Result<&mut File> {
+        if self.file.is_none() {
+            echo_operation("follow", &self.path.display().to_string());
+            let mut file = File::open(&self.path).map_err(context("follow", &[&self.path]))?;
+            if !self.from_start {
+                file.seek(SeekFrom::End(0))?;
+            }
+            let metadata = file.metadata()?;
+            self.file = Some((file, (metadata.dev(), metadata.ino())));
+        }
+        Ok(&mut self.file.as_mut().unwrap().0)
+    }
+
+    /// Reopen or rewind the file if it was replaced or truncated since it was opened.
+    fn check_replaced(&mut self) -> std::io::Result<()> {
+        let Some((file, id)) = &mut self.file else {
+            return Ok(());
+        };
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            // Rotated away and not yet recreated
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        if (metadata.dev(), metadata.ino()) != *id {
+            *file = File::open(&self.path)?;
+            *id = (metadata.dev(), metadata.ino());
+        } else if metadata.len() < file.stream_position()? {
+            file.seek(SeekFrom::Start(0))?;
+        }
+        Ok(())
+    }
+}
+
+impl Read for Follow {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let n = self.open()?.read(buf)?;
+            if n > 0 {
+                return Ok(n);
+            }
+            self.check_replaced()?;
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+/// Follow a file as it grows, like `tail -F`.
+///
+/// The returned reader starts at the current end of the file (see [`Follow::from_start`]) and
+/// blocks waiting for new data instead of reaching end of file. It polls the file, so it behaves
+/// the same on every platform. The file is opened, and the operation echoed, on the first read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// // Print errors as they are logged, until interrupted
+/// fs::follow("app.log").pipe(cmd!("grep", "--line-buffered", "ERROR")).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn follow(path: impl AsRef<Path>) -> Follow {
+    Follow {
+        path: path.as_ref().to_path_buf(),
+        poll_interval: Duration::from_millis(100),
+        from_start: false,
+        file: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp_dir_scoped;
+    use std::io::Write;
+
+    fn read_n(reader: &mut Follow, n: usize) -> String {
+        let mut buf = vec![0; n];
+        reader.read_exact(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn append(path: &Path, data: &str) {
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_follow_appended_data() {
+        let tmp = temp_dir_scoped().unwrap();
+        let path = tmp.path().join("app.log");
+        std::fs::write(&path, "old\n").unwrap();
+
+        let mut reader = follow(&path).poll_interval(Duration::from_millis(10));
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                append(&path, "new\n");
+            })
+        };
+        assert_eq!(read_n(&mut reader, 4), "new\n");
+        writer.join().unwrap();
+
+        let mut reader = follow(&path).from_start();
+        assert_eq!(read_n(&mut reader, 8), "old\nnew\n");
+    }
+
+    #[test]
+    fn test_follow_truncated_and_replaced() {
+        let tmp = temp_dir_scoped().unwrap();
+        let path = tmp.path().join("app.log");
+        std::fs::write(&path, "first line\n").unwrap();
+
+        let mut reader = follow(&path)
+            .from_start()
+            .poll_interval(Duration::from_millis(10));
+        assert_eq!(read_n(&mut reader, 11), "first line\n");
+
+        // Truncated to a shorter file: read again from the start
+        std::fs::write(&path, "short\n").unwrap();
+        assert_eq!(read_n(&mut reader, 6), "short\n");
+
+        // Replaced by a new file, as done by log rotation
+        let rotated = tmp.path().join("app.log.new");
+        std::fs::write(&rotated, "rotated\n").unwrap();
+        std::fs::rename(&rotated, &path).unwrap();
+        assert_eq!(read_n(&mut reader, 8), "rotated\n");
+    }
+
+    #[test]
+    fn test_follow_missing_file() {
+        let mut reader = follow("/nonexistent/app.log");
+        let error = reader.read(&mut [0; 16]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,135 @@
+//! File checksums, like `sha256sum` and `md5sum`.
+
+use super::echo_operation;
+use super::error::context;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+
+/// A hash algorithm for [`hash_reader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// SHA-256, as computed by `sha256sum` and `shasum -a 256`.
+    Sha256,
+    /// MD5, as computed by `md5sum`. Only suitable for checking against published checksums,
+    /// not for security.
+    Md5,
+}
+
+/// Returns the SHA-256 checksum of a file as a lowercase hex string.
+///
+/// Gives the same checksum as `sha256sum` on Linux and `shasum -a 256` on macOS. Requires the
+/// `hash` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "hash")]
+/// # {
+/// use scripty::*;
+///
+/// let expected = fs::read_to_string("tool.tar.gz.sha256")?;
+/// if !expected.starts_with(&fs::sha256("tool.tar.gz")?) {
+///     return Err("checksum mismatch".into());
+/// }
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn sha256(path: impl AsRef<Path>) -> std::io::Result<String> {
+    hash_file("sha256", path.as_ref(), HashAlgorithm::Sha256)
+}
+
+/// Returns the MD5 checksum of a file as a lowercase hex string.
+///
+/// Gives the same checksum as `md5sum`. Requires the `hash` feature.
+pub fn md5(path: impl AsRef<Path>) -> std::io::Result<String> {
+    hash_file("md5", path.as_ref(), HashAlgorithm::Md5)
+}
+
+fn hash_file(
+    operation: &'static str,
+    path: &Path,
+    algorithm: HashAlgorithm,
+) -> std::io::Result<String> {
+    echo_operation(operation, &path.display().to_string());
+    std::fs::File::open(path)
+        .and_then(|file| hash_reader(file, algorithm))
+        .map_err(context(operation, &[path]))
+}
+
+/// Returns the checksum of everything read from `reader` as a lowercase hex string.
+///
+/// The input is hashed as it is read, so large inputs are not held in memory. Requires the
+/// `hash` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "hash")]
+/// # {
+/// use scripty::*;
+///
+/// // Like `sha256sum` reading from stdin
+/// println!("{}", fs::hash_reader(std::io::stdin(), fs::HashAlgorithm::Sha256)?);
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn hash_reader(reader: impl Read, algorithm: HashAlgorithm) -> std::io::Result<String> {
+    match algorithm {
+        HashAlgorithm::Sha256 => digest_reader::<Sha256>(reader),
+        HashAlgorithm::Md5 => digest_reader::<Md5>(reader),
+    }
+}
+
+fn digest_reader<D: Digest>(mut reader: impl Read) -> std::io::Result<String> {
+    let mut hasher = D::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp_dir_scoped;
+
+    #[test]
+    fn test_file_checksums() {
+        let tmp = temp_dir_scoped().unwrap();
+        let path = tmp.path().join("data.txt");
+        std::fs::write(&path, "hello\n").unwrap();
+
+        assert_eq!(
+            sha256(&path).unwrap(),
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"
+        );
+        assert_eq!(md5(&path).unwrap(), "b1946ac92492d2347c6235b4d2611184");
+
+        let error = sha256(tmp.path().join("missing")).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        assert_eq!(
+            hash_reader(&b""[..], HashAlgorithm::Sha256).unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hash_reader(&b""[..], HashAlgorithm::Md5).unwrap(),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+    }
+}
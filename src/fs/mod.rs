@@ -0,0 +1,1051 @@
+//! File system operations.
+//!
+//! This module provides wrappers around `std::fs` functions that optionally echo the operation to the console.
+//! It offers a set of functions for working with files and directories.
+//!
+//! For more information on the behavior of these functions, see the documentation for the corresponding
+//! functions in [`std::fs`].
+
+use crate::style::{BOLD_CYAN, BOLD_UNDERLINE};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+mod compare;
+pub use compare::{diff, files_equal};
+
+mod error;
+pub use error::FsError;
+pub(crate) use error::context;
+
+#[cfg(feature = "hash")]
+mod hash;
+#[cfg(feature = "hash")]
+pub use hash::{HashAlgorithm, hash_reader, md5, sha256};
+
+mod follow;
+pub use follow::{Follow, follow};
+
+mod walk;
+pub use walk::{Walk, WalkEntry, walk};
+
+pub(crate) fn echo_operation(op: &str, details: &str) {
+    crate::echo::emit("  ", "fs", |styled| {
+        if styled {
+            format!("{BOLD_CYAN}{op}{BOLD_CYAN:#} {BOLD_UNDERLINE}{details}{BOLD_UNDERLINE:#}")
+        } else {
+            format!("{op} {details}")
+        }
+    });
+}
+
+/// Returns the canonical, absolute form of a path with all intermediate components normalized
+/// and symbolic links resolved.
+///
+/// This is a wrapper around [`std::fs::canonicalize`] that echoes the operation to the console.
+pub fn canonicalize(path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
+    let path = path.as_ref();
+    echo_operation("canonicalize", &path.display().to_string());
+    std::fs::canonicalize(path).map_err(context("canonicalize", &[path]))
+}
+
+/// Copy the contents of one file to another.
+///
+/// This is a wrapper around [`std::fs::copy`] that echoes the operation to the console.
+pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<u64> {
+    let from = from.as_ref();
+    let to = to.as_ref();
+    echo_operation("copy", &format!("{} -> {}", from.display(), to.display()));
+    std::fs::copy(from, to).map_err(context("copy", &[from, to]))
+}
+
+/// Create a new, empty directory at the provided path.
+///
+/// This is a wrapper around [`std::fs::create_dir`] that echoes the operation to the console.
+pub fn create_dir(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    echo_operation("create_dir", &path.display().to_string());
+    std::fs::create_dir(path).map_err(context("create_dir", &[path]))
+}
+
+/// Recursively create a directory and all of its parent components if they are missing.
+///
+/// This is a wrapper around [`std::fs::create_dir_all`] that echoes the operation to the console.
+pub fn create_dir_all(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    echo_operation("create_dir_all", &path.display().to_string());
+    std::fs::create_dir_all(path).map_err(context("create_dir_all", &[path]))
+}
+
+/// Create a new hard link to a file.
+///
+/// This is a wrapper around [`std::fs::hard_link`] that echoes the operation to the console.
+pub fn hard_link(original: impl AsRef<Path>, link: impl AsRef<Path>) -> std::io::Result<()> {
+    let original = original.as_ref();
+    let link = link.as_ref();
+    echo_operation(
+        "hard_link",
+        &format!("{} -> {}", original.display(), link.display()),
+    );
+    std::fs::hard_link(original, link).map_err(context("hard_link", &[original, link]))
+}
+
+/// Given a path, query the file system to get information about a file, directory, etc.
+///
+/// This is a wrapper around [`std::fs::metadata`] that echoes the operation to the console.
+pub fn metadata(path: impl AsRef<Path>) -> std::io::Result<std::fs::Metadata> {
+    let path = path.as_ref();
+    echo_operation("metadata", &path.display().to_string());
+    std::fs::metadata(path).map_err(context("metadata", &[path]))
+}
+
+/// Read the entire contents of a file into a bytes vector.
+///
+/// This is a wrapper around [`std::fs::read`] that echoes the operation to the console.
+pub fn read(path: impl AsRef<Path>) -> std::io::Result<Vec<u8>> {
+    let path = path.as_ref();
+    echo_operation("read", &path.display().to_string());
+    std::fs::read(path).map_err(context("read", &[path]))
+}
+
+/// Returns an iterator over the entries within a directory.
+///
+/// This is a wrapper around [`std::fs::read_dir`] that echoes the operation to the console.
+pub fn read_dir(path: impl AsRef<Path>) -> std::io::Result<std::fs::ReadDir> {
+    let path = path.as_ref();
+    echo_operation("read_dir", &path.display().to_string());
+    std::fs::read_dir(path).map_err(context("read_dir", &[path]))
+}
+
+/// Reads a symbolic link, returning the file that the link points to.
+///
+/// This is a wrapper around [`std::fs::read_link`] that echoes the operation to the console.
+pub fn read_link(path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
+    let path = path.as_ref();
+    echo_operation("read_link", &path.display().to_string());
+    std::fs::read_link(path).map_err(context("read_link", &[path]))
+}
+
+/// Read the entire contents of a file into a string.
+///
+/// This is a wrapper around [`std::fs::read_to_string`] that echoes the operation to the console.
+pub fn read_to_string(path: impl AsRef<Path>) -> std::io::Result<String> {
+    let path = path.as_ref();
+    echo_operation("read_to_string", &path.display().to_string());
+    std::fs::read_to_string(path).map_err(context("read_to_string", &[path]))
+}
+
+/// Removes an empty directory.
+///
+/// This is a wrapper around [`std::fs::remove_dir`] that echoes the operation to the console.
+pub fn remove_dir(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    echo_operation("remove_dir", &path.display().to_string());
+    std::fs::remove_dir(path).map_err(context("remove_dir", &[path]))
+}
+
+/// Removes a directory at this path, after removing all its contents. Use carefully!
+///
+/// This is a wrapper around [`std::fs::remove_dir_all`] that echoes the operation to the console.
+pub fn remove_dir_all(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    echo_operation("remove_dir_all", &path.display().to_string());
+    std::fs::remove_dir_all(path).map_err(context("remove_dir_all", &[path]))
+}
+
+/// Removes a file from the filesystem.
+///
+/// This is a wrapper around [`std::fs::remove_file`] that echoes the operation to the console.
+pub fn remove_file(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    echo_operation("remove_file", &path.display().to_string());
+    std::fs::remove_file(path).map_err(context("remove_file", &[path]))
+}
+
+/// Rename a file or directory to a new name, replacing the original file if `to` already exists.
+///
+/// This is a wrapper around [`std::fs::rename`] that echoes the operation to the console.
+pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<()> {
+    let from = from.as_ref();
+    let to = to.as_ref();
+    echo_operation("rename", &format!("{} -> {}", from.display(), to.display()));
+    std::fs::rename(from, to).map_err(context("rename", &[from, to]))
+}
+
+/// Changes the permissions found on a file or a directory.
+///
+/// This is a wrapper around [`std::fs::set_permissions`] that echoes the operation to the console.
+pub fn set_permissions(path: impl AsRef<Path>, perm: std::fs::Permissions) -> std::io::Result<()> {
+    let path = path.as_ref();
+    echo_operation("set_permissions", &path.display().to_string());
+    std::fs::set_permissions(path, perm).map_err(context("set_permissions", &[path]))
+}
+
+/// Creates a new symbolic link on the filesystem.
+///
+/// The `link` path will be a symbolic link pointing to the `original` path.
+///
+/// This is a wrapper around [`std::os::unix::fs::symlink`] that echoes the operation to the console.
+pub fn symlink(original: impl AsRef<Path>, link: impl AsRef<Path>) -> std::io::Result<()> {
+    let original = original.as_ref();
+    let link = link.as_ref();
+    echo_operation(
+        "symlink",
+        &format!("{} -> {}", original.display(), link.display()),
+    );
+    std::os::unix::fs::symlink(original, link).map_err(context("symlink", &[original, link]))
+}
+
+/// Query the metadata about a file without following symlinks.
+///
+/// This is a wrapper around [`std::fs::symlink_metadata`] that echoes the operation to the console.
+pub fn symlink_metadata(path: impl AsRef<Path>) -> std::io::Result<std::fs::Metadata> {
+    let path = path.as_ref();
+    echo_operation("symlink_metadata", &path.display().to_string());
+    std::fs::symlink_metadata(path).map_err(context("symlink_metadata", &[path]))
+}
+
+/// Write a slice as the entire contents of a file.
+///
+/// This is a wrapper around [`std::fs::write`] that echoes the operation to the console.
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    echo_operation(
+        "write",
+        &format!("{} bytes -> {}", contents.len(), path.display()),
+    );
+    std::fs::write(path, contents).map_err(context("write", &[path]))
+}
+
+/// Write a slice as the entire contents of a file, replacing it atomically.
+///
+/// The contents are written to a temporary file in the same directory, flushed to disk, and
+/// then renamed over `path`, so readers see either the old or the new contents, never a partial
+/// write. The temporary file is removed if anything fails. The new file gets default
+/// permissions rather than those of the file it replaces.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// fs::write_atomic("Cargo.lock", "# regenerated\n")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    echo_operation(
+        "write_atomic",
+        &format!("{} bytes -> {}", contents.len(), path.display()),
+    );
+
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut file = None;
+    let temp = create_unique(dir, |temp| {
+        file = Some(
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(temp)?,
+        );
+        Ok(())
+    })
+    .map_err(context("write_atomic", &[path]))?;
+
+    let mut file = file.expect("temp file is open once created");
+    let result = file
+        .write_all(contents)
+        .and_then(|()| file.sync_all())
+        .and_then(|()| std::fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result.map_err(context("write_atomic", &[path]))
+}
+
+/// Append a slice to the end of a file, creating it if it does not exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// fs::append("build.log", "build finished\n")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn append(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    echo_operation(
+        "append",
+        &format!("{} bytes >> {}", contents.len(), path.display()),
+    );
+    std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents))
+        .map_err(context("append", &[path]))
+}
+
+/// Options for [`copy_dir_all_with`] and [`move_path_with`].
+///
+/// By default existing destination files are overwritten, like `cp -r`.
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+    overwrite: bool,
+    skip_existing: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            overwrite: true,
+            skip_existing: false,
+        }
+    }
+}
+
+impl CopyOptions {
+    /// Create the default options (overwrite existing files).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overwrite files that already exist at the destination.
+    ///
+    /// When disabled (and `skip_existing` is not set), an existing destination file is an error.
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Leave files that already exist at the destination untouched.
+    ///
+    /// Takes precedence over [`overwrite`](Self::overwrite).
+    pub fn skip_existing(mut self, skip_existing: bool) -> Self {
+        self.skip_existing = skip_existing;
+        self
+    }
+}
+
+/// Recursively copy a directory and all of its contents, like `cp -r`.
+///
+/// Each copied entry is echoed to the console. Returns the total number of bytes copied.
+/// Existing destination files are overwritten; see [`copy_dir_all_with`] to change this.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// fs::copy_dir_all("assets", "dist/assets")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn copy_dir_all(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<u64> {
+    copy_dir_all_with(from, to, &CopyOptions::default())
+}
+
+/// Recursively copy a directory and all of its contents using the given options.
+///
+/// Symbolic links are recreated rather than followed. A destination inside the source
+/// directory fails with [`InvalidInput`](std::io::ErrorKind::InvalidInput).
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// // Only add files that are not already present
+/// let options = fs::CopyOptions::new().skip_existing(true);
+/// fs::copy_dir_all_with("defaults", "config", &options)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn copy_dir_all_with(
+    from: impl AsRef<Path>,
+    to: impl AsRef<Path>,
+    options: &CopyOptions,
+) -> std::io::Result<u64> {
+    let from = from.as_ref();
+    let to = to.as_ref();
+    echo_operation(
+        "copy_dir_all",
+        &format!("{} -> {}", from.display(), to.display()),
+    );
+    check_outside("copy_dir_all", from, to)?;
+    copy_dir_entries(from, to, options)
+}
+
+/// Fail with [`InvalidInput`](std::io::ErrorKind::InvalidInput) if `to` is the directory
+/// `from` or inside it, where copying it would never end.
+///
+/// Paths that cannot be resolved are left for the copy to report.
+fn check_outside(operation: &'static str, from: &Path, to: &Path) -> std::io::Result<()> {
+    let Ok(from_real) = std::fs::canonicalize(from) else {
+        return Ok(());
+    };
+    // The destination may not exist yet, but its parent has to
+    let to_real = std::fs::canonicalize(to).ok().or_else(|| {
+        let parent = to.parent().filter(|parent| !parent.as_os_str().is_empty());
+        let parent = std::fs::canonicalize(parent.unwrap_or(Path::new("."))).ok()?;
+        Some(parent.join(to.file_name()?))
+    });
+    if to_real.is_some_and(|to_real| to_real.starts_with(&from_real)) {
+        let error = std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "the destination is inside the source directory",
+        );
+        return Err(context(operation, &[from, to])(error));
+    }
+    Ok(())
+}
+
+fn copy_dir_entries(from: &Path, to: &Path, options: &CopyOptions) -> std::io::Result<u64> {
+    if !to.is_dir() {
+        create_dir(to)?;
+    }
+
+    let mut total = 0;
+    for entry in std::fs::read_dir(from).map_err(context("read_dir", &[from]))? {
+        let entry = entry.map_err(context("read_dir", &[from]))?;
+        let src = entry.path();
+        let dst = to.join(entry.file_name());
+        let file_type = entry.file_type().map_err(context("read_dir", &[from]))?;
+
+        if file_type.is_dir() {
+            total += copy_dir_entries(&src, &dst, options)?;
+            continue;
+        }
+
+        total += copy_entry(&src, &dst, file_type, options)?.unwrap_or(0);
+    }
+    Ok(total)
+}
+
+/// Copy the file or symlink `from`, of type `file_type`, to `to` as `options` ask for an
+/// existing destination. A symlink is recreated rather than followed.
+///
+/// Returns the bytes copied, or `None` if the destination exists and is skipped.
+fn copy_entry(
+    from: &Path,
+    to: &Path,
+    file_type: std::fs::FileType,
+    options: &CopyOptions,
+) -> std::io::Result<Option<u64>> {
+    if std::fs::symlink_metadata(to).is_ok() {
+        if options.skip_existing {
+            return Ok(None);
+        }
+        if !options.overwrite {
+            let error = std::io::Error::from(std::io::ErrorKind::AlreadyExists);
+            return Err(context("copy", &[from, to])(error));
+        }
+        if file_type.is_symlink() {
+            std::fs::remove_file(to).map_err(context("remove_file", &[to]))?;
+        }
+    }
+
+    if file_type.is_symlink() {
+        let original = std::fs::read_link(from).map_err(context("read_link", &[from]))?;
+        symlink(original, to)?;
+        Ok(Some(0))
+    } else {
+        copy(from, to).map(Some)
+    }
+}
+
+/// Move a file or directory, like `mv`.
+///
+/// Uses [`rename`] when possible and falls back to copying and removing the source when the
+/// destination is on a different filesystem.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// fs::move_path("build/output", "/mnt/artifacts/output")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn move_path(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<()> {
+    move_path_with(from, to, &CopyOptions::default())
+}
+
+/// Move a file or directory using the given options for files that exist at the destination.
+///
+/// [`rename`] replaces an existing destination, so it is only used when the destination does
+/// not exist or the options allow overwriting it. Otherwise, and when the destination is on a
+/// different filesystem, the move copies and removes the source, for a single file as for the
+/// entries of a directory. A symlink is then recreated rather than followed, and files skipped
+/// because they exist at the destination are left in the source, along with the directories
+/// holding them.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// // Fails instead of replacing an existing release
+/// let options = fs::CopyOptions::new().overwrite(false);
+/// fs::move_path_with("build/release.tar.gz", "releases/release.tar.gz", &options)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn move_path_with(
+    from: impl AsRef<Path>,
+    to: impl AsRef<Path>,
+    options: &CopyOptions,
+) -> std::io::Result<()> {
+    let from = from.as_ref();
+    let to = to.as_ref();
+    echo_operation(
+        "move_path",
+        &format!("{} -> {}", from.display(), to.display()),
+    );
+
+    let keep_existing = options.skip_existing || !options.overwrite;
+    if keep_existing && std::fs::symlink_metadata(to).is_ok() {
+        return move_by_copy(from, to, options);
+    }
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => move_by_copy(from, to, options),
+        result => result.map_err(context("move_path", &[from, to])),
+    }
+}
+
+/// Move `from` to `to` by copying it as `options` ask and removing it. A file that is skipped
+/// because the destination exists is left in place, along with the directories holding it.
+fn move_by_copy(from: &Path, to: &Path, options: &CopyOptions) -> std::io::Result<()> {
+    let metadata = std::fs::symlink_metadata(from).map_err(context("move_path", &[from, to]))?;
+    if metadata.is_dir() {
+        check_outside("move_path", from, to)?;
+        if move_dir_entries(from, to, options)? {
+            remove_dir(from)?;
+        }
+        return Ok(());
+    }
+    match copy_entry(from, to, metadata.file_type(), options)? {
+        Some(_) => remove_file(from),
+        None => Ok(()),
+    }
+}
+
+/// Move the entries of the directory `from` into `to` by copying them as `options` ask and
+/// removing each one once it is copied.
+///
+/// Returns whether all entries were moved, leaving `from` empty.
+fn move_dir_entries(from: &Path, to: &Path, options: &CopyOptions) -> std::io::Result<bool> {
+    if !to.is_dir() {
+        create_dir(to)?;
+    }
+
+    let mut moved_all = true;
+    for entry in std::fs::read_dir(from).map_err(context("read_dir", &[from]))? {
+        let entry = entry.map_err(context("read_dir", &[from]))?;
+        let src = entry.path();
+        let dst = to.join(entry.file_name());
+        let file_type = entry.file_type().map_err(context("read_dir", &[from]))?;
+
+        let moved = if file_type.is_dir() {
+            let moved = move_dir_entries(&src, &dst, options)?;
+            if moved {
+                std::fs::remove_dir(&src).map_err(context("remove_dir", &[&src]))?;
+            }
+            moved
+        } else {
+            match copy_entry(&src, &dst, file_type, options)? {
+                Some(_) => {
+                    std::fs::remove_file(&src).map_err(context("remove_file", &[&src]))?;
+                    true
+                }
+                None => false,
+            }
+        };
+        moved_all &= moved;
+    }
+    Ok(moved_all)
+}
+
+/// A temporary directory that is removed when dropped.
+///
+/// Created by [`temp_dir_scoped`]. Use [`TempDir::keep`] to disable the automatic cleanup.
+#[derive(Debug)]
+pub struct TempDir {
+    path: Option<PathBuf>,
+}
+
+impl TempDir {
+    /// Returns the path of the temporary directory.
+    pub fn path(&self) -> &Path {
+        self.path
+            .as_deref()
+            .expect("temp dir path is present until dropped")
+    }
+
+    /// Keep the directory on disk and return its path, disabling the automatic cleanup.
+    pub fn keep(mut self) -> PathBuf {
+        self.path
+            .take()
+            .expect("temp dir path is present until dropped")
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            let _ = remove_dir_all(path);
+        }
+    }
+}
+
+/// A temporary file that is removed when dropped.
+///
+/// Created by [`temp_file_scoped`]. Use [`TempFile::keep`] to disable the automatic cleanup.
+#[derive(Debug)]
+pub struct TempFile {
+    path: Option<PathBuf>,
+}
+
+impl TempFile {
+    /// Returns the path of the temporary file.
+    pub fn path(&self) -> &Path {
+        self.path
+            .as_deref()
+            .expect("temp file path is present until dropped")
+    }
+
+    /// Keep the file on disk and return its path, disabling the automatic cleanup.
+    pub fn keep(mut self) -> PathBuf {
+        self.path
+            .take()
+            .expect("temp file path is present until dropped")
+    }
+}
+
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            let _ = remove_file(path);
+        }
+    }
+}
+
+/// Create a uniquely named temporary directory that is removed when the guard is dropped.
+///
+/// This is the equivalent of `mktemp -d`. The directory is created inside [`std::env::temp_dir`]
+/// with a random name, and only the current user can access it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let dir = fs::temp_dir_scoped()?;
+/// fs::write(dir.path().join("config.txt"), "debug=true")?;
+/// cmd!("ls").current_dir(dir.path()).run()?;
+/// // The directory and its contents are removed here
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn temp_dir_scoped() -> std::io::Result<TempDir> {
+    let path = create_unique(&std::env::temp_dir(), |path| {
+        std::fs::DirBuilder::new().mode(0o700).create(path)
+    })?;
+    echo_operation("temp_dir", &path.display().to_string());
+    Ok(TempDir { path: Some(path) })
+}
+
+/// Create a uniquely named, empty temporary file that is removed when the guard is dropped.
+///
+/// This is the equivalent of `mktemp`. The file is created inside [`std::env::temp_dir`] with a
+/// random name, and only the current user can read and write it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let file = fs::temp_file_scoped()?;
+/// cmd!("date").write_to(std::fs::File::create(file.path())?)?;
+/// println!("{}", fs::read_to_string(&file)?);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn temp_file_scoped() -> std::io::Result<TempFile> {
+    let path = create_unique(&std::env::temp_dir(), |path| {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)
+            .map(|_| ())
+    })?;
+    echo_operation("temp_file", &path.display().to_string());
+    Ok(TempFile { path: Some(path) })
+}
+
+/// Create an unlinked temporary file open for reading and writing, without echoing it.
+pub(crate) fn anonymous_temp_file() -> std::io::Result<std::fs::File> {
+    let mut file = None;
+    let path = create_unique(&std::env::temp_dir(), |path| {
+        file = Some(
+            std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(path)?,
+        );
+        Ok(())
+    })?;
+    std::fs::remove_file(path)?;
+    Ok(file.expect("temp file is open once created"))
+}
+
+/// Create a new entry with a unique name in `dir`, retrying on name collisions.
+///
+/// The name ends in a random suffix, so that other users cannot guess it in a shared
+/// directory.
+fn create_unique(
+    dir: &Path,
+    mut create: impl FnMut(&Path) -> std::io::Result<()>,
+) -> std::io::Result<PathBuf> {
+    use std::hash::{BuildHasher, RandomState};
+
+    loop {
+        // Every RandomState is seeded from the OS random number generator
+        let random = RandomState::new().hash_one(std::process::id());
+        let path = dir.join(format!("{}-{random:016x}", env!("CARGO_PKG_NAME")));
+        match create(&path) {
+            Ok(()) => return Ok(path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_dir_scoped_removed_on_drop() {
+        let dir = temp_dir_scoped().unwrap();
+        let path = dir.path().to_path_buf();
+        assert!(path.is_dir());
+        std::fs::write(path.join("file.txt"), "data").unwrap();
+
+        drop(dir);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_temp_file_scoped_removed_on_drop() {
+        let file = temp_file_scoped().unwrap();
+        let path = file.path().to_path_buf();
+        assert!(path.is_file());
+
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_temp_keep_disables_cleanup() {
+        let dir = temp_dir_scoped().unwrap().keep();
+        assert!(dir.is_dir());
+        std::fs::remove_dir(&dir).unwrap();
+
+        let file = temp_file_scoped().unwrap().keep();
+        assert!(file.is_file());
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_copy_dir_all() {
+        let tmp = temp_dir_scoped().unwrap();
+        let src = tmp.path().join("src");
+        std::fs::create_dir_all(src.join("nested")).unwrap();
+        std::fs::write(src.join("a.txt"), "a").unwrap();
+        std::fs::write(src.join("nested/b.txt"), "bb").unwrap();
+        std::os::unix::fs::symlink("a.txt", src.join("link")).unwrap();
+
+        let dst = tmp.path().join("dst");
+        let bytes = copy_dir_all(&src, &dst).unwrap();
+        assert_eq!(bytes, 3);
+        assert_eq!(std::fs::read_to_string(dst.join("a.txt")).unwrap(), "a");
+        assert_eq!(
+            std::fs::read_to_string(dst.join("nested/b.txt")).unwrap(),
+            "bb"
+        );
+        assert_eq!(
+            std::fs::read_link(dst.join("link")).unwrap(),
+            PathBuf::from("a.txt")
+        );
+
+        // Copying again overwrites by default
+        std::fs::write(src.join("a.txt"), "updated").unwrap();
+        copy_dir_all(&src, &dst).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dst.join("a.txt")).unwrap(),
+            "updated"
+        );
+    }
+
+    #[test]
+    fn test_copy_dir_all_existing_policies() {
+        let tmp = temp_dir_scoped().unwrap();
+        let src = tmp.path().join("src");
+        let dst = tmp.path().join("dst");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::create_dir_all(&dst).unwrap();
+        std::fs::write(src.join("a.txt"), "new").unwrap();
+        std::fs::write(dst.join("a.txt"), "old").unwrap();
+
+        let skip = CopyOptions::new().skip_existing(true);
+        copy_dir_all_with(&src, &dst, &skip).unwrap();
+        assert_eq!(std::fs::read_to_string(dst.join("a.txt")).unwrap(), "old");
+
+        let no_overwrite = CopyOptions::new().overwrite(false);
+        let err = copy_dir_all_with(&src, &dst, &no_overwrite).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_copy_dir_all_into_itself() {
+        let tmp = temp_dir_scoped().unwrap();
+        let src = tmp.path().join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("a.txt"), "a").unwrap();
+
+        let err = copy_dir_all(&src, src.join("copy")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(!src.join("copy").exists());
+        let err = copy_dir_all(&src, &src).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        // The same goes for a destination reached through a symlink
+        symlink(&src, tmp.path().join("link")).unwrap();
+        let err = copy_dir_all(&src, tmp.path().join("link/copy")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        let err = move_by_copy(&src, &src.join("moved"), &CopyOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(src.join("a.txt").exists());
+    }
+
+    #[test]
+    fn test_move_path() {
+        let tmp = temp_dir_scoped().unwrap();
+        let src = tmp.path().join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("a.txt"), "a").unwrap();
+
+        let dst = tmp.path().join("dst");
+        move_path(&src, &dst).unwrap();
+        assert!(!src.exists());
+        assert_eq!(std::fs::read_to_string(dst.join("a.txt")).unwrap(), "a");
+
+        let file = tmp.path().join("moved.txt");
+        move_path(dst.join("a.txt"), &file).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "a");
+    }
+
+    #[test]
+    fn test_move_path_with_existing_target() {
+        let tmp = temp_dir_scoped().unwrap();
+        let src = tmp.path().join("a.txt");
+        let dst = tmp.path().join("b.txt");
+        std::fs::write(&src, "new").unwrap();
+        std::fs::write(&dst, "old").unwrap();
+
+        let no_overwrite = CopyOptions::new().overwrite(false);
+        let err = move_path_with(&src, &dst, &no_overwrite).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "old");
+        assert!(src.exists());
+
+        let skip = CopyOptions::new().skip_existing(true);
+        move_path_with(&src, &dst, &skip).unwrap();
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "old");
+        assert!(src.exists());
+
+        // A directory is merged into an existing one rather than replacing it
+        let src_dir = tmp.path().join("src");
+        let dst_dir = tmp.path().join("dst");
+        std::fs::create_dir_all(&src_dir).unwrap();
+        std::fs::create_dir_all(&dst_dir).unwrap();
+        std::fs::write(src_dir.join("added.txt"), "added").unwrap();
+        std::fs::write(dst_dir.join("kept.txt"), "kept").unwrap();
+        move_path_with(&src_dir, &dst_dir, &no_overwrite).unwrap();
+        assert!(!src_dir.exists());
+        assert_eq!(
+            std::fs::read_to_string(dst_dir.join("added.txt")).unwrap(),
+            "added"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dst_dir.join("kept.txt")).unwrap(),
+            "kept"
+        );
+
+        move_path_with(&src, &dst, &CopyOptions::default()).unwrap();
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "new");
+        assert!(!src.exists());
+    }
+
+    #[test]
+    fn test_move_by_copy_existing_policies() {
+        let tmp = temp_dir_scoped().unwrap();
+        let src = tmp.path().join("a.txt");
+        let dst = tmp.path().join("b.txt");
+        std::fs::write(&src, "new").unwrap();
+        std::fs::write(&dst, "old").unwrap();
+
+        let no_overwrite = CopyOptions::new().overwrite(false);
+        let err = move_by_copy(&src, &dst, &no_overwrite).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "old");
+        assert!(src.exists());
+
+        let skip = CopyOptions::new().skip_existing(true);
+        move_by_copy(&src, &dst, &skip).unwrap();
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "old");
+        assert!(src.exists());
+
+        // Skipped entries stay in the source directory, while the others are moved
+        let src_dir = tmp.path().join("src");
+        let dst_dir = tmp.path().join("dst");
+        std::fs::create_dir_all(src_dir.join("nested")).unwrap();
+        std::fs::create_dir_all(dst_dir.join("nested")).unwrap();
+        std::fs::write(src_dir.join("nested/kept.txt"), "new").unwrap();
+        std::fs::write(dst_dir.join("nested/kept.txt"), "old").unwrap();
+        std::fs::write(src_dir.join("moved.txt"), "moved").unwrap();
+        move_by_copy(&src_dir, &dst_dir, &skip).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(src_dir.join("nested/kept.txt")).unwrap(),
+            "new"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dst_dir.join("nested/kept.txt")).unwrap(),
+            "old"
+        );
+        assert!(!src_dir.join("moved.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(dst_dir.join("moved.txt")).unwrap(),
+            "moved"
+        );
+
+        let link = tmp.path().join("link");
+        let moved = tmp.path().join("moved");
+        symlink("a.txt", &link).unwrap();
+        move_by_copy(&link, &moved, &CopyOptions::default()).unwrap();
+        assert_eq!(std::fs::read_link(&moved).unwrap(), Path::new("a.txt"));
+        assert!(std::fs::symlink_metadata(&link).is_err());
+    }
+
+    #[test]
+    fn test_symlink_read_link_canonicalize() {
+        let tmp = temp_dir_scoped().unwrap();
+        let target = tmp.path().join("target.txt");
+        std::fs::write(&target, "data").unwrap();
+        let link = tmp.path().join("link");
+
+        symlink("target.txt", &link).unwrap();
+        assert_eq!(read_link(&link).unwrap(), PathBuf::from("target.txt"));
+        assert_eq!(
+            canonicalize(&link).unwrap(),
+            std::fs::canonicalize(&target).unwrap()
+        );
+        assert!(read_link(&target).is_err());
+    }
+
+    #[test]
+    fn test_temp_paths_are_unique() {
+        let a = temp_file_scoped().unwrap();
+        let b = temp_file_scoped().unwrap();
+        assert_ne!(a.path(), b.path());
+    }
+
+    #[test]
+    fn test_temp_paths_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir_scoped().unwrap();
+        let mode = std::fs::metadata(dir.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        let file = temp_file_scoped().unwrap();
+        let mode = std::fs::metadata(file.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let name = file.path().file_name().unwrap().to_str().unwrap();
+        let suffix = name.strip_prefix("scripty-").unwrap();
+        assert_eq!(suffix.len(), 16);
+        assert!(suffix.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_errors_include_operation_and_paths() {
+        let tmp = temp_dir_scoped().unwrap();
+        let missing = tmp.path().join("missing.txt");
+
+        let error = read_to_string(&missing).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(
+            error
+                .to_string()
+                .starts_with(&format!("Failed to read_to_string {}: ", missing.display()))
+        );
+        let details = FsError::from_io(&error).unwrap();
+        assert_eq!(details.operation(), "read_to_string");
+        assert_eq!(details.paths(), std::slice::from_ref(&missing));
+
+        let target = tmp.path().join("copy.txt");
+        let error = copy(&missing, &target).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        let details = FsError::from_io(&error).unwrap();
+        assert_eq!(details.paths(), [missing, target]);
+        assert!(error.to_string().contains(" -> "));
+
+        assert!(FsError::from_io(&std::io::Error::other("other")).is_none());
+    }
+
+    #[test]
+    fn test_write_atomic_and_append() {
+        let tmp = temp_dir_scoped().unwrap();
+        let path = tmp.path().join("config.txt");
+
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        // No temporary files are left behind
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 1);
+
+        let log = tmp.path().join("build.log");
+        append(&log, "one\n").unwrap();
+        append(&log, "two\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "one\ntwo\n");
+
+        // Renaming over a directory fails and removes the temporary file
+        let dir = tmp.path().join("dir");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("inside"), "").unwrap();
+        assert!(write_atomic(&dir, "data").is_err());
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 3);
+    }
+}
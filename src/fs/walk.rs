@@ -0,0 +1,354 @@
+//! Recursive directory traversal.
+
+use super::echo_operation;
+use super::error::context;
+use std::fs::{FileType, Metadata, ReadDir};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// An entry yielded by [`Walk`].
+#[derive(Debug, Clone)]
+pub struct WalkEntry {
+    path: PathBuf,
+    depth: usize,
+    file_type: FileType,
+    followed_link: bool,
+}
+
+impl WalkEntry {
+    /// The full path of this entry.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Consume the entry and return its path.
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    /// The file name of this entry.
+    pub fn file_name(&self) -> &std::ffi::OsStr {
+        self.path
+            .file_name()
+            .unwrap_or_else(|| self.path.as_os_str())
+    }
+
+    /// The depth of this entry relative to the root, which has depth 0.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The file type of this entry.
+    ///
+    /// When following symbolic links, this is the type of the link target.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// Returns `true` if this entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.file_type.is_dir()
+    }
+
+    /// Returns `true` if this entry is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.file_type.is_file()
+    }
+
+    /// Query the metadata of this entry, following symbolic links if the walk does.
+    pub fn metadata(&self) -> std::io::Result<Metadata> {
+        if self.followed_link {
+            std::fs::metadata(&self.path)
+        } else {
+            std::fs::symlink_metadata(&self.path)
+        }
+    }
+}
+
+type EntryFilter = Box<dyn FnMut(&WalkEntry) -> bool>;
+
+/// A recursive directory iterator created by [`walk`].
+///
+/// Entries are yielded depth-first, starting with the root itself. Directory contents are
+/// yielded in the order returned by the operating system.
+pub struct Walk {
+    root: PathBuf,
+    max_depth: usize,
+    follow_symlinks: bool,
+    filter: Option<EntryFilter>,
+    started: bool,
+    /// Open directories being traversed, with the (device, inode) of each for loop detection.
+    stack: Vec<(ReadDir, (u64, u64))>,
+}
+
+impl std::fmt::Debug for Walk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Walk")
+            .field("root", &self.root)
+            .field("max_depth", &self.max_depth)
+            .field("follow_symlinks", &self.follow_symlinks)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Recursively walk a directory, like `find`.
+///
+/// The root traversal is echoed once when iteration starts. Use the builder methods on
+/// [`Walk`] to limit the depth, follow symbolic links, or prune entries.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+/// use std::time::{Duration, SystemTime};
+///
+/// // Find all *.log files older than 7 days
+/// let cutoff = SystemTime::now() - Duration::from_secs(7 * 24 * 60 * 60);
+/// for entry in fs::walk("/var/log/myapp").max_depth(3) {
+///     let entry = entry?;
+///     let is_log = entry.path().extension().is_some_and(|ext| ext == "log");
+///     if entry.is_file() && is_log && entry.metadata()?.modified()? < cutoff {
+///         println!("{}", entry.path().display());
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn walk(path: impl AsRef<Path>) -> Walk {
+    Walk {
+        root: path.as_ref().to_path_buf(),
+        max_depth: usize::MAX,
+        follow_symlinks: false,
+        filter: None,
+        started: false,
+        stack: Vec::new(),
+    }
+}
+
+impl Walk {
+    /// Do not descend more than `depth` levels below the root (the root has depth 0).
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Follow symbolic links to directories (default: `false`).
+    ///
+    /// Filesystem loops created by links are reported as errors instead of recursing forever.
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Skip entries for which `predicate` returns `false`.
+    ///
+    /// Unlike [`Iterator::filter`], a rejected directory is not descended into, which makes this
+    /// the efficient way to prune whole subtrees such as `.git` or `target`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let sources = fs::walk(".")
+    ///     .filter_entry(|e| e.file_name() != ".git" && e.file_name() != "target")
+    ///     .filter_map(|e| e.ok())
+    ///     .filter(|e| e.path().extension().is_some_and(|ext| ext == "rs"));
+    /// for entry in sources {
+    ///     println!("{}", entry.path().display());
+    /// }
+    /// ```
+    pub fn filter_entry(mut self, predicate: impl FnMut(&WalkEntry) -> bool + 'static) -> Self {
+        self.filter = Some(Box::new(predicate));
+        self
+    }
+
+    /// Build an entry for `path`, resolving symbolic links if configured.
+    fn entry(&self, path: PathBuf, depth: usize) -> std::io::Result<(WalkEntry, Metadata)> {
+        let metadata = std::fs::symlink_metadata(&path).map_err(context("walk", &[&path]))?;
+        let (metadata, followed_link) = if self.follow_symlinks && metadata.is_symlink() {
+            let target = std::fs::metadata(&path).map_err(context("walk", &[&path]))?;
+            (target, true)
+        } else {
+            (metadata, false)
+        };
+        let entry = WalkEntry {
+            path,
+            depth,
+            file_type: metadata.file_type(),
+            followed_link,
+        };
+        Ok((entry, metadata))
+    }
+
+    /// Apply the filter and descend into directories. Returns `None` if the entry is filtered out.
+    fn visit(
+        &mut self,
+        entry: WalkEntry,
+        metadata: Metadata,
+    ) -> Option<std::io::Result<WalkEntry>> {
+        if let Some(filter) = &mut self.filter {
+            if !filter(&entry) {
+                return None;
+            }
+        }
+
+        if entry.is_dir() && entry.depth < self.max_depth {
+            let id = (metadata.dev(), metadata.ino());
+            if self.stack.iter().any(|(_, ancestor)| *ancestor == id) {
+                return Some(Err(std::io::Error::other(format!(
+                    "filesystem loop detected at {}",
+                    entry.path.display()
+                ))));
+            }
+            match std::fs::read_dir(&entry.path) {
+                Ok(read_dir) => self.stack.push((read_dir, id)),
+                Err(e) => return Some(Err(context("walk", &[&entry.path])(e))),
+            }
+        }
+
+        Some(Ok(entry))
+    }
+}
+
+impl Iterator for Walk {
+    type Item = std::io::Result<WalkEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            echo_operation("walk", &self.root.display().to_string());
+            return match self.entry(self.root.clone(), 0) {
+                Ok((entry, metadata)) => self.visit(entry, metadata),
+                Err(e) => Some(Err(e)),
+            };
+        }
+
+        loop {
+            let depth = self.stack.len();
+            let (read_dir, _) = self.stack.last_mut()?;
+            let dir_entry = match read_dir.next() {
+                Some(Ok(dir_entry)) => dir_entry,
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+
+            match self.entry(dir_entry.path(), depth) {
+                Ok((entry, metadata)) => {
+                    if let Some(result) = self.visit(entry, metadata) {
+                        return Some(result);
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp_dir_scoped;
+
+    fn relative_paths(root: &Path, walk: Walk) -> Vec<String> {
+        let mut paths: Vec<String> = walk
+            .map(|e| {
+                e.unwrap()
+                    .path()
+                    .strip_prefix(root)
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn make_tree() -> crate::fs::TempDir {
+        let tmp = temp_dir_scoped().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::create_dir_all(root.join("skip/inner")).unwrap();
+        std::fs::write(root.join("top.log"), "").unwrap();
+        std::fs::write(root.join("a/one.log"), "").unwrap();
+        std::fs::write(root.join("a/b/two.txt"), "").unwrap();
+        std::fs::write(root.join("skip/inner/three.log"), "").unwrap();
+        tmp
+    }
+
+    #[test]
+    fn test_walk_all_entries() {
+        let tmp = make_tree();
+        let paths = relative_paths(tmp.path(), walk(tmp.path()));
+        assert_eq!(
+            paths,
+            vec![
+                "",
+                "a",
+                "a/b",
+                "a/b/two.txt",
+                "a/one.log",
+                "skip",
+                "skip/inner",
+                "skip/inner/three.log",
+                "top.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_max_depth() {
+        let tmp = make_tree();
+        let paths = relative_paths(tmp.path(), walk(tmp.path()).max_depth(1));
+        assert_eq!(paths, vec!["", "a", "skip", "top.log"]);
+
+        let depths: Vec<usize> = walk(tmp.path())
+            .max_depth(2)
+            .map(|e| e.unwrap().depth())
+            .collect();
+        assert_eq!(depths.iter().max(), Some(&2));
+    }
+
+    #[test]
+    fn test_walk_filter_entry_prunes() {
+        let tmp = make_tree();
+        let paths = relative_paths(
+            tmp.path(),
+            walk(tmp.path()).filter_entry(|e| e.file_name() != "skip"),
+        );
+        assert!(!paths.iter().any(|p| p.starts_with("skip")));
+        assert!(paths.contains(&"a/one.log".to_string()));
+    }
+
+    #[test]
+    fn test_walk_symlinks() {
+        let tmp = make_tree();
+        std::os::unix::fs::symlink(tmp.path().join("a"), tmp.path().join("link")).unwrap();
+
+        // Not followed by default
+        let paths = relative_paths(tmp.path(), walk(tmp.path()));
+        assert!(paths.contains(&"link".to_string()));
+        assert!(!paths.contains(&"link/one.log".to_string()));
+
+        let paths = relative_paths(tmp.path(), walk(tmp.path()).follow_symlinks(true));
+        assert!(paths.contains(&"link/one.log".to_string()));
+
+        // Loops are reported instead of recursing forever
+        std::os::unix::fs::symlink(tmp.path(), tmp.path().join("a/loop")).unwrap();
+        let errors = walk(tmp.path())
+            .follow_symlinks(true)
+            .filter(|e| e.is_err())
+            .count();
+        assert!(errors > 0);
+    }
+
+    #[test]
+    fn test_walk_missing_root() {
+        let mut walk = walk("/nonexistent/scripty/walk/root");
+        assert!(walk.next().unwrap().is_err());
+        assert!(walk.next().is_none());
+    }
+}
@@ -0,0 +1,175 @@
+//! Common `git` queries and operations, returning typed results instead of raw output.
+//!
+//! Every function runs `git` in the current directory (see [`cd`](crate::cd)) and is echoed like
+//! any other command. Failures, such as running outside a repository, are returned as
+//! [`Error`]s carrying git's stderr.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use scripty::*;
+//!
+//! if git::is_dirty()? {
+//!     return Err("commit your changes before releasing".into());
+//! }
+//! let branch = git::current_branch()?.ok_or("HEAD is detached")?;
+//! let version = format!("{branch}-{}", git::rev_parse_short()?);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{Error, cmd};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// The name of the checked out branch, or `None` if `HEAD` is detached.
+pub fn current_branch() -> Result<Option<String>, Error> {
+    let branch = cmd!("git", "branch", "--show-current").output()?;
+    let branch = branch.trim();
+    Ok((!branch.is_empty()).then(|| branch.to_string()))
+}
+
+/// Whether the working tree has uncommitted changes, including untracked files.
+pub fn is_dirty() -> Result<bool, Error> {
+    let status = cmd!("git", "status", "--porcelain").output()?;
+    Ok(!status.trim().is_empty())
+}
+
+/// The abbreviated hash of the `HEAD` commit, such as `3f2a9c1`.
+pub fn rev_parse_short() -> Result<String, Error> {
+    let hash = cmd!("git", "rev-parse", "--short", "HEAD").output()?;
+    Ok(hash.trim().to_string())
+}
+
+/// Clone the repository at `url` into `path`.
+///
+/// Set options on the returned [`GitClone`], then call [`run`](GitClone::run).
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// git::clone("https://github.com/h-zasu/scripty.git", "/tmp/scripty")
+///     .depth(1)
+///     .branch("main")
+///     .run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn clone(url: impl Into<OsString>, path: impl AsRef<Path>) -> GitClone {
+    GitClone {
+        url: url.into(),
+        path: path.as_ref().to_path_buf(),
+        depth: None,
+        branch: None,
+    }
+}
+
+/// A `git clone` configured by [`clone`].
+#[derive(Debug)]
+#[must_use = "nothing is cloned until `run()` is called"]
+pub struct GitClone {
+    url: OsString,
+    path: PathBuf,
+    depth: Option<u32>,
+    branch: Option<String>,
+}
+
+impl GitClone {
+    /// Fetch only the last `depth` commits.
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Check out `branch`, or a tag, instead of the remote's default branch.
+    pub fn branch(mut self, branch: impl Into<String>) -> Self {
+        self.branch = Some(branch.into());
+        self
+    }
+
+    /// Run `git clone`.
+    pub fn run(self) -> Result<(), Error> {
+        let mut git = cmd!("git", "clone");
+        if let Some(depth) = self.depth {
+            git = git.arg(format!("--depth={depth}"));
+        }
+        if let Some(branch) = self.branch {
+            git = git.arg(format!("--branch={branch}"));
+        }
+        git.arg("--").arg(self.url).arg(self.path).run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{self, MockExecutor, MockOutput};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_queries() {
+        let git = Arc::new(MockExecutor::new());
+        git.expect(
+            ["git", "branch", "--show-current"],
+            MockOutput::new().stdout("main\n"),
+        )
+        .expect(["git", "branch", "--show-current"], MockOutput::new())
+        .expect(
+            ["git", "status", "--porcelain"],
+            MockOutput::new().stdout("?? new.rs\n"),
+        )
+        .expect(["git", "status", "--porcelain"], MockOutput::new())
+        .expect(
+            ["git", "rev-parse", "--short", "HEAD"],
+            MockOutput::new().stdout("3f2a9c1\n"),
+        );
+        let _guard = mock::set_executor(git.clone());
+
+        assert_eq!(current_branch().unwrap(), Some("main".to_string()));
+        assert_eq!(current_branch().unwrap(), None);
+        assert!(is_dirty().unwrap());
+        assert!(!is_dirty().unwrap());
+        assert_eq!(rev_parse_short().unwrap(), "3f2a9c1");
+        git.verify();
+    }
+
+    #[test]
+    fn test_clone() {
+        let git = Arc::new(MockExecutor::new());
+        git.expect(
+            [
+                "git",
+                "clone",
+                "--depth=1",
+                "--branch=v1.0",
+                "--",
+                "https://example.com/repo.git",
+                "/tmp/repo",
+            ],
+            MockOutput::new(),
+        );
+        let _guard = mock::set_executor(git.clone());
+
+        clone("https://example.com/repo.git", "/tmp/repo")
+            .depth(1)
+            .branch("v1.0")
+            .run()
+            .unwrap();
+        git.verify();
+    }
+
+    #[test]
+    fn test_error_outside_repository() {
+        let git = Arc::new(MockExecutor::new());
+        git.expect(
+            ["git", "status", "--porcelain"],
+            MockOutput::new()
+                .stderr("fatal: not a git repository\n")
+                .exit_code(128),
+        );
+        let _guard = mock::set_executor(git.clone());
+
+        let error = is_dirty().unwrap_err();
+        assert_eq!(error.stderr(), Some("fatal: not a git repository"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Downloading over HTTP(S), in place of `curl` or `wget`.
+//!
+//! Requests are made in-process with [`ureq`](https://docs.rs/ureq), using rustls for HTTPS, so
+//! scripts work the same whether or not `curl` or `wget` is installed. Redirects are followed, and
+//! a response status of 400 or above is an error. Each request is echoed like commands and file
+//! system operations. Requires the `http` feature.
+
+use crate::style::{BOLD_CYAN, BOLD_UNDERLINE};
+use std::io::Read;
+use std::path::Path;
+
+fn echo_operation(op: &str, details: &str) {
+    crate::echo::emit("  ", "http", |styled| {
+        if styled {
+            format!("{BOLD_CYAN}{op}{BOLD_CYAN:#} {BOLD_UNDERLINE}{details}{BOLD_UNDERLINE:#}")
+        } else {
+            format!("{op} {details}")
+        }
+    });
+}
+
+/// Add the operation and URL to an error, keeping its kind.
+fn context(operation: &str, url: &str) -> impl FnOnce(std::io::Error) -> std::io::Error {
+    move |e| std::io::Error::new(e.kind(), format!("Failed to {operation} {url}: {e}"))
+}
+
+/// Send a GET request and return a reader over the response body.
+fn get(url: &str) -> std::io::Result<impl Read + use<>> {
+    let response = ureq::get(url).call().map_err(ureq::Error::into_io)?;
+    Ok(response.into_body().into_reader())
+}
+
+/// Download `url` to a file, returning the number of bytes written.
+///
+/// The body is streamed to disk, so large files are not held in memory. If the download fails
+/// part way, the incomplete file is removed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "http")]
+/// # {
+/// use scripty::*;
+///
+/// http::download("https://example.com/tool.tar.gz", "tool.tar.gz")?;
+/// cmd!("tar", "xzf", "tool.tar.gz").run()?;
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn download(url: &str, path: impl AsRef<Path>) -> std::io::Result<u64> {
+    let path = path.as_ref();
+    echo_operation("download", &format!("{url} -> {}", path.display()));
+
+    let mut body = get(url).map_err(context("download", url))?;
+    let mut file = std::fs::File::create(path).map_err(context("download", url))?;
+    let result = std::io::copy(&mut body, &mut file).map_err(context("download", url));
+    if result.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+/// Fetch `url` and return the response body as a string.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "http")]
+/// # {
+/// use scripty::*;
+///
+/// let version = http::get_string("https://example.com/latest-version")?;
+/// println!("Latest version: {}", version.trim());
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn get_string(url: &str) -> std::io::Result<String> {
+    echo_operation("get", url);
+    let mut body = String::new();
+    get(url)
+        .and_then(|mut reader| reader.read_to_string(&mut body))
+        .map_err(context("get", url))?;
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp_dir_scoped;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    /// Serve one connection with the given status and body, returning the URL to request.
+    fn serve_once(status: &'static str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file.txt", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn test_get_string() {
+        let url = serve_once("200 OK", "1.2.3\n");
+        assert_eq!(get_string(&url).unwrap(), "1.2.3\n");
+    }
+
+    #[test]
+    fn test_download() {
+        let tmp = temp_dir_scoped().unwrap();
+        let path = tmp.path().join("file.txt");
+        let url = serve_once("200 OK", "contents");
+        assert_eq!(download(&url, &path).unwrap(), 8);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "contents");
+    }
+
+    #[test]
+    fn test_error_status() {
+        let url = serve_once("404 Not Found", "missing");
+        let error = get_string(&url).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with(&format!("Failed to get {url}: "))
+        );
+        assert!(error.to_string().contains("404"));
+    }
+}
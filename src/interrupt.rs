@@ -0,0 +1,176 @@
+//! Running cleanup code when the script is interrupted with Ctrl-C.
+//!
+//! [`on_interrupt`] installs a `SIGINT` handler that only writes to a self-pipe. A dedicated
+//! thread reads it and runs the registered handlers, so they are ordinary Rust code rather than
+//! signal handlers. The script is not terminated: the interrupted command fails with an error, so
+//! the script unwinds through its normal error handling and drops its temporary directories.
+//!
+//! Commands in the foreground receive Ctrl-C from the terminal themselves. A `SIGINT` sent to the
+//! script alone, with `kill -INT`, is forwarded to the commands it is running.
+
+use crate::cmd::reaper;
+use std::os::fd::RawFd;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Mutex, Once};
+
+type Handler = Box<dyn Fn() + Send + Sync>;
+
+static HANDLERS: Mutex<Vec<Handler>> = Mutex::new(Vec::new());
+
+/// Commands spawned since the handler was installed, by process ID.
+static RUNNING: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Write end of the self-pipe, for the signal handler, or -1 before [`on_interrupt`].
+static WAKE_FD: AtomicI32 = AtomicI32::new(-1);
+
+static INSTALL: Once = Once::new();
+
+/// Run `handler` when the script receives `SIGINT`, such as when the user presses Ctrl-C,
+/// instead of being killed.
+///
+/// Handlers run in the order they were added, on a background thread. Call
+/// [`std::process::exit`] from one to stop the script right away; otherwise the interrupted
+/// command fails and the script continues with its error handling.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// on_interrupt(|| eprintln!("interrupted, cleaning up"));
+///
+/// let build = fs::temp_dir_scoped()?;
+/// // Ctrl-C stops `make`; the error is returned and `build` is removed when dropped
+/// cmd!("make", "-C", build.path()).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn on_interrupt(handler: impl Fn() + Send + Sync + 'static) {
+    HANDLERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Box::new(handler));
+    INSTALL.call_once(install);
+}
+
+/// Remember a spawned command, to forward `SIGINT` to it while it runs.
+pub(crate) fn track(pid: u32) {
+    if WAKE_FD.load(Ordering::Relaxed) < 0 {
+        return;
+    }
+    let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    running.retain(|&pid| !reaper::has_exited(pid as libc::pid_t));
+    running.push(pid);
+}
+
+fn install() {
+    let mut fds = [-1; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        eprintln!(
+            "Warning: Failed to install interrupt handler: {}",
+            std::io::Error::last_os_error()
+        );
+        return;
+    }
+    for fd in fds {
+        unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+    }
+    unsafe { libc::fcntl(fds[1], libc::F_SETFL, libc::O_NONBLOCK) };
+    let [read_fd, wake_fd] = fds;
+
+    let spawned = std::thread::Builder::new()
+        .name("scripty-interrupt".to_string())
+        .spawn(move || run(read_fd));
+    if let Err(e) = spawned {
+        eprintln!("Warning: Failed to start interrupt thread: {}", e);
+        return;
+    }
+    WAKE_FD.store(wake_fd, Ordering::Relaxed);
+
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    action.sa_sigaction = on_sigint as *const () as usize;
+    action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+    unsafe {
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut());
+    }
+}
+
+fn run(read_fd: RawFd) {
+    loop {
+        let mut forward = 0u8;
+        match unsafe { libc::read(read_fd, (&raw mut forward).cast(), 1) } {
+            1 => interrupted(forward == 1),
+            -1 if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted => {}
+            _ => return,
+        }
+    }
+}
+
+/// Forward the interrupt to the running commands if asked, then run the handlers.
+fn interrupted(forward: bool) {
+    if forward {
+        let running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+        forward_to(&running);
+    }
+    for handler in HANDLERS.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+        handler();
+    }
+}
+
+/// Send `SIGINT` to the processes in `pids` that are still running.
+fn forward_to(pids: &[u32]) {
+    for &pid in pids {
+        let pid = pid as libc::pid_t;
+        // Reaped processes are skipped, so a reused process ID is never signalled
+        if !reaper::has_exited(pid) {
+            unsafe { libc::kill(pid, libc::SIGINT) };
+        }
+    }
+}
+
+extern "C" fn on_sigint(_: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+    let saved_errno = reaper::errno();
+    // The terminal sends Ctrl-C to the whole foreground process group, with a positive
+    // `si_code`; only a signal sent to this process alone needs forwarding
+    let forward = u8::from(unsafe { (*info).si_code } <= 0);
+    unsafe {
+        libc::write(
+            WAKE_FD.load(Ordering::Relaxed),
+            (&raw const forward).cast(),
+            1,
+        );
+        *reaper::errno_location() = saved_errno;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_handlers_run_in_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        for i in 0..2 {
+            let calls = Arc::clone(&calls);
+            on_interrupt(move || calls.lock().unwrap().push(i));
+        }
+        interrupted(false);
+        let calls = calls.lock().unwrap();
+        assert!(calls.windows(2).any(|pair| pair == [0, 1]));
+    }
+
+    #[test]
+    fn test_forward_to_running() {
+        let mut sleep = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+        let mut done = std::process::Command::new("true").spawn().unwrap();
+        done.wait().unwrap();
+
+        forward_to(&[sleep.id(), done.id()]);
+        assert_eq!(sleep.wait().unwrap().signal(), Some(libc::SIGINT));
+    }
+}
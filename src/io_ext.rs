@@ -1,7 +1,10 @@
 //! Extension traits for standard library I/O types to enable fluent piping.
 
-use crate::cmd::{Cmd, CmdInput, Pipeline};
-use std::io::Read;
+use crate::cmd::{Cmd, CmdInput, Error, PipeMode, Pipeline};
+use std::any::Any;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 
 /// Extension trait for `std::io::Read` to enable fluent piping to commands.
 ///
@@ -76,15 +79,198 @@ pub trait ReadExt: Read {
     where
         Self: Sized + Send + 'static,
     {
-        let mut pipeline = cmd.into_pipeline();
-        pipeline.input = Some(CmdInput::Reader(Box::new(self)));
-        pipeline
+        pipe_reader(self, cmd, PipeMode::Stdout)
+    }
+
+    /// Pipe this reader's data to a command's stdin, treating the reader as an error stream.
+    ///
+    /// This is the reader counterpart of [`Cmd::pipe_err`], for readers that carry another
+    /// process's stderr (such as a `ChildStderr` from `spawn_io_err()`). The data is fed to the
+    /// command's stdin exactly like [`pipe`](Self::pipe); the resulting pipeline can then branch
+    /// on the command's own stderr with [`Pipeline::pipe_err`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// let (handle, stderr) = cmd!("cargo", "build").spawn_io_err()?;
+    /// if let Some(stderr) = stderr {
+    ///     // Count warnings, and keep the lines the filter itself complains about
+    ///     stderr
+    ///         .pipe_err(cmd!("grep", "-c", "warning"))
+    ///         .pipe_err(cmd!("tee", "grep-errors.log"))
+    ///         .run()?;
+    /// }
+    /// handle.wait()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn pipe_err(self, cmd: Cmd) -> Pipeline
+    where
+        Self: Sized + Send + 'static,
+    {
+        pipe_reader(self, cmd, PipeMode::Stderr)
+    }
+
+    /// Pipe this reader's data to a command's stdin, treating the reader as a combined
+    /// stdout and stderr stream.
+    ///
+    /// This is the reader counterpart of [`Cmd::pipe_out_err`]; the data is fed to the command's
+    /// stdin exactly like [`pipe`](Self::pipe).
+    fn pipe_out_err(self, cmd: Cmd) -> Pipeline
+    where
+        Self: Sized + Send + 'static,
+    {
+        pipe_reader(self, cmd, PipeMode::Both)
+    }
+}
+
+/// Extension trait for `std::io::Write` to make a writer the destination of a command.
+///
+/// This mirrors [`ReadExt`]: where a reader starts a pipeline, a writer ends one. It is
+/// equivalent to [`Pipeline::pipe_to`] called the other way around.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+/// use std::fs::File;
+///
+/// let log = File::create("build.log")?;
+/// log.pipe_from(cmd!("cargo", "build").pipe_out_err(cmd!("cat")))?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait WriteExt: Write {
+    /// Write the stdout of a command or pipeline to this writer.
+    ///
+    /// The writer appears in the echo as a `>` redirection; see [`Pipeline::pipe_to`].
+    fn pipe_from(self, source: impl Into<Pipeline>) -> Result<(), Error>
+    where
+        Self: Sized + 'static,
+    {
+        source.into().pipe_to(self)
     }
 }
 
+// Implement WriteExt for all types that implement Write
+impl<W: Write> WriteExt for W {}
+
+/// Describe a writer for display as a redirection target in the command echo.
+pub(crate) fn describe_writer<W: Write + 'static>(writer: &W) -> String {
+    let writer: &dyn Any = writer;
+    let file = writer.downcast_ref::<File>().or_else(|| {
+        writer
+            .downcast_ref::<BufWriter<File>>()
+            .map(BufWriter::get_ref)
+    });
+    if let Some(file) = file {
+        return match file_path(file) {
+            Some(path) => Cmd::quote_argument(path.as_os_str()),
+            None => "<file>".to_string(),
+        };
+    }
+    if writer.is::<std::io::Stdout>() {
+        return "/dev/stdout".to_string();
+    }
+    if writer.is::<std::io::Stderr>() {
+        return "/dev/stderr".to_string();
+    }
+
+    let name = std::any::type_name::<W>();
+    // Keep the type itself, without its module path or generic parameters
+    let name = name.split('<').next().unwrap_or(name);
+    let name = name.rsplit("::").next().unwrap_or(name);
+    format!("<{}>", name)
+}
+
+/// The path an open file refers to, if the platform can tell.
+#[cfg(target_os = "linux")]
+fn file_path(file: &File) -> Option<PathBuf> {
+    use std::os::fd::AsRawFd;
+    std::fs::read_link(format!("/proc/self/fd/{}", file.as_raw_fd())).ok()
+}
+
+/// The path an open file refers to, if the platform can tell.
+#[cfg(target_os = "macos")]
+fn file_path(file: &File) -> Option<PathBuf> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut buf = vec![0u8; libc::PATH_MAX as usize];
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETPATH, buf.as_mut_ptr()) } == -1 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0)?;
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(&buf[..len])))
+}
+
+/// The path an open file refers to, if the platform can tell.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn file_path(_file: &File) -> Option<PathBuf> {
+    None
+}
+
+fn pipe_reader<R: Read + Send + 'static>(reader: R, cmd: Cmd, mode: PipeMode) -> Pipeline {
+    let mut pipeline = cmd.into_pipeline();
+    pipeline.connections[0].1 = mode;
+    // Files are kept as such, so they can be copied to the command without a userspace buffer
+    let mut reader = Some(reader);
+    let input = match (&mut reader as &mut dyn Any).downcast_mut::<Option<File>>() {
+        Some(file) => file.take().map(CmdInput::File),
+        None => reader.map(|reader| CmdInput::Reader(Box::new(reader))),
+    };
+    pipeline.input = input;
+    pipeline
+}
+
 // Implement ReadExt for all types that implement Read
 impl<R: Read> ReadExt for R {}
 
+/// Open a file to feed to a command's stdin, like `command < file` in a shell.
+///
+/// The file is opened immediately, so a missing file is reported here rather than when the
+/// pipeline runs. With the `mmap` feature it is memory-mapped, as with
+/// [`Pipeline::input_mmap`]. The file is shown in the echo as a `<` redirection.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// // Echoed as: grep ERROR < app.log | sort | uniq -c > errors.txt
+/// cat("app.log")?
+///     .pipe(cmd!("grep", "ERROR"))
+///     .pipe(cmd!("sort"))
+///     .pipe(cmd!("uniq", "-c"))
+///     .to_file("errors.txt")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn cat(path: impl AsRef<Path>) -> std::io::Result<FileSource> {
+    let path = path.as_ref();
+    let input = CmdInput::from_file(path).map_err(crate::fs::context("open", &[path]))?;
+    Ok(FileSource {
+        path: path.to_path_buf(),
+        input,
+    })
+}
+
+/// A file opened by [`cat`], to be piped into a command.
+#[derive(Debug)]
+pub struct FileSource {
+    path: PathBuf,
+    input: CmdInput,
+}
+
+impl FileSource {
+    /// Feed the file to a command's stdin.
+    pub fn pipe(self, cmd: Cmd) -> Pipeline {
+        let mut pipeline = cmd.into_pipeline();
+        pipeline.input = Some(self.input);
+        pipeline.input_path = Some(self.path);
+        pipeline
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,4 +309,68 @@ mod tests {
         assert_eq!(result.trim(), "6");
         Ok(())
     }
+    #[test]
+    fn test_read_ext_pipe_err_mode() {
+        let pipeline = Cursor::new(b"data").pipe_err(cmd!("cat"));
+        assert_eq!(pipeline.connections[0].1, PipeMode::Stderr);
+
+        let pipeline = Cursor::new(b"data").pipe_out_err(cmd!("cat"));
+        assert_eq!(pipeline.connections[0].1, PipeMode::Both);
+    }
+
+    #[test]
+    fn test_read_ext_pipe_err_branches_on_stderr() -> Result<(), Box<dyn std::error::Error>> {
+        let data = Cursor::new(b"error: one\nerror: two\n");
+        let result = data
+            .pipe_err(cmd!("sh", "-c", "cat >&2; echo ignored"))
+            .pipe_err(cmd!("wc", "-l"))
+            .output()?;
+        assert_eq!(result.trim(), "2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_ext_pipe_out_err() -> Result<(), Box<dyn std::error::Error>> {
+        let data = Cursor::new(b"line\n");
+        let result = data
+            .pipe_out_err(cmd!("sh", "-c", "cat; echo err >&2"))
+            .pipe_out_err(cmd!("sort"))
+            .output()?;
+        assert_eq!(result, "err\nline\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_pipe_to_file() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = crate::fs::temp_file_scoped()?;
+        cmd!("printf", "b\\na\\n")
+            .pipe(cmd!("sort"))
+            .no_echo()
+            .pipe_to(File::create(tmp.path())?)?;
+        assert_eq!(std::fs::read_to_string(tmp.path())?, "a\nb\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_ext_pipe_from() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = crate::fs::temp_file_scoped()?;
+        let file = BufWriter::new(File::create(tmp.path())?);
+        file.pipe_from(cmd!("echo", "hello").no_echo())?;
+        assert_eq!(std::fs::read_to_string(tmp.path())?, "hello\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_writer() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = crate::fs::temp_file_scoped()?;
+        let file = File::create(tmp.path())?;
+        let expected = Cmd::quote_argument(std::fs::canonicalize(tmp.path())?.as_os_str());
+        assert_eq!(describe_writer(&file), expected);
+        assert_eq!(describe_writer(&BufWriter::new(file)), expected);
+
+        assert_eq!(describe_writer(&std::io::stdout()), "/dev/stdout");
+        assert_eq!(describe_writer(&Vec::<u8>::new()), "<Vec>");
+        assert_eq!(describe_writer(&Cursor::new(Vec::<u8>::new())), "<Cursor>");
+        Ok(())
+    }
 }
@@ -0,0 +1,314 @@
+//! Running several commands in the background under names, like `foreman` or
+//! `docker compose up`.
+//!
+//! A [`Jobs`] registry starts pipelines as named jobs, and can list, wait for, and kill them.
+//! Each line a job writes to stdout or stderr is forwarded to the same stream with the job's
+//! name in front, so the output of several services can be told apart:
+//!
+//! ```text
+//! web    | Serving HTTP on 0.0.0.0 port 8000
+//! assets | Rebuilding...
+//! ```
+//!
+//! Jobs read from `/dev/null` unless they are given input or
+//! [`stdin_inherit`](crate::Cmd::stdin_inherit), so they do not compete for the terminal. Jobs
+//! still running when the registry is dropped are killed.
+
+use crate::cmd::{DropPolicy, Error, Pipeline, PipelineHandle, StdioConfig};
+use crate::lines::LineStream;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Start a registry with a single job. More can be added with [`Jobs::spawn`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let mut services = jobs::spawn("web", cmd!("python3", "-m", "http.server"))?;
+/// services.spawn("assets", cmd!("npx", "esbuild", "src/app.ts", "--watch"))?;
+///
+/// // Stop everything as soon as one service exits
+/// if let Some((name, result)) = services.wait_any() {
+///     echo::error(format!("{name} exited: {result:?}"));
+/// }
+/// services.kill_all()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn spawn(name: &str, pipeline: impl Into<Pipeline>) -> Result<Jobs, Error> {
+    let mut jobs = Jobs::new();
+    jobs.spawn(name, pipeline)?;
+    Ok(jobs)
+}
+
+/// Named background jobs. See the [module documentation](self).
+pub struct Jobs {
+    jobs: Vec<Job>,
+    next_id: u64,
+    /// Length of the longest job name, which the prefixes are padded to.
+    width: Arc<AtomicUsize>,
+    output: Output,
+    exit_sender: Sender<u64>,
+    exit_receiver: Receiver<u64>,
+}
+
+struct Job {
+    id: u64,
+    name: String,
+    handle: PipelineHandle,
+    /// Threads forwarding the job's stdout and stderr.
+    forwarders: Vec<JoinHandle<()>>,
+}
+
+/// Where the prefixed output of jobs is written.
+#[derive(Clone)]
+enum Output {
+    /// Job stdout to stdout, and job stderr to stderr.
+    Std,
+    /// Both streams to one writer.
+    Writer(Arc<Mutex<dyn Write + Send>>),
+}
+
+impl Jobs {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        let (exit_sender, exit_receiver) = std::sync::mpsc::channel();
+        Self {
+            jobs: Vec::new(),
+            next_id: 0,
+            width: Arc::new(AtomicUsize::new(0)),
+            output: Output::Std,
+            exit_sender,
+            exit_receiver,
+        }
+    }
+
+    /// Write the output of jobs spawned from now on to `writer` instead of stdout and stderr.
+    ///
+    /// Lines from stdout and stderr are interleaved in the order they arrive.
+    pub fn log_to(&mut self, writer: impl Write + Send + 'static) {
+        self.output = Output::Writer(Arc::new(Mutex::new(writer)));
+    }
+
+    /// Start a pipeline in the background as a job called `name`.
+    ///
+    /// It is an error if a job with the same name has not been waited for yet.
+    pub fn spawn(&mut self, name: &str, pipeline: impl Into<Pipeline>) -> Result<(), Error> {
+        if self.names().any(|job| job == name) {
+            return Err(Error::io(
+                "Job already exists",
+                std::io::Error::new(std::io::ErrorKind::AlreadyExists, name),
+            ));
+        }
+
+        let mut pipeline = pipeline.into();
+        if pipeline.input.is_none() {
+            if let Some((first, _)) = pipeline.connections.first_mut() {
+                if first.stdin == StdioConfig::Piped {
+                    first.stdin = StdioConfig::Null;
+                }
+            }
+        }
+        let (mut handle, stdout, stderr) = pipeline.spawn_io_out_err()?;
+        handle.set_drop_policy(DropPolicy::Kill);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        handle.notify_on_exit(self.exit_sender.clone(), id);
+        self.width.fetch_max(name.len(), Ordering::Relaxed);
+
+        let mut forwarders = Vec::new();
+        if let Some(stdout) = stdout {
+            forwarders.push(self.forward(name, stdout, false));
+        }
+        if let Some(stderr) = stderr {
+            forwarders.push(self.forward(name, stderr, true));
+        }
+        self.jobs.push(Job {
+            id,
+            name: name.to_string(),
+            handle,
+            forwarders,
+        });
+        Ok(())
+    }
+
+    /// Forward the lines of `stream` on a new thread, prefixed with the job name.
+    fn forward(
+        &self,
+        name: &str,
+        stream: impl Read + Send + 'static,
+        is_stderr: bool,
+    ) -> JoinHandle<()> {
+        let name = name.to_string();
+        let width = Arc::clone(&self.width);
+        let output = self.output.clone();
+        std::thread::spawn(move || {
+            let mut lines = LineStream::new(stream);
+            while let Ok(Some(line)) = lines.next_line() {
+                let width = width.load(Ordering::Relaxed);
+                let line = format!("{name:<width$} | {}\n", String::from_utf8_lossy(line));
+                // Write whole lines, so lines from different jobs are never mixed up
+                let _ = match &output {
+                    Output::Std if is_stderr => std::io::stderr().lock().write_all(line.as_bytes()),
+                    Output::Std => std::io::stdout().lock().write_all(line.as_bytes()),
+                    Output::Writer(writer) => writer
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .write_all(line.as_bytes()),
+                };
+            }
+        })
+    }
+
+    /// The names of the jobs that have not been waited for, in the order they were spawned.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.jobs.iter().map(|job| job.name.as_str())
+    }
+
+    /// Wait for the job called `name` to exit and remove it from the registry.
+    pub fn wait(&mut self, name: &str) -> Result<(), Error> {
+        let index = self.position(name)?;
+        self.jobs.remove(index).wait()
+    }
+
+    /// Wait for whichever job exits first and remove it from the registry, returning its name
+    /// and result.
+    ///
+    /// Returns `None` if there are no jobs left.
+    pub fn wait_any(&mut self) -> Option<(String, Result<(), Error>)> {
+        while !self.jobs.is_empty() {
+            // The registry holds a sender, so the channel is never closed
+            let id = self.exit_receiver.recv().ok()?;
+            // Jobs already waited for by name are no longer in the registry
+            if let Some(index) = self.jobs.iter().position(|job| job.id == id) {
+                let job = self.jobs.remove(index);
+                let name = job.name.clone();
+                return Some((name, job.wait()));
+            }
+        }
+        None
+    }
+
+    /// Wait for all jobs to exit, returning the first failure.
+    pub fn wait_all(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        for job in self.jobs.drain(..) {
+            let job_result = job.wait();
+            if result.is_ok() {
+                result = job_result;
+            }
+        }
+        result
+    }
+
+    /// Kill the job called `name` with `SIGKILL`.
+    ///
+    /// The job stays in the registry until it is waited for.
+    pub fn kill(&mut self, name: &str) -> Result<(), Error> {
+        let index = self.position(name)?;
+        self.jobs[index].handle.kill()
+    }
+
+    /// Kill all jobs with `SIGKILL`.
+    pub fn kill_all(&mut self) -> Result<(), Error> {
+        for job in &mut self.jobs {
+            job.handle.kill()?;
+        }
+        Ok(())
+    }
+
+    fn position(&self, name: &str) -> Result<usize, Error> {
+        self.names().position(|job| job == name).ok_or_else(|| {
+            Error::io(
+                "No such job",
+                std::io::Error::new(std::io::ErrorKind::NotFound, name),
+            )
+        })
+    }
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Job {
+    fn wait(self) -> Result<(), Error> {
+        let result = self.handle.wait();
+        // Forward the rest of the output before returning
+        for forwarder in self.forwarders {
+            let _ = forwarder.join();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+
+    /// A writer that can be inspected while jobs write to it.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_output_prefixes() {
+        let buffer = SharedBuffer::default();
+        let mut jobs = Jobs::new();
+        jobs.log_to(buffer.clone());
+        jobs.spawn("web", cmd!("echo", "hello").no_echo()).unwrap();
+        jobs.spawn("db", cmd!("sh", "-c", "echo out; echo err >&2").no_echo())
+            .unwrap();
+        jobs.wait_all().unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let mut lines: Vec<&str> = output.lines().collect();
+        lines.sort();
+        assert_eq!(lines, ["db  | err", "db  | out", "web | hello"]);
+    }
+
+    #[test]
+    fn test_wait_any_and_kill() {
+        let mut jobs = spawn("slow", cmd!("sleep", "10").no_echo()).unwrap();
+        jobs.spawn("fast", cmd!("true").no_echo()).unwrap();
+        assert_eq!(jobs.names().collect::<Vec<_>>(), ["slow", "fast"]);
+
+        let (name, result) = jobs.wait_any().unwrap();
+        assert_eq!(name, "fast");
+        assert!(result.is_ok());
+
+        jobs.kill("slow").unwrap();
+        assert!(jobs.wait_any().unwrap().1.is_err());
+        assert!(jobs.wait_any().is_none());
+    }
+
+    #[test]
+    fn test_job_names() {
+        let mut jobs = spawn("web", cmd!("true").no_echo()).unwrap();
+        let error = jobs.spawn("web", cmd!("true").no_echo()).unwrap_err();
+        assert!(error.to_string().starts_with("Job already exists"));
+        assert!(jobs.wait("db").is_err());
+
+        jobs.wait("web").unwrap();
+        // The name can be used again once the job has been waited for
+        jobs.spawn("web", cmd!("true").no_echo()).unwrap();
+        jobs.wait("web").unwrap();
+    }
+}
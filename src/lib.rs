@@ -55,6 +55,8 @@
 //! - Validate and sanitize all inputs before use
 //! - Be aware of command injection risks when constructing command arguments dynamically
 //! - Consider using allowlists for command names and arguments when dealing with user input
+//! - Wrap untrusted values in `Tainted` to have debug builds report them when they are formatted
+//!   into a `sh -c` script (see the `taint` module)
 //!
 //! Example of unsafe usage:
 //! ```no_run
@@ -406,6 +408,11 @@
 //! let input_data = Cursor::new("test data\nmore data");
 //! let log_file = File::create("process.log")?;
 //! cmd!("complex-tool").run_with_both_io(input_data, log_file)?;
+//!
+//! // Keep stdout and stderr apart
+//! let input_data = Cursor::new("test data\nmore data");
+//! let mut results = Vec::new();
+//! cmd!("complex-tool").run_with_io_full(input_data, &mut results, File::create("errors.log")?)?;
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 //!
@@ -472,12 +479,30 @@
 //! Control scripty's behavior with environment variables:
 //!
 //! - `NO_ECHO`: Set to any value to suppress command echoing globally
+//! - `NO_COLOR`: Set to a non-empty value to disable colors in the echo (colors are also disabled
+//!   when the echo is not written to a terminal; override with `color::set_choice()`)
+//! - `SCRIPTY_VERBOSE`: Set to any value to include extra details (such as resource limits) in the
+//!   command echo, and to show long arguments in full instead of truncating them to the terminal
+//!   width
+//! - `SCRIPTY_ASSUME_YES`: Set to a non-empty value other than `0` to answer yes to
+//!   `prompt::confirm()` when stdin is not a terminal
 //!
 //! ```bash
 //! NO_ECHO=1 cargo run  # Run without command echoing
 //! ```
 //!
-//! Or use the `.no_echo()` method on individual commands.
+//! Or use the `.no_echo()` method on individual commands. The echo prefix, colors, and output
+//! stream can be changed with `echo::set_style()`:
+//!
+//! ```rust
+//! use scripty::echo::{self, EchoStyle, EchoTarget};
+//!
+//! echo::set_style(EchoStyle {
+//!     prefix: "[deploy]".to_string(),
+//!     colors: false,
+//!     target: EchoTarget::Stdout,
+//! });
+//! ```
 //!
 //! ## Examples
 //!
@@ -667,13 +692,70 @@ pub use cmd::*;
 
 pub mod fs;
 
+pub mod cgroup;
+
+pub mod git;
+
+pub mod jobs;
+
+mod cwd;
+pub use cwd::{DirGuard, cd, with_dir};
+
+mod interrupt;
+pub use interrupt::on_interrupt;
+
 mod io_ext;
-pub use io_ext::ReadExt;
+pub use io_ext::{FileSource, ReadExt, WriteExt, cat};
+
+mod lines;
+pub use lines::LineStream;
 
 mod output;
+pub use output::{Verbosity, set_verbosity, verbosity};
+
+mod pager;
+pub use pager::page_output;
+
+pub mod prompt;
+
+mod schedule;
+pub use schedule::{CancelToken, Every, WaitFor, every, wait_for};
+
+mod session;
+pub use session::{Session, session};
+
+mod shell;
+pub use shell::{shell_join, shell_quote};
+
+pub mod sink;
+
+#[cfg(feature = "http")]
+pub mod http;
+
+#[cfg(feature = "archive")]
+pub mod archive;
+
+#[cfg(feature = "docker")]
+pub mod docker;
+
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "watch")]
+pub use watch::{Watch, WatchEvent, watch};
 
 pub mod color;
+pub mod echo;
 mod style;
 
+mod diff;
+pub use diff::{DiffLine, LineDiff};
+
+pub mod mock;
+pub mod testing;
+pub mod trace;
+
+pub mod taint;
+pub use taint::Tainted;
+
 /// Result type with a boxed error for convenience
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
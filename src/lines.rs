@@ -0,0 +1,221 @@
+//! Streaming line splitter with buffer reuse.
+
+use std::io::{BufRead, BufReader, Read};
+
+/// Splits a reader into lines while reusing a single buffer.
+///
+/// Unlike [`BufRead::lines`], `LineStream` does not allocate a new `String` per line when used
+/// through [`next_line`](Self::next_line), supports a custom delimiter, and can reject lines
+/// longer than a configured maximum instead of buffering unbounded input.
+///
+/// With the default `\n` delimiter, a trailing `\r` is also stripped (like [`BufRead::lines`]).
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let (handle, stdout) = cmd!("journalctl", "-f").spawn_io_out()?;
+/// let mut lines = LineStream::new(stdout.unwrap()).max_line_length(64 * 1024);
+/// while let Some(line) = lines.next_line()? {
+///     if line.starts_with(b"ERROR") {
+///         println!("{}", String::from_utf8_lossy(line));
+///     }
+/// }
+/// handle.wait()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct LineStream<R> {
+    reader: BufReader<R>,
+    buf: Vec<u8>,
+    delimiter: u8,
+    max_line_length: Option<usize>,
+}
+
+impl<R: Read> LineStream<R> {
+    /// Create a line stream splitting on `\n` with no line length limit.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            buf: Vec::new(),
+            delimiter: b'\n',
+            max_line_length: None,
+        }
+    }
+
+    /// Split on `delimiter` instead of `\n` (for example `b'\0'` for `find -print0` output).
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Fail with [`std::io::ErrorKind::InvalidData`] when a line exceeds `max` bytes.
+    ///
+    /// The rest of the offending line is discarded without being buffered, so reading can
+    /// continue with the next line after the error.
+    pub fn max_line_length(mut self, max: usize) -> Self {
+        self.max_line_length = Some(max);
+        self
+    }
+
+    /// Read the next line, without its delimiter, into the internal buffer.
+    ///
+    /// Returns `Ok(None)` at end of input. The returned slice is only valid until the next call.
+    pub fn next_line(&mut self) -> std::io::Result<Option<&[u8]>> {
+        self.buf.clear();
+        let mut read_any = false;
+
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                break;
+            }
+            read_any = true;
+
+            let (chunk, found) = match available.iter().position(|&b| b == self.delimiter) {
+                Some(pos) => (&available[..pos], true),
+                None => (available, false),
+            };
+
+            if let Some(max) = self.max_line_length {
+                if self.buf.len() + chunk.len() > max {
+                    let consumed = chunk.len() + usize::from(found);
+                    self.reader.consume(consumed);
+                    if !found {
+                        self.skip_line()?;
+                    }
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("line exceeds maximum length of {} bytes", max),
+                    ));
+                }
+            }
+
+            self.buf.extend_from_slice(chunk);
+            let consumed = chunk.len() + usize::from(found);
+            self.reader.consume(consumed);
+            if found {
+                break;
+            }
+        }
+
+        if !read_any {
+            return Ok(None);
+        }
+        if self.delimiter == b'\n' && self.buf.last() == Some(&b'\r') {
+            self.buf.pop();
+        }
+        Ok(Some(&self.buf))
+    }
+
+    /// Read the next line as UTF-8 text.
+    ///
+    /// Invalid UTF-8 is reported as an [`std::io::ErrorKind::InvalidData`] error.
+    pub fn next_str(&mut self) -> std::io::Result<Option<&str>> {
+        match self.next_line()? {
+            Some(line) => std::str::from_utf8(line)
+                .map(Some)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+            None => Ok(None),
+        }
+    }
+
+    /// Discard input up to and including the next delimiter.
+    fn skip_line(&mut self) -> std::io::Result<()> {
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                return Ok(());
+            }
+            match available.iter().position(|&b| b == self.delimiter) {
+                Some(pos) => {
+                    self.reader.consume(pos + 1);
+                    return Ok(());
+                }
+                None => {
+                    let len = available.len();
+                    self.reader.consume(len);
+                }
+            }
+        }
+    }
+
+    /// Consume the stream and return the underlying reader.
+    ///
+    /// Any data buffered but not yet returned as a line is lost.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+impl<R: Read> Iterator for LineStream<R> {
+    type Item = std::io::Result<Vec<u8>>;
+
+    /// Yield owned lines. Prefer [`LineStream::next_line`] to avoid allocating per line.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line()
+            .map(|line| line.map(<[u8]>::to_vec))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_lines_basic() {
+        let lines: Vec<Vec<u8>> = LineStream::new(Cursor::new("a\nbb\r\n\nccc"))
+            .map(|l| l.unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![b"a".to_vec(), b"bb".to_vec(), vec![], b"ccc".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_custom_delimiter() {
+        let mut stream = LineStream::new(Cursor::new("one\0two\r\0")).delimiter(0);
+        assert_eq!(stream.next_str().unwrap(), Some("one"));
+        assert_eq!(stream.next_str().unwrap(), Some("two\r"));
+        assert_eq!(stream.next_str().unwrap(), None);
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        let input = format!("short\n{}\nafter\n", "x".repeat(100));
+        let mut stream = LineStream::new(Cursor::new(input)).max_line_length(10);
+        assert_eq!(stream.next_str().unwrap(), Some("short"));
+        let err = stream.next_line().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        // The long line is skipped and reading continues
+        assert_eq!(stream.next_str().unwrap(), Some("after"));
+        assert_eq!(stream.next_str().unwrap(), None);
+    }
+
+    #[test]
+    fn test_max_line_length_across_buffer_refills() {
+        // Larger than BufReader's default capacity so the line spans several fills
+        let input = format!("{}\nok\n", "y".repeat(64 * 1024));
+        let mut stream = LineStream::new(Cursor::new(input)).max_line_length(1024);
+        assert!(stream.next_line().is_err());
+        assert_eq!(stream.next_str().unwrap(), Some("ok"));
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut stream = LineStream::new(Cursor::new(vec![0xff, b'\n']));
+        assert!(stream.next_str().is_err());
+    }
+}
@@ -0,0 +1,355 @@
+//! Replacing the programs a script runs with canned results, so code built on scripty can be
+//! unit-tested without the real programs installed.
+//!
+//! Every command is built by the [`Executor`] installed on the current thread with
+//! [`set_executor`], or run as is when there is none. [`MockExecutor`] answers the commands it
+//! was told to expect with their [`MockOutput`], through a small `sh` process that stands in
+//! for the real program. Pipes, input, exit codes and errors therefore behave exactly as with
+//! real commands, and the echo and [`trace`](crate::trace) still show the original commands.
+//!
+//! The executor is per thread, so that tests running in parallel do not see each other's
+//! mocks. Commands spawned on other threads, such as threads the code under test starts
+//! itself, run the real programs unless the executor is installed there too; the same
+//! `Arc` can be installed on several threads.
+//!
+//! # Examples
+//!
+//! ```
+//! use scripty::*;
+//! use scripty::mock::{MockExecutor, MockOutput};
+//! use std::sync::Arc;
+//!
+//! fn current_branch() -> Result<String> {
+//!     Ok(cmd!("git", "branch", "--show-current").output()?.trim().to_string())
+//! }
+//!
+//! let git = Arc::new(MockExecutor::new());
+//! git.expect(["git", "branch", "--show-current"], MockOutput::new().stdout("main\n"));
+//! let _guard = mock::set_executor(git.clone());
+//!
+//! assert_eq!(current_branch()?, "main");
+//! git.verify();
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::cell::RefCell;
+use std::ffi::{OsStr, OsString};
+use std::io::{Seek, Write};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+thread_local! {
+    static EXECUTOR: RefCell<Option<Arc<dyn Executor>>> = const { RefCell::new(None) };
+}
+
+/// Decides which process is spawned for a command.
+pub trait Executor: Send + Sync {
+    /// Return the process to spawn for `command`, which is fully configured with the
+    /// program, arguments, environment and working directory. Stdio is set up afterwards.
+    fn command(&self, command: Command) -> Command;
+}
+
+/// Runs commands as they are, as scripty does when no executor is installed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessExecutor;
+
+impl Executor for ProcessExecutor {
+    fn command(&self, command: Command) -> Command {
+        command
+    }
+}
+
+/// Use `executor` for the commands spawned by the current thread until the guard is dropped.
+///
+/// Other threads are not affected; see the [module documentation](self).
+pub fn set_executor(executor: Arc<dyn Executor>) -> ExecutorGuard {
+    let previous = EXECUTOR.with_borrow_mut(|current| current.replace(executor));
+    ExecutorGuard { previous }
+}
+
+/// Restores the previous executor when dropped. Returned by [`set_executor`].
+#[must_use = "the executor is uninstalled when the guard is dropped"]
+pub struct ExecutorGuard {
+    previous: Option<Arc<dyn Executor>>,
+}
+
+impl Drop for ExecutorGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        EXECUTOR.with_borrow_mut(|current| *current = previous);
+    }
+}
+
+/// Pass `command` through the executor installed on the current thread, if any.
+pub(crate) fn apply(command: Command) -> Command {
+    match EXECUTOR.with_borrow(Option::clone) {
+        Some(executor) => executor.command(command),
+        None => command,
+    }
+}
+
+/// The result a [`MockExecutor`] gives for an expected command. Succeeds without output by
+/// default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    exit_code: i32,
+}
+
+impl MockOutput {
+    /// A successful result without output.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write `stdout` to stdout.
+    pub fn stdout(mut self, stdout: impl AsRef<[u8]>) -> Self {
+        self.stdout = stdout.as_ref().to_vec();
+        self
+    }
+
+    /// Write `stderr` to stderr.
+    pub fn stderr(mut self, stderr: impl AsRef<[u8]>) -> Self {
+        self.stderr = stderr.as_ref().to_vec();
+        self
+    }
+
+    /// Exit with `code`.
+    pub fn exit_code(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    /// A `sh` process writing this output. It reads its stdin to the end, so that a previous
+    /// pipeline stage is not killed by `SIGPIPE`, unless stdin is a terminal.
+    ///
+    /// The output is stored in unlinked temporary files that the process reads as file
+    /// descriptors 3 and 4, so it can be binary and of any size.
+    fn command(&self) -> Command {
+        let files = (canned_file(&self.stdout), canned_file(&self.stderr));
+        let fds = match files {
+            (Ok(stdout), Ok(stderr)) => [OwnedFd::from(stdout), OwnedFd::from(stderr)],
+            (Err(e), _) | (_, Err(e)) => {
+                let mut command = Command::new("/bin/sh");
+                command.args(["-c", r#"printf '%s\n' "$1" >&2; exit 127"#, "mock"]);
+                command.arg(format!("mock: failed to store the output: {e}"));
+                return command;
+            }
+        };
+
+        let mut command = Command::new("/bin/sh");
+        command.args([
+            "-c",
+            r#"cat <&3; cat <&4 >&2; [ -t 0 ] || cat >/dev/null; exit "$1""#,
+            "mock",
+        ]);
+        command.arg(self.exit_code.to_string());
+        // SAFETY: the closure only calls `fcntl`, `dup2` and `close`, which are
+        // async-signal-safe
+        unsafe {
+            command.pre_exec(move || {
+                // Duplicate above the target numbers first, so neither file is closed by the
+                // other one taking its number
+                let mut high = [-1; 2];
+                for (fd, high) in fds.iter().zip(&mut high) {
+                    *high = libc::fcntl(fd.as_raw_fd(), libc::F_DUPFD, 10);
+                    if *high < 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                for (target, high) in (3..).zip(high) {
+                    if libc::dup2(high, target) < 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    libc::close(high);
+                }
+                Ok(())
+            });
+        }
+        command
+    }
+}
+
+/// An unlinked temporary file holding `bytes`, positioned at the start.
+fn canned_file(bytes: &[u8]) -> std::io::Result<std::fs::File> {
+    let mut file = crate::fs::anonymous_temp_file()?;
+    file.write_all(bytes)?;
+    file.rewind()?;
+    Ok(file)
+}
+
+/// An [`Executor`] answering expected commands with canned results. See the
+/// [module documentation](self).
+///
+/// Each expectation is used once, in any order. A command that was not expected fails with
+/// exit code 127 and a message on stderr, and is reported by [`verify`](Self::verify).
+///
+/// Only the commands of the threads the executor is installed on with [`set_executor`] are
+/// answered. Commands spawned on other threads run the real programs and are not checked by
+/// `verify`.
+#[derive(Debug, Default)]
+pub struct MockExecutor {
+    state: Mutex<MockState>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    expected: Vec<(Vec<OsString>, MockOutput)>,
+    unexpected: Vec<Vec<OsString>>,
+}
+
+impl MockExecutor {
+    /// Create an executor expecting no commands.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect `command`, the program followed by its arguments, to be run once, and answer
+    /// it with `output`.
+    pub fn expect(
+        &self,
+        command: impl IntoIterator<Item = impl AsRef<OsStr>>,
+        output: MockOutput,
+    ) -> &Self {
+        let command = command
+            .into_iter()
+            .map(|word| word.as_ref().to_os_string())
+            .collect();
+        self.lock().expected.push((command, output));
+        self
+    }
+
+    /// Panic if an expected command was not run, or a command was run that was not expected.
+    #[track_caller]
+    pub fn verify(&self) {
+        let state = self.lock();
+        let mut problems = Vec::new();
+        for (command, _) in &state.expected {
+            problems.push(format!("not run: {}", crate::shell_join(command)));
+        }
+        for command in &state.unexpected {
+            problems.push(format!("unexpected: {}", crate::shell_join(command)));
+        }
+        if !problems.is_empty() {
+            panic!("mock commands did not match:\n  {}", problems.join("\n  "));
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Executor for MockExecutor {
+    fn command(&self, command: Command) -> Command {
+        let words: Vec<OsString> = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(OsStr::to_os_string)
+            .collect();
+        let mut state = self.lock();
+        match state
+            .expected
+            .iter()
+            .position(|(expected, _)| *expected == words)
+        {
+            Some(index) => state.expected.remove(index).1.command(),
+            None => {
+                let message = format!("mock: unexpected command: {}\n", crate::shell_join(&words));
+                state.unexpected.push(words);
+                MockOutput::new().stderr(message).exit_code(127).command()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+
+    #[test]
+    fn test_mock_outputs() {
+        let mock = Arc::new(MockExecutor::new());
+        mock.expect(
+            ["git", "status", "--short"],
+            MockOutput::new().stdout(" M a.rs\n"),
+        )
+        .expect(
+            ["cargo", "test"],
+            MockOutput::new().stderr("1 failed\n").exit_code(101),
+        );
+        let _guard = set_executor(mock.clone());
+
+        let output = cmd!("git", "status", "--short")
+            .no_echo()
+            .pipe(cmd!("wc", "-l"))
+            .output();
+        // `wc` was not expected
+        assert!(output.is_err());
+        let error = cmd!("cargo", "test").no_echo().output().unwrap_err();
+        assert_eq!(error.stderr(), Some("1 failed"));
+        assert_eq!(error.command(), Some("cargo test"));
+
+        let panic = std::panic::catch_unwind(|| mock.verify()).unwrap_err();
+        assert_eq!(
+            panic.downcast_ref::<String>().unwrap(),
+            "mock commands did not match:\n  unexpected: wc -l"
+        );
+    }
+
+    #[test]
+    fn test_binary_and_large_outputs() {
+        let binary: Vec<u8> = (0..=255).collect();
+        let large = "line\n".repeat(100_000);
+        let mock = Arc::new(MockExecutor::new());
+        mock.expect(["dump"], MockOutput::new().stdout(&binary))
+            .expect(["report"], MockOutput::new().stdout(&large).stderr(&binary));
+        let _guard = set_executor(mock.clone());
+
+        assert_eq!(cmd!("dump").no_echo().output_bytes().unwrap(), binary);
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        cmd!("report")
+            .no_echo()
+            .write_split_to(&mut stdout, &mut stderr)
+            .unwrap();
+        assert_eq!(stdout, large.as_bytes());
+        assert_eq!(stderr, binary);
+        mock.verify();
+    }
+
+    #[test]
+    fn test_executor_is_per_thread() {
+        let mock = Arc::new(MockExecutor::new());
+        mock.expect(["uname"], MockOutput::new().stdout("Mocked\n"));
+        let _guard = set_executor(mock.clone());
+
+        let real = std::thread::spawn(|| cmd!("uname").no_echo().output().unwrap());
+        assert_ne!(real.join().unwrap(), "Mocked\n");
+
+        let mocked = std::thread::spawn({
+            let mock = mock.clone();
+            move || {
+                let _guard = set_executor(mock);
+                cmd!("uname").no_echo().output().unwrap()
+            }
+        });
+        assert_eq!(mocked.join().unwrap(), "Mocked\n");
+        mock.verify();
+    }
+
+    #[test]
+    fn test_guard_restores_executor() {
+        let mock = Arc::new(MockExecutor::new());
+        mock.expect(["uname"], MockOutput::new().stdout("Mocked\n"));
+        {
+            let _guard = set_executor(mock.clone());
+            assert_eq!(cmd!("uname").no_echo().output().unwrap(), "Mocked\n");
+            mock.verify();
+        }
+        assert_ne!(cmd!("uname").no_echo().output().unwrap(), "Mocked\n");
+    }
+}
@@ -1,15 +1,75 @@
 //! Output utilities for scripty
 
-/// Check if output should be echoed based on NO_ECHO environment variable
+use std::sync::RwLock;
+
+/// How much output scripts produce, set with [`set_verbosity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Verbosity {
+    /// No command echo, and command output goes to `/dev/null` as with
+    /// [`Cmd::quiet`](crate::Cmd::quiet). Captured output is still returned.
+    Quiet,
+    /// Commands are echoed and their output is shown (the default).
+    #[default]
+    Normal,
+    /// Echo extra details, as when `SCRIPTY_VERBOSE` is set.
+    Verbose,
+}
+
+static VERBOSITY: RwLock<Verbosity> = RwLock::new(Verbosity::Normal);
+
+/// Set the verbosity for the rest of the process.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// set_verbosity(Verbosity::Quiet);
+/// cmd!("cargo", "build").run()?; // prints nothing, fails as usual
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn set_verbosity(level: Verbosity) {
+    *VERBOSITY.write().unwrap_or_else(|e| e.into_inner()) = level;
+}
+
+/// Get the current verbosity.
+pub fn verbosity() -> Verbosity {
+    *VERBOSITY.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Check if output should be echoed based on NO_ECHO environment variable and the verbosity
 pub(crate) fn should_echo() -> bool {
-    std::env::var_os("NO_ECHO").is_none()
+    std::env::var_os("NO_ECHO").is_none() && verbosity() != Verbosity::Quiet
 }
 
-/// Print to stderr if echo is enabled
-pub(crate) fn conditional_eprintln(args: std::fmt::Arguments) {
-    if should_echo() {
-        eprintln!("{}", args);
-    }
+/// Check if echo should include extra details based on SCRIPTY_VERBOSE environment variable
+/// and the verbosity
+pub(crate) fn verbose_echo() -> bool {
+    std::env::var_os("SCRIPTY_VERBOSE").is_some() || verbosity() == Verbosity::Verbose
+}
+
+/// Width of the terminal attached to stderr, falling back to `COLUMNS` and then to 80 columns
+pub(crate) fn terminal_width() -> usize {
+    const DEFAULT_WIDTH: usize = 80;
+    const MIN_WIDTH: usize = 20;
+
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let width = if unsafe { libc::ioctl(libc::STDERR_FILENO, libc::TIOCGWINSZ, &mut size) } == 0
+        && size.ws_col > 0
+    {
+        size.ws_col as usize
+    } else {
+        std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(DEFAULT_WIDTH)
+    };
+    width.max(MIN_WIDTH)
 }
 
 #[cfg(test)]
@@ -62,8 +122,16 @@ mod tests {
     }
 
     #[test]
-    fn test_conditional_functions_compile() {
-        // Test that the functions compile and don't panic
-        conditional_eprintln(format_args!("test"));
+    #[serial]
+    fn test_verbosity() {
+        assert_eq!(verbosity(), Verbosity::Normal);
+
+        set_verbosity(Verbosity::Quiet);
+        assert!(!should_echo());
+
+        set_verbosity(Verbosity::Verbose);
+        assert!(verbose_echo());
+
+        set_verbosity(Verbosity::Normal);
     }
 }
@@ -0,0 +1,156 @@
+//! Showing long command output through the user's pager.
+
+use crate::cmd::{Cmd, Error, Pipeline, reaper};
+use std::ffi::{OsStr, OsString};
+use std::io::IsTerminal;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command as StdCommand, Stdio};
+
+/// Pagers tried in order when `PAGER` is not set.
+const FALLBACK_PAGERS: [&str; 2] = ["less", "more"];
+
+/// Run a command with its output shown through the user's pager, like `git log` does.
+///
+/// The pager is taken from `PAGER` and run through `sh -c`, so it may include arguments
+/// (`less -S`). When `PAGER` is not set, `less` is used, or `more` if `less` is not installed.
+/// `LESS` defaults to `FRX`, so short output is printed without waiting for input and colors are
+/// kept. The command writes directly to the pager, and quitting the pager before the end of the
+/// output is not an error.
+///
+/// When stdout is not a terminal, or `PAGER` is empty or `cat`, the command simply runs with
+/// its output going to stdout.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// page_output(cmd!("git", "log", "--color=always", "--oneline"))?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn page_output(cmd: impl Into<Pipeline>) -> Result<(), Error> {
+    let pipeline = cmd.into();
+    let pager = match pager(std::env::var_os("PAGER")) {
+        Some(pager) if std::io::stdout().is_terminal() => pager,
+        _ => return pipeline.run(),
+    };
+
+    let (mut handle, stdout) = pipeline.spawn_io_out()?;
+    let mut pager_cmd = StdCommand::new("sh");
+    pager_cmd.arg("-c").arg(&pager);
+    if std::env::var_os("LESS").is_none() {
+        pager_cmd.env("LESS", "FRX");
+    }
+    if let Some(stdout) = stdout {
+        pager_cmd.stdin(Stdio::from(stdout));
+    }
+
+    let pager_status = pager_cmd
+        .spawn()
+        .and_then(|mut child| child.wait())
+        .map_err(|e| Error::io("Failed to run pager", e));
+
+    // The command ends with SIGPIPE if the pager was quit early, which is expected
+    let mut result = pager_status.map(drop);
+    for mut child in handle.children.drain(..) {
+        let status = match reaper::reap(&mut child, &handle.reap_lock) {
+            Ok(status) => status,
+            Err(e) => {
+                let e = Error::io("Failed to wait for child process", e);
+                result = result.and(Err(e.with_command(&handle.command)));
+                continue;
+            }
+        };
+        if !status.success() && status.signal() != Some(libc::SIGPIPE) {
+            let e = Error::exit_status(status).with_command(&handle.command);
+            result = result.and(Err(e));
+        }
+    }
+    result
+}
+
+impl Pipeline {
+    /// Run the pipeline with its output shown through the user's pager.
+    ///
+    /// Same as [`page_output`], which describes how the pager is chosen.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scripty::*;
+    ///
+    /// cmd!("git", "diff", "--color=always", "main")
+    ///     .pipe(cmd!("grep", "-v", "^index "))
+    ///     .pipe_to_pager()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn pipe_to_pager(self) -> Result<(), Error> {
+        page_output(self)
+    }
+}
+
+impl Cmd {
+    /// Run the command with its output shown through the user's pager.
+    ///
+    /// Same as [`page_output`], which describes how the pager is chosen.
+    pub fn pipe_to_pager(self) -> Result<(), Error> {
+        page_output(self)
+    }
+}
+
+/// The pager command to use for the given `PAGER` value, or `None` to not page.
+fn pager(env: Option<OsString>) -> Option<OsString> {
+    match env {
+        Some(pager) if pager.is_empty() || pager == "cat" => None,
+        Some(pager) => Some(pager),
+        None => FALLBACK_PAGERS
+            .iter()
+            .find(|name| in_path(OsStr::new(name)))
+            .map(OsString::from),
+    }
+}
+
+/// Returns whether an executable named `name` is found in `PATH`.
+fn in_path(name: &OsStr) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(name).is_file()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+
+    #[test]
+    fn test_pager_from_env() {
+        assert_eq!(
+            pager(Some(OsString::from("less -S"))),
+            Some(OsString::from("less -S"))
+        );
+        assert_eq!(pager(Some(OsString::from("cat"))), None);
+        assert_eq!(pager(Some(OsString::new())), None);
+    }
+
+    #[test]
+    fn test_pager_fallback() {
+        if let Some(pager) = pager(None) {
+            assert!(FALLBACK_PAGERS.iter().any(|name| pager == *name));
+        }
+    }
+
+    #[test]
+    fn test_page_output_without_terminal() {
+        // Test output is captured, so stdout is not a terminal and the command runs directly
+        page_output(cmd!("true").no_echo()).unwrap();
+        assert!(page_output(cmd!("false").no_echo()).is_err());
+        cmd!("echo", "paged").no_echo().pipe_to_pager().unwrap();
+        assert!(
+            cmd!("false")
+                .no_echo()
+                .pipe(cmd!("cat"))
+                .pipe_to_pager()
+                .is_err()
+        );
+    }
+}
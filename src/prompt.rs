@@ -0,0 +1,383 @@
+//! Asking the user questions, like `read -p` in a shell script.
+//!
+//! Prompts are written to stderr, so they do not mix with output captured from stdout, and
+//! answers are read from stdin. When stdin is not a terminal (in CI, or with input piped in),
+//! nothing waits for a user: [`confirm`] answers from `SCRIPTY_ASSUME_YES`, and the other
+//! functions read their answer from the next line of stdin, so answers can be scripted:
+//!
+//! ```bash
+//! printf '1.4.0\n' | cargo xtask release
+//! ```
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::os::fd::AsRawFd;
+
+/// Ask a yes/no question, returning `true` if the user answers `y` or `yes`.
+///
+/// The default answer is no. When stdin is not a terminal, the answer is yes if
+/// `SCRIPTY_ASSUME_YES` is set to a non-empty value other than `0`, and no otherwise.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// if prompt::confirm("Delete all build artifacts?")? {
+///     fs::remove_dir_all("target")?;
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn confirm(question: &str) -> std::io::Result<bool> {
+    let assume_yes = std::env::var("SCRIPTY_ASSUME_YES").is_ok_and(|v| !v.is_empty() && v != "0");
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut input = stdin.lock();
+    let terminal = interactive.then_some(&mut input);
+    confirm_from(question, terminal, assume_yes, &mut std::io::stderr())
+}
+
+fn confirm_from(
+    question: &str,
+    terminal: Option<&mut impl BufRead>,
+    assume_yes: bool,
+    output: &mut impl Write,
+) -> std::io::Result<bool> {
+    write!(output, "{question} [y/N] ")?;
+    let Some(input) = terminal else {
+        writeln!(output, "{}", if assume_yes { "y" } else { "n" })?;
+        return Ok(assume_yes);
+    };
+    output.flush()?;
+    let answer = read_answer(input)?.unwrap_or_default().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+/// Ask for a line of text, returning it without the trailing newline.
+///
+/// When stdin is not a terminal, the next line of stdin is the answer and the prompt is not
+/// shown. It is an error if stdin has ended.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let version = prompt::input("Version: ")?;
+/// cmd!("git", "tag", format!("v{version}")).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn input(prompt: &str) -> std::io::Result<String> {
+    let interactive = std::io::stdin().is_terminal();
+    input_from(
+        prompt,
+        interactive,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stderr(),
+    )
+}
+
+fn input_from(
+    prompt: &str,
+    interactive: bool,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> std::io::Result<String> {
+    if interactive {
+        write!(output, "{prompt}")?;
+        output.flush()?;
+    }
+    read_answer(input)?.ok_or_else(|| no_answer(prompt))
+}
+
+/// Ask for a line of text, returning `default` if the answer is empty or stdin has ended.
+///
+/// The default is shown in brackets after the prompt.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let target = prompt::input_or("Target", "x86_64-unknown-linux-gnu")?;
+/// cmd!("cargo", "build", "--target", target).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn input_or(prompt: &str, default: &str) -> std::io::Result<String> {
+    let interactive = std::io::stdin().is_terminal();
+    input_or_from(
+        prompt,
+        default,
+        interactive,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stderr(),
+    )
+}
+
+fn input_or_from(
+    prompt: &str,
+    default: &str,
+    interactive: bool,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> std::io::Result<String> {
+    if interactive {
+        write!(output, "{prompt} [{default}]: ")?;
+        output.flush()?;
+    }
+    match read_answer(input)? {
+        Some(answer) if !answer.is_empty() => Ok(answer),
+        _ => Ok(default.to_string()),
+    }
+}
+
+/// Ask for a secret, without showing what is typed.
+///
+/// When stdin is not a terminal, the next line of stdin is the answer, as with [`input`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let token = prompt::password("Registry token: ")?;
+/// cmd!("cargo", "login").input(token).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn password(prompt: &str) -> std::io::Result<String> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return input_from(prompt, false, &mut stdin.lock(), &mut std::io::stderr());
+    }
+
+    let mut stderr = std::io::stderr();
+    write!(stderr, "{prompt}")?;
+    stderr.flush()?;
+    let answer = {
+        let _echo_off = EchoOff::new(stdin.as_raw_fd())?;
+        read_answer(&mut stdin.lock())
+    };
+    // The newline typed by the user was not echoed
+    writeln!(stderr)?;
+    answer?.ok_or_else(|| no_answer(prompt))
+}
+
+/// Turns off terminal echo until dropped.
+struct EchoOff {
+    fd: i32,
+    saved: libc::termios,
+}
+
+impl EchoOff {
+    fn new(fd: i32) -> std::io::Result<Self> {
+        // SAFETY: termios is plain data filled in by tcgetattr, and fd is a valid descriptor
+        unsafe {
+            let mut saved = std::mem::zeroed::<libc::termios>();
+            if libc::tcgetattr(fd, &mut saved) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let mut quiet = saved;
+            quiet.c_lflag &= !libc::ECHO;
+            if libc::tcsetattr(fd, libc::TCSANOW, &quiet) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(Self { fd, saved })
+        }
+    }
+}
+
+impl Drop for EchoOff {
+    fn drop(&mut self) {
+        // SAFETY: restores the settings read from the same descriptor in new()
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSANOW, &self.saved);
+        }
+    }
+}
+
+/// Ask the user to pick one of `options`, returning its index.
+///
+/// The options are listed with numbers, and the answer can be a number or the text of an
+/// option. On a terminal an invalid answer asks again; when stdin is not a terminal, the next
+/// line of stdin is the answer, and an invalid one is an error.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let profiles = ["dev", "release", "bench"];
+/// let profile = profiles[prompt::select("Build profile:", &profiles)?];
+/// cmd!("cargo", "build", "--profile", profile).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn select(prompt: &str, options: &[impl AsRef<str>]) -> std::io::Result<usize> {
+    let interactive = std::io::stdin().is_terminal();
+    select_from(
+        prompt,
+        options,
+        interactive,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stderr(),
+    )
+}
+
+fn select_from(
+    prompt: &str,
+    options: &[impl AsRef<str>],
+    interactive: bool,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> std::io::Result<usize> {
+    if options.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("No options to select from for: {prompt}"),
+        ));
+    }
+
+    if interactive {
+        writeln!(output, "{prompt}")?;
+        for (i, option) in options.iter().enumerate() {
+            writeln!(output, "  {}) {}", i + 1, option.as_ref())?;
+        }
+    }
+    loop {
+        if interactive {
+            write!(output, "Enter 1-{}: ", options.len())?;
+            output.flush()?;
+        }
+        let answer = read_answer(input)?.ok_or_else(|| no_answer(prompt))?;
+        let choice = match answer.parse::<usize>() {
+            Ok(n) if (1..=options.len()).contains(&n) => Some(n - 1),
+            _ => options.iter().position(|option| option.as_ref() == answer),
+        };
+        match choice {
+            Some(index) => return Ok(index),
+            None if interactive => continue,
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Invalid selection for {prompt:?}: {answer:?}"),
+                ));
+            }
+        }
+    }
+}
+
+/// Read one line, without the line ending and surrounding whitespace. Returns `None` at the
+/// end of the input.
+fn read_answer(input: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+fn no_answer(prompt: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        format!("No answer for {:?}: stdin has ended", prompt.trim()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirm() {
+        let mut output = Vec::new();
+        let answers = [
+            ("y\n", true),
+            ("Yes\n", true),
+            ("n\n", false),
+            ("\n", false),
+            ("", false),
+        ];
+        for (answer, expected) in answers {
+            let result = confirm_from("Delete?", Some(&mut answer.as_bytes()), false, &mut output);
+            assert_eq!(result.unwrap(), expected, "answer {answer:?}");
+        }
+
+        let mut output = Vec::new();
+        assert!(confirm_from("Delete?", None::<&mut &[u8]>, true, &mut output).unwrap());
+        assert!(!confirm_from("Delete?", None::<&mut &[u8]>, false, &mut output).unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Delete? [y/N] y\nDelete? [y/N] n\n"
+        );
+    }
+
+    #[test]
+    fn test_input() {
+        let mut output = Vec::new();
+        let answer = input_from("Version: ", true, &mut "1.4.0\n".as_bytes(), &mut output);
+        assert_eq!(answer.unwrap(), "1.4.0");
+        assert_eq!(output, b"Version: ");
+
+        // Not a terminal: the answer is read without showing the prompt
+        let mut output = Vec::new();
+        let answer = input_from("Version: ", false, &mut "1.4.0".as_bytes(), &mut output);
+        assert_eq!(answer.unwrap(), "1.4.0");
+        assert!(output.is_empty());
+
+        let error = input_from("Version: ", false, &mut "".as_bytes(), &mut output).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_input_or() {
+        let mut output = Vec::new();
+        let answer = input_or_from("Target", "x86_64", true, &mut "\n".as_bytes(), &mut output);
+        assert_eq!(answer.unwrap(), "x86_64");
+        assert_eq!(output, b"Target [x86_64]: ");
+        let answer = input_or_from("Target", "x86_64", false, &mut "".as_bytes(), &mut output);
+        assert_eq!(answer.unwrap(), "x86_64");
+        let answer = input_or_from(
+            "Target",
+            "x86_64",
+            false,
+            &mut "arm\n".as_bytes(),
+            &mut output,
+        );
+        assert_eq!(answer.unwrap(), "arm");
+    }
+
+    #[test]
+    fn test_select() {
+        let options = ["dev", "release"];
+        let mut output = Vec::new();
+        let choice = select_from(
+            "Profile:",
+            &options,
+            true,
+            &mut "3\nrelease\n".as_bytes(),
+            &mut output,
+        );
+        assert_eq!(choice.unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Profile:\n  1) dev\n  2) release\nEnter 1-2: Enter 1-2: "
+        );
+
+        let mut output = Vec::new();
+        let choice = select_from(
+            "Profile:",
+            &options,
+            false,
+            &mut "1\n".as_bytes(),
+            &mut output,
+        );
+        assert_eq!(choice.unwrap(), 0);
+        let error = select_from(
+            "Profile:",
+            &options,
+            false,
+            &mut "3\n".as_bytes(),
+            &mut output,
+        );
+        assert_eq!(error.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert!(output.is_empty());
+    }
+}
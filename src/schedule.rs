@@ -0,0 +1,305 @@
+//! Running a closure repeatedly at an interval, and waiting for a condition, in place of
+//! hand-written sleep loops.
+
+use crate::style::{BOLD_CYAN, BOLD_UNDERLINE};
+use std::hash::{BuildHasher, RandomState};
+use std::ops::ControlFlow;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+fn echo_iteration(interval: Duration, iteration: u64) {
+    crate::echo::emit("  ", "every", |styled| {
+        if styled {
+            format!(
+                "{BOLD_CYAN}every{BOLD_CYAN:#} {interval:?} {BOLD_UNDERLINE}#{iteration}{BOLD_UNDERLINE:#}"
+            )
+        } else {
+            format!("every {interval:?} #{iteration}")
+        }
+    });
+}
+
+/// Run a closure every `interval` until it breaks, times out, or is cancelled.
+///
+/// Call [`run`](Every::run) to start. The first run is immediate, and each iteration is echoed
+/// with its number.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+/// use std::ops::ControlFlow;
+/// use std::time::Duration;
+///
+/// // Wait for a service to become healthy
+/// let healthy = every(Duration::from_secs(2))
+///     .jitter(Duration::from_millis(500))
+///     .timeout(Duration::from_secs(60))
+///     .run(|| match cmd!("curl", "-fs", "http://localhost:8080/health").quiet().run() {
+///         Ok(()) => ControlFlow::Break(()),
+///         Err(_) => ControlFlow::Continue(()),
+///     });
+/// if healthy.is_none() {
+///     return Err("service did not become healthy".into());
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn every(interval: Duration) -> Every {
+    Every {
+        interval,
+        jitter: Duration::ZERO,
+        timeout: None,
+        cancel: None,
+    }
+}
+
+/// A repeated run configured by [`every`].
+#[derive(Debug, Clone)]
+#[must_use = "nothing runs until `run()` is called"]
+pub struct Every {
+    interval: Duration,
+    jitter: Duration,
+    timeout: Option<Duration>,
+    cancel: Option<CancelToken>,
+}
+
+impl Every {
+    /// Wait a random extra time of up to `max` between runs, so that many scripts polling the
+    /// same service do not do it in lockstep.
+    pub fn jitter(mut self, max: Duration) -> Self {
+        self.jitter = max;
+        self
+    }
+
+    /// Stop once `timeout` has passed since the first run. A run in progress is not
+    /// interrupted.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stop when `token` is cancelled, from another thread or from within the closure.
+    ///
+    /// A wait between runs ends as soon as the token is cancelled.
+    pub fn cancel_on(mut self, token: &CancelToken) -> Self {
+        self.cancel = Some(token.clone());
+        self
+    }
+
+    /// Run `f` repeatedly until it returns [`ControlFlow::Break`], returning the value it
+    /// breaks with.
+    ///
+    /// Returns `None` if the timeout passed or the run was cancelled first.
+    pub fn run<T>(self, mut f: impl FnMut() -> ControlFlow<T>) -> Option<T> {
+        let start = Instant::now();
+        let deadline = self.timeout.map(|timeout| start + timeout);
+        let mut iteration = 0;
+        loop {
+            iteration += 1;
+            if self.is_cancelled() {
+                return None;
+            }
+            echo_iteration(self.interval, iteration);
+            if let ControlFlow::Break(value) = f() {
+                return Some(value);
+            }
+
+            let wake = Instant::now() + self.interval + self.random_jitter();
+            // Give up early rather than sleep past the deadline
+            if deadline.is_some_and(|deadline| wake >= deadline) {
+                return None;
+            }
+            match &self.cancel {
+                Some(token) => token.sleep_until(wake),
+                None => std::thread::sleep(wake.saturating_duration_since(Instant::now())),
+            }
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    fn random_jitter(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return Duration::ZERO;
+        }
+        // Every RandomState is seeded differently, which is random enough for spreading out polls
+        let random = RandomState::new().hash_one(Instant::now());
+        self.jitter.mul_f64(random as f64 / u64::MAX as f64)
+    }
+}
+
+/// Wait until `condition` returns `true`, checking it every second for up to 30 seconds.
+///
+/// Change the defaults with [`interval`](WaitFor::interval) and [`timeout`](WaitFor::timeout),
+/// then call [`run`](WaitFor::run) to start. Each check is echoed like [`every`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+/// use std::time::Duration;
+///
+/// cmd!("docker", "compose", "up", "-d").run()?;
+/// wait_for(|| cmd!("curl", "-sf", "http://localhost:8080/health").quiet().run().is_ok())
+///     .timeout(Duration::from_secs(60))
+///     .interval(Duration::from_millis(500))
+///     .run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn wait_for<F: FnMut() -> bool>(condition: F) -> WaitFor<F> {
+    WaitFor {
+        condition,
+        interval: Duration::from_secs(1),
+        timeout: Duration::from_secs(30),
+    }
+}
+
+/// A readiness check configured by [`wait_for`].
+#[must_use = "nothing is checked until `run()` is called"]
+pub struct WaitFor<F> {
+    condition: F,
+    interval: Duration,
+    timeout: Duration,
+}
+
+impl<F: FnMut() -> bool> WaitFor<F> {
+    /// Time between checks. Defaults to one second.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// How long to wait before giving up. Defaults to 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Check the condition until it holds.
+    ///
+    /// Returns an error of kind [`TimedOut`](std::io::ErrorKind::TimedOut), with the time spent
+    /// waiting, if the condition still does not hold when the timeout passes.
+    pub fn run(mut self) -> std::io::Result<()> {
+        let start = Instant::now();
+        every(self.interval)
+            .timeout(self.timeout)
+            .run(|| {
+                if (self.condition)() {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("Condition not met after {:.1?}", start.elapsed()),
+                )
+            })
+    }
+}
+
+/// A flag for stopping an [`every`] loop, shared between threads by cloning.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CancelToken {
+    /// Create a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the token, waking up loops waiting on it.
+    pub fn cancel(&self) {
+        let (cancelled, wakeup) = &*self.state;
+        *cancelled.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        wakeup.notify_all();
+    }
+
+    /// Whether [`cancel`](Self::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        *self.state.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sleep until `wake`, or until the token is cancelled.
+    fn sleep_until(&self, wake: Instant) {
+        let (cancelled, wakeup) = &*self.state;
+        let guard = cancelled.lock().unwrap_or_else(|e| e.into_inner());
+        let timeout = wake.saturating_duration_since(Instant::now());
+        let _ = wakeup.wait_timeout_while(guard, timeout, |cancelled| !*cancelled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_until_break() {
+        let mut runs = 0;
+        let result = every(Duration::from_millis(1)).run(|| {
+            runs += 1;
+            if runs == 3 {
+                ControlFlow::Break(runs)
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(result, Some(3));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let start = Instant::now();
+        let result = every(Duration::from_millis(20))
+            .jitter(Duration::from_millis(5))
+            .timeout(Duration::from_millis(100))
+            .run(|| ControlFlow::<()>::Continue(()));
+        assert_eq!(result, None);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_wait_for() {
+        let mut checks = 0;
+        wait_for(|| {
+            checks += 1;
+            checks == 2
+        })
+        .interval(Duration::from_millis(1))
+        .run()
+        .unwrap();
+        assert_eq!(checks, 2);
+
+        let error = wait_for(|| false)
+            .interval(Duration::from_millis(10))
+            .timeout(Duration::from_millis(50))
+            .run()
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+        assert!(error.to_string().starts_with("Condition not met after "));
+    }
+
+    #[test]
+    fn test_cancel_wakes_sleep() {
+        let token = CancelToken::new();
+        let canceller = {
+            let token = token.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                token.cancel();
+            })
+        };
+        let start = Instant::now();
+        let result = every(Duration::from_secs(60))
+            .cancel_on(&token)
+            .run(|| ControlFlow::<()>::Continue(()));
+        canceller.join().unwrap();
+        assert_eq!(result, None);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,198 @@
+//! Settings shared by a group of commands, like the state of a shell session.
+
+use crate::Cmd;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Create a [`Session`] with no settings.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let sh = session().dir("/srv/app").env("RUST_LOG", "info");
+/// sh.cmd("git").arg("pull").run()?;
+/// sh.apply(cmd!("cargo", "build", "--release")).run()?;
+/// sh.cmd("systemctl").args(["restart", "app"]).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn session() -> Session {
+    Session::default()
+}
+
+/// A working directory, environment, and echo and dry-run settings applied to every command
+/// created from it.
+///
+/// Commands are created with [`cmd`](Self::cmd), or with [`apply`](Self::apply) for commands
+/// built with [`cmd!`](crate::cmd!). Settings made on a command itself take precedence: its
+/// environment variables override the session's, and a relative
+/// [`current_dir`](Cmd::current_dir) is resolved against the session directory. Unlike
+/// [`cd`](crate::cd), a session does not change the working directory of the process.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    dir: Option<PathBuf>,
+    envs: Vec<(OsString, OsString)>,
+    no_echo: bool,
+    dry_run: bool,
+    /// Why the session's commands cannot run, reported when they are spawned.
+    invalid: Option<String>,
+}
+
+impl Session {
+    /// Run commands in `dir`.
+    pub fn dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set an environment variable for every command.
+    pub fn env(mut self, key: impl AsRef<OsStr>, val: impl AsRef<OsStr>) -> Self {
+        self.envs
+            .push((key.as_ref().to_os_string(), val.as_ref().to_os_string()));
+        self
+    }
+
+    /// Add `dir` to the front of `PATH` for every command, as with [`Cmd::prepend_path`].
+    pub fn prepend_path(self, dir: impl AsRef<Path>) -> Self {
+        let path = Cmd::path_with_dir(&self.envs, dir.as_ref(), true);
+        self.env_path(path)
+    }
+
+    /// Add `dir` to the end of `PATH` for every command, as with [`Cmd::append_path`].
+    pub fn append_path(self, dir: impl AsRef<Path>) -> Self {
+        let path = Cmd::path_with_dir(&self.envs, dir.as_ref(), false);
+        self.env_path(path)
+    }
+
+    /// Set `PATH` as built by `Cmd::path_with_dir()`, or keep the failure for the commands.
+    fn env_path(mut self, path: Result<OsString, String>) -> Self {
+        match path {
+            Ok(path) => self.env("PATH", path),
+            Err(reason) => {
+                self.invalid.get_or_insert(reason);
+                self
+            }
+        }
+    }
+
+    /// Run commands without echoing them.
+    pub fn no_echo(mut self) -> Self {
+        self.no_echo = true;
+        self
+    }
+
+    /// Echo commands without running them, as with [`Cmd::dry_run`].
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Create a command with the session's settings.
+    pub fn cmd(&self, program: impl AsRef<OsStr>) -> Cmd {
+        self.apply(Cmd::new(program))
+    }
+
+    /// Apply the session's settings to a command.
+    pub fn apply(&self, mut cmd: Cmd) -> Cmd {
+        if let Some(dir) = &self.dir {
+            cmd.current_dir = Some(match cmd.current_dir {
+                Some(own) => dir.join(own),
+                None => dir.clone(),
+            });
+        }
+        cmd.envs.splice(0..0, self.envs.iter().cloned());
+        cmd.suppress_echo |= self.no_echo;
+        cmd.dry_run |= self.dry_run;
+        if cmd.invalid.is_none() {
+            cmd.invalid = self.invalid.clone();
+        }
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+    use crate::fs::temp_dir_scoped;
+
+    #[test]
+    fn test_session_settings() {
+        let tmp = temp_dir_scoped().unwrap();
+        let dir = tmp.path().canonicalize().unwrap();
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        let sh = session().dir(&dir).env("GREETING", "hello").no_echo();
+
+        let output = sh.cmd("sh").args(["-c", "pwd; echo $GREETING"]).output();
+        assert_eq!(output.unwrap(), format!("{}\nhello\n", dir.display()));
+
+        // Settings on the command itself take precedence
+        let output = sh
+            .apply(cmd!("sh", "-c", "pwd; echo $GREETING").current_dir("sub"))
+            .env("GREETING", "hi")
+            .output();
+        assert_eq!(
+            output.unwrap(),
+            format!("{}\nhi\n", dir.join("sub").display())
+        );
+    }
+
+    #[test]
+    fn test_session_path() {
+        let tmp = temp_dir_scoped().unwrap();
+        let bin = tmp.path().join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        let tool = bin.join("scripty-session-tool");
+        std::fs::write(&tool, "#!/bin/sh\necho from bin\n").unwrap();
+        std::fs::set_permissions(&tool, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let sh = session().prepend_path(&bin).no_echo();
+        let output = sh.cmd("scripty-session-tool").output().unwrap();
+        assert_eq!(output, "from bin\n");
+
+        // The command extends the session's PATH
+        let output = sh
+            .cmd("sh")
+            .args(["-c", "echo $PATH"])
+            .append_path("/opt/x")
+            .output();
+        let output = output.unwrap();
+        assert!(output.starts_with(&format!("{}:", bin.display())));
+        assert!(output.trim_end().ends_with(":/opt/x"));
+    }
+
+    #[test]
+    fn test_session_path_with_colon() {
+        let sh = session().append_path("/opt/a:b").no_echo();
+        let error = sh.cmd("true").run().unwrap_err();
+        assert!(error.to_string().contains("cannot add /opt/a:b to PATH"));
+    }
+
+    #[test]
+    fn test_session_dry_run() {
+        let tmp = temp_dir_scoped().unwrap();
+        let marker = tmp.path().join("marker");
+        let sh = session().dry_run().no_echo();
+
+        let output = sh.cmd("touch").arg(&marker).output().unwrap();
+        assert_eq!(output, "");
+        assert!(!marker.exists());
+        assert!(
+            sh.apply(cmd!("false"))
+                .pipe(cmd!("cat"))
+                .input("ignored")
+                .run()
+                .is_ok()
+        );
+        assert_eq!(
+            sh.cmd("rm")
+                .arg("-rf")
+                .arg("build")
+                .into_pipeline()
+                .render(false),
+            "dry-run: rm -rf build"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Quoting arguments for POSIX shells.
+
+use crate::cmd::CmdInput;
+use crate::{Cmd, PipeMode, Pipeline, StderrMode, StdioConfig};
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+
+/// Quote an argument so a POSIX shell reads it back as a single word, unchanged.
+///
+/// Arguments made only of letters, digits and `@%+=:,./_-` are returned as they are. Anything
+/// else is wrapped in single quotes, with embedded single quotes written as `'\''`, so no
+/// character (`$`, `` ` ``, `\`, `!`, newlines) is interpreted by the shell. Unlike the command
+/// echo, which favors readability, the result is always safe to paste into a shell script.
+///
+/// The result is a `String`, so bytes that are not valid UTF-8 are replaced with `U+FFFD`.
+///
+/// # Examples
+///
+/// ```
+/// use scripty::*;
+///
+/// assert_eq!(shell_quote("build/out.txt"), "build/out.txt");
+/// assert_eq!(shell_quote("it's here"), r"'it'\''s here'");
+/// assert_eq!(shell_quote(""), "''");
+/// ```
+pub fn shell_quote(arg: impl AsRef<OsStr>) -> String {
+    let arg = arg.as_ref().to_string_lossy();
+    let safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        return arg.into_owned();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Quote each argument with [`shell_quote`] and join them with spaces into a command line.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// // Run a command on a remote host, where ssh passes it to the remote shell
+/// let remote = shell_join(["grep", "-r", "TODO: fix", "/srv/app"]);
+/// cmd!("ssh", "deploy@example.com", remote).run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn shell_join(args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> String {
+    args.into_iter()
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Pipeline {
+    /// Render the pipeline as a POSIX shell command line that runs it the same way, for logs,
+    /// documentation or passing to `ssh`.
+    ///
+    /// Unlike the command echo, every argument is quoted with [`shell_quote`], and the working
+    /// directory (`cd <dir> &&`), environment (`env -u <name> <name>=<value>`), input, `/dev/null`
+    /// redirections, [`combine_err`](Cmd::combine_err) and the pipe modes are written out.
+    /// Values set with [`env_secret`](Cmd::env_secret) are included as they are. Process
+    /// settings such as resource limits, the user, the umask or a cgroup are left out.
+    ///
+    /// Returns `None` if the pipeline cannot be written as a shell command: when it has a
+    /// [`filter_lines`](Self::filter_lines) or [`map_lines`](Self::map_lines) stage, or input
+    /// streamed from a reader or a closure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripty::*;
+    ///
+    /// let pipeline = cmd!("grep", "-i", "error: disk")
+    ///     .current_dir("/var/log")
+    ///     .env("LC_ALL", "C")
+    ///     .input("Error: disk full\n")
+    ///     .pipe(cmd!("wc", "-l"));
+    /// assert_eq!(
+    ///     pipeline.to_shell_string().unwrap(),
+    ///     "printf '%s' 'Error: disk full\n' | (cd /var/log && env LC_ALL=C grep -i 'error: disk') | wc -l"
+    /// );
+    /// ```
+    pub fn to_shell_string(&self) -> Option<String> {
+        let mut script = String::new();
+        match (&self.input_path, &self.input) {
+            (Some(_), _) | (None, None) => {}
+            (None, Some(CmdInput::Bytes(bytes))) => {
+                script = format!("printf '%s' {} | ", shell_quote(OsStr::from_bytes(bytes)));
+            }
+            (None, Some(CmdInput::Heredoc(text))) => {
+                script = format!("printf '%s' {} | ", shell_quote(text));
+            }
+            (None, Some(_)) => return None,
+        }
+
+        // A `cd` only applies to its own stage when it is run in a subshell
+        let subshell = self.connections.len() > 1 || !script.is_empty();
+        let mut uses_fd3 = false;
+        for (i, (cmd, _)) in self.connections.iter().enumerate() {
+            if i > 0 {
+                script.push_str(" | ");
+            }
+            if let Some(filter) = &cmd.filter {
+                script.push_str(&filter.shell_command()?);
+                continue;
+            }
+
+            let mut stage = shell_join(cmd.shell_words());
+            if i == 0 {
+                if let Some(path) = &self.input_path {
+                    stage = format!("{stage} < {}", shell_quote(path));
+                } else if self.input.is_none() && cmd.stdin == StdioConfig::Null {
+                    stage.push_str(" < /dev/null");
+                }
+            }
+            // How the next stage reads this one's output
+            match self.connections.get(i + 1).map(|(_, mode)| mode) {
+                Some(PipeMode::Both) => stage.push_str(" 2>&1"),
+                Some(PipeMode::Stderr) => {
+                    // Stdout goes to the pipeline's stdout, kept on fd 3 around the pipeline
+                    stage.push_str(" 2>&1 >&3 3>&-");
+                    uses_fd3 = true;
+                }
+                Some(PipeMode::Stdout) => match self.stage_stderr {
+                    StderrMode::Null => stage.push_str(" 2> /dev/null"),
+                    StderrMode::MergeIntoPipe => stage.push_str(" 2>&1"),
+                    StderrMode::Inherit | StderrMode::Capture => {}
+                },
+                None => {}
+            }
+            if cmd.stdout_null {
+                stage.push_str(" > /dev/null");
+            }
+            if cmd.stderr_null {
+                stage.push_str(" 2> /dev/null");
+            }
+            if cmd.combine_err {
+                stage.push_str(" 2>&1");
+            }
+            if let Some(dir) = &cmd.current_dir {
+                stage = format!("cd {} && {stage}", shell_quote(dir));
+                if subshell {
+                    stage = format!("({stage})");
+                }
+            }
+            script.push_str(&stage);
+        }
+
+        if uses_fd3 {
+            script = format!("{{ {script}; }} 3>&1");
+        }
+        Some(script)
+    }
+}
+
+impl Cmd {
+    /// Render the command as a POSIX shell command line.
+    ///
+    /// See [`Pipeline::to_shell_string`] for details.
+    pub fn to_shell_string(&self) -> Option<String> {
+        self.clone().into_pipeline().to_shell_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("simple"), "simple");
+        assert_eq!(shell_quote("--name=a,b:c@d%e+f"), "--name=a,b:c@d%e+f");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("hello world"), "'hello world'");
+        assert_eq!(shell_quote("$HOME"), "'$HOME'");
+        assert_eq!(shell_quote("'"), r"''\'''");
+        assert_eq!(shell_quote("~"), "'~'");
+    }
+
+    #[test]
+    fn test_shell_join_round_trip() {
+        let args = [
+            "plain",
+            "",
+            "with space",
+            "it's",
+            "\"double\"",
+            "$HOME `id` $(id)",
+            "back\\slash",
+            "new\nline",
+            "*?[a]{b,c}!#;&|<>()",
+        ];
+        let script = format!("printf '%s\\0' {}", shell_join(args));
+        let output = cmd!("sh", "-c", script).no_echo().output_bytes().unwrap();
+        let words: Vec<&[u8]> = output.split(|&b| b == 0).collect();
+        let expected: Vec<&[u8]> = args
+            .iter()
+            .map(|arg| arg.as_bytes())
+            .chain([&b""[..]])
+            .collect();
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn test_to_shell_string() {
+        let pipeline = cmd!("make", "it's")
+            .current_dir("/tmp")
+            .env("A", "1 2")
+            .stdin_null()
+            .stderr_null()
+            .pipe(cmd!("grep", "x").stdout_null())
+            .pipe(cmd!("cat").combine_err());
+        assert_eq!(
+            pipeline.to_shell_string().unwrap(),
+            r"(cd /tmp && env 'A=1 2' make 'it'\''s' < /dev/null 2> /dev/null) | grep x > /dev/null | cat 2>&1"
+        );
+        assert_eq!(
+            cmd!("make")
+                .pipe(cmd!("grep", "x"))
+                .pipe(cmd!("wc", "-l"))
+                .stage_stderr(StderrMode::MergeIntoPipe)
+                .to_shell_string()
+                .unwrap(),
+            "make 2>&1 | grep x 2>&1 | wc -l"
+        );
+        assert_eq!(
+            cmd!("ls").current_dir("/").to_shell_string().unwrap(),
+            "cd / && ls"
+        );
+        assert_eq!(
+            cmd!("seq", "5").filter_lines(|_| true).to_shell_string(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_to_shell_string_runs() {
+        let pipeline = cmd!("sh", "-c", "echo out; echo err >&2")
+            .pipe_err(cmd!("tr", "a-z", "A-Z"))
+            .tail(1)
+            .pipe_out_err(cmd!("sed", "s/^/> /"))
+            .input("ignored");
+        let script = pipeline.to_shell_string().unwrap();
+        let output = cmd!("sh", "-c", &script).no_echo().output().unwrap();
+        assert_eq!(output, "out\n> ERR\n");
+    }
+}
@@ -0,0 +1,204 @@
+//! Writers for command output that runs for a long time.
+//!
+//! A [`RotatingFileWriter`] appends to a log file and moves it aside once it grows too large or
+//! too old, so that a pipeline left running for days does not fill a single file. Pass it to
+//! [`write_to`](crate::Pipeline::write_to) or [`write_both_to`](crate::Pipeline::write_both_to):
+//!
+//! ```no_run
+//! use scripty::*;
+//! use std::time::Duration;
+//!
+//! let log = sink::RotatingFileWriter::new("access.log")?
+//!     .max_size(100 * 1024 * 1024)
+//!     .max_age(Duration::from_secs(24 * 60 * 60));
+//! cmd!("tail", "-F", "/var/log/nginx/access.log").write_to(log)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A file that is rotated by size or age, created by [`RotatingFileWriter::new`].
+///
+/// When a limit is reached, the file is renamed with the time of the rotation appended, such as
+/// `access.log.20261016-142500`, and a new file is started under the original name. Each write
+/// goes entirely to one file, so a line written in one call is never split across two.
+#[derive(Debug)]
+pub struct RotatingFileWriter {
+    path: PathBuf,
+    file: File,
+    /// Bytes in the current file.
+    size: u64,
+    opened: Instant,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+}
+
+impl RotatingFileWriter {
+    /// Open `path` for appending, creating it if needed.
+    ///
+    /// Without [`max_size`](Self::max_size) or [`max_age`](Self::max_age) the file is never
+    /// rotated.
+    pub fn new(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = Self::open(&path)?;
+        Ok(Self {
+            size: file.metadata()?.len(),
+            path,
+            file,
+            opened: Instant::now(),
+            max_size: None,
+            max_age: None,
+        })
+    }
+
+    /// Rotate before a write would make the file larger than `bytes`.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Rotate at the first write once the file has been open for `age`.
+    pub fn max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// The path of the file currently written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open(path: &Path) -> std::io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// Whether the file must be rotated before writing `len` bytes. An empty file never is.
+    fn needs_rotation(&self, len: usize) -> bool {
+        let too_large = self
+            .max_size
+            .is_some_and(|max| self.size + len as u64 > max);
+        let too_old = self.max_age.is_some_and(|max| self.opened.elapsed() >= max);
+        self.size > 0 && (too_large || too_old)
+    }
+
+    /// Move the current file aside and start a new one.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{}", timestamp()));
+        let mut rotated = PathBuf::from(&name);
+        // Several rotations within a second get a counter
+        let mut counter = 1;
+        while rotated.exists() {
+            rotated = PathBuf::from(format!("{}.{counter}", name.to_string_lossy()));
+            counter += 1;
+        }
+        std::fs::rename(&self.path, &rotated)?;
+        self.file = Self::open(&self.path)?;
+        self.size = 0;
+        self.opened = Instant::now();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.needs_rotation(buf.len()) {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// The local time as `YYYYMMDD-HHMMSS`.
+fn timestamp() -> String {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&now, &mut tm) };
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp_dir_scoped;
+
+    fn rotated_files(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name != "out.log")
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_rotates_by_size() {
+        let dir = temp_dir_scoped().unwrap();
+        let path = dir.path().join("out.log");
+        let mut writer = RotatingFileWriter::new(&path).unwrap().max_size(10);
+        writer.write_all(b"first\n").unwrap();
+        writer.write_all(b"second\n").unwrap();
+        writer.write_all(b"third\n").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "third\n");
+        let rotated = rotated_files(dir.path());
+        assert_eq!(rotated.len(), 2);
+        assert!(rotated[0].starts_with("out.log."));
+        let contents: Vec<String> = rotated
+            .iter()
+            .map(|name| std::fs::read_to_string(dir.path().join(name)).unwrap())
+            .collect();
+        assert!(contents.contains(&"first\n".to_string()));
+        assert!(contents.contains(&"second\n".to_string()));
+    }
+
+    #[test]
+    fn test_rotates_by_age() {
+        let dir = temp_dir_scoped().unwrap();
+        let path = dir.path().join("out.log");
+        let mut writer = RotatingFileWriter::new(&path)
+            .unwrap()
+            .max_age(Duration::ZERO);
+        writer.write_all(b"old\n").unwrap();
+        writer.write_all(b"new\n").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new\n");
+        let rotated = rotated_files(dir.path());
+        assert_eq!(rotated.len(), 1);
+        let old = std::fs::read_to_string(dir.path().join(&rotated[0])).unwrap();
+        assert_eq!(old, "old\n");
+    }
+
+    #[test]
+    fn test_appends_without_limits() {
+        let dir = temp_dir_scoped().unwrap();
+        let path = dir.path().join("out.log");
+        std::fs::write(&path, "existing\n").unwrap();
+        crate::cmd!("echo", "more")
+            .no_echo()
+            .write_to(RotatingFileWriter::new(&path).unwrap())
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "existing\nmore\n");
+        assert!(rotated_files(dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+//! Tracking of untrusted values to catch shell injection in debug builds.
+//!
+//! Wrap values that come from outside the script (user input, file names, HTTP responses) in
+//! [`Tainted`]. Passing them as separate arguments with [`Cmd::arg`](crate::Cmd::arg) is always
+//! safe. Formatting them into a `sh -c` script is not, and in debug builds scripty notices: every
+//! tainted value written with `Display` is remembered, and a shell script (`sh -c`, `bash -c`,
+//! ...) containing one is reported before it runs, according to the [`ShellFormatCheck`].
+//!
+//! The check is a heuristic. It only sees values that were wrapped, and a short value may also
+//! appear in a script by coincidence. Release builds skip both the tracking and the check.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use scripty::*;
+//!
+//! let branch = Tainted::new(std::env::args().nth(1).unwrap_or_default());
+//!
+//! // Safe: the value is a single argument
+//! cmd!("git", "checkout").arg(&branch).run()?;
+//!
+//! // Reported in debug builds: the value is interpolated into a shell script
+//! cmd!("sh", "-c", format!("git checkout {} && make", branch)).run()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::sync::{Mutex, RwLock};
+
+/// Programs whose `-c` argument is a shell script.
+const SHELLS: &[&str] = &["sh", "bash", "dash", "zsh", "ksh", "fish"];
+
+/// Number of recently formatted tainted values remembered for the check.
+#[cfg(debug_assertions)]
+const MAX_TRACKED: usize = 256;
+
+/// A value from an untrusted source.
+///
+/// See the [module documentation](self) for how tainted values are checked.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Tainted<T>(T);
+
+impl<T> Tainted<T> {
+    /// Mark a value as untrusted.
+    pub fn new(value: T) -> Self {
+        Tainted(value)
+    }
+
+    /// Returns the wrapped value, giving up the tracking.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Tainted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(debug_assertions)]
+        track(self.0.to_string());
+        self.0.fmt(f)
+    }
+}
+
+impl<T: AsRef<OsStr>> AsRef<OsStr> for Tainted<T> {
+    fn as_ref(&self) -> &OsStr {
+        self.0.as_ref()
+    }
+}
+
+/// What to do when a tainted value is found in a shell script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShellFormatCheck {
+    /// Do not check.
+    Off,
+    /// Print a warning and run the command anyway (the default).
+    #[default]
+    Warn,
+    /// Refuse to run the command and return an error.
+    Deny,
+}
+
+static CHECK: RwLock<ShellFormatCheck> = RwLock::new(ShellFormatCheck::Warn);
+
+static TRACKED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Set how shell scripts containing tainted values are handled in debug builds.
+///
+/// # Examples
+///
+/// ```
+/// use scripty::taint::{self, ShellFormatCheck};
+///
+/// // Fail the command instead of warning, e.g. in a test suite
+/// taint::set_shell_format_check(ShellFormatCheck::Deny);
+/// ```
+pub fn set_shell_format_check(check: ShellFormatCheck) {
+    *CHECK.write().unwrap_or_else(|e| e.into_inner()) = check;
+}
+
+/// Get the current shell format check.
+pub fn shell_format_check() -> ShellFormatCheck {
+    *CHECK.read().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(debug_assertions)]
+fn track(value: String) {
+    if value.is_empty() {
+        return;
+    }
+    let mut tracked = TRACKED.lock().unwrap_or_else(|e| e.into_inner());
+    if tracked.len() == MAX_TRACKED {
+        tracked.remove(0);
+    }
+    tracked.push(value);
+}
+
+/// Returns the shell script passed with `-c` if `program` is a known shell.
+fn shell_script<'a>(program: &OsStr, args: &'a [std::ffi::OsString]) -> Option<&'a OsStr> {
+    let name = std::path::Path::new(program).file_name()?.to_str()?;
+    if !SHELLS.contains(&name) {
+        return None;
+    }
+    // `-c` may be combined with other flags, as in `bash -ec`
+    let flag = args.iter().position(|arg| {
+        let arg = arg.to_string_lossy();
+        arg.starts_with('-') && !arg.starts_with("--") && arg.contains('c')
+    })?;
+    args.get(flag + 1).map(|script| script.as_os_str())
+}
+
+/// Apply the [`ShellFormatCheck`] to a command about to be spawned.
+pub(crate) fn check_command(
+    program: &OsStr,
+    args: &[std::ffi::OsString],
+) -> Result<(), crate::Error> {
+    let Some(value) = find_tainted(program, args) else {
+        return Ok(());
+    };
+    let reason = format!(
+        "tainted value {:?} is formatted into a shell script; pass it with .arg() instead",
+        value
+    );
+    match shell_format_check() {
+        ShellFormatCheck::Deny => Err(crate::Error::invalid_command(&reason)),
+        _ => {
+            eprintln!("Warning: {}", reason);
+            Ok(())
+        }
+    }
+}
+
+/// Returns the first tracked tainted value that appears in the command's shell script.
+fn find_tainted(program: &OsStr, args: &[std::ffi::OsString]) -> Option<String> {
+    if !cfg!(debug_assertions) || shell_format_check() == ShellFormatCheck::Off {
+        return None;
+    }
+    let script = shell_script(program, args)?.to_string_lossy();
+    let tracked = TRACKED.lock().unwrap_or_else(|e| e.into_inner());
+    tracked
+        .iter()
+        .rev()
+        .find(|value| script.contains(value.as_str()))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_shell_script_detection() {
+        let bash_args = args(&["-ec", "echo hi"]);
+        let script = shell_script(OsStr::new("/bin/bash"), &bash_args);
+        assert_eq!(script, Some(OsStr::new("echo hi")));
+
+        assert_eq!(shell_script(OsStr::new("sh"), &args(&["script.sh"])), None);
+        assert_eq!(
+            shell_script(OsStr::new("python"), &args(&["-c", "1"])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_tainted_in_formatted_script() {
+        let name = Tainted::new("scripty-taint-test; rm -rf ~");
+        let script = format!("echo {}", name);
+
+        let found = find_tainted(OsStr::new("sh"), &args(&["-c", &script]));
+        assert_eq!(found.as_deref(), Some("scripty-taint-test; rm -rf ~"));
+
+        // Passing the value as an argument is not flagged
+        let found = find_tainted(
+            OsStr::new("sh"),
+            &args(&["-c", "echo \"$1\"", "sh", "scripty-taint-test; rm -rf ~"]),
+        );
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_deny_returns_error() {
+        let file = Tainted::new("scripty-taint-deny.txt");
+        let script = format!("cat {}", file);
+
+        set_shell_format_check(ShellFormatCheck::Deny);
+        let result = crate::cmd!("sh", "-c", &script).no_echo().run();
+        set_shell_format_check(ShellFormatCheck::default());
+
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("scripty-taint-deny.txt"));
+    }
+}
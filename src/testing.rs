@@ -0,0 +1,104 @@
+//! Helpers for testing command pipelines.
+//!
+//! These are useful when migrating shell pipelines to native stages (or to different tools)
+//! and verifying that the output stays the same.
+
+use crate::cmd::{Error, Pipeline};
+use crate::diff::LineDiff;
+
+/// Run two pipelines and compute the line-by-line difference between their outputs.
+///
+/// The first pipeline's output is treated as the expected (left) side.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// let diff = testing::diff_outputs(
+///     cmd!("sort", "data.txt").pipe(cmd!("uniq")),
+///     cmd!("sort", "-u", "data.txt"),
+/// )?;
+/// if !diff.is_empty() {
+///     println!("{}", diff);
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn diff_outputs(
+    expected: impl Into<Pipeline>,
+    actual: impl Into<Pipeline>,
+) -> Result<LineDiff, Error> {
+    let expected = expected.into().output()?;
+    let actual = actual.into().output()?;
+    Ok(LineDiff::new(&expected, &actual))
+}
+
+/// Assert that two pipelines produce the same output, line by line.
+///
+/// # Panics
+///
+/// Panics with a readable diff if the outputs differ, or if either pipeline fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scripty::*;
+///
+/// testing::assert_same_output(
+///     cmd!("cat", "words.txt").pipe(cmd!("tr", "a-z", "A-Z")),
+///     cmd!("awk", "{ print toupper($0) }", "words.txt"),
+/// );
+/// ```
+#[track_caller]
+pub fn assert_same_output(expected: impl Into<Pipeline>, actual: impl Into<Pipeline>) {
+    match diff_outputs(expected, actual) {
+        Ok(diff) if diff.is_empty() => {}
+        Ok(diff) => panic!("pipeline outputs differ (- expected, + actual):\n{}", diff),
+        Err(e) => panic!("failed to run pipelines for comparison: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+
+    #[test]
+    fn test_diff_outputs_equal() {
+        let diff = diff_outputs(
+            cmd!("printf", "b\\na\\nb\\n")
+                .pipe(cmd!("sort"))
+                .pipe(cmd!("uniq"))
+                .no_echo(),
+            cmd!("printf", "b\\na\\nb\\n")
+                .pipe(cmd!("sort", "-u"))
+                .no_echo(),
+        )
+        .unwrap();
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_diff_outputs_different() {
+        let diff = diff_outputs(
+            cmd!("printf", "a\\nb\\n").no_echo(),
+            cmd!("printf", "a\\nc\\n").no_echo(),
+        )
+        .unwrap();
+        assert_eq!(diff.to_string(), "  a\n- b\n+ c\n");
+    }
+
+    #[test]
+    fn test_assert_same_output_passes() {
+        assert_same_output(
+            cmd!("echo", "hello").no_echo(),
+            cmd!("printf", "hello\\n").no_echo(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "pipeline outputs differ")]
+    fn test_assert_same_output_panics() {
+        assert_same_output(cmd!("echo", "a").no_echo(), cmd!("echo", "b").no_echo());
+    }
+}
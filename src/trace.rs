@@ -0,0 +1,185 @@
+//! Recording the commands a script runs, so tests can assert on them.
+//!
+//! Once [`enable`] is called, every command spawned by the current thread is recorded with its
+//! arguments, working directory, environment variables and exit code. [`take`] returns the
+//! commands recorded so far. Combined with [`Cmd::dry_run`](crate::Cmd::dry_run) or a
+//! [`Session`](crate::Session) in dry-run mode, this checks which commands automation logic
+//! would run without running them.
+//!
+//! Recording is per thread, so tests running in parallel do not see each other's commands.
+//!
+//! # Examples
+//!
+//! ```
+//! use scripty::*;
+//!
+//! fn release(sh: &Session, version: &str) -> Result<()> {
+//!     sh.cmd("git").args(["tag", version]).run()?;
+//!     sh.cmd("git").args(["push", "origin", version]).run()?;
+//!     Ok(())
+//! }
+//!
+//! trace::enable();
+//! release(&session().dry_run().no_echo(), "v1.2.0")?;
+//! let commands = trace::take();
+//! assert_eq!(commands.len(), 2);
+//! assert_eq!(commands[1].program(), "git");
+//! assert_eq!(commands[1].args(), ["push", "origin", "v1.2.0"]);
+//! assert_eq!(commands[1].exit_code(), Some(0));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::Cmd;
+use std::cell::RefCell;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+thread_local! {
+    /// Commands recorded on this thread, or `None` when recording is off.
+    static RECORDED: RefCell<Option<Vec<Arc<Mutex<TraceEntry>>>>> = const { RefCell::new(None) };
+}
+
+/// Recorded commands still running, by process ID. Their exit code is filled in when they are
+/// waited for, possibly on another thread.
+static RUNNING: Mutex<Vec<(u32, Arc<Mutex<TraceEntry>>)>> = Mutex::new(Vec::new());
+
+/// A command recorded by [`enable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    program: OsString,
+    args: Vec<OsString>,
+    current_dir: Option<PathBuf>,
+    envs: Vec<(OsString, OsString)>,
+    exit_code: Option<i32>,
+}
+
+impl TraceEntry {
+    /// The program, as given to [`Cmd::new`].
+    pub fn program(&self) -> &OsStr {
+        &self.program
+    }
+
+    /// The arguments, including the `--` added by
+    /// [`auto_end_of_options`](Cmd::auto_end_of_options).
+    pub fn args(&self) -> &[OsString] {
+        &self.args
+    }
+
+    /// The working directory set with [`Cmd::current_dir`].
+    pub fn current_dir(&self) -> Option<&Path> {
+        self.current_dir.as_deref()
+    }
+
+    /// The environment variables set on the command, in the order they were set. Inherited
+    /// variables are not included.
+    pub fn envs(&self) -> &[(OsString, OsString)] {
+        &self.envs
+    }
+
+    /// The exit code, or `None` if the command was not waited for yet or was killed by a
+    /// signal.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+}
+
+/// Start recording the commands spawned by the current thread.
+pub fn enable() {
+    RECORDED.with_borrow_mut(|recorded| {
+        recorded.get_or_insert_with(Vec::new);
+    });
+}
+
+/// Stop recording on the current thread, discarding the commands not yet taken.
+pub fn disable() {
+    RECORDED.with_borrow_mut(|recorded| *recorded = None);
+}
+
+/// Return the commands recorded on the current thread since recording was enabled or the last
+/// call, in the order they were spawned.
+pub fn take() -> Vec<TraceEntry> {
+    let entries = RECORDED
+        .with_borrow_mut(|recorded| recorded.as_mut().map(std::mem::take).unwrap_or_default());
+    entries
+        .iter()
+        .map(|entry| entry.lock().unwrap_or_else(|e| e.into_inner()).clone())
+        .collect()
+}
+
+/// Record a command that was just spawned as process `pid`, if recording is on.
+pub(crate) fn record_spawn(cmd: &Cmd, pid: u32) {
+    RECORDED.with_borrow_mut(|recorded| {
+        let Some(recorded) = recorded else {
+            return;
+        };
+        let entry = Arc::new(Mutex::new(TraceEntry {
+            program: cmd.program.clone(),
+            args: cmd.effective_args().into_owned(),
+            current_dir: cmd.current_dir.clone(),
+            envs: cmd.envs.clone(),
+            exit_code: None,
+        }));
+        recorded.push(Arc::clone(&entry));
+        RUNNING
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((pid, entry));
+    });
+}
+
+/// Record the exit code of process `pid`, if it was recorded when spawned.
+pub(crate) fn record_exit(pid: u32, code: Option<i32>) {
+    let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(index) = running
+        .iter()
+        .position(|(running_pid, _)| *running_pid == pid)
+    {
+        let (_, entry) = running.swap_remove(index);
+        entry.lock().unwrap_or_else(|e| e.into_inner()).exit_code = code;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+
+    #[test]
+    fn test_records_commands() {
+        enable();
+        cmd!("true").no_echo().run().unwrap();
+        let _ = cmd!("sh", "-c", "exit 3")
+            .env("MODE", "test")
+            .current_dir("/")
+            .no_echo()
+            .pipe(cmd!("cat").no_echo())
+            .output();
+        let entries = take();
+        disable();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].program(), "true");
+        assert_eq!(entries[0].exit_code(), Some(0));
+        assert_eq!(entries[1].args(), ["-c", "exit 3"]);
+        assert_eq!(entries[1].current_dir(), Some(Path::new("/")));
+        assert_eq!(
+            entries[1].envs(),
+            [(OsString::from("MODE"), OsString::from("test"))]
+        );
+        assert_eq!(entries[1].exit_code(), Some(3));
+        assert_eq!(entries[2].program(), "cat");
+        assert!(take().is_empty());
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        cmd!("true").no_echo().run().unwrap();
+        assert!(take().is_empty());
+
+        enable();
+        disable();
+        cmd!("true").no_echo().run().unwrap();
+        assert!(take().is_empty());
+    }
+}
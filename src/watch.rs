@@ -0,0 +1,197 @@
+//! Rerunning an action when files change, like `watchexec` or `entr`.
+//!
+//! Changes are detected with the platform's file notification API (inotify on Linux, FSEvents
+//! on macOS) through [`notify`](https://docs.rs/notify). Requires the `watch` feature.
+
+use crate::style::{BOLD_CYAN, BOLD_UNDERLINE};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+
+fn echo_operation(op: &str, details: &str) {
+    crate::echo::emit("  ", "watch", |styled| {
+        if styled {
+            format!("{BOLD_CYAN}{op}{BOLD_CYAN:#} {BOLD_UNDERLINE}{details}{BOLD_UNDERLINE:#}")
+        } else {
+            format!("{op} {details}")
+        }
+    });
+}
+
+/// Watch files and directories for changes. Directories are watched recursively.
+///
+/// Changes are collected until none have been seen for `debounce`, so saving several files at
+/// once, or an editor writing a file in steps, results in one [`WatchEvent`]. Nothing is
+/// watched until [`on_change`](Watch::on_change) is called. Requires the `watch` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "watch")]
+/// # {
+/// use scripty::*;
+/// use std::time::Duration;
+///
+/// // Rebuild on save
+/// watch(["src", "Cargo.toml"], Duration::from_millis(200))
+///     .on_change(|_| cmd!("cargo", "build").run())?;
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn watch(paths: impl IntoIterator<Item = impl AsRef<Path>>, debounce: Duration) -> Watch {
+    Watch {
+        paths: paths
+            .into_iter()
+            .map(|path| path.as_ref().to_path_buf())
+            .collect(),
+        debounce,
+    }
+}
+
+/// Paths to watch, created by [`watch`].
+#[derive(Debug, Clone)]
+pub struct Watch {
+    paths: Vec<PathBuf>,
+    debounce: Duration,
+}
+
+/// The changes seen during one debounce period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchEvent {
+    paths: Vec<PathBuf>,
+}
+
+impl WatchEvent {
+    /// The paths that were created, modified or removed, sorted and without duplicates.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+}
+
+impl Watch {
+    /// Run `action` each time the watched paths change. Does not return unless watching fails.
+    ///
+    /// An error returned by `action` is shown with [`echo::error`](crate::echo::error) and
+    /// watching continues, so a failed build waits for the next save. Changes made while
+    /// `action` runs are ignored, so an action that writes into a watched directory does not
+    /// trigger itself.
+    pub fn on_change<E: Display>(
+        self,
+        mut action: impl FnMut(&WatchEvent) -> Result<(), E>,
+    ) -> std::io::Result<()> {
+        self.each_event(|event| {
+            if let Err(e) = action(event) {
+                crate::echo::error(e);
+            }
+            true
+        })
+    }
+
+    /// Call `handle` with each debounced event until it returns `false`.
+    fn each_event(self, mut handle: impl FnMut(&WatchEvent) -> bool) -> std::io::Result<()> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(into_io)?;
+        for path in &self.paths {
+            watcher
+                .watch(path, RecursiveMode::Recursive)
+                .map_err(|e| crate::fs::context("watch", &[path.as_path()])(into_io(e)))?;
+        }
+        let names: Vec<_> = self.paths.iter().map(|p| p.display().to_string()).collect();
+        echo_operation("watch", &names.join(" "));
+
+        loop {
+            // The watcher holds the sender, so the channel stays open
+            let Ok(first) = receiver.recv() else {
+                return Ok(());
+            };
+            let mut paths = Vec::new();
+            let mut next = Some(first);
+            while let Some(result) = next {
+                let event = result.map_err(into_io)?;
+                if !matches!(event.kind, EventKind::Access(_)) {
+                    paths.extend(event.paths);
+                }
+                next = match receiver.recv_timeout(self.debounce) {
+                    Ok(result) => Some(result),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                };
+            }
+            if paths.is_empty() {
+                continue;
+            }
+            paths.sort();
+            paths.dedup();
+
+            let event = WatchEvent { paths };
+            echo_operation("changed", &describe(&event.paths));
+            if !handle(&event) {
+                return Ok(());
+            }
+            while receiver.try_recv().is_ok() {}
+        }
+    }
+}
+
+/// The first changed path, and how many others changed with it.
+fn describe(paths: &[PathBuf]) -> String {
+    match paths {
+        [path] => path.display().to_string(),
+        [path, rest @ ..] => format!("{} (and {} more)", path.display(), rest.len()),
+        [] => String::new(),
+    }
+}
+
+fn into_io(error: notify::Error) -> std::io::Error {
+    match error.kind {
+        notify::ErrorKind::Io(e) => e,
+        notify::ErrorKind::PathNotFound => std::io::ErrorKind::NotFound.into(),
+        _ => std::io::Error::other(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp_dir_scoped;
+
+    #[test]
+    fn test_change_is_reported() {
+        let tmp = temp_dir_scoped().unwrap();
+        let dir = tmp.path().canonicalize().unwrap();
+        let file = dir.join("main.rs");
+
+        let writer = {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                // Give the watcher time to start
+                std::thread::sleep(Duration::from_millis(300));
+                std::fs::write(&file, "fn main() {}").unwrap();
+                std::fs::write(&file, "fn main() { println!() }").unwrap();
+            })
+        };
+        let mut events = Vec::new();
+        watch([&dir], Duration::from_millis(100))
+            .each_event(|event| {
+                events.push(event.clone());
+                false
+            })
+            .unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].paths(), [file]);
+    }
+
+    #[test]
+    fn test_missing_path() {
+        let tmp = temp_dir_scoped().unwrap();
+        let error = watch([tmp.path().join("missing")], Duration::ZERO)
+            .on_change(|_| Ok::<(), std::io::Error>(()))
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(error.to_string().starts_with("Failed to watch "));
+    }
+}
@@ -1,7 +1,7 @@
 use clap::{Parser, Subcommand};
 use scripty::*;
 use std::fs::File;
-use std::io::{self, BufWriter, Write};
+use std::io::BufWriter;
 use std::path::PathBuf;
 use toml::Value;
 
@@ -55,7 +55,9 @@ fn main() -> Result<()> {
 
     // Set global verbosity
     let verbose = cli.verbose && !cli.quiet;
-    let _quiet = cli.quiet;
+    if cli.quiet {
+        set_verbosity(Verbosity::Quiet);
+    }
 
     match cli.command {
         Commands::Precommit => run_precommit(verbose)?,
@@ -76,20 +78,14 @@ fn ensure_tool_installed(name: &str, package: &str, _verbose: bool) -> Result<bo
             return Err(format!("{} is not installed. Please install it manually.", name).into());
         }
 
-        println!("⚠️  {} is not installed.", name);
-        print!("Would you like to install it via cargo? [y/N] ");
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-
-        if input.trim().to_lowercase() == "y" {
-            println!("📦 Installing {}...", name);
+        echo::warn(format!("{} is not installed.", name));
+        if prompt::confirm("Would you like to install it via cargo?")? {
+            echo::info(format!("Installing {}...", name));
             cmd!("cargo", "install", package).run()?;
-            println!("✅ {} installed successfully!", name);
+            echo::success(format!("{} installed successfully!", name));
             Ok(true)
         } else {
-            println!("⚠️  Skipping {} formatting (not installed)", name);
+            echo::warn(format!("Skipping {} formatting (not installed)", name));
             Ok(false)
         }
     } else {